@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::manifest::MirrorEntry;
+use crate::models::VacEntry;
+use crate::version::is_outdated;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Chart held locally, e.g. in the cache database or in browser storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalChart {
+    pub oaci: String,
+    pub vac_type: String,
+    pub version: String,
+    /// SHA-256 of the file, if known
+    pub file_hash: Option<String>,
+}
+
+impl From<&VacEntry> for LocalChart {
+    fn from(entry: &VacEntry) -> Self {
+        LocalChart {
+            oaci: entry.oaci.clone(),
+            vac_type: entry.vac_type.clone(),
+            version: entry.version.clone(),
+            file_hash: entry.file_hash.clone(),
+        }
+    }
+}
+
+/// Why a chart of a manifest should be downloaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "reason")]
+pub enum ChangeReason {
+    /// The chart is not held locally
+    New,
+    /// Another version is published
+    Outdated { local_version: String },
+    /// The same version was published again with a different file
+    Republished,
+}
+
+/// Chart of a manifest that differs from the local one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartChange<'a> {
+    pub entry: &'a MirrorEntry,
+    #[serde(flatten)]
+    pub reason: ChangeReason,
+}
+
+/// Why the chart of a manifest entry should be downloaded, or None if the local
+/// chart is current
+///
+/// Versions are compared with [`is_outdated`]. A local chart of unknown hash is
+/// current when its version matches.
+pub fn change_reason(entry: &MirrorEntry, local: Option<&LocalChart>) -> Option<ChangeReason> {
+    let local = match local {
+        None => return Some(ChangeReason::New),
+        Some(local) => local,
+    };
+    if is_outdated(Some(&local.version), &entry.version) {
+        return Some(ChangeReason::Outdated {
+            local_version: local.version.clone(),
+        });
+    }
+    match &local.file_hash {
+        Some(hash) if *hash != entry.file_hash => Some(ChangeReason::Republished),
+        _ => None,
+    }
+}
+
+/// Charts of a manifest that are missing or differ locally, in the manifest order
+pub fn changes<'a>(entries: &'a [MirrorEntry], local: &[LocalChart]) -> Vec<ChartChange<'a>> {
+    let local: BTreeMap<(&str, &str), &LocalChart> = local
+        .iter()
+        .map(|chart| ((chart.oaci.as_str(), chart.vac_type.as_str()), chart))
+        .collect();
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let chart = local.get(&(entry.oaci.as_str(), entry.vac_type.as_str()));
+            change_reason(entry, chart.copied()).map(|reason| ChartChange { entry, reason })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn entry(oaci: &str, version: &str, hash: &str) -> MirrorEntry {
+        MirrorEntry {
+            oaci: oaci.to_string(),
+            city: String::new(),
+            vac_type: "AD".to_string(),
+            version: version.to_string(),
+            file_name: oaci.to_string() + ".pdf",
+            file_hash: hash.to_string(),
+            file_size: 1,
+        }
+    }
+
+    fn local(oaci: &str, version: &str, hash: Option<&str>) -> LocalChart {
+        LocalChart {
+            oaci: oaci.to_string(),
+            vac_type: "AD".to_string(),
+            version: version.to_string(),
+            file_hash: hash.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let entries = vec![
+            entry("LFPG", "2", "a"),
+            entry("LFPN", "1", "b"),
+            entry("LFPO", "1", "c"),
+            entry("LFPT", "1", "d"),
+            entry("LFPZ", "1", "e"),
+        ];
+        let held = vec![
+            local("LFPG", "1", Some("z")),
+            local("LFPN", "1", Some("x")),
+            local("LFPO", "1", Some("c")),
+            local("LFPT", "1", None),
+        ];
+        let reasons: Vec<(&str, ChangeReason)> = changes(&entries, &held)
+            .into_iter()
+            .map(|change| (change.entry.oaci.as_str(), change.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    "LFPG",
+                    ChangeReason::Outdated {
+                        local_version: "1".to_string()
+                    }
+                ),
+                ("LFPN", ChangeReason::Republished),
+                ("LFPZ", ChangeReason::New),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_json() {
+        let entry = entry("LFPZ", "1", "e");
+        let change = ChartChange {
+            entry: &entry,
+            reason: ChangeReason::New,
+        };
+        let json = serde_json::to_value(change).unwrap();
+        assert_eq!(json["reason"], "new");
+        assert_eq!(json["entry"]["oaci"], "LFPZ");
+    }
+}
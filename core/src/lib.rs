@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Data model and diff logic of `vac_downloader`, without any I/O
+//!
+//! The crate is `no_std` (it only needs an allocator), so that it compiles to
+//! WASM: a web frontend can parse a mirror manifest and compute which charts
+//! are outdated in the browser, with the same rules as the library.
+//!
+//! The `std` feature adds what needs the platform math library, the distance
+//! between two coordinates.
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod diff;
+pub mod manifest;
+pub mod models;
+pub mod version;
+
+pub use diff::{changes, ChangeReason, ChartChange, LocalChart};
+pub use manifest::{MirrorEntry, MirrorManifest, MIRROR_FORMAT_VERSION, MIRROR_MANIFEST_NAME};
+pub use models::{
+    Coordinates, Frequency, Ground, Information, MagneticHeading, Map, OacisEntry, ParseError,
+    RadioFrequency, Runway, VacEntry,
+};
+pub use version::is_outdated;
@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Name of the manifest file written at the root of a mirrored download directory
+pub const MIRROR_MANIFEST_NAME: &str = "manifest.json";
+
+/// Version of the mirror manifest layout
+pub const MIRROR_FORMAT_VERSION: u32 = 1;
+
+/// Manifest describing the charts available in a mirrored download directory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MirrorManifest {
+    pub format_version: u32,
+    /// Generation time (seconds since the Unix epoch)
+    pub generated_at: u64,
+    pub entries: Vec<MirrorEntry>,
+}
+
+/// Individual chart in a mirror manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorEntry {
+    pub oaci: String,
+    pub city: String,
+    pub vac_type: String,
+    pub version: String,
+    /// File name relative to the manifest location
+    pub file_name: String,
+    pub file_hash: String,
+    pub file_size: i64,
+}
+
+impl MirrorManifest {
+    /// Manifest of the current layout
+    pub fn new(generated_at: u64, entries: Vec<MirrorEntry>) -> Self {
+        MirrorManifest {
+            format_version: MIRROR_FORMAT_VERSION,
+            generated_at,
+            entries,
+        }
+    }
+}
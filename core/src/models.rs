@@ -0,0 +1,608 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::manifest::MirrorEntry;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Mean radius of the Earth in meters
+#[cfg(feature = "std")]
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Value of the API that could not be parsed or is out of range, e.g. a runway length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Custom deserializer for elevation that handles both String and f64
+fn deserialize_elevation<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ElevationVisitor;
+
+    impl<'de> Visitor<'de> for ElevationVisitor {
+        type Value = Option<f64>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string or number representing elevation")
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(ElevationValueVisitor)
+        }
+    }
+
+    struct ElevationValueVisitor;
+
+    impl<'de> Visitor<'de> for ElevationValueVisitor {
+        type Value = Option<f64>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string or number")
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(value))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(value as f64))
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(value as f64))
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            value.parse::<f64>().map(Some).map_err(|_| {
+                de::Error::custom(format!("failed to parse elevation string: {}", value))
+            })
+        }
+
+        fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            self.visit_str(&value)
+        }
+    }
+
+    deserializer.deserialize_option(ElevationVisitor)
+}
+
+/// Individual OACIS entry (VAC/Heliport)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OacisEntry {
+    pub code: String,
+    pub city: String,
+    #[serde(default)]
+    pub grounds: Vec<Ground>,
+    #[serde(default)]
+    pub maps: Vec<Map>,
+    #[serde(default)]
+    pub runways: Vec<Runway>,
+    #[serde(default)]
+    pub frequencies: Vec<Frequency>,
+    #[serde(default)]
+    pub information: Vec<Information>,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl OacisEntry {
+    /// Position of the airfield, from the first ground that has one
+    pub fn coordinates(&self) -> Option<&Coordinates> {
+        self.grounds
+            .iter()
+            .find_map(|ground| ground.coordinates.as_ref())
+    }
+
+    /// Elevation of the airfield in feet, from the first ground that has one
+    pub fn elevation(&self) -> Option<f64> {
+        self.grounds.iter().find_map(|ground| ground.elevation)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Ground {
+    #[serde(rename = "type")]
+    pub ground_type: String,
+    #[serde(deserialize_with = "deserialize_elevation")]
+    pub elevation: Option<f64>,
+    pub coordinates: Option<Coordinates>,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Position in decimal degrees, checked to be on the globe
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self, ParseError> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(ParseError(format!(
+                "Invalid coordinates: {}, {}",
+                latitude, longitude
+            )));
+        }
+        Ok(Coordinates {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Great-circle distance to another position in meters, with the `std`
+    /// feature as the trigonometry comes from the platform math library
+    #[cfg(feature = "std")]
+    pub fn distance(&self, other: &Coordinates) -> f64 {
+        let (lat_a, lat_b) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat_b - lat_a;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let h =
+            (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * h.sqrt().asin()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Map {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "type")]
+    pub map_type: String,
+    pub version: String,
+    #[serde(rename = "fileSize")]
+    pub file_size: i64,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Runway {
+    pub length: String,
+    pub width: String,
+    #[serde(rename = "type")]
+    pub runway_type: String,
+    pub degrees: String,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Runway {
+    /// Length in meters, e.g. 1100 for "1100"
+    pub fn length_meters(&self) -> Result<u32, ParseError> {
+        parse_meters(&self.length, "length")
+    }
+
+    /// Width in meters, e.g. 30 for "30"
+    pub fn width_meters(&self) -> Result<u32, ParseError> {
+        parse_meters(&self.width, "width")
+    }
+
+    /// Magnetic headings of the runway directions, e.g. 70 and 250 for "07/25"
+    pub fn headings(&self) -> Result<Vec<MagneticHeading>, ParseError> {
+        self.degrees
+            .split('/')
+            .map(|direction| direction.parse())
+            .collect()
+    }
+}
+
+/// Parse a runway dimension in meters such as "1100" or "1100.0"
+fn parse_meters(value: &str, dimension: &str) -> Result<u32, ParseError> {
+    let invalid = || {
+        ParseError(format!(
+            "Invalid runway {}: {:?} is not a number of meters",
+            dimension, value
+        ))
+    };
+    let meters: f64 = value
+        .trim()
+        .trim_end_matches('m')
+        .trim()
+        .parse()
+        .map_err(|_| invalid())?;
+    if !meters.is_finite() || meters < 0.0 || meters > u32::MAX as f64 {
+        return Err(invalid());
+    }
+    // Rounded half up by hand, as f64::round needs std
+    Ok((meters + 0.5) as u32)
+}
+
+/// Magnetic heading in degrees, from 1 to 360
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct MagneticHeading(u16);
+
+impl MagneticHeading {
+    pub fn new(degrees: u16) -> Result<Self, ParseError> {
+        if !(1..=360).contains(&degrees) {
+            return Err(ParseError(format!("Invalid magnetic heading: {}", degrees)));
+        }
+        Ok(MagneticHeading(degrees))
+    }
+
+    pub fn degrees(&self) -> u16 {
+        self.0
+    }
+}
+
+impl FromStr for MagneticHeading {
+    type Err = ParseError;
+
+    /// Parse a runway designator such as "07" or "25L", in tens of degrees,
+    /// or a heading in degrees such as "070"
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let value = value.trim();
+        let invalid = || ParseError(format!("Invalid magnetic heading: {:?}", value));
+        let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+        let number: u16 = digits.parse().map_err(|_| invalid())?;
+        match digits.len() {
+            1 | 2 => Self::new(number * 10),
+            3 => Self::new(number),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for MagneticHeading {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:03}°", self.0)
+    }
+}
+
+/// Radio frequency, held in kHz so it compares exactly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct RadioFrequency(u32);
+
+impl RadioFrequency {
+    pub fn from_khz(khz: u32) -> Self {
+        RadioFrequency(khz)
+    }
+
+    pub fn khz(&self) -> u32 {
+        self.0
+    }
+
+    pub fn mhz(&self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+}
+
+impl FromStr for RadioFrequency {
+    type Err = ParseError;
+
+    /// Parse a frequency in MHz such as "118.650", "118,65" or "118.65 MHz"
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let invalid = || ParseError(format!("Invalid frequency: {:?}", value));
+        let trimmed = value.trim();
+        let number = trimmed
+            .strip_suffix("MHz")
+            .or_else(|| trimmed.strip_suffix("mhz"))
+            .unwrap_or(trimmed)
+            .trim();
+        let (whole, fraction) = number.split_once(['.', ',']).unwrap_or((number, ""));
+        if whole.is_empty()
+            || fraction.len() > 3
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let mhz: u32 = whole.parse().map_err(|_| invalid())?;
+        let khz = format!("{:0<3}", fraction)
+            .parse::<u32>()
+            .map_err(|_| invalid())?;
+        let khz = mhz
+            .checked_mul(1000)
+            .and_then(|total| total.checked_add(khz))
+            .filter(|&total| total > 0)
+            .ok_or_else(invalid)?;
+        Ok(RadioFrequency(khz))
+    }
+}
+
+impl fmt::Display for RadioFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Frequency {
+    #[serde(rename = "freqAPP")]
+    pub freq_app: Option<String>,
+    #[serde(rename = "freqTWR")]
+    pub freq_twr: Option<String>,
+    #[serde(rename = "freqVDF")]
+    pub freq_vdf: Option<String>,
+    #[serde(rename = "freqATIS")]
+    pub freq_atis: Option<String>,
+    #[serde(rename = "freqFIS")]
+    pub freq_fis: Option<String>,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Frequency {
+    /// Listed frequencies with the name of their service, e.g. ("TWR", "118.650")
+    pub fn named(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("TWR", &self.freq_twr),
+            ("APP", &self.freq_app),
+            ("ATIS", &self.freq_atis),
+            ("VDF", &self.freq_vdf),
+            ("FIS", &self.freq_fis),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+        .collect()
+    }
+
+    /// Listed frequencies that parse, with the name of their service
+    pub fn parsed(&self) -> Vec<(&'static str, RadioFrequency)> {
+        self.named()
+            .into_iter()
+            .filter_map(|(name, value)| value.parse().ok().map(|frequency| (name, frequency)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Information {
+    pub address: Option<String>,
+    #[serde(rename = "phoneNumber")]
+    pub phone_number: Option<String>,
+    #[serde(rename = "faxNumber")]
+    pub fax_number: Option<String>,
+    pub hotel: Option<String>,
+    pub restaurant: Option<String>,
+    pub fuel: Option<String>,
+    pub repair: Option<String>,
+    pub night: Option<bool>,
+    #[serde(rename = "codeActivity")]
+    pub code_activity: Option<String>,
+    #[serde(rename = "descriptionActivity")]
+    pub description_activity: Option<String>,
+    pub language: Option<String>,
+    pub manager: Option<String>,
+    pub bank: Option<String>,
+    /// Fields the API sent that are not modeled, kept for round-tripping
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Processed VAC entry for database storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacEntry {
+    pub oaci: String,
+    pub city: String,
+    pub vac_type: String,
+    pub version: String,
+    pub file_name: String,
+    /// File name provided by the server, kept for traceability when the
+    /// stored name is sanitized or templated
+    #[serde(default)]
+    pub remote_file_name: Option<String>,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    /// Number of pages of the stored PDF, once validated by `verify` or a
+    /// mirror sync
+    #[serde(default)]
+    pub page_count: Option<u32>,
+    #[serde(default)]
+    pub available_locally: bool,
+}
+
+impl VacEntry {
+    /// Extract AD (airport) entries from OACIS data
+    pub fn from_oacis_entry(entry: &OacisEntry) -> Vec<Self> {
+        let mut results = Vec::new();
+
+        for map in &entry.maps {
+            // Filter only "AD" type (airports)
+            if map.map_type == "AD" {
+                results.push(VacEntry {
+                    oaci: entry.code.clone(),
+                    city: entry.city.clone(),
+                    vac_type: map.map_type.clone(),
+                    version: map.version.clone(),
+                    file_name: map.file_name.clone(),
+                    file_size: map.file_size,
+                    file_hash: None, // Hash computed after download
+                    remote_file_name: Some(map.file_name.clone()),
+                    page_count: None,
+                    available_locally: false, // Not yet known to be local
+                });
+            }
+        }
+
+        results
+    }
+}
+
+impl From<MirrorEntry> for VacEntry {
+    fn from(entry: MirrorEntry) -> Self {
+        VacEntry {
+            oaci: entry.oaci,
+            city: entry.city,
+            vac_type: entry.vac_type,
+            version: entry.version,
+            file_name: entry.file_name,
+            file_size: entry.file_size,
+            file_hash: Some(entry.file_hash),
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn runway(length: &str, width: &str, degrees: &str) -> Runway {
+        Runway {
+            length: length.to_string(),
+            width: width.to_string(),
+            runway_type: "Revêtue".to_string(),
+            degrees: degrees.to_string(),
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_runway_dimensions() {
+        let runway = runway("1100", "30.0", "07/25");
+        assert_eq!(runway.length_meters().unwrap(), 1100);
+        assert_eq!(runway.width_meters().unwrap(), 30);
+        assert!(self::runway("", "30", "07").length_meters().is_err());
+        assert!(self::runway("-5", "30", "07").length_meters().is_err());
+    }
+
+    #[test]
+    fn test_runway_headings() {
+        let headings: Vec<u16> = runway("1100", "30", "07/25")
+            .headings()
+            .unwrap()
+            .iter()
+            .map(MagneticHeading::degrees)
+            .collect();
+        assert_eq!(headings, vec![70, 250]);
+        assert_eq!("25L".parse::<MagneticHeading>().unwrap().degrees(), 250);
+        assert_eq!("070".parse::<MagneticHeading>().unwrap().degrees(), 70);
+        assert_eq!("36".parse::<MagneticHeading>().unwrap().to_string(), "360°");
+        assert!("00".parse::<MagneticHeading>().is_err());
+        assert!("37".parse::<MagneticHeading>().is_err());
+        assert!("L".parse::<MagneticHeading>().is_err());
+        assert!(runway("1100", "30", "07/XX").headings().is_err());
+    }
+
+    #[test]
+    fn test_radio_frequency() {
+        let frequency: RadioFrequency = "118.650".parse().unwrap();
+        assert_eq!(frequency.khz(), 118_650);
+        assert_eq!("118,65".parse::<RadioFrequency>().unwrap(), frequency);
+        assert_eq!("118.65 MHz".parse::<RadioFrequency>().unwrap(), frequency);
+        assert_eq!(
+            "123".parse::<RadioFrequency>().unwrap().to_string(),
+            "123.000"
+        );
+        assert_eq!(RadioFrequency::from_khz(118_005).to_string(), "118.005");
+        for invalid in ["", "118.6500", "118.6a", "-118.5", "0", "ATIS"] {
+            assert!(invalid.parse::<RadioFrequency>().is_err(), "{}", invalid);
+        }
+
+        let frequencies = Frequency {
+            freq_app: Some("n/a".to_string()),
+            freq_twr: Some("119.25".to_string()),
+            freq_vdf: None,
+            freq_atis: Some("127.875".to_string()),
+            freq_fis: None,
+            extra: Default::default(),
+        };
+        assert_eq!(
+            frequencies.parsed(),
+            vec![
+                ("TWR", RadioFrequency::from_khz(119_250)),
+                ("ATIS", RadioFrequency::from_khz(127_875)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let json = serde_json::json!({
+            "code": "LFPN",
+            "city": "TOUSSUS LE NOBLE",
+            "grounds": [],
+            "maps": [],
+            "runways": [{"length": "1100", "width": "30", "type": "Revêtue",
+                "degrees": "07/25", "lighting": "HI"}],
+            "frequencies": [],
+            "information": [],
+            "status": "open"
+        });
+        let entry: OacisEntry = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(entry.extra["status"], "open");
+        assert_eq!(entry.runways[0].extra["lighting"], "HI");
+        assert_eq!(serde_json::to_value(&entry).unwrap(), json);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_coordinates() {
+        let pontoise = Coordinates::new(49.0967, 2.0408).unwrap();
+        let toussus = Coordinates::new(48.7519, 2.1061).unwrap();
+        let distance = pontoise.distance(&toussus);
+        assert!((38_000.0..39_000.0).contains(&distance), "{}", distance);
+        assert!(Coordinates::new(91.0, 0.0).is_err());
+        assert!(Coordinates::new(0.0, -181.0).is_err());
+    }
+}
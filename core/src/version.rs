@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+/// Whether a published version of a chart supersedes the local one, given as
+/// `None` when the chart is not held locally
+///
+/// Versions are compared for equality, as the published ones are not ordered
+/// (dates, AIRAC cycles and free text alike): any other version is an update.
+pub fn is_outdated(local_version: Option<&str>, published_version: &str) -> bool {
+    local_version != Some(published_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_outdated() {
+        assert!(is_outdated(None, "2024-01-25"));
+        assert!(is_outdated(Some("2024-01-25"), "2024-02-22"));
+        // An older looking version is still the published one
+        assert!(is_outdated(Some("2024-02-22"), "2024-01-25"));
+        assert!(!is_outdated(Some("2024-01-25"), "2024-01-25"));
+    }
+}
@@ -0,0 +1,359 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! C bindings of the `vac_downloader` library
+//!
+//! Every function takes and returns JSON strings, so that they can be called
+//! from any language with a C FFI. Results are either `{"ok": ...}` or
+//! `{"error": "..."}`, and must be released with [`vac_downloader_string_free`].
+//! The header is generated with `cbindgen --config cbindgen.toml`.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use vac_downloader::{changes, MirrorManifest, VacDownloader};
+
+/// Downloader created by [`vac_downloader_new`], opaque to C
+pub struct VacDownloaderHandle {
+    downloader: VacDownloader,
+}
+
+/// Settings of [`vac_downloader_new`]
+#[derive(Deserialize)]
+struct NewRequest {
+    db_path: PathBuf,
+    download_dir: PathBuf,
+    /// Base URL of the SIA API, the official server by default
+    api_url: Option<String>,
+    /// Only use the local cache, never fetching the listing
+    #[serde(default)]
+    offline: bool,
+}
+
+/// Airfields of [`vac_downloader_sync`] and [`vac_downloader_list`], all of them when empty
+#[derive(Deserialize, Default)]
+struct FilterRequest {
+    #[serde(default)]
+    oaci: Vec<String>,
+}
+
+/// Charts removed by [`vac_downloader_delete`]
+#[derive(Deserialize)]
+struct DeleteRequest {
+    /// OACI codes or patterns, e.g. "LFP*"
+    oaci: Vec<String>,
+    /// Only this chart type, every type by default
+    #[serde(rename = "type")]
+    vac_type: Option<String>,
+}
+
+/// Entry removed by a delete
+#[derive(Serialize)]
+struct Deleted {
+    oaci: String,
+    #[serde(rename = "type")]
+    vac_type: Option<String>,
+    database_deleted: bool,
+    file_deleted: bool,
+}
+
+/// Create a downloader from JSON settings, e.g.
+/// `{"db_path": "vac_cache.db", "download_dir": "downloads"}`
+///
+/// Returns NULL on failure, with the `{"error": ...}` result in `*error` when
+/// `error` is not NULL. The downloader is released with [`vac_downloader_free`].
+///
+/// # Safety
+/// `settings` must be a NUL-terminated string and `error` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_new(
+    settings: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut VacDownloaderHandle {
+    let result = guard(|| {
+        let request: NewRequest = parse(settings)?;
+        let mut builder = VacDownloader::builder(&request.db_path, &request.download_dir);
+        if let Some(api_url) = &request.api_url {
+            builder = builder.base_url(api_url);
+        }
+        let downloader = builder
+            .build()
+            .map_err(|e| format!("{:#}", e))?
+            .with_offline(request.offline);
+        Ok(VacDownloaderHandle { downloader })
+    });
+    match result {
+        Ok(handle) => Box::into_raw(Box::new(handle)),
+        Err(message) => {
+            if !error.is_null() {
+                *error = to_c_string(json!({ "error": message }));
+            }
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Release a downloader created by [`vac_downloader_new`]
+///
+/// # Safety
+/// `handle` must be NULL or returned by [`vac_downloader_new`], and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_free(handle: *mut VacDownloaderHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Sync the charts of the airfields of `filter`, e.g. `{"oaci": ["LFPN"]}`,
+/// or of every airfield when `filter` is NULL
+///
+/// The result holds the counters, updates, failures and per-chart results of
+/// the sync.
+///
+/// # Safety
+/// `handle` must come from [`vac_downloader_new`], and `filter` be NULL or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_sync(
+    handle: *const VacDownloaderHandle,
+    filter: *const c_char,
+) -> *mut c_char {
+    call(handle, |downloader| {
+        let filter: FilterRequest = parse_optional(filter)?;
+        let codes = (!filter.oaci.is_empty()).then_some(filter.oaci.as_slice());
+        let stats = downloader.sync(codes).map_err(|e| format!("{:#}", e))?;
+        to_value(&stats)
+    })
+}
+
+/// List the charts of the listing and the cache, of the airfields of `filter`
+/// or of every airfield when `filter` is NULL
+///
+/// # Safety
+/// As for [`vac_downloader_sync`].
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_list(
+    handle: *const VacDownloaderHandle,
+    filter: *const c_char,
+) -> *mut c_char {
+    call(handle, |downloader| {
+        let filter: FilterRequest = parse_optional(filter)?;
+        let codes = (!filter.oaci.is_empty()).then_some(filter.oaci.as_slice());
+        let charts = downloader
+            .list_charts(codes)
+            .map_err(|e| format!("{:#}", e))?;
+        to_value(charts)
+    })
+}
+
+/// Compare a mirror manifest with the cache, returning the charts of the
+/// manifest that are new, outdated or republished, each with its `reason`
+///
+/// # Safety
+/// `handle` must come from [`vac_downloader_new`] and `manifest` be a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_changes(
+    handle: *const VacDownloaderHandle,
+    manifest: *const c_char,
+) -> *mut c_char {
+    call(handle, |downloader| {
+        let manifest: MirrorManifest = parse(manifest)?;
+        let local = downloader.local_charts().map_err(|e| format!("{:#}", e))?;
+        to_value(changes(&manifest.entries, &local))
+    })
+}
+
+/// Delete the cached charts of `request`, e.g. `{"oaci": ["LFPN"], "type": "AD"}`
+///
+/// # Safety
+/// `handle` must come from [`vac_downloader_new`] and `request` be a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_delete(
+    handle: *const VacDownloaderHandle,
+    request: *const c_char,
+) -> *mut c_char {
+    call(handle, |downloader| {
+        let request: DeleteRequest = parse(request)?;
+        let results = downloader
+            .delete(&request.oaci, request.vac_type.as_deref())
+            .map_err(|e| format!("{:#}", e))?;
+        let deleted: Vec<Deleted> = results
+            .into_iter()
+            .map(|result| Deleted {
+                oaci: result.oaci,
+                vac_type: result.vac_type,
+                database_deleted: result.database_deleted,
+                file_deleted: result.file_deleted,
+            })
+            .collect();
+        to_value(deleted)
+    })
+}
+
+/// Release a string returned by the other functions
+///
+/// # Safety
+/// `string` must be NULL or returned by this library, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vac_downloader_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Run an operation on a downloader, returning its JSON result
+unsafe fn call(
+    handle: *const VacDownloaderHandle,
+    operation: impl FnOnce(&VacDownloader) -> Result<Value, String>,
+) -> *mut c_char {
+    let result = match handle.as_ref() {
+        Some(handle) => guard(|| operation(&handle.downloader)),
+        None => Err("NULL downloader".to_string()),
+    };
+    to_c_string(match result {
+        Ok(value) => json!({ "ok": value }),
+        Err(message) => json!({ "error": message }),
+    })
+}
+
+/// Run an operation, turning a panic into an error, as unwinding into C is undefined
+fn guard<T>(operation: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    catch_unwind(AssertUnwindSafe(operation)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(format!("Internal error: {}", message))
+    })
+}
+
+/// Parse a JSON argument
+unsafe fn parse<T: DeserializeOwned>(json: *const c_char) -> Result<T, String> {
+    if json.is_null() {
+        return Err("NULL argument".to_string());
+    }
+    let json = CStr::from_ptr(json)
+        .to_str()
+        .map_err(|_| "Invalid UTF-8 in argument".to_string())?;
+    serde_json::from_str(json).map_err(|e| format!("Invalid argument: {}", e))
+}
+
+/// Parse a JSON argument that may be NULL
+unsafe fn parse_optional<T: DeserializeOwned + Default>(json: *const c_char) -> Result<T, String> {
+    if json.is_null() {
+        return Ok(T::default());
+    }
+    parse(json)
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+fn to_c_string(value: Value) -> *mut c_char {
+    // JSON escapes control characters, so there is no NUL to reject
+    CString::new(value.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vac_downloader::MirrorEntry;
+
+    /// Take a result string, releasing it
+    unsafe fn take(string: *mut c_char) -> Value {
+        let value = serde_json::from_str(CStr::from_ptr(string).to_str().unwrap()).unwrap();
+        vac_downloader_string_free(string);
+        value
+    }
+
+    #[test]
+    fn test_ffi() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = json!({
+            "db_path": dir.path().join("vac_cache.db"),
+            "download_dir": dir.path().join("downloads"),
+            "offline": true,
+        });
+        let settings = CString::new(settings.to_string()).unwrap();
+        unsafe {
+            let handle = vac_downloader_new(settings.as_ptr(), std::ptr::null_mut());
+            assert!(!handle.is_null());
+
+            let listed = take(vac_downloader_list(handle, std::ptr::null()));
+            assert_eq!(listed, json!({ "ok": [] }));
+
+            let manifest = MirrorManifest::new(
+                0,
+                vec![MirrorEntry {
+                    oaci: "LFPN".to_string(),
+                    city: "TOUSSUS LE NOBLE".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "2024-01-25".to_string(),
+                    file_name: "LFPN.pdf".to_string(),
+                    file_hash: "abc".to_string(),
+                    file_size: 1,
+                }],
+            );
+            let manifest = CString::new(serde_json::to_string(&manifest).unwrap()).unwrap();
+            let changed = take(vac_downloader_changes(handle, manifest.as_ptr()));
+            assert_eq!(changed["ok"][0]["entry"]["oaci"], "LFPN");
+            assert_eq!(changed["ok"][0]["reason"], "new");
+
+            let request = CString::new(r#"{"oaci": ["LFPN"]}"#).unwrap();
+            let deleted = take(vac_downloader_delete(handle, request.as_ptr()));
+            assert_eq!(deleted["ok"][0]["oaci"], "LFPN");
+            assert_eq!(deleted["ok"][0]["database_deleted"], false);
+
+            let invalid = CString::new("{").unwrap();
+            let error = take(vac_downloader_delete(handle, invalid.as_ptr()));
+            assert!(error["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid argument"));
+
+            vac_downloader_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_new_error() {
+        let settings = CString::new(r#"{"db_path": "vac_cache.db"}"#).unwrap();
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let handle = vac_downloader_new(settings.as_ptr(), &mut error);
+            assert!(handle.is_null());
+            let error = take(error);
+            assert!(error["error"].as_str().unwrap().contains("download_dir"));
+
+            let result = take(vac_downloader_list(std::ptr::null(), std::ptr::null()));
+            assert_eq!(result, json!({ "error": "NULL downloader" }));
+        }
+    }
+}
@@ -2,7 +2,7 @@
  * Copyright (c) 2025 Jeremie Corbier
  *
  * Permission is hereby granted, free of charge, to any person obtaining a copy of
- * this software and associated documentation files (the "Software"), to deal in
+ * this software and associated documentation files (the “Software”), to deal in
  * the Software without restriction, including without limitation the rights to
  * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
  * the Software, and to permit persons to whom the Software is furnished to do so,
@@ -11,7 +11,7 @@
  * The above copyright notice and this permission notice shall be included in all
  * copies or substantial portions of the Software.
  *
- * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
  * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
  * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
  * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
@@ -19,34 +19,630 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use serde::Deserialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use vac_downloader::{
+    hooks, parse_duration, parse_size, EmailConfig, EmailNotifier, FileNameTemplate, RateLimiter,
+    SigningKey, StorageConfig, SyncHooks, Territory,
+};
+
+/// Database location of releases that kept their data in the working directory
+pub const LEGACY_DB_PATH: &str = "vac_cache.db";
+
+/// Download directory of releases that kept their data in the working directory
+pub const LEGACY_DOWNLOAD_DIR: &str = "./downloads";
+
+/// Prefix of the environment variables overriding configuration keys
+const ENV_PREFIX: &str = "VAC_DOWNLOADER_";
+
+/// Documented configuration written by `config init`
+pub const EXAMPLE_CONFIG: &str = include_str!("../../config.toml.example");
+
+/// Top-level keys of the configuration file
+const KEYS: &[&str] = &[
+    "db_path",
+    "db_passphrase",
+    "db_passphrase_command",
+    "download_dir",
+    "file_name_template",
+    "versioned_file_names",
+    "favorites",
+    "max_cache_size",
+    "keep_versions",
+    "keep_days",
+    "compress_versions",
+    "api_url",
+    "api_fallback_urls",
+    "proxy",
+    "timeout",
+    "connect_timeout",
+    "retries",
+    "cache_ttl",
+    "page_size",
+    "stream_listing",
+    "rate_limit",
+    "max_consecutive_failures",
+    "quarantine_after",
+    "signing_key",
+    "signing_key_password",
+    "pre_sync",
+    "post_sync",
+    "post_download",
+    "desktop_notifications",
+    "oaci",
+    "territory",
+    "max_duration",
+    "file_timeout",
+    "skip_not_found",
+    "sync_interval",
+    "groups",
+    "storage",
+    "email",
+    "serve",
+    "profile",
+];
+
+/// Settings of the HTTP server of `serve`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServeConfig {
+    /// Address to listen on (defaults to 127.0.0.1, this machine only)
+    pub bind: Option<IpAddr>,
+    /// Port to listen on (defaults to 8080)
+    pub port: Option<u16>,
+    /// Token expected in an `Authorization: Bearer` header
+    pub token: Option<String>,
+    /// Username and password expected with basic authentication
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
 
 /// Configuration structure for VAC Downloader
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Config {
     /// Path to the SQLite database file
     pub db_path: Option<String>,
 
+    /// Passphrase of the database, encrypted with SQLCipher
+    pub db_passphrase: Option<String>,
+
+    /// Command printing the database passphrase, e.g. a keyring lookup
+    pub db_passphrase_command: Option<String>,
+
     /// Directory where PDFs will be downloaded
     pub download_dir: Option<String>,
+
+    /// Path template for downloaded PDFs (e.g. "{oaci}/{oaci}_{type}_{version}.pdf")
+    pub file_name_template: Option<String>,
+
+    /// Name files after the chart version (e.g. "LFPG_AD_v2024-11.pdf")
+    pub versioned_file_names: Option<bool>,
+
+    /// OACI codes of airfields downloaded first during a sync
+    pub favorites: Option<Vec<String>>,
+
+    /// Maximum total size of cached PDFs (e.g. "2GB"); old airfields are evicted beyond it
+    pub max_cache_size: Option<String>,
+
+    /// Number of previous versions kept per chart
+    pub keep_versions: Option<u32>,
+
+    /// Number of days previous chart versions are kept for
+    pub keep_days: Option<u32>,
+
+    /// Compress the previous versions of charts with zstd
+    pub compress_versions: Option<bool>,
+
+    /// Base URL of the SIA API (defaults to the official server)
+    pub api_url: Option<String>,
+
+    /// API servers tried in order when the primary one is unreachable
+    pub api_fallback_urls: Option<Vec<String>>,
+
+    /// Proxy for all requests (e.g. "http://proxy:3128" or "socks5://proxy:1080")
+    pub proxy: Option<String>,
+
+    /// Timeout of each HTTP request (e.g. "30s")
+    pub timeout: Option<String>,
+
+    /// Timeout for establishing connections (e.g. "10s")
+    pub connect_timeout: Option<String>,
+
+    /// Number of retries of requests that failed with a transient error
+    pub retries: Option<u32>,
+
+    /// How long the OACIS listing is reused before being fetched again (e.g. "10m")
+    pub cache_ttl: Option<String>,
+
+    /// Number of airfields requested per page of the OACIS listing
+    pub page_size: Option<u32>,
+
+    /// Download the charts of each page of the listing before fetching the next one
+    pub stream_listing: Option<bool>,
+
+    /// Maximum number of API requests per second (e.g. 2 or 0.5)
+    pub rate_limit: Option<f64>,
+
+    /// Stop a sync once this many downloads in a row failed with a 401, a 403 or a DNS error
+    pub max_consecutive_failures: Option<usize>,
+
+    /// Skip the charts whose download failed this many times in a row
+    pub quarantine_after: Option<u32>,
+
+    /// minisign secret key signing the SHA256SUMS manifest
+    pub signing_key: Option<String>,
+
+    /// Password of the minisign secret key, if it has one
+    pub signing_key_password: Option<String>,
+
+    /// OACI codes processed when none are given on the command line
+    pub oaci: Option<Vec<String>>,
+
+    /// Territories of the processed airfields (e.g. "metropole"), or "all"
+    pub territory: Option<Vec<String>>,
+
+    /// Stop a sync once it has run for this long (e.g. "1h")
+    pub max_duration: Option<String>,
+
+    /// Abandon the download of a file once it has run for this long (e.g. "2m")
+    pub file_timeout: Option<String>,
+
+    /// Skip the downloads the server answered with a 404 for this long (e.g. "7d")
+    pub skip_not_found: Option<String>,
+
+    /// Time between the syncs of the service installed by `daemon install-service` (e.g. "6h")
+    pub sync_interval: Option<String>,
+
+    /// Command run before each sync, aborting it when it fails
+    pub pre_sync: Option<String>,
+
+    /// Command run after each sync, with the outcome in `VAC_*` environment variables
+    pub post_sync: Option<String>,
+
+    /// Command run after each downloaded chart, described by `VAC_*` environment variables
+    pub post_download: Option<String>,
+
+    /// Raise a desktop notification after syncs that downloaded new versions or had failures
+    pub desktop_notifications: Option<bool>,
+
+    /// Named lists of OACI codes, selected with `--group`
+    pub groups: Option<BTreeMap<String, Vec<String>>>,
+
+    /// Storage backend for downloaded PDFs (defaults to the download directory)
+    pub storage: Option<StorageConfig>,
+
+    /// SMTP settings of the emails sent after syncs that downloaded new versions or had failures
+    pub email: Option<EmailConfig>,
+
+    /// Address and authentication of the HTTP server of `serve`
+    pub serve: Option<ServeConfig>,
+
+    /// Named sets of settings applied on top of the others with `--profile`
+    pub profile: Option<BTreeMap<String, Config>>,
 }
 
 impl Config {
-    /// Load configuration from the platform-specific config file
+    /// Load configuration from the platform-specific config file, or the one
+    /// of the portable root in portable mode
     ///
     /// Returns None if the config file doesn't exist or can't be read.
     /// Returns Some(Config) if the file exists and is valid TOML.
-    pub fn load() -> Option<Self> {
-        let config_path = Self::get_config_path()?;
+    pub fn load(portable_root: Option<&Path>) -> Option<Self> {
+        let config_path = Self::get_config_path(portable_root)?;
 
         if !config_path.exists() {
             return None;
         }
 
-        let contents = fs::read_to_string(&config_path).ok()?;
-        toml::from_str(&contents).ok()
+        Self::load_from(&config_path).ok()
+    }
+
+    /// Load configuration from a file, reporting why it cannot be used
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents).context(format!("Failed to parse {}", path.display()))
+    }
+
+    /// Keys of a configuration file that are not configuration keys, including in profiles
+    pub fn unknown_keys(contents: &str) -> Result<Vec<String>> {
+        let table: toml::Table = toml::from_str(contents)?;
+        let mut unknown: Vec<String> = table
+            .keys()
+            .filter(|key| !KEYS.contains(&key.as_str()))
+            .cloned()
+            .collect();
+
+        let profiles = table.get("profile").and_then(|p| p.as_table());
+        for (name, profile) in profiles.into_iter().flatten() {
+            let keys = profile.as_table().into_iter().flat_map(|p| p.keys());
+            // Profiles cannot be nested
+            unknown.extend(
+                keys.filter(|key| *key == "profile" || !KEYS.contains(&key.as_str()))
+                    .map(|key| format!("profile.{}.{}", name, key)),
+            );
+        }
+
+        Ok(unknown)
+    }
+
+    /// Apply the settings of a named profile on top of the top-level ones
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profiles = self.profile.take().unwrap_or_default();
+        let Some(profile) = profiles.get(name) else {
+            let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            if available.is_empty() {
+                anyhow::bail!("Unknown profile: {} (no profile is configured)", name);
+            }
+            anyhow::bail!(
+                "Unknown profile: {} (available: {})",
+                name,
+                available.join(", ")
+            );
+        };
+
+        let mut table = toml::Table::try_from(&*self)?;
+        table.extend(toml::Table::try_from(profile)?);
+        *self = table.try_into()?;
+        Ok(())
+    }
+
+    /// Override keys with `VAC_DOWNLOADER_*` environment variables
+    ///
+    /// Lists are comma-separated. The `groups`, `storage`, `email`, `serve` and `profile` tables
+    /// cannot be overridden.
+    pub fn apply_env(&mut self) -> Result<()> {
+        self.apply_env_from(|name| std::env::var(name).ok())
+    }
+
+    fn apply_env_from(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let get = |key: &str| var(&env_var_name(key));
+        let list = |value: String| -> Vec<String> {
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+
+        for (key, field) in [
+            ("db_path", &mut self.db_path),
+            ("db_passphrase", &mut self.db_passphrase),
+            ("db_passphrase_command", &mut self.db_passphrase_command),
+            ("download_dir", &mut self.download_dir),
+            ("file_name_template", &mut self.file_name_template),
+            ("max_cache_size", &mut self.max_cache_size),
+            ("api_url", &mut self.api_url),
+            ("proxy", &mut self.proxy),
+            ("timeout", &mut self.timeout),
+            ("connect_timeout", &mut self.connect_timeout),
+            ("cache_ttl", &mut self.cache_ttl),
+            ("max_duration", &mut self.max_duration),
+            ("file_timeout", &mut self.file_timeout),
+            ("skip_not_found", &mut self.skip_not_found),
+            ("sync_interval", &mut self.sync_interval),
+            ("signing_key", &mut self.signing_key),
+            ("signing_key_password", &mut self.signing_key_password),
+            ("pre_sync", &mut self.pre_sync),
+            ("post_sync", &mut self.post_sync),
+            ("post_download", &mut self.post_download),
+        ] {
+            if let Some(value) = get(key) {
+                *field = Some(value);
+            }
+        }
+
+        for (key, field) in [
+            ("favorites", &mut self.favorites),
+            ("api_fallback_urls", &mut self.api_fallback_urls),
+            ("oaci", &mut self.oaci),
+            ("territory", &mut self.territory),
+        ] {
+            if let Some(value) = get(key) {
+                *field = Some(list(value));
+            }
+        }
+
+        for (key, field) in [
+            ("versioned_file_names", &mut self.versioned_file_names),
+            ("compress_versions", &mut self.compress_versions),
+            ("stream_listing", &mut self.stream_listing),
+            ("desktop_notifications", &mut self.desktop_notifications),
+        ] {
+            if let Some(value) = get(key) {
+                *field = Some(match value.to_lowercase().as_str() {
+                    "1" | "true" | "yes" => true,
+                    "0" | "false" | "no" => false,
+                    _ => anyhow::bail!("Invalid {}: expected true or false", env_var_name(key)),
+                });
+            }
+        }
+        if let Some(value) = get("max_consecutive_failures") {
+            self.max_consecutive_failures = Some(value.parse().context(format!(
+                "Invalid {}",
+                env_var_name("max_consecutive_failures")
+            ))?);
+        }
+        if let Some(value) = get("quarantine_after") {
+            self.quarantine_after = Some(
+                value
+                    .parse()
+                    .context(format!("Invalid {}", env_var_name("quarantine_after")))?,
+            );
+        }
+        if let Some(value) = get("rate_limit") {
+            self.rate_limit = Some(
+                value
+                    .parse()
+                    .context(format!("Invalid {}", env_var_name("rate_limit")))?,
+            );
+        }
+        for (key, field) in [
+            ("retries", &mut self.retries),
+            ("page_size", &mut self.page_size),
+            ("keep_versions", &mut self.keep_versions),
+            ("keep_days", &mut self.keep_days),
+        ] {
+            if let Some(value) = get(key) {
+                *field = Some(
+                    value
+                        .parse()
+                        .context(format!("Invalid {}", env_var_name(key)))?,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// OACI codes of a named group
+    pub fn group(&self, name: &str) -> Result<&[String]> {
+        let groups = self.groups.as_ref();
+        if let Some(codes) = groups.and_then(|groups| groups.get(name)) {
+            return Ok(codes);
+        }
+
+        let available: Vec<&str> = groups
+            .into_iter()
+            .flat_map(|groups| groups.keys())
+            .map(String::as_str)
+            .collect();
+        if available.is_empty() {
+            anyhow::bail!("Unknown group: {} (no group is configured)", name);
+        }
+        anyhow::bail!(
+            "Unknown group: {} (available: {})",
+            name,
+            available.join(", ")
+        )
+    }
+
+    /// Timeout of each HTTP request
+    pub fn timeout(&self) -> Result<Option<Duration>> {
+        parse_duration_key("timeout", &self.timeout)
+    }
+
+    /// Timeout for establishing connections
+    pub fn connect_timeout(&self) -> Result<Option<Duration>> {
+        parse_duration_key("connect_timeout", &self.connect_timeout)
+    }
+
+    /// Lifetime of the fetched OACIS listing
+    pub fn cache_ttl(&self) -> Result<Option<Duration>> {
+        parse_duration_key("cache_ttl", &self.cache_ttl)
+    }
+
+    /// Time budget of a sync
+    pub fn max_duration(&self) -> Result<Option<Duration>> {
+        parse_duration_key("max_duration", &self.max_duration)
+    }
+
+    /// Time budget of each file download
+    pub fn file_timeout(&self) -> Result<Option<Duration>> {
+        parse_duration_key("file_timeout", &self.file_timeout)
+    }
+
+    /// Time during which downloads answered with a 404 are skipped
+    pub fn skip_not_found(&self) -> Result<Option<Duration>> {
+        parse_duration_key("skip_not_found", &self.skip_not_found)
+    }
+
+    /// Time between the syncs of the installed service
+    pub fn sync_interval(&self) -> Result<Option<Duration>> {
+        parse_duration_key("sync_interval", &self.sync_interval)
+    }
+
+    /// Passphrase of the encrypted database, from `db_passphrase` or the
+    /// output of `db_passphrase_command`
+    pub fn db_passphrase(&self) -> Result<Option<String>> {
+        if let Some(passphrase) = &self.db_passphrase {
+            return Ok(Some(passphrase.clone()));
+        }
+        let Some(command) = &self.db_passphrase_command else {
+            return Ok(None);
+        };
+
+        let output = hooks::shell(command)
+            .stderr(Stdio::inherit())
+            .output()
+            .context("Failed to run db_passphrase_command")?;
+        if !output.status.success() {
+            anyhow::bail!("db_passphrase_command failed ({})", output.status);
+        }
+        let passphrase = String::from_utf8(output.stdout)
+            .context("db_passphrase_command printed an invalid passphrase")?;
+        let passphrase = passphrase.trim_end_matches(['\r', '\n']);
+        if passphrase.is_empty() {
+            anyhow::bail!("db_passphrase_command printed an empty passphrase");
+        }
+        Ok(Some(passphrase.to_string()))
+    }
+
+    /// minisign key signing the SHA256SUMS manifest
+    pub fn signing_key(&self) -> Result<Option<SigningKey>> {
+        self.signing_key
+            .as_deref()
+            .map(|path| SigningKey::from_file(path, self.signing_key_password.as_deref()))
+            .transpose()
+    }
+
+    /// Notifier emailing the report of syncs
+    pub fn email_notifier(&self) -> Result<Option<EmailNotifier>> {
+        self.email
+            .as_ref()
+            .map(|email| EmailNotifier::new(email).context("Invalid [email] settings"))
+            .transpose()
+    }
+
+    /// Username and password of the basic authentication of `serve`, checking the other
+    /// settings of the [serve] table
+    pub fn serve_credentials(&self) -> Result<Option<(String, String)>> {
+        let Some(serve) = &self.serve else {
+            return Ok(None);
+        };
+        if serve.token.as_deref() == Some("") {
+            anyhow::bail!("Invalid [serve] settings: empty token");
+        }
+        match (&serve.username, &serve.password) {
+            (Some(username), Some(password)) => Ok(Some((username.clone(), password.clone()))),
+            (None, None) => Ok(None),
+            _ => anyhow::bail!(
+                "Invalid [serve] settings: username and password must be set together"
+            ),
+        }
+    }
+
+    /// Commands run around syncs and downloads
+    pub fn hooks(&self) -> SyncHooks {
+        SyncHooks {
+            pre_sync: self.pre_sync.clone(),
+            post_sync: self.post_sync.clone(),
+            post_download: self.post_download.clone(),
+        }
+    }
+
+    /// Territories of the processed airfields, empty for all of them
+    pub fn territories(&self) -> Result<Vec<Territory>> {
+        let names = self.territory.iter().flatten();
+        if names.clone().any(|name| name.eq_ignore_ascii_case("all")) {
+            return Ok(Vec::new());
+        }
+        names
+            .map(|name| name.parse().context("Invalid territory"))
+            .collect()
+    }
+
+    /// Maximum total size of cached PDFs, in bytes
+    pub fn max_cache_size(&self) -> Result<Option<u64>> {
+        self.max_cache_size
+            .as_deref()
+            .map(|value| parse_size(value).context("Invalid max_cache_size"))
+            .transpose()
+    }
+
+    /// Template for stored file names, from `file_name_template` or `versioned_file_names`
+    pub fn file_name_template(&self) -> Result<Option<FileNameTemplate>> {
+        match (
+            self.versioned_file_names.unwrap_or(false),
+            &self.file_name_template,
+        ) {
+            (true, Some(_)) => {
+                anyhow::bail!("Versioned file names cannot be combined with file_name_template")
+            }
+            (true, None) => Ok(Some(FileNameTemplate::versioned())),
+            (false, Some(template)) => Ok(Some(
+                FileNameTemplate::new(template).context("Invalid file_name_template")?,
+            )),
+            (false, None) => Ok(None),
+        }
+    }
+
+    /// Proxy for all requests
+    pub fn proxy(&self) -> Result<Option<reqwest::Proxy>> {
+        self.proxy
+            .as_deref()
+            .map(|proxy| reqwest::Proxy::all(proxy).context(format!("Invalid proxy: {}", proxy)))
+            .transpose()
+    }
+
+    /// Check the values of the configuration, describing each problem found
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |result: Result<()>| {
+            if let Err(e) = result {
+                problems.push(format!("{:#}", e));
+            }
+        };
+
+        check(self.timeout().map(drop));
+        check(self.connect_timeout().map(drop));
+        check(self.cache_ttl().map(drop));
+        check(self.max_duration().map(drop));
+        check(self.file_timeout().map(drop));
+        check(self.skip_not_found().map(drop));
+        check(self.sync_interval().map(drop));
+        check(self.max_cache_size().map(drop));
+        check(self.file_name_template().map(drop));
+        check(self.proxy().map(drop));
+        check(self.signing_key().map(drop));
+        check(self.email_notifier().map(drop));
+        check(self.serve_credentials().map(drop));
+        check(self.territories().map(drop));
+        if self.db_passphrase.is_some() && self.db_passphrase_command.is_some() {
+            check(Err(anyhow::anyhow!(
+                "db_passphrase cannot be combined with db_passphrase_command"
+            )));
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            check(RateLimiter::new(rate_limit).map(drop));
+        }
+        if self.page_size == Some(0) {
+            check(Err(anyhow::anyhow!(
+                "Invalid page_size: must be at least 1"
+            )));
+        }
+        for url in self
+            .api_url
+            .iter()
+            .chain(self.api_fallback_urls.iter().flatten())
+        {
+            check(
+                reqwest::Url::parse(url)
+                    .map(drop)
+                    .context(format!("Invalid API URL: {}", url)),
+            );
+        }
+
+        for (name, profile) in self.profile.iter().flatten() {
+            problems.extend(
+                profile
+                    .validate()
+                    .into_iter()
+                    .map(|problem| format!("profile.{}: {}", name, problem)),
+            );
+        }
+
+        problems
+    }
+
+    /// Resolve relative `db_path` and `download_dir` settings against `root`
+    ///
+    /// In portable mode, they are relative to the portable root rather than to
+    /// the working directory, which differs between machines.
+    pub fn resolve_paths(&mut self, root: &Path) {
+        for path in [&mut self.db_path, &mut self.download_dir]
+            .into_iter()
+            .flatten()
+        {
+            if Path::new(path.as_str()).is_relative() {
+                *path = root.join(&*path).to_string_lossy().to_string();
+            }
+        }
     }
 
     /// Get the platform-specific configuration file path
@@ -54,34 +650,430 @@ impl Config {
     /// - Linux: ~/.config/vac-downloader/config.toml
     /// - macOS: ~/Library/Application Support/vac-downloader/config.toml
     /// - Windows: %APPDATA%\vac-downloader\config.toml
-    fn get_config_path() -> Option<PathBuf> {
+    /// - Portable mode: config.toml in the portable root
+    pub fn get_config_path(portable_root: Option<&Path>) -> Option<PathBuf> {
+        if let Some(root) = portable_root {
+            return Some(root.join("config.toml"));
+        }
         let config_dir = dirs::config_dir()?;
         Some(config_dir.join("vac-downloader").join("config.toml"))
     }
 
     /// Get the configuration file path as a string for display purposes
-    pub fn get_config_path_display() -> String {
-        Self::get_config_path()
+    pub fn get_config_path_display(portable_root: Option<&Path>) -> String {
+        Self::get_config_path(portable_root)
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_else(|| "Unable to determine config path".to_string())
     }
 }
 
+/// Directory of the executable, holding the configuration, database and
+/// downloads in portable mode
+pub fn portable_root() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("Failed to locate the executable")?;
+    let exe = exe.canonicalize().unwrap_or(exe);
+    exe.parent()
+        .map(Path::to_path_buf)
+        .context("Failed to locate the directory of the executable")
+}
+
+/// Default locations of the database and the download directory
+///
+/// - Linux: ~/.local/share/vac-downloader/{vac_cache.db,downloads}
+/// - macOS: ~/Library/Application Support/vac-downloader/{vac_cache.db,downloads}
+/// - Windows: %APPDATA%\vac-downloader\{vac_cache.db,downloads}
+/// - Portable mode: {vac_cache.db,downloads} in the portable root
+///
+/// Falls back to the working directory when the platform has no data directory.
+pub fn default_locations(portable_root: Option<&Path>) -> (PathBuf, PathBuf) {
+    if let Some(root) = portable_root {
+        return (root.join("vac_cache.db"), root.join("downloads"));
+    }
+    match dirs::data_dir() {
+        Some(data_dir) => {
+            let root = data_dir.join("vac-downloader");
+            (root.join("vac_cache.db"), root.join("downloads"))
+        }
+        None => (
+            PathBuf::from(LEGACY_DB_PATH),
+            PathBuf::from(LEGACY_DOWNLOAD_DIR),
+        ),
+    }
+}
+
+/// Name of the environment variable overriding a configuration key
+fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.to_uppercase())
+}
+
+/// Parse an optional duration setting, naming the key in errors
+fn parse_duration_key(key: &str, value: &Option<String>) -> Result<Option<Duration>> {
+    value
+        .as_deref()
+        .map(|value| parse_duration(value).context(format!("Invalid {}", key)))
+        .transpose()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use vac_downloader::SmtpSecurity;
 
     #[test]
     fn test_config_path_exists() {
         // Just verify we can get a config path
-        let path = Config::get_config_path();
+        let path = Config::get_config_path(None);
         assert!(path.is_some());
     }
 
+    #[test]
+    fn test_portable_paths() {
+        let root = Path::new("/media/usb/vac");
+        assert_eq!(
+            Config::get_config_path(Some(root)),
+            Some(root.join("config.toml"))
+        );
+        assert_eq!(
+            default_locations(Some(root)),
+            (root.join("vac_cache.db"), root.join("downloads"))
+        );
+
+        let absolute = std::env::temp_dir()
+            .join("pdfs")
+            .to_string_lossy()
+            .to_string();
+        let mut config = Config {
+            db_path: Some("club.db".to_string()),
+            download_dir: Some(absolute.clone()),
+            ..Default::default()
+        };
+        config.resolve_paths(root);
+        assert_eq!(
+            config.db_path.map(PathBuf::from),
+            Some(root.join("club.db"))
+        );
+        assert_eq!(config.download_dir, Some(absolute));
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
         assert!(config.db_path.is_none());
         assert!(config.download_dir.is_none());
+        assert!(config.storage.is_none());
+    }
+
+    #[test]
+    fn test_storage_config() {
+        let config: Config = toml::from_str(
+            r#"
+            download_dir = "./downloads"
+
+            [storage]
+            backend = "s3"
+            endpoint = "https://s3.eu-west-3.amazonaws.com"
+            bucket = "charts"
+            region = "eu-west-3"
+            access_key = "key"
+            secret_key = "secret"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(config.storage, Some(StorageConfig::S3 { .. })));
+    }
+
+    #[test]
+    fn test_territories() {
+        let config: Config = toml::from_str(r#"territory = ["metropole", "Antilles"]"#).unwrap();
+        assert_eq!(
+            config.territories().unwrap(),
+            [Territory::Metropole, Territory::Antilles]
+        );
+        let config: Config = toml::from_str(r#"territory = ["metropole", "all"]"#).unwrap();
+        assert!(config.territories().unwrap().is_empty());
+        let config: Config = toml::from_str(r#"territory = ["mars"]"#).unwrap();
+        assert_eq!(config.validate().len(), 1);
+    }
+
+    #[test]
+    fn test_email_config() {
+        let config: Config = toml::from_str(
+            r#"
+            [email]
+            server = "smtp.club.example"
+            username = "vac@club.example"
+            password = "secret"
+            from = "VAC Downloader <vac@club.example>"
+            to = ["chief@club.example"]
+            "#,
+        )
+        .unwrap();
+        let email = config.email.as_ref().unwrap();
+        assert_eq!(email.security, SmtpSecurity::Starttls);
+        assert!(config.validate().is_empty());
+
+        let config: Config = toml::from_str(
+            r#"
+            [email]
+            server = "smtp.club.example"
+            from = "VAC Downloader <vac@club.example>"
+            to = []
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.validate().len(), 1);
+    }
+
+    #[test]
+    fn test_serve_config() {
+        let config: Config = toml::from_str(
+            r#"
+            [serve]
+            bind = "0.0.0.0"
+            username = "club"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+        let serve = config.serve.as_ref().unwrap();
+        assert_eq!(serve.bind, Some(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(
+            config.serve_credentials().unwrap(),
+            Some(("club".to_string(), "secret".to_string()))
+        );
+        assert!(config.validate().is_empty());
+
+        let config: Config = toml::from_str("[serve]\nusername = \"club\"").unwrap();
+        assert_eq!(config.validate().len(), 1);
+        assert!(toml::from_str::<Config>("[serve]\nbind = \"club.local\"").is_err());
+    }
+
+    #[test]
+    fn test_api_urls() {
+        let config: Config = toml::from_str(
+            r#"
+            api_url = "https://sia.example.com"
+            api_fallback_urls = ["https://mirror1.example.com", "https://mirror2.example.com"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.api_url.as_deref(), Some("https://sia.example.com"));
+        assert_eq!(config.api_fallback_urls.map(|urls| urls.len()), Some(2));
+    }
+
+    #[test]
+    fn test_max_cache_size() {
+        let config: Config = toml::from_str(r#"max_cache_size = "500MB""#).unwrap();
+        assert_eq!(config.max_cache_size.as_deref(), Some("500MB"));
+    }
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config: Config = toml::from_str(EXAMPLE_CONFIG).unwrap();
+        assert!(config.validate().is_empty());
+        assert!(Config::unknown_keys(EXAMPLE_CONFIG).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_keys_match_fields() {
+        let config = Config {
+            db_path: Some(String::new()),
+            db_passphrase: Some(String::new()),
+            db_passphrase_command: Some(String::new()),
+            download_dir: Some(String::new()),
+            file_name_template: Some(String::new()),
+            versioned_file_names: Some(false),
+            favorites: Some(Vec::new()),
+            max_cache_size: Some(String::new()),
+            keep_versions: Some(3),
+            keep_days: Some(90),
+            compress_versions: Some(true),
+            api_url: Some(String::new()),
+            api_fallback_urls: Some(Vec::new()),
+            proxy: Some(String::new()),
+            timeout: Some(String::new()),
+            connect_timeout: Some(String::new()),
+            retries: Some(0),
+            cache_ttl: Some(String::new()),
+            page_size: Some(100),
+            stream_listing: Some(true),
+            rate_limit: Some(1.0),
+            max_consecutive_failures: Some(5),
+            quarantine_after: Some(3),
+            signing_key: Some(String::new()),
+            signing_key_password: Some(String::new()),
+            pre_sync: Some(String::new()),
+            post_sync: Some(String::new()),
+            post_download: Some(String::new()),
+            desktop_notifications: Some(true),
+            oaci: Some(Vec::new()),
+            territory: Some(Vec::new()),
+            max_duration: Some(String::new()),
+            file_timeout: Some(String::new()),
+            skip_not_found: Some(String::new()),
+            sync_interval: Some(String::new()),
+            groups: Some(BTreeMap::new()),
+            storage: Some(StorageConfig::Local),
+            email: Some(EmailConfig {
+                server: String::new(),
+                port: None,
+                security: Default::default(),
+                username: None,
+                password: None,
+                from: String::new(),
+                to: Vec::new(),
+            }),
+            serve: Some(ServeConfig::default()),
+            profile: Some(BTreeMap::from([("tablet".to_string(), Config::default())])),
+        };
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(Config::unknown_keys(&serialized).unwrap().is_empty());
+        let table: toml::Table = toml::from_str(&serialized).unwrap();
+        assert_eq!(table.len(), KEYS.len());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let unknown =
+            Config::unknown_keys("db_path = \"a.db\"\ndownload_directory = \"pdfs\"").unwrap();
+        assert_eq!(unknown, vec!["download_directory".to_string()]);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let contents = r#"
+            db_path = "main.db"
+            download_dir = "./pdfs"
+            retries = 2
+
+            [profile.tablet]
+            db_path = "tablet.db"
+            oaci = ["LFPN", "LFPT"]
+
+            [profile.archive]
+            download_dir = "/srv/archive"
+            versioned_file_names = true
+            "#;
+        let config: Config = toml::from_str(contents).unwrap();
+        assert!(Config::unknown_keys(contents).unwrap().is_empty());
+        assert!(config.validate().is_empty());
+
+        let mut tablet: Config = toml::from_str(contents).unwrap();
+        tablet.apply_profile("tablet").unwrap();
+        assert_eq!(tablet.db_path.as_deref(), Some("tablet.db"));
+        assert_eq!(tablet.download_dir.as_deref(), Some("./pdfs"));
+        assert_eq!(tablet.retries, Some(2));
+        assert_eq!(tablet.oaci.map(|oaci| oaci.len()), Some(2));
+
+        let mut unknown: Config = toml::from_str(contents).unwrap();
+        let error = unknown.apply_profile("glider").unwrap_err();
+        assert!(error.to_string().contains("archive, tablet"));
+    }
+
+    #[test]
+    fn test_groups() {
+        let config: Config = toml::from_str(
+            r#"
+            [groups]
+            ile_de_france = ["LFPN", "LFPT", "LFPZ"]
+            bretagne = ["LFRB", "LFRN"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.group("ile_de_france").unwrap().len(), 3);
+        let error = config.group("alpes").unwrap_err();
+        assert!(error.to_string().contains("bretagne, ile_de_france"));
+        assert!(Config::default().group("alpes").is_err());
+    }
+
+    #[test]
+    fn test_unknown_profile_keys() {
+        let unknown = Config::unknown_keys(
+            r#"
+            [profile.tablet]
+            db_pth = "tablet.db"
+
+            [profile.tablet.profile.nested]
+            db_path = "nested.db"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            unknown,
+            vec!["profile.tablet.db_pth", "profile.tablet.profile"]
+        );
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut config: Config = toml::from_str(
+            r#"
+            db_path = "file.db"
+            download_dir = "./pdfs"
+            "#,
+        )
+        .unwrap();
+        config
+            .apply_env_from(|name| match name {
+                "VAC_DOWNLOADER_DB_PATH" => Some("env.db".to_string()),
+                "VAC_DOWNLOADER_OACI" => Some("LFPG, LFPO".to_string()),
+                "VAC_DOWNLOADER_RETRIES" => Some("3".to_string()),
+                "VAC_DOWNLOADER_PAGE_SIZE" => Some("100".to_string()),
+                "VAC_DOWNLOADER_RATE_LIMIT" => Some("0.5".to_string()),
+                "VAC_DOWNLOADER_VERSIONED_FILE_NAMES" => Some("yes".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config.db_path.as_deref(), Some("env.db"));
+        assert_eq!(config.download_dir.as_deref(), Some("./pdfs"));
+        assert_eq!(
+            config.oaci,
+            Some(vec!["LFPG".to_string(), "LFPO".to_string()])
+        );
+        assert_eq!(config.retries, Some(3));
+        assert_eq!(config.page_size, Some(100));
+        assert_eq!(config.rate_limit, Some(0.5));
+        assert_eq!(config.versioned_file_names, Some(true));
+
+        let result = config
+            .apply_env_from(|name| (name == "VAC_DOWNLOADER_RETRIES").then(|| "many".to_string()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config: Config = toml::from_str(
+            r#"
+            timeout = "soon"
+            max_cache_size = "2GB"
+            versioned_file_names = true
+            file_name_template = "{oaci}.pdf"
+            "#,
+        )
+        .unwrap();
+        let problems = config.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("Invalid timeout"));
+
+        let config: Config = toml::from_str("rate_limit = 0\npage_size = 0").unwrap();
+        let problems = config.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("Invalid rate limit"));
+        let config: Config = toml::from_str("rate_limit = 2").unwrap();
+        assert_eq!(config.rate_limit, Some(2.0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_db_passphrase_command() {
+        let config: Config = toml::from_str(r#"db_passphrase_command = "echo secret""#).unwrap();
+        assert_eq!(config.db_passphrase().unwrap().as_deref(), Some("secret"));
+
+        let config: Config = toml::from_str(r#"db_passphrase_command = "exit 3""#).unwrap();
+        assert!(config.db_passphrase().is_err());
+
+        let config: Config =
+            toml::from_str("db_passphrase = \"a\"\ndb_passphrase_command = \"echo b\"").unwrap();
+        assert_eq!(config.validate().len(), 1);
     }
 }
@@ -0,0 +1,372 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Translations of the messages printed by the command line
+//!
+//! Messages are looked up by their English text, which is also the fallback
+//! when a translation is missing. Log messages and `--help` stay in English.
+
+use clap::ValueEnum;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Language of the printed messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Lang {
+    /// Language of the user's locale (`LC_ALL`, `LC_MESSAGES` or `LANG` on
+    /// Unix, the display language on Windows and macOS), English by default
+    pub fn detect() -> Self {
+        sys_locale::get_locale()
+            .map(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Language of a locale name such as "fr_FR.UTF-8" or "fr-CA"
+    fn from_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        if language.eq_ignore_ascii_case("fr") {
+            Lang::Fr
+        } else {
+            Lang::En
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Select the language of the messages, once at startup
+pub fn init(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// Translate a message into the selected language
+pub fn tr(message: &'static str) -> &'static str {
+    match LANG.get() {
+        Some(Lang::Fr) => FRENCH
+            .iter()
+            .find(|(english, _)| *english == message)
+            .map_or(message, |(_, french)| french),
+        _ => message,
+    }
+}
+
+/// Replace the `{}` placeholders of a message with the arguments, in order
+pub fn fill(message: &str, args: &[&dyn Display]) -> String {
+    let mut parts = message.split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+/// Translate a message, filling its `{}` placeholders with the arguments
+macro_rules! t {
+    ($message:literal) => {
+        $crate::i18n::tr($message)
+    };
+    ($message:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::tr($message), &[$(&$arg),+])
+    };
+}
+pub(crate) use t;
+
+/// French translations, by English message
+const FRENCH: &[(&str, &str)] = &[
+    // Sync
+    ("No airfield selected", "Aucun aérodrome sélectionné"),
+    ("Total entries:", "Cartes examinées :"),
+    ("Up to date:", "À jour :"),
+    ("Verified:", "Vérifiées :"),
+    ("Downloaded:", "Téléchargées :"),
+    (
+        "Redownloaded (corrupted/missing):",
+        "Téléchargées à nouveau (corrompues ou absentes) :",
+    ),
+    ("Failed:", "Échecs :"),
+    ("not found", "introuvable(s)"),
+    ("authentication", "authentification"),
+    ("network", "réseau"),
+    ("server error", "erreur serveur"),
+    ("other", "autre(s)"),
+    (
+        "Skipped (not found recently):",
+        "Ignorées (introuvables récemment) :",
+    ),
+    ("Quarantined:", "En quarantaine :"),
+    ("Evicted:", "Évincées :"),
+    ("Deferred (time budget):", "Reportées (durée maximale) :"),
+    ("Supplements:", "Suppléments :"),
+    ("Downloaded size:", "Taille téléchargée :"),
+    ("Throughput:", "Débit :"),
+    ("Duration:", "Durée :"),
+    ("{} downloaded, {} removed", "{} téléchargé(s), {} supprimé(s)"),
+    // Maintenance
+    ("\n{} orphaned file(s) found", "\n{} fichier(s) orphelin(s) trouvé(s)"),
+    (
+        "\n{} orphaned file(s) removed",
+        "\n{} fichier(s) orphelin(s) supprimé(s)",
+    ),
+    (
+        "\n{} chart(s) verified, {} with problems",
+        "\n{} carte(s) vérifiée(s), {} avec des problèmes",
+    ),
+    (
+        "Download them again with: vac-downloader sync --force-oaci {}",
+        "Pour les télécharger à nouveau : vac-downloader sync --force-oaci {}",
+    ),
+    ("\n{} chart(s) deleted", "\n{} carte(s) supprimée(s)"),
+    (
+        "\n{} airfield(s) exported to {}",
+        "\n{} aérodrome(s) exporté(s) dans {}",
+    ),
+    (
+        "\n{} chart(s) packed into {}",
+        "\n{} carte(s) empaquetée(s) dans {}",
+    ),
+    ("Up to date ({} charts)", "À jour ({} cartes)"),
+    (
+        "{} update(s) available, {} chart(s) up to date",
+        "{} mise(s) à jour disponible(s), {} carte(s) à jour",
+    ),
+    (
+        "Wrote {} with {} chart(s), {} page(s)",
+        "{} écrit avec {} carte(s), {} page(s)",
+    ),
+    (
+        "Wrote {} with {} chart(s), {} sheet side(s)",
+        "{} écrit avec {} carte(s), {} face(s) de feuille",
+    ),
+    (
+        "\n{} cycle(s) and {} chart update(s) written to {}",
+        "\n{} cycle(s) et {} mise(s) à jour de carte écrits dans {}",
+    ),
+    ("Wrote {} man page(s) to {}", "{} page(s) de manuel écrite(s) dans {}"),
+    (
+        "Wrote an encrypted copy of {} to {}, set it as db_path to use it",
+        "Copie chiffrée de {} écrite dans {}, à indiquer comme db_path pour l'utiliser",
+    ),
+    (
+        "Skipping {}: a location is configured ({})",
+        "{} ignoré : un emplacement est configuré ({})",
+    ),
+    ("Moved {} to {}", "{} déplacé vers {}"),
+    ("Nothing to migrate", "Rien à migrer"),
+    // Configuration
+    ("Wrote {}", "{} écrit"),
+    ("No configuration file at {}", "Aucun fichier de configuration à {}"),
+    ("{} is valid", "{} est valide"),
+    // Desktop notifications
+    (
+        "VAC sync: {} chart(s) downloaded",
+        "Synchro VAC : {} carte(s) téléchargée(s)",
+    ),
+    (
+        "VAC sync: {} chart(s) downloaded, {} failed",
+        "Synchro VAC : {} carte(s) téléchargée(s), {} échec(s)",
+    ),
+    ("Updated: {}", "Mises à jour : {}"),
+    ("Failed: {}", "Échecs : {}"),
+    ("VAC sync failed", "Échec de la synchro VAC"),
+    ("{} and {} more", "{} et {} autre(s)"),
+    // daemon
+    (
+        "Service installed, syncing every {}",
+        "Service installé, synchronisation toutes les {}",
+    ),
+    (
+        "Run `loginctl enable-linger` to also sync while you are logged out",
+        "Lancez `loginctl enable-linger` pour synchroniser aussi en votre absence",
+    ),
+    ("Removed {}", "{} supprimé"),
+    ("Service uninstalled", "Service désinstallé"),
+    // Tables
+    ("OACI", "OACI"),
+    ("Type", "Type"),
+    ("Version", "Version"),
+    ("Priority", "Priorité"),
+    ("Attempts", "Essais"),
+    ("Source", "Source"),
+    ("Started (UTC)", "Début (UTC)"),
+    ("Duration", "Durée"),
+    ("Downloaded", "Téléch."),
+    ("Failed", "Échecs"),
+    ("Size", "Taille"),
+    ("Throughput", "Débit"),
+    ("City", "Ville"),
+    ("Charts", "Cartes"),
+    ("Region", "Région"),
+    ("Airfields", "Aérodromes"),
+    ("Reason", "Raison"),
+    ("No pending downloads", "Aucun téléchargement en attente"),
+    ("No airfield found", "Aucun aérodrome trouvé"),
+    ("No outdated chart", "Aucune carte obsolète"),
+    ("No sync recorded yet", "Aucune synchronisation enregistrée"),
+    ("No change recorded yet", "Aucune modification enregistrée"),
+    (
+        "{} rolled back from version {} to {}",
+        "{} ramené de la version {} à la {}",
+    ),
+    (
+        "Syncs keep this version until: vac-downloader sync --force-oaci {}",
+        "Les synchronisations conservent cette version jusqu'à : vac-downloader sync --force-oaci {}",
+    ),
+    (
+        "No retention policy set: keep_versions, keep_days and compress_versions are not configured",
+        "Aucune politique de rétention : keep_versions, keep_days et compress_versions ne sont pas configurés",
+    ),
+    (
+        "{} previous version(s) to compress",
+        "{} version(s) précédente(s) à compresser",
+    ),
+    (
+        "{} previous version(s) compressed",
+        "{} version(s) précédente(s) compressée(s)",
+    ),
+    (
+        "Version {} of {} written to {}",
+        "Version {} de {} écrite dans {}",
+    ),
+    (
+        "\n{} previous version(s) to remove, {} to free",
+        "\n{} version(s) précédente(s) à supprimer, {} à libérer",
+    ),
+    (
+        "\n{} previous version(s) removed, {} freed",
+        "\n{} version(s) précédente(s) supprimée(s), {} libéré(s)",
+    ),
+    (
+        "\n{} identical file(s) to link, {} to save",
+        "\n{} fichier(s) identique(s) à lier, {} à économiser",
+    ),
+    (
+        "\n{} identical file(s) linked, {} saved",
+        "\n{} fichier(s) identique(s) lié(s), {} économisé(s)",
+    ),
+    ("Time (UTC)", "Heure (UTC)"),
+    ("Chart", "Carte"),
+    ("File", "Fichier"),
+    // Doctor
+    ("AUTH header signature", "Signature de l'en-tête AUTH"),
+    ("Basic authentication", "Authentification Basic"),
+    ("{} accepted", "{} acceptée"),
+    ("{} failed", "{} en échec"),
+    (
+        "Basic authentication not checked, as the signed request failed",
+        "Authentification Basic non vérifiée, la requête signée ayant échoué",
+    ),
+    (
+        "Basic authentication not checked, as the listing has no chart",
+        "Authentification Basic non vérifiée, la liste ne contenant aucune carte",
+    ),
+    // AIRAC cycles
+    (
+        "Current cycle: AIRAC {}, {} to {}",
+        "Cycle en cours :  AIRAC {}, du {} au {}",
+    ),
+    (
+        "Next cycle:    AIRAC {}, begins {} (in {} days)",
+        "Cycle suivant :   AIRAC {}, débute le {} (dans {} jours)",
+    ),
+    (
+        "No cached chart expires when the next cycle begins",
+        "Aucune carte en cache n'expire au début du prochain cycle",
+    ),
+    (
+        "{} cached chart(s) expire when AIRAC {} begins, if a new edition is published:",
+        "{} carte(s) en cache expirent au début de l'AIRAC {}, si une nouvelle édition est publiée :",
+    ),
+    (
+        "  {} {} {} (effective {}, AIRAC {})",
+        "  {} {} {} (en vigueur le {}, AIRAC {})",
+    ),
+    (
+        "  ... {} more, list them with --all",
+        "  ... {} de plus, à lister avec --all",
+    ),
+    (
+        "{} cached chart(s) already published for a later cycle:",
+        "{} carte(s) en cache déjà publiée(s) pour un cycle ultérieur :",
+    ),
+    ("  {} {} {} (effective {})", "  {} {} {} (en vigueur le {})"),
+    (
+        "{} cached chart(s) have no effective date in their version",
+        "{} carte(s) en cache sans date d'entrée en vigueur dans leur version",
+    ),
+    // Storage
+    ("Storage usage", "Espace utilisé"),
+    ("Airfields:", "Aérodromes :"),
+    ("Charts:", "Cartes :"),
+    ("Cached size:", "Taille en cache :"),
+    ("Stored size:", "Taille stockée :"),
+    ("Database size:", "Taille de la base :"),
+    ("Missing files: {}", "Fichiers manquants : {}"),
+    ("... and {} more", "... et {} de plus"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(Lang::from_locale("fr_FR.UTF-8"), Lang::Fr);
+        assert_eq!(Lang::from_locale("fr-CA"), Lang::Fr);
+        assert_eq!(Lang::from_locale("FR"), Lang::Fr);
+        assert_eq!(Lang::from_locale("en_GB.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("C"), Lang::En);
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(fill("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(fill("no placeholder", &[]), "no placeholder");
+    }
+
+    #[test]
+    fn test_french_catalog() {
+        for (index, (english, french)) in FRENCH.iter().enumerate() {
+            assert_eq!(
+                english.matches("{}").count(),
+                french.matches("{}").count(),
+                "placeholders of {:?}",
+                english
+            );
+            assert!(
+                !FRENCH[..index].iter().any(|(other, _)| other == english),
+                "duplicate {:?}",
+                english
+            );
+        }
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::style;
+use clap::ValueEnum;
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Output format of log messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable messages
+    #[default]
+    Plain,
+    /// One JSON object per event, written to stderr
+    Json,
+}
+
+/// Filter directives for a verbosity level (negative when quiet)
+fn filter_directives(verbosity: i8) -> &'static str {
+    match verbosity {
+        i8::MIN..=-1 => "warn",
+        0 => "warn,vac_downloader=info",
+        1 => "info,vac_downloader=debug",
+        2 => "debug,vac_downloader=trace",
+        _ => "trace",
+    }
+}
+
+/// Install the global subscriber for log messages
+///
+/// `RUST_LOG` takes precedence over the verbosity flags when it is set.
+/// Plain messages go to stdout, except warnings and errors which go to stderr.
+pub fn init(verbosity: i8, format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(filter_directives(verbosity)));
+    let plain_writer = std::io::stderr
+        .with_max_level(Level::WARN)
+        .or_else(std::io::stdout);
+
+    match format {
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .init(),
+        LogFormat::Plain if verbosity > 0 => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_ansi(style::stderr_color())
+            .with_writer(plain_writer)
+            .init(),
+        LogFormat::Plain => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .event_format(PlainFormat)
+            .with_writer(plain_writer)
+            .init(),
+    }
+}
+
+/// Event format printing only the message, indented by the depth of nested spans
+///
+/// A leading emoji is replaced by the glyph of its status, colored on terminals.
+struct PlainFormat;
+
+impl<S, N> FormatEvent<S, N> for PlainFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let depth = ctx.event_scope().map_or(0, |scope| scope.count());
+        write!(writer, "{:width$}", "", width = depth.saturating_sub(1) * 2)?;
+
+        let mut visitor = MessageVisitor {
+            message: String::new(),
+        };
+        event.record(&mut visitor);
+
+        // Warnings and errors go to stderr, the other messages to stdout
+        let color = if *event.metadata().level() <= Level::WARN {
+            style::stderr_color()
+        } else {
+            style::color()
+        };
+        writeln!(writer, "{}", style::decorate(&visitor.message, color))
+    }
+}
+
+/// Field visitor collecting the message of an event
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_directives() {
+        for verbosity in -1..=3 {
+            assert!(EnvFilter::try_new(filter_directives(verbosity)).is_ok());
+        }
+        assert_eq!(filter_directives(-1), "warn");
+    }
+}
@@ -19,80 +19,2323 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use anyhow::Result;
-use clap::Parser;
-use vac_downloader::VacDownloader;
+use anyhow::{Context, Result};
+use clap::{ArgAction, Args as ClapArgs, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use vac_downloader::{
+    format_size, parse_duration, AiracCycle, AuditEntry, AuthError, AuthScheme, BoundingBox,
+    CheckResult, CycleOutlook, ExportFormat, NdjsonEvents, PendingDownload, ReportFormat,
+    RetryPolicy, StorageReport, SyncReport, SyncRun, TripLayout, VacDownloader,
+};
 
 mod config;
+mod i18n;
+mod logging;
+mod notify;
+mod output;
+#[cfg(feature = "interactive")]
+mod picker;
+#[cfg(feature = "serve")]
+mod serve;
+mod service;
+mod style;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "serve")]
+mod webdav;
 use config::Config;
+use i18n::{t, Lang};
+use logging::LogFormat;
+use notify::Notification;
+use output::{OutputArgs, SyncSummary};
+use style::{Status, Table};
 
 /// VAC Downloader - Airport (AD) PDF Sync Tool
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Path to the SQLite database file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     db_path: Option<String>,
 
     /// Directory where PDFs will be downloaded
-    #[arg(short = 'o', long)]
+    #[arg(short = 'o', long, global = true)]
     download_dir: Option<String>,
 
     /// OACI codes to download (if not specified, all entries will be synced)
-    #[arg(short = 'c', long = "oaci", value_name = "CODE", value_delimiter = ',')]
+    #[arg(
+        short = 'c',
+        long = "oaci",
+        value_name = "CODE",
+        value_delimiter = ',',
+        global = true
+    )]
     oaci_codes: Vec<String>,
+
+    /// Add the OACI codes of groups defined in the configuration file
+    #[arg(
+        short,
+        long = "group",
+        value_name = "NAME",
+        value_delimiter = ',',
+        global = true
+    )]
+    groups: Vec<String>,
+
+    /// Add the airfields along the route of a flight plan (GPX, Garmin .fpl or SkyDemon)
+    #[arg(long, value_name = "FILE", global = true)]
+    route: Option<PathBuf>,
+
+    /// Add the airfields of French regions, e.g. bretagne or ile-de-france
+    #[arg(
+        long = "region",
+        value_name = "NAME",
+        value_delimiter = ',',
+        global = true
+    )]
+    regions: Vec<String>,
+
+    /// Add the airfields of French departments, by number or name, e.g. 44
+    #[arg(
+        long = "department",
+        value_name = "CODE",
+        value_delimiter = ',',
+        global = true
+    )]
+    departments: Vec<String>,
+
+    /// Add the airfields within a rectangle given by two opposite corners in
+    /// decimal degrees, e.g. 48.6,1.8,49.2,2.4
+    #[arg(
+        long,
+        value_name = "LAT1,LON1,LAT2,LON2",
+        allow_hyphen_values = true,
+        global = true
+    )]
+    bbox: Option<BoundingBox>,
+
+    /// Only process the airfields of these territories: metropole, antilles,
+    /// guyane, ocean-indien, polynesie, nouvelle-caledonie, wallis-et-futuna,
+    /// saint-pierre-et-miquelon or all
+    #[arg(long, value_name = "NAME", value_delimiter = ',', global = true)]
+    territory: Vec<String>,
+
+    /// Include the chart version in stored file names (e.g. LFPG_AD_v2024-11.pdf)
+    #[arg(long, global = true)]
+    versioned_names: bool,
+
+    /// Keep the configuration file, database and downloads next to the executable,
+    /// e.g. to carry the tool and its charts on a USB stick
+    ///
+    /// Relative `db_path` and `download_dir` settings are then relative to the
+    /// directory of the executable.
+    #[arg(long, global = true)]
+    portable: bool,
+
+    /// Use the settings of a `[profile.NAME]` table of the configuration file
+    #[arg(short, long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// Wait for another running instance to finish instead of failing
+    #[arg(long, global = true)]
+    wait: bool,
+
+    /// Proxy for all requests (e.g. http://proxy:3128 or socks5://proxy:1080)
+    ///
+    /// Defaults to the HTTPS_PROXY, HTTP_PROXY and ALL_PROXY environment variables.
+    #[arg(long, value_name = "URL", global = true)]
+    proxy: Option<String>,
+
+    /// Write the raw API responses that fail to parse into this directory
+    #[arg(long, value_name = "DIR", global = true)]
+    dump_raw: Option<PathBuf>,
+
+    /// Log more details (-v for HTTP requests, -vv for everything)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of log messages
+    #[arg(long, value_enum, default_value_t = LogFormat::Plain, global = true)]
+    log_format: LogFormat,
+
+    /// Language of the printed messages (defaults to the language of the locale)
+    #[arg(long, value_enum, global = true)]
+    lang: Option<Lang>,
+
+    /// Never color the output (also set by a non-empty NO_COLOR variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Command to run (defaults to `sync`)
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Download new and updated VAC PDFs
+    Sync(SyncArgs),
+
+    /// Sync, then write a `manifest.json` so the download directory can be mirrored
+    Mirror,
+
+    /// Remove files in the download directory that are not in the cache
+    Clean {
+        /// Only list orphaned files without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check that the cached charts are intact, readable PDFs
+    Verify,
+
+    /// Remove airfields from the cache and delete their PDFs
+    Delete {
+        /// OACI codes or glob patterns, e.g. LFPG or "LFP*"
+        #[arg(required = true, value_name = "CODE")]
+        codes: Vec<String>,
+
+        /// Only delete the charts of this map type, e.g. AD
+        #[arg(long = "type", value_name = "TYPE")]
+        vac_type: Option<String>,
+    },
+
+    /// Restore a previously downloaded version of a chart, e.g. when a new one is defective
+    ///
+    /// The chart is kept at that version by the following syncs, until a
+    /// download is forced with `sync --force-oaci`. Previous versions are kept
+    /// in the archive directory, or under their name with `--versioned-names`.
+    Rollback {
+        /// OACI code of the airfield, e.g. LFPG
+        oaci: String,
+
+        /// Version to restore, or its beginning, e.g. 2024-10; defaults to the
+        /// most recent version before the cached one
+        #[arg(long, value_name = "VERSION")]
+        to: Option<String>,
+
+        /// Map type of the chart
+        #[arg(long = "type", value_name = "TYPE", default_value = "AD")]
+        vac_type: String,
+    },
+
+    /// Replace identical stored charts with hard links to one of them
+    Dedupe {
+        /// Only list the files to link without changing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove the previous chart versions beyond keep_versions and keep_days,
+    /// and compress the others with compress_versions
+    Gc {
+        /// Only list the versions to remove without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Export the cached airfields for mapping tools such as QGIS
+    Export {
+        /// File format
+        #[arg(long, value_enum)]
+        format: ExportFormatArg,
+
+        /// Path of the file to create, `airfields.<extension>` by default
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Package the database and PDFs into a bundle archive
+    ExportBundle {
+        /// Path of the bundle archive to create
+        output: PathBuf,
+    },
+
+    /// Package the PDFs as a content pack for EFB apps such as ForeFlight
+    ExportPack {
+        /// Path of the zip file to create
+        #[arg(long, value_name = "FILE", default_value = "vac-pack.zip")]
+        output: PathBuf,
+
+        /// Name of the pack shown in the app
+        #[arg(long, default_value = "VAC France")]
+        name: String,
+
+        /// Short name of the pack, also the name of its root folder
+        #[arg(long, default_value = "VAC")]
+        abbreviation: String,
+    },
+
+    /// Restore the database and PDFs from a bundle archive
+    ImportBundle {
+        /// Path of the bundle archive to import
+        bundle: PathBuf,
+    },
+
+    /// Add a directory of already downloaded PDFs to the cache
+    Import {
+        /// Directory containing the PDFs
+        dir: PathBuf,
+    },
+
+    /// Check whether updates are available, without downloading them
+    ///
+    /// Exits with status 0 when everything is up to date, 100 when updates
+    /// are available and 1 on errors.
+    Check,
+
+    /// Check that the API accepts the credentials of vac-downloader
+    ///
+    /// Tests the AUTH header signature with a one-airfield page of the listing,
+    /// then the Basic authentication with the request for a chart, explaining
+    /// which one the server rejected. Exits with status 1 when one was rejected.
+    Doctor,
+
+    /// List the charts of the remote listing and the local cache
+    List {
+        /// Only list the cached charts of which a newer version is published,
+        /// i.e. the ones a sync would update
+        #[arg(long)]
+        outdated: bool,
+
+        /// Only list the cached charts, without fetching the listing
+        #[arg(long, conflicts_with = "outdated")]
+        offline: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Find airfields by city name or OACI code
+    Search {
+        /// City name or part of it, e.g. chartres; accents and typos are tolerated
+        #[arg(required = true, value_name = "QUERY")]
+        query: Vec<String>,
+
+        /// Only search the cached charts, without fetching the listing
+        #[arg(long)]
+        offline: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Find the cached charts containing a word or phrase, e.g. a frequency or "ULM"
+    Grep {
+        /// Words to find, matched as a phrase ignoring case and accents
+        #[arg(required = true, value_name = "QUERY")]
+        query: Vec<String>,
+
+        /// Maximum number of matching pages to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Merge the cached charts of airfields into a single PDF with a table of contents
+    Bundle {
+        /// OACI codes of the airfields, in the order of the route; defaults to
+        /// the airfields of `--route`, `--oaci` and `--group`
+        #[arg(value_name = "CODE")]
+        codes: Vec<String>,
+
+        /// Path of the PDF to create
+        #[arg(long, value_name = "FILE", default_value = "trip.pdf")]
+        output: PathBuf,
+
+        /// Lay out the pages on A4 landscape sheets to print duplex (short edge) and fold into an A5 booklet
+        #[arg(long)]
+        booklet: bool,
+    },
+
+    /// Open the cached chart of an airfield in the default PDF viewer
+    Open {
+        /// OACI code of the airfield, e.g. LFPO
+        oaci: String,
+
+        /// Sync the airfield first, downloading its chart if it is missing or outdated
+        #[arg(long)]
+        sync: bool,
+    },
+
+    /// Print the absolute path of the cached chart of an airfield
+    Path {
+        /// OACI code of the airfield, e.g. LFPO
+        oaci: String,
+    },
+
+    /// Browse the cache and the listing in a terminal dashboard, to sync, delete or open charts
+    Tui,
+
+    /// Serve the downloaded PDFs and a REST API of the charts over HTTP on localhost
+    Serve {
+        /// Address to listen on, e.g. 0.0.0.0 for every network interface [default: 127.0.0.1, or bind in [serve]]
+        #[arg(long, value_name = "ADDRESS")]
+        bind: Option<IpAddr>,
+
+        /// Port to listen on [default: 8080, or port in [serve]]
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Print the OpenAPI document of the REST API, also served at /openapi.json, and exit
+        #[arg(long)]
+        openapi: bool,
+    },
+
+    /// Show downloads waiting in the queue
+    Queue,
+
+    /// Show past sync runs, or write a version of a chart to a file
+    History {
+        /// Number of runs to show, newest first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Write a version of the chart of this airfield to a file instead,
+        /// decompressing it if needed, e.g. LFPG
+        #[arg(long, value_name = "OACI")]
+        extract: Option<String>,
+
+        /// Version to extract, or its beginning, e.g. 2024-10; defaults to the
+        /// most recently downloaded one
+        #[arg(long, value_name = "VERSION", requires = "extract")]
+        version: Option<String>,
+
+        /// Map type of the chart to extract
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            default_value = "AD",
+            requires = "extract"
+        )]
+        vac_type: String,
+
+        /// Output file of the extracted version; defaults to its file name in the current directory
+        #[arg(long, requires = "extract")]
+        output: Option<PathBuf>,
+    },
+
+    /// Show the changes made to the cache and the stored files, e.g. why a chart was removed
+    Log {
+        /// Only show the changes of this airfield, e.g. LFPO
+        oaci: Option<String>,
+
+        /// Number of changes to show, newest first
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Show the current and next AIRAC cycles and the cached charts expiring when the next one begins
+    NextCycle {
+        /// List every expiring chart instead of the first ones
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Write an iCalendar feed of the upcoming AIRAC cycles and of the chart updates
+    Calendar {
+        /// Path of the .ics file to create
+        #[arg(long, value_name = "FILE", default_value = "vac-updates.ics")]
+        output: PathBuf,
+
+        /// Number of AIRAC cycles in the feed, starting with the current one
+        #[arg(long, default_value_t = 13)]
+        cycles: usize,
+    },
+
+    /// Show how much storage the cache uses
+    Stats {
+        /// Number of airfields to list, largest first
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Move a database and download directory found in the working directory
+    /// to the default data location
+    MigrateData,
+
+    /// Write a copy of the database encrypted with `db_passphrase`, for the `sqlcipher` feature
+    EncryptDatabase {
+        /// Path of the encrypted copy, to be set as `db_path` afterwards
+        output: PathBuf,
+    },
+
+    /// Run syncs in the background, at the interval of the sync_interval setting
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonCommand,
+    },
+
+    /// Create, show or validate the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Generate man pages from the command-line definitions
+    ///
+    /// Prints the page of vac-downloader(1) to stdout, or writes it to DIR
+    /// together with one page per command (vac-downloader-sync(1), ...).
+    Manpage {
+        /// Directory where the pages are written
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DaemonCommand {
+    /// Install a systemd user timer (Linux), a launchd agent (macOS) or a
+    /// scheduled task (Windows) running `sync` periodically
+    ///
+    /// The syncs use the configuration file, with the --portable, --profile,
+    /// --db-path and --download-dir options given to this command.
+    InstallService {
+        /// Time between two syncs (e.g. "6h"), instead of the sync_interval setting
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        interval: Option<Duration>,
+
+        /// Print the service definition instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Remove the service installed by install-service
+    UninstallService,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write a documented configuration file to the default location
+    Init {
+        /// Overwrite an existing configuration file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the configuration file
+    Show {
+        /// Print the settings in effect, after applying defaults, environment variables and options
+        #[arg(long)]
+        effective: bool,
+    },
+
+    /// Check the configuration file for unknown keys and invalid values
+    Validate,
+}
+
+#[derive(ClapArgs, Debug, Default)]
+struct SyncArgs {
+    /// Sync from a mirror's `manifest.json` instead of the SIA API
+    #[arg(long, value_name = "URL")]
+    from_mirror: Option<String>,
+
+    /// Stop the sync after this long, leaving the remaining downloads queued (e.g. "10m", "1h30m")
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    max_duration: Option<Duration>,
+
+    /// Re-download every chart matched by the filter, ignoring versions and hashes
+    #[arg(long)]
+    force: bool,
+
+    /// Re-download the charts of these airfields, ignoring versions and hashes
+    #[arg(long, value_name = "CODE", value_delimiter = ',')]
+    force_oaci: Vec<String>,
+
+    /// Try the downloads of the charts quarantined after failing repeatedly (see quarantine_after)
+    #[arg(long)]
+    retry_quarantined: bool,
+
+    /// Pick the airfields to sync in a searchable list of the remote listing
+    #[arg(long, conflicts_with = "from_mirror")]
+    interactive: bool,
+
+    /// Also download the AIP supplements (SUP AIP) affecting cached airfields
+    #[arg(long, conflicts_with = "from_mirror")]
+    include_sup: bool,
+
+    /// Write an `index.html` listing the charts in the download directory, kept up to date by later syncs
+    #[arg(long)]
+    html_index: bool,
+
+    /// Index the text of the charts for the `grep` command, kept up to date by later syncs
+    #[arg(long)]
+    index_text: bool,
+
+    /// Write a report of the sync (new versions, failures, totals) to this file, as Markdown for `.md` files and HTML otherwise
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Write Prometheus metrics of the cache and the sync history to this file, e.g. for the textfile collector of node_exporter
+    #[arg(long, value_name = "PATH")]
+    metrics_file: Option<PathBuf>,
+
+    /// Write the outcome of the sync as JSON to this file (status, counters, new versions, failures and per-chart results), including when it fails
+    #[arg(long, value_name = "PATH")]
+    summary_file: Option<PathBuf>,
+
+    /// Write a `SHA256SUMS` manifest of the PDFs, signed with `signing_key` if set, kept up to date by later syncs
+    #[arg(long)]
+    checksums: bool,
+
+    /// Write the events of the sync (listing fetched, downloads, verifications, failures) in this format
+    #[arg(long, value_name = "FORMAT", value_enum)]
+    events: Option<EventFormat>,
+
+    /// File the events are appended to, instead of stdout (which then only receives the events)
+    #[arg(long, value_name = "PATH", requires = "events")]
+    events_file: Option<PathBuf>,
+
+    /// Raise a desktop notification summarizing the new versions and failures, as with desktop_notifications
+    #[arg(long)]
+    notify: bool,
+}
+
+/// Format of the event stream of `sync --events`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum EventFormat {
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// File format of the `export` command
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormatArg {
+    /// GeoJSON FeatureCollection of airfield points
+    Geojson,
+    /// KML placemarks for Google Earth
+    Kml,
+    /// KML zipped as a KMZ archive
+    Kmz,
+    /// SeeYou waypoints for glide computers and XCSoar
+    Cup,
+    /// CSV of the radio frequencies of each airfield, for radio programming tools
+    FrequenciesCsv,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Geojson => ExportFormat::GeoJson,
+            ExportFormatArg::Kml => ExportFormat::Kml,
+            ExportFormatArg::Kmz => ExportFormat::Kmz,
+            ExportFormatArg::Cup => ExportFormat::Cup,
+            ExportFormatArg::FrequenciesCsv => ExportFormat::FrequenciesCsv,
+        }
+    }
+}
+
+/// Exit status when some downloads failed
+const EXIT_FAILED: i32 = 1;
+
+/// Exit status when the time budget ran out before all downloads were done
+const EXIT_INCOMPLETE: i32 = 3;
+
+/// Exit status of `check` when updates are available
+const EXIT_UPDATES_AVAILABLE: i32 = 100;
+
+/// Exit status of `check`: missing and corrupted files count as updates, as a
+/// sync downloads them again
+fn check_exit_code(result: &CheckResult) -> i32 {
+    if result.updates.is_empty() {
+        0
+    } else {
+        EXIT_UPDATES_AVAILABLE
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    i18n::init(args.lang.unwrap_or_else(Lang::detect));
+    style::init(args.no_color);
+    if let Some(Command::Manpage { dir }) = &args.command {
+        return write_manpages(dir.as_deref());
+    }
+
+    // Events written to stdout must not be mixed with messages
+    let events_on_stdout = matches!(
+        &args.command,
+        Some(Command::Sync(sync_args)) if sync_args.events.is_some() && sync_args.events_file.is_none()
+    );
+    // `check` and `config` only print their output unless verbose output is requested
+    let quiet = args.quiet
+        || events_on_stdout
+        || (matches!(
+            args.command,
+            Some(Command::Check)
+                | Some(Command::List { .. })
+                | Some(Command::Search { .. })
+                | Some(Command::Grep { .. })
+                | Some(Command::Open { .. })
+                | Some(Command::Path { .. })
+                | Some(Command::NextCycle { .. })
+                | Some(Command::Config { .. })
+                | Some(Command::Daemon { .. })
+                | Some(Command::Serve { openapi: true, .. })
+        ) && args.verbose == 0);
+    let verbosity = if quiet { -1 } else { args.verbose as i8 };
+    // The dashboard owns the terminal, log messages would garble it
+    if !matches!(args.command, Some(Command::Tui)) {
+        logging::init(verbosity, args.log_format);
+    }
+    // Human readable summaries are only printed alongside plain messages
+    let print_summaries = !args.quiet && !events_on_stdout && args.log_format == LogFormat::Plain;
 
-    println!("🛩️  VAC Downloader - Airport (AD) PDF Sync Tool\n");
+    info!("🛩️  VAC Downloader - Airport (AD) PDF Sync Tool");
+
+    let portable_root = if args.portable {
+        Some(config::portable_root()?)
+    } else {
+        None
+    };
+    let portable_root = portable_root.as_deref();
 
     // Load configuration from file (if exists)
-    let config = Config::load();
+    let file_config = Config::load(portable_root);
+    let config_loaded = file_config.is_some();
 
     // Merge config with CLI args (CLI takes precedence)
-    // Priority: CLI args > config file > defaults
-    let db_path = args
-        .db_path
-        .or_else(|| config.as_ref().and_then(|c| c.db_path.clone()))
-        .unwrap_or_else(|| "vac_cache.db".to_string());
+    // Priority: CLI args > environment variables > profile > config file > defaults
+    let mut config = file_config.unwrap_or_default();
+    if let Some(profile) = &args.profile {
+        config.apply_profile(profile)?;
+    }
+    config.apply_env()?;
+    if let Some(root) = portable_root {
+        config.resolve_paths(root);
+    }
+    let service_options = service_options(&args)?;
+    if let Some(db_path) = args.db_path {
+        config.db_path = Some(db_path);
+    }
+    if let Some(download_dir) = args.download_dir {
+        config.download_dir = Some(download_dir);
+    }
+    if let Some(proxy) = args.proxy {
+        config.proxy = Some(proxy);
+    }
+    if args.versioned_names {
+        config.versioned_file_names = Some(true);
+    }
+    if !args.territory.is_empty() {
+        config.territory = Some(args.territory);
+    }
+    if !args.oaci_codes.is_empty()
+        || !args.groups.is_empty()
+        || args.route.is_some()
+        || !args.regions.is_empty()
+        || !args.departments.is_empty()
+        || args.bbox.is_some()
+    {
+        let mut oaci_codes = args.oaci_codes;
+        for group in &args.groups {
+            oaci_codes.extend(config.group(group)?.iter().cloned());
+        }
+        config.oaci = Some(oaci_codes);
+    }
+    let command = args.command.unwrap_or(Command::Sync(SyncArgs::default()));
+    if let Command::MigrateData = command {
+        return migrate_data(&config, portable_root);
+    }
 
-    let download_dir = args
+    let (default_db_path, default_download_dir) = config::default_locations(portable_root);
+    let db_path = config
+        .db_path
+        .get_or_insert_with(|| match portable_root {
+            Some(_) => default_db_path.to_string_lossy().to_string(),
+            None => default_location(&default_db_path, config::LEGACY_DB_PATH),
+        })
+        .clone();
+    let download_dir = config
         .download_dir
-        .or_else(|| config.as_ref().and_then(|c| c.download_dir.clone()))
-        .unwrap_or_else(|| "./downloads".to_string());
+        .get_or_insert_with(|| match portable_root {
+            Some(_) => default_download_dir.to_string_lossy().to_string(),
+            None => default_location(&default_download_dir, config::LEGACY_DOWNLOAD_DIR),
+        })
+        .clone();
+
+    if let Command::Config { action } = command {
+        return run_config_command(action, &config, portable_root);
+    }
+    if let Command::Daemon { action } = command {
+        return run_daemon_command(action, &config, service_options);
+    }
+    if let Command::EncryptDatabase { output } = &command {
+        return encrypt_database(&db_path, output, &config);
+    }
 
     // Show configuration source
-    if config.is_some() {
-        println!(
+    if config_loaded {
+        info!(
             "📝 Loaded configuration from: {}",
-            Config::get_config_path_display()
+            Config::get_config_path_display(portable_root)
         );
     }
-    println!("📂 Database: {}", db_path);
-    println!("📥 Download directory: {}", download_dir);
+    if let Some(root) = portable_root {
+        info!("💾 Portable mode: {}", root.display());
+    }
+    if let Some(profile) = &args.profile {
+        info!("👤 Profile: {}", profile);
+    }
+    info!("📂 Database: {}", db_path);
+    info!("📥 Download directory: {}", download_dir);
 
-    if !args.oaci_codes.is_empty() {
-        println!("🎯 OACI filter: {}", args.oaci_codes.join(", "));
+    let mut oaci_codes = config.oaci.clone().unwrap_or_default();
+    if !oaci_codes.is_empty() {
+        info!("🎯 OACI filter: {}", oaci_codes.join(", "));
     }
-    println!();
+
+    let offline = matches!(
+        command,
+        Command::List { offline: true, .. } | Command::Search { offline: true, .. }
+    );
+    // Commands that only query the cache open it read-only, so that they work on read-only media
+    let read_only = offline
+        || matches!(
+            command,
+            Command::Open { sync: false, .. }
+                | Command::Path { .. }
+                | Command::Queue
+                | Command::History { .. }
+                | Command::Log { .. }
+                | Command::Stats { .. }
+        );
 
     // Create downloader
-    let downloader = VacDownloader::new(&db_path, &download_dir)?;
+    let mut builder = VacDownloader::builder(&db_path, &download_dir).read_only(read_only);
+    if let Some(api_url) = &config.api_url {
+        info!("🌐 API: {}", api_url);
+        builder = builder.base_url(api_url);
+    }
+    if let Some(fallbacks) = config.api_fallback_urls.clone() {
+        builder = builder.fallback_base_urls(fallbacks);
+    }
+    if let Some(proxy) = config.proxy()? {
+        info!("🔀 Proxy: {}", config.proxy.as_deref().unwrap_or_default());
+        builder = builder.proxy(proxy);
+    }
+    if let Some(timeout) = config.timeout()? {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(connect_timeout) = config.connect_timeout()? {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(retries) = config.retries {
+        builder = builder.retry_policy(RetryPolicy::exponential(retries, Duration::from_secs(1)));
+    }
+    if let Some(rate_limit) = config.rate_limit {
+        builder = builder.rate_limit(rate_limit);
+    }
+    if let Some(passphrase) = config.db_passphrase()? {
+        builder = builder.db_passphrase(passphrase);
+    }
+    if let Some(page_size) = config.page_size {
+        builder = builder.page_size(page_size);
+    }
+    if let Some(dump_dir) = &args.dump_raw {
+        builder = builder.dump_dir(dump_dir);
+    }
+    let mut downloader = builder.build()?.with_offline(offline);
+    if let Some(storage) = &config.storage {
+        downloader = downloader.with_storage(storage.build(download_dir.as_ref())?);
+    }
+    if let Some(template) = config.file_name_template()? {
+        downloader = downloader.with_file_name_template(template);
+    }
+    if let Some(favorites) = config.favorites.clone() {
+        downloader = downloader.with_favorites(favorites);
+    }
+    if let Some(max_bytes) = config.max_cache_size()? {
+        downloader = downloader.with_max_cache_size(max_bytes);
+    }
+    if let Some(keep_versions) = config.keep_versions {
+        downloader = downloader.with_keep_versions(keep_versions as usize);
+    }
+    if let Some(keep_days) = config.keep_days {
+        downloader = downloader.with_keep_days(keep_days);
+    }
+    if config.compress_versions == Some(true) {
+        downloader = downloader.with_compress_versions(true);
+    }
+    if config.stream_listing == Some(true) {
+        downloader = downloader.with_stream_listing(true);
+    }
+    if let Some(threshold) = config.max_consecutive_failures {
+        downloader = downloader.with_max_consecutive_failures(threshold);
+    }
+    if let Some(threshold) = config.quarantine_after {
+        downloader = downloader.with_quarantine_after(threshold);
+    }
+    if let Some(key) = config.signing_key()? {
+        downloader = downloader.with_signing_key(key);
+    }
+    let hooks = config.hooks();
+    if !hooks.is_empty() {
+        downloader = downloader.with_hooks(hooks);
+    }
+    if let Some(cache_ttl) = config.cache_ttl()? {
+        downloader = downloader.with_cache_ttl(cache_ttl);
+    }
+    let territories = config.territories()?;
+    if !territories.is_empty() {
+        downloader = downloader.with_territories(territories);
+    }
+    if args.wait {
+        downloader = downloader.with_wait_for_lock();
+    }
+    if let Some(route) = &args.route {
+        for code in downloader.route_airfields(route)? {
+            if !oaci_codes.contains(&code) {
+                oaci_codes.push(code);
+            }
+        }
+    }
+    if !args.regions.is_empty() || !args.departments.is_empty() {
+        for code in downloader.area_airfields(&args.regions, &args.departments)? {
+            if !oaci_codes.contains(&code) {
+                oaci_codes.push(code);
+            }
+        }
+    }
+    if let Some(bbox) = &args.bbox {
+        for code in downloader.bbox_airfields(bbox)? {
+            if !oaci_codes.contains(&code) {
+                oaci_codes.push(code);
+            }
+        }
+    }
 
-    // Run sync with optional OACI filter
-    let oaci_filter = if args.oaci_codes.is_empty() {
+    let oaci_filter = if oaci_codes.is_empty() {
         None
     } else {
-        Some(args.oaci_codes.as_slice())
+        Some(oaci_codes.as_slice())
+    };
+
+    match command {
+        Command::Sync(sync_args) => {
+            if let Some(max_duration) = sync_args.max_duration.or(config.max_duration()?) {
+                downloader = downloader.with_max_duration(max_duration);
+            }
+            if let Some(file_timeout) = config.file_timeout()? {
+                downloader = downloader.with_file_timeout(file_timeout);
+            }
+            if let Some(period) = config.skip_not_found()? {
+                downloader = downloader.with_skip_not_found(period);
+            }
+            if sync_args.force {
+                downloader = downloader.with_force();
+            }
+            if !sync_args.force_oaci.is_empty() {
+                downloader = downloader.with_force_oacis(sync_args.force_oaci);
+            }
+            if sync_args.retry_quarantined {
+                downloader = downloader.with_retry_quarantined();
+            }
+            if sync_args.include_sup {
+                downloader = downloader.with_supplements();
+            }
+            if let Some(EventFormat::Ndjson) = sync_args.events {
+                let out: Box<dyn std::io::Write + Send> = match &sync_args.events_file {
+                    Some(path) => Box::new(
+                        std::fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(path)
+                            .context(format!("Failed to open {}", path.display()))?,
+                    ),
+                    None => Box::new(std::io::stdout()),
+                };
+                downloader = downloader.with_observer(Arc::new(NdjsonEvents::new(out)));
+            }
+
+            let picked;
+            let oaci_filter = if sync_args.interactive {
+                picked = pick_airfields(&downloader, &oaci_codes)?;
+                if picked.is_empty() {
+                    println!("{}", t!("No airfield selected"));
+                    return Ok(());
+                }
+                Some(picked.as_slice())
+            } else {
+                oaci_filter
+            };
+
+            // Invalid email settings are reported before the sync rather than after it
+            let notifier = config.email_notifier()?;
+
+            // Run sync with optional OACI filter
+            let result = match sync_args.from_mirror {
+                Some(mirror_url) => downloader.sync_from_mirror(&mirror_url, oaci_filter),
+                None => downloader.sync(oaci_filter),
+            };
+            // Failed runs are exported too, so that alerts can fire on them
+            if let Some(path) = &sync_args.metrics_file {
+                downloader.metrics()?.write(path)?;
+            }
+            if let Some(path) = &sync_args.summary_file {
+                SyncSummary::new(&result).write(path)?;
+                info!("📝 Wrote sync summary to {}", path.display());
+            }
+            if sync_args.notify || config.desktop_notifications == Some(true) {
+                let notification = match &result {
+                    Ok(stats) => Notification::from_stats(stats),
+                    Err(e) => Some(Notification::from_error(e)),
+                };
+                // Like the emails, a notification failure does not fail the run
+                if let Err(e) = notification.map_or(Ok(()), |n| n.show()) {
+                    warn!("⚠️  {:#}", e);
+                }
+            }
+            // A sync that could not run has no report, its error is emailed instead
+            if let (Some(notifier), Err(e)) = (&notifier, &result) {
+                if let Err(e) = notifier.notify_error(e) {
+                    warn!("⚠️  {:#}", e);
+                }
+            }
+            let stats = result?;
+            if print_summaries {
+                print_sync_summary(&stats);
+            }
+            if sync_args.html_index {
+                downloader.write_html_index()?;
+            }
+            if sync_args.index_text {
+                downloader.index_text()?;
+            }
+            if sync_args.checksums {
+                downloader.write_checksums()?;
+            }
+            if let Some(path) = &sync_args.report {
+                let report = SyncReport::new(&stats).render(ReportFormat::from_path(path));
+                std::fs::write(path, report)
+                    .context(format!("Failed to write {}", path.display()))?;
+                info!("📝 Wrote sync report to {}", path.display());
+            }
+            if let Some(notifier) = &notifier {
+                // The charts are synced either way, a notification failure does not fail the run
+                if let Err(e) = notifier.notify(&stats) {
+                    warn!("⚠️  {:#}", e);
+                }
+            }
+
+            // Exit with error code if any downloads failed or were deferred
+            if stats.failed > 0 {
+                std::process::exit(EXIT_FAILED);
+            }
+            if stats.deferred > 0 {
+                std::process::exit(EXIT_INCOMPLETE);
+            }
+        }
+        Command::Mirror => {
+            let stats = downloader.sync(oaci_filter)?;
+            if print_summaries {
+                print_sync_summary(&stats);
+            }
+            downloader.write_mirror_manifest()?;
+
+            if stats.failed > 0 {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Clean { dry_run } => {
+            let result = downloader.clean(dry_run)?;
+            if dry_run {
+                println!(
+                    "{}",
+                    t!("\n{} orphaned file(s) found", result.orphans.len())
+                );
+            } else {
+                println!(
+                    "{}",
+                    t!("\n{} orphaned file(s) removed", result.removed.len())
+                );
+            }
+        }
+        Command::Verify => {
+            let result = downloader.verify()?;
+            println!(
+                "{}",
+                t!(
+                    "\n{} chart(s) verified, {} with problems",
+                    result.verified + result.problems.len(),
+                    result.problems.len()
+                )
+            );
+
+            // Exit with error code if some charts are broken
+            if !result.problems.is_empty() {
+                let mut codes: Vec<&str> = result
+                    .problems
+                    .iter()
+                    .map(|(entry, _)| entry.oaci.as_str())
+                    .collect();
+                codes.dedup();
+                println!(
+                    "{}",
+                    t!(
+                        "Download them again with: vac-downloader sync --force-oaci {}",
+                        codes.join(",")
+                    )
+                );
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Delete { codes, vac_type } => {
+            let results = downloader.delete(&codes, vac_type.as_deref())?;
+            let deleted = results.iter().filter(|r| r.database_deleted).count();
+            let not_found = results.len() - deleted;
+            println!("{}", t!("\n{} chart(s) deleted", deleted));
+
+            // Exit with error code if some codes matched nothing
+            if not_found > 0 {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Rollback { oaci, to, vac_type } => {
+            let result = downloader.rollback(&oaci, &vac_type, to.as_deref())?;
+            println!(
+                "{}",
+                style::status(
+                    Status::Success,
+                    t!(
+                        "{} rolled back from version {} to {}",
+                        result.entry.oaci,
+                        result.from_version,
+                        result.entry.version
+                    )
+                )
+            );
+            println!(
+                "{}",
+                t!(
+                    "Syncs keep this version until: vac-downloader sync --force-oaci {}",
+                    result.entry.oaci
+                )
+            );
+        }
+        Command::Dedupe { dry_run } => {
+            let result = downloader.dedupe(dry_run)?;
+            let message = if dry_run {
+                t!(
+                    "\n{} identical file(s) to link, {} to save",
+                    result.linked.len(),
+                    format_size(result.saved)
+                )
+            } else {
+                t!(
+                    "\n{} identical file(s) linked, {} saved",
+                    result.linked.len(),
+                    format_size(result.saved)
+                )
+            };
+            println!("{}", message);
+        }
+        Command::Gc { dry_run } => {
+            if config.keep_versions.is_none()
+                && config.keep_days.is_none()
+                && config.compress_versions != Some(true)
+            {
+                println!(
+                    "{}",
+                    t!("No retention policy set: keep_versions, keep_days and compress_versions are not configured")
+                );
+                return Ok(());
+            }
+            let result = downloader.gc(dry_run)?;
+            let message = if dry_run {
+                t!(
+                    "\n{} previous version(s) to remove, {} to free",
+                    result.expired.len(),
+                    format_size(result.freed)
+                )
+            } else {
+                t!(
+                    "\n{} previous version(s) removed, {} freed",
+                    result.expired.len(),
+                    format_size(result.freed)
+                )
+            };
+            println!("{}", message);
+            if !result.compressed.is_empty() {
+                println!(
+                    "{}",
+                    if dry_run {
+                        t!(
+                            "{} previous version(s) to compress",
+                            result.compressed.len()
+                        )
+                    } else {
+                        t!("{} previous version(s) compressed", result.compressed.len())
+                    }
+                );
+            }
+        }
+        Command::History {
+            extract: Some(oaci),
+            version,
+            vac_type,
+            output,
+            ..
+        } => {
+            let (version, data) =
+                downloader.extract_version(&oaci, &vac_type, version.as_deref())?;
+            let output = output.unwrap_or_else(|| {
+                PathBuf::from(
+                    Path::new(&version.file_name)
+                        .file_name()
+                        .unwrap_or(version.file_name.as_ref()),
+                )
+            });
+            std::fs::write(&output, data)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!(
+                "{}",
+                t!(
+                    "Version {} of {} written to {}",
+                    version.version,
+                    version.oaci,
+                    output.display()
+                )
+            );
+        }
+        Command::Export { format, output } => {
+            let format = ExportFormat::from(format);
+            let output = output
+                .unwrap_or_else(|| PathBuf::from(format!("airfields.{}", format.extension())));
+            let count = downloader.export_airfields(format, &output)?;
+            println!(
+                "{}",
+                t!("\n{} airfield(s) exported to {}", count, output.display())
+            );
+        }
+        Command::ExportBundle { output } => {
+            downloader.export_bundle(&output, oaci_filter)?;
+        }
+        Command::ExportPack {
+            output,
+            name,
+            abbreviation,
+        } => {
+            let pack = downloader.export_pack(&output, &name, &abbreviation, oaci_filter)?;
+            println!(
+                "{}",
+                t!(
+                    "\n{} chart(s) packed into {}",
+                    pack.plates.len(),
+                    output.display()
+                )
+            );
+        }
+        Command::ImportBundle { bundle } => {
+            let result = downloader.import_bundle(&bundle)?;
+
+            // Exit with error code if some entries could not be restored
+            if !result.rejected.is_empty() {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Import { dir } => {
+            let result = downloader.import_directory(&dir)?;
+
+            // Exit with error code if some files could not be matched
+            if !result.unmatched.is_empty() {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Check => {
+            let result = downloader.check(oaci_filter)?;
+            if result.updates.is_empty() {
+                println!(
+                    "{}",
+                    style::status(
+                        Status::Success,
+                        t!("Up to date ({} charts)", result.up_to_date)
+                    )
+                );
+            } else {
+                println!(
+                    "{}",
+                    t!(
+                        "{} update(s) available, {} chart(s) up to date",
+                        result.updates.len(),
+                        result.up_to_date
+                    )
+                );
+                let mut table = Table::new([t!("OACI"), t!("Type"), t!("Version"), t!("Reason")]);
+                for update in &result.updates {
+                    table.row(vec![
+                        update.entry.oaci.clone(),
+                        update.entry.vac_type.clone(),
+                        update.entry.version.clone(),
+                        update.reason.to_string(),
+                    ]);
+                }
+                println!("{}", table.render());
+                std::process::exit(check_exit_code(&result));
+            }
+        }
+        Command::Doctor => {
+            let checks = downloader.api().check_auth();
+            let failed = checks.iter().any(|(_, result)| result.is_err());
+            let signature_failed = checks.len() == 1 && failed;
+            for (scheme, result) in &checks {
+                let name = match scheme {
+                    AuthScheme::Signature => t!("AUTH header signature"),
+                    AuthScheme::Basic => t!("Basic authentication"),
+                };
+                match result {
+                    Ok(()) => println!(
+                        "{}",
+                        style::status(Status::Success, t!("{} accepted", name))
+                    ),
+                    Err(e) => {
+                        println!("{}", style::status(Status::Failure, t!("{} failed", name)));
+                        match e.downcast_ref::<AuthError>() {
+                            Some(auth_error) => println!("   {}", auth_error),
+                            None => println!("   {:#}", e),
+                        }
+                    }
+                }
+            }
+            if checks.len() == 1 {
+                let reason = if signature_failed {
+                    t!("Basic authentication not checked, as the signed request failed")
+                } else {
+                    t!("Basic authentication not checked, as the listing has no chart")
+                };
+                println!("{}", style::status(Status::Info, reason));
+            }
+            if failed {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::List {
+            outdated: false,
+            output,
+            ..
+        } => {
+            let charts = downloader.list_charts(oaci_filter)?;
+            output::print_charts(charts, &output, t!("No airfield found"));
+        }
+        Command::List {
+            outdated: true,
+            mut output,
+            ..
+        } => {
+            let charts = downloader.list_outdated(oaci_filter)?;
+            output.default_columns(output::OUTDATED_COLUMNS);
+            output::print_charts(charts, &output, t!("No outdated chart"));
+        }
+        Command::Search { query, output, .. } => {
+            let charts = downloader.search(&query.join(" "))?;
+            output::print_charts(charts, &output, t!("No airfield found"));
+        }
+        Command::Grep { query, limit } => {
+            let matches = downloader.search_text(&query.join(" "), limit)?;
+            for found in &matches {
+                println!(
+                    "{} {} p.{}: {}",
+                    found.oaci,
+                    found.vac_type,
+                    found.page,
+                    found
+                        .snippet
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                );
+            }
+
+            // Exit with error code when nothing matched, like grep
+            if matches.is_empty() {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+        Command::Bundle {
+            codes,
+            output,
+            booklet,
+        } => {
+            let layout = if booklet {
+                TripLayout::Booklet
+            } else {
+                TripLayout::Pages
+            };
+            let codes = if codes.is_empty() {
+                oaci_codes.clone()
+            } else {
+                codes
+            };
+            if codes.is_empty() {
+                anyhow::bail!(
+                    "No airfields to bundle, give OACI codes or a flight plan with --route"
+                );
+            }
+            let trip = downloader.write_trip_pdf(&codes, &output, layout)?;
+            let (count, output) = (trip.charts.len(), output.display());
+            let message = if booklet {
+                t!(
+                    "Wrote {} with {} chart(s), {} sheet side(s)",
+                    output,
+                    count,
+                    trip.pages
+                )
+            } else {
+                t!(
+                    "Wrote {} with {} chart(s), {} page(s)",
+                    output,
+                    count,
+                    trip.pages
+                )
+            };
+            println!("{}", style::status(Status::Success, message));
+        }
+        Command::Open { oaci, sync } => {
+            let oaci = oaci.to_uppercase();
+            if sync {
+                let stats = downloader.sync(Some(std::slice::from_ref(&oaci)))?;
+                if stats.failed > 0 {
+                    warn!("⚠️  Failed to sync {}, opening the cached chart", oaci);
+                }
+            }
+            open_chart(&downloader, &oaci)?;
+        }
+        Command::Path { oaci } => {
+            let path = downloader.get_pdf_path(&oaci.to_uppercase())?;
+            println!("{}", path.canonicalize().unwrap_or(path).display());
+        }
+        Command::Tui => run_tui(downloader)?,
+        Command::Serve {
+            bind,
+            port,
+            openapi,
+        } => {
+            let serve_config = config.serve.clone().unwrap_or_default();
+            let address = SocketAddr::new(
+                bind.or(serve_config.bind)
+                    .unwrap_or(IpAddr::from([127, 0, 0, 1])),
+                port.or(serve_config.port).unwrap_or(DEFAULT_SERVE_PORT),
+            );
+            let credentials = config.serve_credentials()?;
+            run_serve(
+                downloader,
+                ServeArgs {
+                    address,
+                    token: serve_config.token,
+                    credentials,
+                    openapi,
+                    download_dir: PathBuf::from(&download_dir),
+                    oaci_codes: oaci_codes.clone(),
+                },
+            )?
+        }
+        Command::Queue => {
+            let queue = downloader.pending_downloads()?;
+            print_download_queue(&queue);
+        }
+        Command::History { limit, .. } => {
+            let runs = downloader.sync_history(limit)?;
+            print_sync_history(&runs);
+        }
+        Command::Log { oaci, limit } => {
+            let log = downloader.audit_log(oaci.as_deref(), limit)?;
+            print_audit_log(&log);
+        }
+        Command::NextCycle { all } => {
+            let today = chrono::Utc::now().date_naive();
+            let outlook = downloader.next_cycle(today, oaci_filter)?;
+            print_cycle_outlook(&outlook, today, all);
+        }
+        Command::Calendar { output, cycles } => {
+            let today = chrono::Utc::now().date_naive();
+            let calendar = downloader.calendar(today, cycles, oaci_filter)?;
+            std::fs::write(&output, calendar.render())
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!(
+                "{}",
+                t!(
+                    "\n{} cycle(s) and {} chart update(s) written to {}",
+                    calendar.cycles.len(),
+                    calendar.updates.len(),
+                    output.display()
+                )
+            );
+        }
+        Command::Stats { top } => {
+            let report = downloader.storage_report()?;
+            print_storage_report(&report, top);
+        }
+        Command::MigrateData
+        | Command::EncryptDatabase { .. }
+        | Command::Config { .. }
+        | Command::Daemon { .. }
+        | Command::Manpage { .. } => {
+            unreachable!("handled before opening the database")
+        }
+    }
+
+    Ok(())
+}
+
+/// Pick the default location of the database or the download directory
+///
+/// Data left in the working directory by older releases keeps being used until
+/// it is moved with `migrate-data`.
+fn default_location(default: &Path, legacy: &str) -> String {
+    if !default.exists() && Path::new(legacy).exists() {
+        warn!(
+            "⚠️  Using {} from the working directory, run `vac-downloader migrate-data` to move it to {}",
+            legacy,
+            default.display()
+        );
+        return legacy.to_string();
+    }
+    default.to_string_lossy().to_string()
+}
+
+/// Render the man pages of the command line
+///
+/// Without a directory, only the main page is printed to stdout.
+fn write_manpages(dir: Option<&Path>) -> Result<()> {
+    let mut command = Args::command().name(env!("CARGO_BIN_NAME"));
+    // Propagate the global options to the pages of the commands
+    command.build();
+    let Some(dir) = dir else {
+        clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
+        return Ok(());
     };
-    let stats = downloader.sync(oaci_filter)?;
 
-    // Exit with error code if any downloads failed
-    if stats.failed > 0 {
-        std::process::exit(1);
+    std::fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    let name = command.get_name().to_string();
+    let mut pages = vec![(name.clone(), command.clone())];
+    for subcommand in command.get_subcommands().filter(|s| s.get_name() != "help") {
+        let page_name = format!("{}-{}", name, subcommand.get_name());
+        let page = subcommand
+            .clone()
+            .display_name(page_name.clone())
+            .bin_name(format!("{} {}", name, subcommand.get_name()))
+            .version(env!("CARGO_PKG_VERSION"));
+        pages.push((page_name, page));
     }
 
+    for (page_name, page) in &pages {
+        let path = dir.join(format!("{}.1", page_name));
+        let mut buffer = Vec::new();
+        clap_mangen::Man::new(page.clone()).render(&mut buffer)?;
+        std::fs::write(&path, buffer).context(format!("Failed to write {}", path.display()))?;
+    }
+    println!(
+        "{}",
+        style::status(
+            Status::Success,
+            t!("Wrote {} man page(s) to {}", pages.len(), dir.display())
+        )
+    );
     Ok(())
 }
+
+/// Write an encrypted copy of a plain database
+fn encrypt_database(db_path: &str, output: &Path, config: &Config) -> Result<()> {
+    let Some(passphrase) = config.db_passphrase()? else {
+        anyhow::bail!("Set db_passphrase or db_passphrase_command to encrypt the database");
+    };
+    if output.exists() {
+        anyhow::bail!("{} already exists", output.display());
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    {
+        let database = vac_downloader::VacDatabase::new(db_path)
+            .context(format!("Failed to open {} as a plain database", db_path))?;
+        database
+            .export_encrypted(output, &passphrase)
+            .context(format!("Failed to write {}", output.display()))?;
+        println!(
+            "{}",
+            style::status(
+                Status::Success,
+                t!(
+                    "Wrote an encrypted copy of {} to {}, set it as db_path to use it",
+                    db_path,
+                    output.display()
+                )
+            )
+        );
+        Ok(())
+    }
+    #[cfg(not(feature = "sqlcipher"))]
+    {
+        let _ = (db_path, passphrase);
+        anyhow::bail!(
+            "Encrypted databases require building vac-downloader with the sqlcipher feature"
+        )
+    }
+}
+
+/// Move the database and download directory of older releases out of the working directory
+fn migrate_data(config: &Config, portable_root: Option<&Path>) -> Result<()> {
+    let (default_db_path, default_download_dir) = config::default_locations(portable_root);
+    let moves = [
+        (config::LEGACY_DB_PATH, &default_db_path, &config.db_path),
+        (
+            config::LEGACY_DOWNLOAD_DIR,
+            &default_download_dir,
+            &config.download_dir,
+        ),
+    ];
+
+    let mut moved = 0;
+    for (legacy, default, configured) in moves {
+        let legacy = Path::new(legacy);
+        if !legacy.exists() || legacy == default.as_path() {
+            continue;
+        }
+        if let Some(configured) = configured {
+            println!(
+                "{}",
+                t!(
+                    "Skipping {}: a location is configured ({})",
+                    legacy.display(),
+                    configured
+                )
+            );
+            continue;
+        }
+        if default.exists() {
+            anyhow::bail!(
+                "Cannot move {}: {} already exists",
+                legacy.display(),
+                default.display()
+            );
+        }
+
+        if let Some(parent) = default.parent() {
+            std::fs::create_dir_all(parent)
+                .context(format!("Failed to create {}", parent.display()))?;
+        }
+        move_path(legacy, default).context(format!("Failed to move {}", legacy.display()))?;
+        println!(
+            "{}",
+            style::status(
+                Status::Success,
+                t!("Moved {} to {}", legacy.display(), default.display())
+            )
+        );
+        // Lock file of the moved database
+        let _ = std::fs::remove_file(format!("{}.lock", legacy.display()));
+        moved += 1;
+    }
+
+    if moved == 0 {
+        println!("{}", t!("Nothing to migrate"));
+    }
+    Ok(())
+}
+
+/// Move a file or directory, copying it when it lives on another file system
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    copy_path(from, to)?;
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)?;
+    } else {
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Recursively copy a file or directory
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Run a `config` subcommand
+///
+/// # Arguments
+/// * `config` - Settings in effect, after applying environment variables and options
+/// * `portable_root` - Directory of the configuration file in portable mode
+fn run_config_command(
+    action: ConfigCommand,
+    config: &Config,
+    portable_root: Option<&Path>,
+) -> Result<()> {
+    let path = Config::get_config_path(portable_root).context("Unable to determine config path")?;
+
+    match action {
+        ConfigCommand::Init { force } => {
+            if path.exists() && !force {
+                anyhow::bail!(
+                    "{} already exists, use --force to overwrite it",
+                    path.display()
+                );
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .context(format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&path, config::EXAMPLE_CONFIG)
+                .context(format!("Failed to write {}", path.display()))?;
+            println!(
+                "{}",
+                style::status(Status::Success, t!("Wrote {}", path.display()))
+            );
+        }
+        ConfigCommand::Show { effective: true } => {
+            let mut value = toml::Value::try_from(config)?;
+            // Profiles that were not selected are not in effect
+            if let Some(table) = value.as_table_mut() {
+                table.remove("profile");
+            }
+            // Keep credentials out of terminals and logs
+            if let Some(table) = value.as_table_mut() {
+                for key in ["db_passphrase", "signing_key_password"] {
+                    if let Some(secret) = table.get_mut(key) {
+                        *secret = toml::Value::String("********".to_string());
+                    }
+                }
+            }
+            for (table, keys) in [
+                ("storage", &["secret_key", "password"][..]),
+                ("email", &["password"]),
+                ("serve", &["token", "password"]),
+            ] {
+                if let Some(table) = value.get_mut(table).and_then(|t| t.as_table_mut()) {
+                    for key in keys {
+                        if let Some(secret) = table.get_mut(*key) {
+                            *secret = toml::Value::String("********".to_string());
+                        }
+                    }
+                }
+            }
+            print!("{}", toml::to_string_pretty(&value)?);
+        }
+        ConfigCommand::Show { effective: false } => {
+            if !path.exists() {
+                println!("{}", t!("No configuration file at {}", path.display()));
+                return Ok(());
+            }
+            println!("# {}", path.display());
+            print!("{}", std::fs::read_to_string(&path)?);
+        }
+        ConfigCommand::Validate => {
+            if !path.exists() {
+                println!("{}", t!("No configuration file at {}", path.display()));
+                return Ok(());
+            }
+            let contents = std::fs::read_to_string(&path)?;
+            let file_config = Config::load_from(&path)?;
+
+            let mut problems: Vec<String> = Config::unknown_keys(&contents)?
+                .into_iter()
+                .map(|key| format!("Unknown key: {}", key))
+                .collect();
+            problems.extend(file_config.validate());
+
+            if problems.is_empty() {
+                println!(
+                    "{}",
+                    style::status(Status::Success, t!("{} is valid", path.display()))
+                );
+            } else {
+                println!(
+                    "{}",
+                    style::status(Status::Failure, format!("{}:", path.display()))
+                );
+                for problem in &problems {
+                    println!("  {}", problem);
+                }
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Default time between the syncs of the installed service
+const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Global options passed on to the syncs of the installed service
+fn service_options(args: &Args) -> Result<Vec<String>> {
+    let mut options = Vec::new();
+    if args.portable {
+        options.push("--portable".to_string());
+    }
+    if let Some(profile) = &args.profile {
+        options.extend(["--profile".to_string(), profile.clone()]);
+    }
+    // The service does not run from the current directory
+    for (option, path) in [
+        ("--db-path", &args.db_path),
+        ("--download-dir", &args.download_dir),
+    ] {
+        if let Some(path) = path {
+            let path = std::path::absolute(path).context(format!("Failed to resolve {}", path))?;
+            options.extend([option.to_string(), path.to_string_lossy().into_owned()]);
+        }
+    }
+    Ok(options)
+}
+
+/// Run a subcommand of `daemon`
+///
+/// # Arguments
+/// * `config` - Settings in effect, after applying environment variables and options
+/// * `options` - Global options passed on to the syncs of the service
+fn run_daemon_command(action: DaemonCommand, config: &Config, options: Vec<String>) -> Result<()> {
+    let scheduler = service::Scheduler::current()?;
+    match action {
+        DaemonCommand::InstallService { interval, print } => {
+            let interval = match interval {
+                Some(interval) => interval,
+                None => config.sync_interval()?.unwrap_or(DEFAULT_SYNC_INTERVAL),
+            };
+            if interval.is_zero() {
+                anyhow::bail!("The interval between syncs must not be zero");
+            }
+            let exe = std::env::current_exe().context("Unable to locate the executable")?;
+            let mut command = vec![exe.to_string_lossy().into_owned()];
+            command.extend(options);
+            command.push("sync".to_string());
+            let service = service::Service { command, interval };
+
+            let files = service.files(scheduler)?;
+            let activation = service.activation(scheduler)?;
+            if print {
+                for (path, contents) in &files {
+                    println!("# {}\n{}", path.display(), contents);
+                }
+                for command in &activation {
+                    println!("{}", command.join(" "));
+                }
+                return Ok(());
+            }
+
+            for (path, contents) in &files {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .context(format!("Failed to create {}", parent.display()))?;
+                }
+                std::fs::write(path, contents)
+                    .context(format!("Failed to write {}", path.display()))?;
+                println!("{}", t!("Wrote {}", path.display()));
+            }
+            for command in &activation {
+                service::run(command)?;
+            }
+            println!(
+                "{}",
+                style::status(
+                    Status::Success,
+                    t!(
+                        "Service installed, syncing every {}",
+                        service::format_interval(interval)
+                    )
+                )
+            );
+            if scheduler == service::Scheduler::Systemd {
+                println!(
+                    "{}",
+                    t!("Run `loginctl enable-linger` to also sync while you are logged out")
+                );
+            }
+        }
+        DaemonCommand::UninstallService => {
+            let (commands, files) = service::installed(scheduler)?;
+            for command in &commands {
+                service::run(command)?;
+            }
+            for path in &files {
+                match std::fs::remove_file(path) {
+                    Ok(()) => println!("{}", t!("Removed {}", path.display())),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => {
+                        return Err(e).context(format!("Failed to remove {}", path.display()))
+                    }
+                }
+            }
+            if scheduler == service::Scheduler::Systemd {
+                service::run(&["systemctl", "--user", "daemon-reload"].map(String::from))?;
+            }
+            println!(
+                "{}",
+                style::status(Status::Success, t!("Service uninstalled"))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Number of failed downloads, with the number of each kind of failure
+fn failed_summary(stats: &vac_downloader::downloader::SyncStats) -> String {
+    let kinds: Vec<String> = stats
+        .failures_by_kind()
+        .into_iter()
+        .map(|(kind, count)| format!("{} {}", count, i18n::tr(kind.label())))
+        .collect();
+    if kinds.is_empty() {
+        stats.failed.to_string()
+    } else {
+        format!("{} ({})", stats.failed, kinds.join(", "))
+    }
+}
+
+/// Print the counters of a sync
+fn print_sync_summary(stats: &vac_downloader::downloader::SyncStats) {
+    let mut fields = vec![
+        (t!("Total entries:"), stats.total_entries.to_string()),
+        (t!("Up to date:"), stats.up_to_date.to_string()),
+        (t!("Verified:"), stats.verified.to_string()),
+        (t!("Downloaded:"), stats.downloaded.to_string()),
+        (
+            t!("Redownloaded (corrupted/missing):"),
+            stats.redownloaded_corrupted.to_string(),
+        ),
+        (t!("Failed:"), failed_summary(stats)),
+    ];
+    if stats.skipped_not_found > 0 {
+        fields.push((
+            t!("Skipped (not found recently):"),
+            stats.skipped_not_found.to_string(),
+        ));
+    }
+    if stats.quarantined > 0 {
+        fields.push((t!("Quarantined:"), stats.quarantined.to_string()));
+    }
+    if stats.evicted > 0 {
+        fields.push((t!("Evicted:"), stats.evicted.to_string()));
+    }
+    if stats.deferred > 0 {
+        fields.push((t!("Deferred (time budget):"), stats.deferred.to_string()));
+    }
+    if stats.supplements_downloaded > 0 || stats.supplements_removed > 0 {
+        fields.push((
+            t!("Supplements:"),
+            t!(
+                "{} downloaded, {} removed",
+                stats.supplements_downloaded,
+                stats.supplements_removed
+            ),
+        ));
+    }
+    fields.push((t!("Downloaded size:"), format_size(stats.bytes_downloaded)));
+    if let Some(rate) = stats.throughput() {
+        fields.push((t!("Throughput:"), format!("{}/s", format_size(rate))));
+    }
+    fields.push((
+        t!("Duration:"),
+        format!("{:.1}s", stats.duration.as_secs_f64()),
+    ));
+    println!("{}", style::fields(&fields));
+}
+
+/// Print the download queue as a table
+fn print_download_queue(queue: &[PendingDownload]) {
+    if queue.is_empty() {
+        println!("{}", t!("No pending downloads"));
+        return;
+    }
+
+    let mut table = Table::new([
+        t!("OACI"),
+        t!("Type"),
+        t!("Version"),
+        t!("Priority"),
+        t!("Attempts"),
+        t!("Source"),
+    ])
+    .right_align(&[3, 4]);
+    for download in queue {
+        table.row(vec![
+            download.entry.oaci.clone(),
+            download.entry.vac_type.clone(),
+            download.entry.version.clone(),
+            download.priority.to_string(),
+            download.attempts.to_string(),
+            download.source.to_string(),
+        ]);
+        if let Some(error) = &download.last_error {
+            table.note(Status::Failure, error);
+        }
+    }
+    println!("{}", table.render());
+}
+
+/// Let the user pick the airfields to sync among the remote listing
+#[cfg(feature = "interactive")]
+fn pick_airfields(downloader: &VacDownloader, preselected: &[String]) -> Result<Vec<String>> {
+    picker::pick_airfields(&downloader.list_charts(None)?, preselected)
+}
+
+/// Interactive selection is unavailable without the `interactive` feature
+#[cfg(not(feature = "interactive"))]
+fn pick_airfields(_downloader: &VacDownloader, _preselected: &[String]) -> Result<Vec<String>> {
+    anyhow::bail!("This build does not support interactive selection (`interactive` feature)")
+}
+
+/// Open the cached chart of an airfield in the default PDF viewer
+fn open_chart(downloader: &VacDownloader, oaci: &str) -> Result<()> {
+    let path = downloader.get_pdf_path(oaci)?;
+    open::that_detached(&path).context(format!("Failed to open {}", path.display()))
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(downloader: VacDownloader) -> Result<()> {
+    tui::run(downloader)
+}
+
+/// The dashboard is unavailable without the `tui` feature
+#[cfg(not(feature = "tui"))]
+fn run_tui(_downloader: VacDownloader) -> Result<()> {
+    anyhow::bail!("This build does not include the dashboard (`tui` feature)")
+}
+
+/// Port of `serve` when neither `--port` nor the [serve] table sets one
+const DEFAULT_SERVE_PORT: u16 = 8080;
+
+/// Settings of the `serve` command, from its options and the [serve] table
+#[cfg_attr(not(feature = "serve"), allow(dead_code))]
+struct ServeArgs {
+    address: SocketAddr,
+    token: Option<String>,
+    credentials: Option<(String, String)>,
+    /// Print the OpenAPI document instead of serving
+    openapi: bool,
+    download_dir: PathBuf,
+    oaci_codes: Vec<String>,
+}
+
+#[cfg(feature = "serve")]
+fn run_serve(downloader: VacDownloader, args: ServeArgs) -> Result<()> {
+    if args.openapi {
+        println!("{}", serde_json::to_string_pretty(&serve::openapi())?);
+        return Ok(());
+    }
+    serve::run(
+        downloader,
+        serve::ServeOptions {
+            address: args.address,
+            auth: serve::Auth {
+                token: args.token,
+                credentials: args.credentials,
+            },
+            download_dir: args.download_dir,
+            oaci_codes: args.oaci_codes,
+        },
+    )
+}
+
+/// The HTTP server is unavailable without the `serve` feature
+#[cfg(not(feature = "serve"))]
+fn run_serve(_downloader: VacDownloader, _args: ServeArgs) -> Result<()> {
+    anyhow::bail!("This build does not include the HTTP server (`serve` feature)")
+}
+
+/// Print past sync runs as a table
+fn print_sync_history(runs: &[SyncRun]) {
+    if runs.is_empty() {
+        println!("{}", t!("No sync recorded yet"));
+        return;
+    }
+
+    let mut table = Table::new([
+        t!("Started (UTC)"),
+        t!("Duration"),
+        t!("Downloaded"),
+        t!("Failed"),
+        t!("Size"),
+        t!("Throughput"),
+        t!("Source"),
+    ])
+    .right_align(&[1, 2, 3, 4, 5]);
+    for run in runs {
+        table.row(vec![
+            run.started_at.to_string(),
+            format!("{:.1}s", run.duration_ms as f64 / 1000.0),
+            run.downloaded.to_string(),
+            run.failed.to_string(),
+            format_size(run.bytes_downloaded),
+            run.throughput().map_or_else(
+                || "-".to_string(),
+                |rate| format!("{}/s", format_size(rate)),
+            ),
+            run.source.to_string(),
+        ]);
+        if let Some(error) = &run.error {
+            table.note(Status::Failure, error);
+        }
+    }
+    println!("{}", table.render());
+}
+
+/// Print the changes of the audit log, newest first
+fn print_audit_log(log: &[AuditEntry]) {
+    if log.is_empty() {
+        println!("{}", t!("No change recorded yet"));
+        return;
+    }
+
+    let mut table = Table::new([
+        t!("Time (UTC)"),
+        t!("Action"),
+        t!("Chart"),
+        t!("File"),
+        t!("Reason"),
+    ]);
+    for entry in log {
+        let chart = match (&entry.oaci, &entry.vac_type) {
+            (Some(oaci), Some(vac_type)) => format!("{} {}", oaci, vac_type),
+            (Some(oaci), None) => oaci.clone(),
+            _ => "-".to_string(),
+        };
+        table.row(vec![
+            entry.at.clone(),
+            entry.action.to_string(),
+            chart,
+            entry.file_name.clone().unwrap_or_else(|| "-".to_string()),
+            entry.reason.clone(),
+        ]);
+    }
+    println!("{}", table.render());
+}
+
+/// Number of expiring charts listed by `next-cycle` without `--all`
+const EXPIRING_PREVIEW: usize = 10;
+
+/// Print the AIRAC cycles and the cached charts expiring at the next one
+fn print_cycle_outlook(outlook: &CycleOutlook, today: chrono::NaiveDate, all: bool) {
+    println!(
+        "{}",
+        t!(
+            "Current cycle: AIRAC {}, {} to {}",
+            outlook.current.ident(),
+            outlook.current.effective_date(),
+            outlook.current.last_date()
+        )
+    );
+    println!(
+        "{}",
+        t!(
+            "Next cycle:    AIRAC {}, begins {} (in {} days)",
+            outlook.next.ident(),
+            outlook.next.effective_date(),
+            (outlook.next.effective_date() - today).num_days()
+        )
+    );
+
+    if outlook.expiring.is_empty() {
+        println!(
+            "{}",
+            t!("No cached chart expires when the next cycle begins")
+        );
+    } else {
+        println!(
+            "{}",
+            t!(
+                "{} cached chart(s) expire when AIRAC {} begins, if a new edition is published:",
+                outlook.expiring.len(),
+                outlook.next.ident()
+            )
+        );
+        let shown = if all {
+            outlook.expiring.len()
+        } else {
+            EXPIRING_PREVIEW
+        };
+        for (entry, date) in outlook.expiring.iter().take(shown) {
+            println!(
+                "{}",
+                t!(
+                    "  {} {} {} (effective {}, AIRAC {})",
+                    entry.oaci,
+                    entry.vac_type,
+                    entry.version,
+                    date,
+                    AiracCycle::containing(*date).ident()
+                )
+            );
+        }
+        if outlook.expiring.len() > shown {
+            println!(
+                "{}",
+                t!(
+                    "  ... {} more, list them with --all",
+                    outlook.expiring.len() - shown
+                )
+            );
+        }
+    }
+    if !outlook.upcoming.is_empty() {
+        println!(
+            "{}",
+            t!(
+                "{} cached chart(s) already published for a later cycle:",
+                outlook.upcoming.len()
+            )
+        );
+        for (entry, date) in &outlook.upcoming {
+            println!(
+                "{}",
+                t!(
+                    "  {} {} {} (effective {})",
+                    entry.oaci,
+                    entry.vac_type,
+                    entry.version,
+                    date
+                )
+            );
+        }
+    }
+    if !outlook.undated.is_empty() {
+        println!(
+            "{}",
+            t!(
+                "{} cached chart(s) have no effective date in their version",
+                outlook.undated.len()
+            )
+        );
+    }
+}
+
+/// Print a storage usage report
+fn print_storage_report(report: &StorageReport, top: usize) {
+    let entries: usize = report.entries_per_type.values().sum();
+    let per_type: Vec<String> = report
+        .entries_per_type
+        .iter()
+        .map(|(vac_type, count)| format!("{} {}", vac_type, count))
+        .collect();
+    let charts = if per_type.is_empty() {
+        entries.to_string()
+    } else {
+        format!("{} ({})", entries, per_type.join(", "))
+    };
+    println!("{}", style::bold(t!("Storage usage")));
+    println!(
+        "{}",
+        style::fields(&[
+            (t!("Airfields:"), report.airfields.len().to_string()),
+            (t!("Charts:"), charts),
+            (t!("Cached size:"), format_size(report.cached_size)),
+            (t!("Stored size:"), format_size(report.stored_size)),
+            (t!("Database size:"), format_size(report.database_size)),
+        ])
+    );
+
+    let missing: usize = report.airfields.iter().map(|a| a.missing_files).sum();
+    if missing > 0 {
+        println!(
+            "   {}",
+            style::status(Status::Warning, t!("Missing files: {}", missing))
+        );
+    }
+
+    if report.airfields.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new([t!("Region"), t!("Airfields"), t!("Charts"), t!("Size")])
+        .right_align(&[1, 2, 3]);
+    for (region, usage) in &report.by_region {
+        table.row(vec![
+            region.clone(),
+            usage.airfields.to_string(),
+            usage.entries.to_string(),
+            format_size(usage.stored_size),
+        ]);
+    }
+    println!("\n{}", table.render());
+
+    let mut table =
+        Table::new([t!("OACI"), t!("City"), t!("Charts"), t!("Size")]).right_align(&[2, 3]);
+    for airfield in report.airfields.iter().take(top) {
+        table.row(vec![
+            airfield.oaci.clone(),
+            airfield.city.clone(),
+            airfield.entries.to_string(),
+            format_size(airfield.stored_size),
+        ]);
+    }
+    println!("\n{}", table.render());
+    if report.airfields.len() > top {
+        println!("{}", t!("... and {} more", report.airfields.len() - top));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vac_downloader::{AvailableUpdate, UpdateReason, VacEntry};
+
+    #[test]
+    fn test_cli_definitions() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn test_check_exit_code() {
+        let update = |reason| AvailableUpdate {
+            entry: VacEntry {
+                oaci: "LFPN".to_string(),
+                city: "TOUSSUS LE NOBLE".to_string(),
+                vac_type: "AD".to_string(),
+                version: "1".to_string(),
+                file_name: "AD-2.LFPN.pdf".to_string(),
+                file_size: 1024,
+                file_hash: None,
+                remote_file_name: None,
+                page_count: None,
+                available_locally: true,
+            },
+            reason,
+        };
+        let result = |updates| CheckResult {
+            up_to_date: 2,
+            updates,
+        };
+
+        assert_eq!(check_exit_code(&result(Vec::new())), 0);
+        for reason in [UpdateReason::Missing, UpdateReason::Corrupted] {
+            assert_eq!(
+                check_exit_code(&result(vec![update(reason)])),
+                EXIT_UPDATES_AVAILABLE
+            );
+        }
+        assert_eq!(
+            check_exit_code(&result(vec![update(UpdateReason::New)])),
+            EXIT_UPDATES_AVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_manpages() {
+        let dir = tempfile::tempdir().unwrap();
+        write_manpages(Some(dir.path())).unwrap();
+
+        let page = std::fs::read_to_string(dir.path().join("vac-downloader-sync.1")).unwrap();
+        assert!(page.starts_with(".ie"));
+        assert!(page.contains("vac\\-downloader sync"));
+        // Global options are documented on the pages of the commands
+        assert!(page.contains("\\-\\-oaci"));
+        assert!(!dir.path().join("vac-downloader-help.1").exists());
+    }
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::i18n::t;
+use anyhow::Result;
+use vac_downloader::downloader::SyncStats;
+
+/// Number of airfields named in the body of a notification
+const MAX_LISTED: usize = 5;
+
+/// Title and body of a desktop notification
+#[derive(Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+}
+
+impl Notification {
+    /// Summarize a sync, unless it neither downloaded new versions nor had failures
+    pub fn from_stats(stats: &SyncStats) -> Option<Self> {
+        if stats.updates.is_empty() && stats.failures.is_empty() {
+            return None;
+        }
+
+        let summary = if stats.failures.is_empty() {
+            t!("VAC sync: {} chart(s) downloaded", stats.updates.len())
+        } else {
+            t!(
+                "VAC sync: {} chart(s) downloaded, {} failed",
+                stats.updates.len(),
+                stats.failures.len()
+            )
+        };
+        let mut lines = Vec::new();
+        if !stats.updates.is_empty() {
+            let codes: Vec<&str> = stats.updates.iter().map(|u| u.oaci.as_str()).collect();
+            lines.push(t!("Updated: {}", list_codes(&codes)));
+        }
+        if !stats.failures.is_empty() {
+            let codes: Vec<&str> = stats.failures.iter().map(|f| f.oaci.as_str()).collect();
+            lines.push(t!("Failed: {}", list_codes(&codes)));
+        }
+        Some(Notification {
+            summary,
+            body: lines.join("\n"),
+        })
+    }
+
+    /// Report a sync that could not run at all
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        Notification {
+            summary: t!("VAC sync failed").to_string(),
+            body: format!("{:#}", error),
+        }
+    }
+
+    /// Raise the notification on the desktop
+    #[cfg(feature = "notifications")]
+    pub fn show(&self) -> Result<()> {
+        use anyhow::Context;
+
+        notify_rust::Notification::new()
+            .appname("VAC Downloader")
+            .summary(&self.summary)
+            .body(&self.body)
+            .show()
+            .context("Failed to show the desktop notification")?;
+        Ok(())
+    }
+
+    /// Desktop notifications are unavailable without the `notifications` feature
+    #[cfg(not(feature = "notifications"))]
+    pub fn show(&self) -> Result<()> {
+        anyhow::bail!("This build does not support desktop notifications (`notifications` feature)")
+    }
+}
+
+/// Join the first codes of a list, counting the others
+fn list_codes(codes: &[&str]) -> String {
+    let mut codes = codes.to_vec();
+    codes.dedup();
+    if codes.len() <= MAX_LISTED {
+        return codes.join(", ");
+    }
+    t!(
+        "{} and {} more",
+        codes[..MAX_LISTED].join(", "),
+        codes.len() - MAX_LISTED
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vac_downloader::{ChartFailure, ChartUpdate, FailureKind};
+
+    fn update(oaci: &str) -> ChartUpdate {
+        ChartUpdate {
+            oaci: oaci.to_string(),
+            city: String::new(),
+            vac_type: "AD".to_string(),
+            version: "2".to_string(),
+            previous_version: Some("1".to_string()),
+            file_size: 1000,
+        }
+    }
+
+    #[test]
+    fn test_notification_from_stats() {
+        let mut stats = SyncStats::default();
+        assert_eq!(Notification::from_stats(&stats), None);
+
+        stats.updates = ["LFPG", "LFPO", "LFPN", "LFPT", "LFPZ", "LFPB", "LFOB"]
+            .iter()
+            .map(|oaci| update(oaci))
+            .collect();
+        let notification = Notification::from_stats(&stats).unwrap();
+        assert_eq!(notification.summary, "VAC sync: 7 chart(s) downloaded");
+        assert_eq!(
+            notification.body,
+            "Updated: LFPG, LFPO, LFPN, LFPT, LFPZ and 2 more"
+        );
+
+        stats.updates.truncate(1);
+        stats.failures.push(ChartFailure {
+            oaci: "LFRB".to_string(),
+            vac_type: "AD".to_string(),
+            kind: FailureKind::Server,
+            error: "HTTP 500".to_string(),
+        });
+        let notification = Notification::from_stats(&stats).unwrap();
+        assert_eq!(
+            notification.summary,
+            "VAC sync: 1 chart(s) downloaded, 1 failed"
+        );
+        assert_eq!(notification.body, "Updated: LFPG\nFailed: LFRB");
+    }
+}
@@ -0,0 +1,466 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::style::{self, Table};
+use anyhow::Context;
+use clap::{Args as ClapArgs, ValueEnum};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+use vac_downloader::downloader::SyncStats;
+use vac_downloader::{csv_field, format_size, ChartSummary};
+
+/// Output format of chart listings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns
+    #[default]
+    Table,
+    /// Comma-separated values with a header row
+    Csv,
+    /// A JSON array of objects
+    Json,
+}
+
+/// Column of chart listings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Oaci,
+    City,
+    Type,
+    /// Version published on the server
+    Version,
+    /// Version in the local cache
+    Cached,
+    /// Local status, e.g. "up to date"
+    Local,
+    /// Version change of outdated charts, e.g. "2024-11 → 2025-01"
+    Delta,
+    /// Size of the PDF
+    Size,
+    /// Time of the last cache update
+    Updated,
+}
+
+/// Sort order of chart listings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Largest first
+    Size,
+    City,
+    Oaci,
+    /// Most recently updated first, charts not cached last
+    Updated,
+}
+
+/// Columns of chart listings when none are given
+const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Oaci,
+    Column::City,
+    Column::Type,
+    Column::Version,
+    Column::Local,
+];
+
+/// Columns of outdated chart listings when none are given
+pub const OUTDATED_COLUMNS: &[Column] = &[
+    Column::Oaci,
+    Column::City,
+    Column::Type,
+    Column::Delta,
+    Column::Size,
+];
+
+/// Output options of the `list` and `search` commands
+#[derive(ClapArgs, Debug)]
+pub struct OutputArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Columns to print, in order [default: oaci,city,type,version,local, or
+    /// oaci,city,type,delta,size with --outdated]
+    #[arg(long, value_enum, value_delimiter = ',')]
+    columns: Option<Vec<Column>>,
+
+    /// Sort the charts instead of keeping the command's order
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+}
+
+impl OutputArgs {
+    /// Replace the default columns, unless columns were given
+    pub fn default_columns(&mut self, columns: &[Column]) {
+        self.columns.get_or_insert_with(|| columns.to_vec());
+    }
+
+    fn columns(&self) -> &[Column] {
+        self.columns.as_deref().unwrap_or(DEFAULT_COLUMNS)
+    }
+}
+
+impl Column {
+    /// Header of the column, also used as JSON key
+    fn name(self) -> &'static str {
+        match self {
+            Column::Oaci => "oaci",
+            Column::City => "city",
+            Column::Type => "type",
+            Column::Version => "version",
+            Column::Cached => "cached",
+            Column::Local => "local",
+            Column::Delta => "delta",
+            Column::Size => "size",
+            Column::Updated => "updated",
+        }
+    }
+
+    /// Value of the column as text, empty when unknown
+    fn text(self, chart: &ChartSummary, human_sizes: bool) -> String {
+        match self {
+            Column::Oaci => chart.oaci.clone(),
+            Column::City => chart.city.clone(),
+            Column::Type => chart.vac_type.clone(),
+            Column::Version => chart.remote_version.clone().unwrap_or_default(),
+            Column::Cached => chart.cached_version.clone().unwrap_or_default(),
+            Column::Local => chart.local_status(),
+            Column::Delta if chart.is_outdated() => format!(
+                "{} → {}",
+                chart.cached_version.as_deref().unwrap_or_default(),
+                chart.remote_version.as_deref().unwrap_or_default()
+            ),
+            Column::Delta => String::new(),
+            Column::Size if human_sizes => format_size(chart.file_size.max(0) as u64),
+            Column::Size => chart.file_size.to_string(),
+            Column::Updated => chart.last_updated.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Value of the column as JSON, null when unknown
+    fn json(self, chart: &ChartSummary) -> Value {
+        match self {
+            Column::Version => chart.remote_version.clone().into(),
+            Column::Cached => chart.cached_version.clone().into(),
+            Column::Size => chart.file_size.into(),
+            Column::Updated => chart.last_updated.clone().into(),
+            Column::Delta if !chart.is_outdated() => Value::Null,
+            column => column.text(chart, false).into(),
+        }
+    }
+}
+
+/// Chart with every column, as listed by `--format json` and the REST API of `serve`
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(not(feature = "serve"), allow(dead_code))]
+pub struct ChartObject {
+    pub oaci: String,
+    pub city: String,
+    #[serde(rename = "type")]
+    pub vac_type: String,
+    /// Version published on the server
+    pub version: Option<String>,
+    /// Version in the local cache
+    pub cached: Option<String>,
+    /// Local status, e.g. "up to date"
+    pub local: String,
+    /// Version change of outdated charts, e.g. "2024-11 → 2025-01"
+    pub delta: Option<String>,
+    /// Size of the PDF
+    pub size: i64,
+    /// Time of the last cache update
+    pub updated: Option<String>,
+}
+
+impl From<&ChartSummary> for ChartObject {
+    fn from(chart: &ChartSummary) -> Self {
+        let text = |column: Column| column.json(chart).as_str().map(str::to_string);
+        ChartObject {
+            oaci: chart.oaci.clone(),
+            city: chart.city.clone(),
+            vac_type: chart.vac_type.clone(),
+            version: chart.remote_version.clone(),
+            cached: chart.cached_version.clone(),
+            local: chart.local_status(),
+            delta: text(Column::Delta),
+            size: chart.file_size,
+            updated: chart.last_updated.clone(),
+        }
+    }
+}
+
+/// Sort charts by a key, keeping the original order between equal charts
+fn sort_charts(charts: &mut [ChartSummary], key: SortKey) {
+    match key {
+        SortKey::Size => charts.sort_by_key(|chart| std::cmp::Reverse(chart.file_size)),
+        SortKey::City => charts.sort_by(|a, b| a.city.cmp(&b.city)),
+        SortKey::Oaci => charts.sort_by(|a, b| a.oaci.cmp(&b.oaci)),
+        // None sorts before Some, so reversing also puts charts not cached last
+        SortKey::Updated => {
+            charts.sort_by(|a, b| b.last_updated.cmp(&a.last_updated));
+        }
+    }
+}
+
+/// Render charts in the requested format, without a trailing newline
+///
+/// Only the header row of tables is styled when `color` is set.
+fn render(charts: &[ChartSummary], args: &OutputArgs, color: bool) -> String {
+    match args.format {
+        OutputFormat::Json => {
+            let objects: Vec<Value> = charts
+                .iter()
+                .map(|chart| {
+                    let object: Map<String, Value> = args
+                        .columns()
+                        .iter()
+                        .map(|column| (column.name().to_string(), column.json(chart)))
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect();
+            serde_json::to_string_pretty(&objects).unwrap_or_default()
+        }
+        OutputFormat::Csv => {
+            let mut lines = vec![args
+                .columns()
+                .iter()
+                .map(|column| column.name())
+                .collect::<Vec<_>>()
+                .join(",")];
+            for chart in charts {
+                lines.push(
+                    args.columns()
+                        .iter()
+                        .map(|column| csv_field(&column.text(chart, false)))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+            }
+            lines.join("\n")
+        }
+        OutputFormat::Table => {
+            let mut table = Table::new(args.columns().iter().map(|column| {
+                let name = column.name();
+                name[..1].to_uppercase() + &name[1..]
+            }));
+            for chart in charts {
+                table.row(
+                    args.columns()
+                        .iter()
+                        .map(|column| column.text(chart, true))
+                        .collect(),
+                );
+            }
+            table.render_colored(color)
+        }
+    }
+}
+
+/// Print charts in the requested format and order
+///
+/// Tables of no chart are replaced by the `empty` message.
+pub fn print_charts(mut charts: Vec<ChartSummary>, args: &OutputArgs, empty: &str) {
+    if charts.is_empty() && args.format == OutputFormat::Table {
+        println!("{}", empty);
+        return;
+    }
+    if let Some(key) = args.sort {
+        sort_charts(&mut charts, key);
+    }
+    println!("{}", render(&charts, args, style::color()));
+}
+
+/// Outcome of a sync, as written by `sync --summary-file`
+#[derive(Debug, Serialize)]
+pub struct SyncSummary<'a> {
+    /// "success", "failed" when downloads failed, "incomplete" when some were
+    /// deferred, or "error" when the sync was aborted
+    pub status: &'static str,
+    /// Time the sync finished (UTC, RFC 3339)
+    pub finished_at: String,
+    /// Error that aborted the sync
+    pub error: Option<String>,
+    /// Counters, new versions, failures and per-chart results of the sync
+    pub stats: Option<&'a SyncStats>,
+}
+
+impl<'a> SyncSummary<'a> {
+    pub fn new(result: &'a anyhow::Result<SyncStats>) -> Self {
+        let status = match result {
+            Err(_) => "error",
+            Ok(stats) if stats.failed > 0 => "failed",
+            Ok(stats) if stats.deferred > 0 => "incomplete",
+            Ok(_) => "success",
+        };
+        SyncSummary {
+            status,
+            finished_at: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            stats: result.as_ref().ok(),
+        }
+    }
+
+    /// Write the summary as JSON
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, json).context(format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(oaci: &str, city: &str, size: i64, updated: Option<&str>) -> ChartSummary {
+        ChartSummary {
+            oaci: oaci.to_string(),
+            city: city.to_string(),
+            vac_type: "AD".to_string(),
+            remote_version: Some("2".to_string()),
+            cached_version: updated.map(|_| "1".to_string()),
+            file_size: size,
+            last_updated: updated.map(str::to_string),
+        }
+    }
+
+    fn args(format: OutputFormat, columns: &[Column]) -> OutputArgs {
+        OutputArgs {
+            format,
+            columns: Some(columns.to_vec()),
+            sort: None,
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let charts = vec![chart(
+            "LFPG",
+            "PARIS, CDG",
+            2048,
+            Some("2026-10-01 12:00:00"),
+        )];
+        let output = render(
+            &charts,
+            &args(
+                OutputFormat::Csv,
+                &[
+                    Column::Oaci,
+                    Column::City,
+                    Column::Size,
+                    Column::Local,
+                    Column::Delta,
+                ],
+            ),
+            false,
+        );
+        assert_eq!(
+            output,
+            "oaci,city,size,local,delta\nLFPG,\"PARIS, CDG\",2048,outdated (1),1 → 2"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let charts = vec![chart("LFPN", "TOUSSUS", 1024, None)];
+        let output = render(
+            &charts,
+            &args(
+                OutputFormat::Json,
+                &[Column::Oaci, Column::Cached, Column::Size, Column::Delta],
+            ),
+            false,
+        );
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{"oaci": "LFPN", "cached": null, "size": 1024, "delta": null}])
+        );
+    }
+
+    #[test]
+    fn test_chart_object() {
+        let charts = vec![chart("LFPG", "PARIS", 1024, Some("2026-10-01 12:00:00"))];
+        let output = render(
+            &charts,
+            &args(OutputFormat::Json, Column::value_variants()),
+            false,
+        );
+        let value: Value = serde_json::from_str(&output).unwrap();
+        let object = serde_json::to_value(ChartObject::from(&charts[0])).unwrap();
+        assert_eq!(value, Value::Array(vec![object]));
+    }
+
+    #[test]
+    fn test_sync_summary() {
+        let result = Ok(SyncStats {
+            downloaded: 1,
+            deferred: 2,
+            ..Default::default()
+        });
+        let summary = serde_json::to_value(SyncSummary::new(&result)).unwrap();
+        assert_eq!(summary["status"], "incomplete");
+        assert_eq!(summary["error"], Value::Null);
+        assert_eq!(summary["stats"]["downloaded"], 1);
+        assert!(summary["stats"]["results"].is_array());
+
+        let result = Err(anyhow::anyhow!("API unreachable"));
+        let summary = serde_json::to_value(SyncSummary::new(&result)).unwrap();
+        assert_eq!(summary["status"], "error");
+        assert_eq!(summary["error"], "API unreachable");
+        assert_eq!(summary["stats"], Value::Null);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let charts = vec![
+            chart("LFPG", "PARIS", 1024, None),
+            chart("LFOR", "CHARTRES", 1024, None),
+        ];
+        let output = render(
+            &charts,
+            &args(OutputFormat::Table, &[Column::Oaci, Column::City]),
+            false,
+        );
+        assert_eq!(output, "Oaci  City\nLFPG  PARIS\nLFOR  CHARTRES");
+    }
+
+    #[test]
+    fn test_sort_charts() {
+        let mut charts = vec![
+            chart("LFPG", "PARIS", 10, Some("2026-01-01 00:00:00")),
+            chart("LFOR", "CHARTRES", 30, None),
+            chart("LFRB", "BREST", 20, Some("2026-06-01 00:00:00")),
+        ];
+        let oacis = |charts: &[ChartSummary]| -> Vec<String> {
+            charts.iter().map(|chart| chart.oaci.clone()).collect()
+        };
+
+        sort_charts(&mut charts, SortKey::Size);
+        assert_eq!(oacis(&charts), ["LFOR", "LFRB", "LFPG"]);
+        sort_charts(&mut charts, SortKey::City);
+        assert_eq!(oacis(&charts), ["LFRB", "LFOR", "LFPG"]);
+        sort_charts(&mut charts, SortKey::Oaci);
+        assert_eq!(oacis(&charts), ["LFOR", "LFPG", "LFRB"]);
+        sort_charts(&mut charts, SortKey::Updated);
+        assert_eq!(oacis(&charts), ["LFRB", "LFPG", "LFOR"]);
+    }
+}
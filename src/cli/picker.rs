@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use inquire::MultiSelect;
+use std::fmt;
+use vac_downloader::ChartSummary;
+
+/// Airfield offered by the picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Airfield {
+    oaci: String,
+    city: String,
+    cached: bool,
+}
+
+impl fmt::Display for Airfield {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<6} {}", self.oaci, self.city)?;
+        if self.cached {
+            write!(f, " (cached)")?;
+        }
+        Ok(())
+    }
+}
+
+/// One airfield per OACI code of the listing, sorted by code
+fn airfields(charts: &[ChartSummary]) -> Vec<Airfield> {
+    let mut airfields: Vec<Airfield> = Vec::new();
+    for chart in charts {
+        let cached = chart.cached_version.is_some();
+        match airfields.iter_mut().find(|a| a.oaci == chart.oaci) {
+            Some(airfield) => airfield.cached |= cached,
+            None => airfields.push(Airfield {
+                oaci: chart.oaci.clone(),
+                city: chart.city.clone(),
+                cached,
+            }),
+        }
+    }
+    airfields.sort_by(|a, b| a.oaci.cmp(&b.oaci));
+    airfields
+}
+
+/// Score of an airfield for the text typed in the picker, higher is better
+fn score(input: &str, airfield: &Airfield) -> Option<i64> {
+    if input.trim().is_empty() {
+        return Some(0);
+    }
+    vac_downloader::matching::search_rank(input, &airfield.oaci, &airfield.city)
+        .map(|rank| -(rank as i64))
+}
+
+/// Let the user pick airfields of the listing in a fuzzy-searchable list
+///
+/// The airfields of `preselected` start selected. Returns the OACI codes of
+/// the selected airfields.
+pub fn pick_airfields(charts: &[ChartSummary], preselected: &[String]) -> Result<Vec<String>> {
+    let options = airfields(charts);
+    if options.is_empty() {
+        anyhow::bail!("No airfield to pick from");
+    }
+    let defaults: Vec<usize> = options
+        .iter()
+        .enumerate()
+        .filter(|(_, airfield)| {
+            preselected
+                .iter()
+                .any(|code| code.eq_ignore_ascii_case(&airfield.oaci))
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    let scorer = |input: &str, airfield: &Airfield, _: &str, _: usize| score(input, airfield);
+    let selected = MultiSelect::new("Airfields to sync:", options)
+        .with_default(&defaults)
+        .with_scorer(&scorer)
+        .with_page_size(15)
+        .with_help_message("type a city or code to filter, space to select, enter to confirm")
+        .prompt()
+        .context("Interactive selection failed (it requires a terminal)")?;
+
+    Ok(selected.into_iter().map(|airfield| airfield.oaci).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(oaci: &str, city: &str, vac_type: &str, cached: bool) -> ChartSummary {
+        ChartSummary {
+            oaci: oaci.to_string(),
+            city: city.to_string(),
+            vac_type: vac_type.to_string(),
+            remote_version: Some("1".to_string()),
+            cached_version: cached.then(|| "1".to_string()),
+            file_size: 1024,
+            last_updated: None,
+        }
+    }
+
+    #[test]
+    fn test_airfields() {
+        let charts = vec![
+            chart("LFPN", "TOUSSUS LE NOBLE", "AD", false),
+            chart("LFPG", "PARIS CHARLES DE GAULLE", "AD", false),
+            chart("LFPN", "TOUSSUS LE NOBLE", "HEL", true),
+        ];
+        let airfields = airfields(&charts);
+        assert_eq!(airfields.len(), 2);
+        assert_eq!(airfields[0].to_string(), "LFPG   PARIS CHARLES DE GAULLE");
+        assert_eq!(airfields[1].to_string(), "LFPN   TOUSSUS LE NOBLE (cached)");
+    }
+
+    #[test]
+    fn test_score() {
+        let airfield = Airfield {
+            oaci: "LFOR".to_string(),
+            city: "CHARTRES MÉTROPOLE".to_string(),
+            cached: false,
+        };
+        assert_eq!(score("", &airfield), Some(0));
+        assert_eq!(score("lfor", &airfield), Some(0));
+        assert!(score("metropole", &airfield).is_some());
+        assert!(score("chartre", &airfield) > score("metropole", &airfield));
+        assert_eq!(score("brest", &airfield), None);
+    }
+}
@@ -0,0 +1,635 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::output::ChartObject;
+use crate::webdav;
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tiny_http::{Header, Method, Response, ResponseBox, Server};
+use tracing::{info, warn};
+use utoipa::{OpenApi, ToSchema};
+use vac_downloader::{SyncRun, VacDownloader};
+
+/// Prefix of the REST API endpoints
+const API_PREFIX: &str = "/api/v1";
+
+/// Prefix of the URLs of the downloaded PDFs
+const PDF_PREFIX: &str = "/pdfs/";
+
+/// Prefix of the WebDAV share of the download directory
+const DAV_PREFIX: &str = "/dav";
+
+/// Realm of the basic authentication challenge
+const REALM: &str = "VAC Downloader";
+
+/// Settings of the `serve` command
+pub struct ServeOptions {
+    pub address: SocketAddr,
+    pub auth: Auth,
+    /// Directory whose PDFs are served under `/pdfs/`
+    pub download_dir: PathBuf,
+    /// Airfields synced by `POST /api/v1/sync` and listed by default, all when empty
+    pub oaci_codes: Vec<String>,
+}
+
+/// Credentials expected from clients, any request being accepted when none are set
+#[derive(Default)]
+pub struct Auth {
+    /// Token expected in an `Authorization: Bearer` header
+    pub token: Option<String>,
+    /// Username and password expected with basic authentication
+    pub credentials: Option<(String, String)>,
+}
+
+impl Auth {
+    fn is_enabled(&self) -> bool {
+        self.token.is_some() || self.credentials.is_some()
+    }
+
+    /// Whether the `Authorization` header of a request carries the expected credentials
+    fn allows(&self, authorization: Option<&str>) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let Some((scheme, value)) = authorization.and_then(|header| header.split_once(' ')) else {
+            return false;
+        };
+        let value = value.trim();
+        if scheme.eq_ignore_ascii_case("Bearer") {
+            return self
+                .token
+                .as_deref()
+                .is_some_and(|token| same_secret(token, value));
+        }
+        if scheme.eq_ignore_ascii_case("Basic") {
+            let Some((username, password)) = &self.credentials else {
+                return false;
+            };
+            let decoded = BASE64
+                .decode(value)
+                .ok()
+                .and_then(|d| String::from_utf8(d).ok());
+            return decoded
+                .as_deref()
+                .and_then(|decoded| decoded.split_once(':'))
+                .is_some_and(|(given_user, given_password)| {
+                    // Both are compared, so that a wrong username takes as long as a wrong password
+                    same_secret(username, given_user) & same_secret(password, given_password)
+                });
+        }
+        false
+    }
+
+    /// `WWW-Authenticate` challenge of unauthorized requests
+    fn challenge(&self) -> String {
+        match self.credentials {
+            Some(_) => format!("Basic realm=\"{}\"", REALM),
+            None => format!("Bearer realm=\"{}\"", REALM),
+        }
+    }
+}
+
+/// Compare secrets in a time independent of the position of the first difference
+fn same_secret(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// State shared by the request handlers and the background syncs
+struct ServerState {
+    downloader: Arc<VacDownloader>,
+    options: ServeOptions,
+    syncing: Arc<AtomicBool>,
+}
+
+/// Answer of an endpoint
+enum Reply {
+    Json(u16, Value),
+    /// A served file, with its content type
+    File(File, &'static str),
+    /// WebDAV multi-status answer
+    MultiStatus(String),
+    /// Capabilities of the WebDAV share
+    DavOptions,
+    /// Missing or wrong credentials, with the `WWW-Authenticate` challenge
+    Unauthorized(String),
+}
+
+/// Endpoint targeted by a request
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Vacs,
+    Vac(String),
+    Sync,
+    Status,
+    OpenApi,
+    Pdf(PathBuf),
+    /// File or collection of the WebDAV share, the download directory for an empty path
+    Dav(PathBuf),
+    NotFound,
+}
+
+/// Status of the server, answered by `GET /api/v1/status`
+#[derive(Serialize, ToSchema)]
+struct ServerStatus {
+    /// Version of vac-downloader
+    version: String,
+    /// Whether a sync started by `POST /api/v1/sync` is running
+    syncing: bool,
+    /// Number of downloads waiting in the queue
+    pending_downloads: usize,
+    /// Last run of the sync history
+    last_sync: Option<SyncRun>,
+}
+
+/// Answer of `POST /api/v1/sync`
+#[derive(Serialize, ToSchema)]
+struct SyncStarted {
+    /// Always "started"
+    status: String,
+}
+
+/// Answer of failed requests
+#[derive(Serialize, ToSchema)]
+struct ApiError {
+    error: String,
+}
+
+/// Serve the downloaded PDFs and the REST API until the process is stopped
+pub fn run(downloader: VacDownloader, options: ServeOptions) -> Result<()> {
+    let server = Server::http(options.address)
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", options.address, e))?;
+    info!(
+        "🌐 Serving the charts on http://{}{}",
+        options.address, API_PREFIX
+    );
+    if !options.address.ip().is_loopback() && !options.auth.is_enabled() {
+        warn!("⚠️  Serving beyond this machine without authentication, see the [serve] settings");
+    }
+    let state = ServerState {
+        downloader: Arc::new(downloader),
+        options,
+        syncing: Arc::new(AtomicBool::new(false)),
+    };
+
+    for request in server.incoming_requests() {
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.as_str());
+        let reply = if state.options.auth.allows(authorization) {
+            state.handle(request.method(), route(request.url()), request.headers())
+        } else {
+            Reply::Unauthorized(state.options.auth.challenge())
+        };
+        if let Err(e) = request.respond(reply.into_response()) {
+            warn!("⚠️  Failed to answer a request: {}", e);
+        }
+    }
+    Ok(())
+}
+
+impl ServerState {
+    fn handle(&self, method: &Method, route: Route, headers: &[Header]) -> Reply {
+        let result = match (method, route) {
+            (method, Route::Dav(path)) => Ok(dav(self, method, &path, headers)),
+            (Method::Get, Route::Vacs) => list_vacs(self),
+            (Method::Get, Route::Vac(oaci)) => get_vac(self, &oaci),
+            (Method::Post, Route::Sync) => Ok(start_sync(self)),
+            (Method::Get, Route::Status) => get_status(self),
+            (Method::Get, Route::OpenApi) => Ok(Reply::Json(200, openapi())),
+            (Method::Get | Method::Head, Route::Pdf(path)) => Ok(get_pdf(self, &path)),
+            (_, Route::NotFound) => Ok(error(404, "Not found")),
+            _ => Ok(error(405, "Method not allowed")),
+        };
+        result.unwrap_or_else(|e| error(500, &format!("{:#}", e)))
+    }
+}
+
+/// OpenAPI document of the REST API, generated from the handlers and models
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "VAC Downloader",
+        description = "Charts synced by vac-downloader serve"
+    ),
+    paths(list_vacs, get_vac, start_sync, get_status, get_pdf),
+    components(schemas(ChartObject, ServerStatus, SyncRun, SyncStarted, ApiError))
+)]
+struct ApiDoc;
+
+/// OpenAPI document of the REST API, as JSON
+pub fn openapi() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap_or_default()
+}
+
+/// The charts of the listing and the cache
+#[utoipa::path(
+    get,
+    path = "/api/v1/vacs",
+    responses(
+        (status = 200, description = "Charts of the processed airfields", body = [ChartObject]),
+        (status = 500, description = "Listing or cache error", body = ApiError)
+    )
+)]
+fn list_vacs(state: &ServerState) -> Result<Reply> {
+    let oaci_codes = &state.options.oaci_codes;
+    let filter = (!oaci_codes.is_empty()).then_some(oaci_codes.as_slice());
+    let charts = state.downloader.list_charts(filter)?;
+    let objects: Vec<ChartObject> = charts.iter().map(ChartObject::from).collect();
+    Ok(Reply::Json(200, serde_json::to_value(objects)?))
+}
+
+/// The charts of an airfield
+#[utoipa::path(
+    get,
+    path = "/api/v1/vacs/{oaci}",
+    params(("oaci" = String, Path, description = "OACI code of the airfield, e.g. LFPN")),
+    responses(
+        (status = 200, description = "Charts of the airfield", body = [ChartObject]),
+        (status = 404, description = "No chart for this airfield", body = ApiError),
+        (status = 500, description = "Listing or cache error", body = ApiError)
+    )
+)]
+fn get_vac(state: &ServerState, oaci: &str) -> Result<Reply> {
+    let oaci = oaci.to_uppercase();
+    let charts = state
+        .downloader
+        .list_charts(Some(std::slice::from_ref(&oaci)))?;
+    let objects: Vec<ChartObject> = charts
+        .iter()
+        .filter(|chart| chart.oaci == oaci)
+        .map(ChartObject::from)
+        .collect();
+    if objects.is_empty() {
+        return Ok(error(404, &format!("No chart for {}", oaci)));
+    }
+    Ok(Reply::Json(200, serde_json::to_value(objects)?))
+}
+
+/// Start a sync in the background, unless one is running
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync",
+    responses(
+        (status = 202, description = "Sync started", body = SyncStarted),
+        (status = 409, description = "A sync is already running", body = ApiError)
+    )
+)]
+fn start_sync(state: &ServerState) -> Reply {
+    if state.syncing.swap(true, Ordering::SeqCst) {
+        return error(409, "A sync is already running");
+    }
+    let downloader = state.downloader.clone();
+    let syncing = state.syncing.clone();
+    let oaci_codes = state.options.oaci_codes.clone();
+    std::thread::spawn(move || {
+        let filter = (!oaci_codes.is_empty()).then_some(oaci_codes.as_slice());
+        // The outcome is recorded in the sync history, answered by the status endpoint
+        if let Err(e) = downloader.sync(filter) {
+            warn!("⚠️  Sync failed: {:#}", e);
+        }
+        syncing.store(false, Ordering::SeqCst);
+    });
+    let started = SyncStarted {
+        status: "started".to_string(),
+    };
+    Reply::Json(202, json!(started))
+}
+
+/// Whether a sync is running and the outcome of the last one
+#[utoipa::path(
+    get,
+    path = "/api/v1/status",
+    responses(
+        (status = 200, description = "Status of the server", body = ServerStatus),
+        (status = 500, description = "Cache error", body = ApiError)
+    )
+)]
+fn get_status(state: &ServerState) -> Result<Reply> {
+    let status = ServerStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        syncing: state.syncing.load(Ordering::SeqCst),
+        pending_downloads: state.downloader.pending_downloads()?.len(),
+        last_sync: state.downloader.sync_history(1)?.into_iter().next(),
+    };
+    Ok(Reply::Json(200, serde_json::to_value(status)?))
+}
+
+/// A file of the download directory
+#[utoipa::path(
+    get,
+    path = "/pdfs/{path}",
+    params(("path" = String, Path, description = "Path of the PDF in the download directory")),
+    responses(
+        (status = 200, description = "The PDF", content_type = "application/pdf", body = Vec<u8>),
+        (status = 404, description = "No such file", body = ApiError)
+    )
+)]
+fn get_pdf(state: &ServerState, path: &Path) -> Reply {
+    match File::open(state.options.download_dir.join(path)) {
+        Ok(file) if file.metadata().is_ok_and(|m| m.is_file()) => {
+            Reply::File(file, webdav::content_type(path))
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Requests of the read-only WebDAV share of the download directory
+fn dav(state: &ServerState, method: &Method, path: &Path, headers: &[Header]) -> Reply {
+    match method.as_str() {
+        "OPTIONS" => Reply::DavOptions,
+        "GET" | "HEAD" => get_pdf(state, path),
+        "PROPFIND" => {
+            // Depth: infinity, the default, is answered as Depth: 1
+            let depth = headers
+                .iter()
+                .find(|header| header.field.equiv("Depth"))
+                .map(|header| header.value.as_str().trim());
+            match webdav::propfind(
+                &state.options.download_dir,
+                path,
+                DAV_PREFIX,
+                depth != Some("0"),
+            ) {
+                Ok(xml) => Reply::MultiStatus(xml),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => error(404, "Not found"),
+                Err(e) => error(500, &e.to_string()),
+            }
+        }
+        _ => error(405, "The WebDAV share is read-only"),
+    }
+}
+
+impl Reply {
+    fn into_response(self) -> ResponseBox {
+        match self {
+            Reply::Json(status, value) => {
+                Response::from_string(serde_json::to_string_pretty(&value).unwrap_or_default())
+                    .with_status_code(status)
+                    .with_header(content_type("application/json"))
+                    .boxed()
+            }
+            Reply::File(file, mime) => Response::from_file(file)
+                .with_header(content_type(mime))
+                .boxed(),
+            Reply::MultiStatus(xml) => Response::from_string(xml)
+                .with_status_code(207)
+                .with_header(content_type("application/xml; charset=utf-8"))
+                .boxed(),
+            Reply::DavOptions => Response::empty(200)
+                .with_header(Header::from_bytes("DAV", "1").expect("valid header"))
+                .with_header(
+                    Header::from_bytes("Allow", webdav::ALLOWED_METHODS).expect("valid header"),
+                )
+                .boxed(),
+            Reply::Unauthorized(challenge) => {
+                let www_authenticate =
+                    Header::from_bytes("WWW-Authenticate", challenge).expect("valid header");
+                error(401, "Authentication required")
+                    .into_response()
+                    .with_header(www_authenticate)
+            }
+        }
+    }
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("valid header")
+}
+
+/// JSON error answer
+fn error(status: u16, message: &str) -> Reply {
+    let error = ApiError {
+        error: message.to_string(),
+    };
+    Reply::Json(status, json!(error))
+}
+
+/// Endpoint of a request URL, ignoring its query
+fn route(url: &str) -> Route {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path == "/openapi.json" {
+        return Route::OpenApi;
+    }
+    if let Some(file) = path.strip_prefix(PDF_PREFIX) {
+        return match percent_decode(file).as_deref().and_then(relative_path) {
+            Some(path) => Route::Pdf(path),
+            None => Route::NotFound,
+        };
+    }
+    if let Some(file) = path.strip_prefix(DAV_PREFIX) {
+        if !file.is_empty() && !file.starts_with('/') {
+            return Route::NotFound;
+        }
+        let file = file.trim_matches('/');
+        return match percent_decode(file).as_deref() {
+            Some("") => Route::Dav(PathBuf::new()),
+            Some(file) => relative_path(file).map_or(Route::NotFound, Route::Dav),
+            None => Route::NotFound,
+        };
+    }
+    let Some(endpoint) = path.strip_prefix(API_PREFIX) else {
+        return Route::NotFound;
+    };
+    match endpoint.trim_end_matches('/') {
+        "/vacs" => Route::Vacs,
+        "/sync" => Route::Sync,
+        "/status" => Route::Status,
+        endpoint => match endpoint.strip_prefix("/vacs/") {
+            Some(oaci) if !oaci.is_empty() && !oaci.contains('/') => Route::Vac(oaci.to_string()),
+            _ => Route::NotFound,
+        },
+    }
+}
+
+/// Relative path of a requested file, or None if it leaves the served directory
+/// or is hidden
+fn relative_path(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path);
+    path.components()
+        .all(|component| match component {
+            Component::Normal(name) => !webdav::is_hidden(&name.to_string_lossy()),
+            _ => false,
+        })
+        .then_some(path)
+}
+
+/// Decode the `%XX` escapes of a URL path
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("/api/v1/vacs"), Route::Vacs);
+        assert_eq!(route("/api/v1/vacs/"), Route::Vacs);
+        assert_eq!(route("/api/v1/vacs/lfpg"), Route::Vac("lfpg".to_string()));
+        assert_eq!(route("/api/v1/sync"), Route::Sync);
+        assert_eq!(route("/api/v1/status?verbose=1"), Route::Status);
+        assert_eq!(route("/openapi.json"), Route::OpenApi);
+        assert_eq!(route("/api/v1/vacs/LFPG/pdf"), Route::NotFound);
+        assert_eq!(route("/api/v2/vacs"), Route::NotFound);
+        assert_eq!(
+            route("/pdfs/LFPG/AD%202.pdf"),
+            Route::Pdf(PathBuf::from("LFPG/AD 2.pdf"))
+        );
+        assert_eq!(route("/pdfs/../vac_cache.db"), Route::NotFound);
+        assert_eq!(route("/pdfs/%2e%2e/vac_cache.db"), Route::NotFound);
+        assert_eq!(route("/pdfs//etc/passwd"), Route::NotFound);
+        assert_eq!(route("/pdfs/bad%zz"), Route::NotFound);
+        assert_eq!(route("/pdfs/.vac-1.part"), Route::NotFound);
+        assert_eq!(route("/dav"), Route::Dav(PathBuf::new()));
+        assert_eq!(route("/dav/"), Route::Dav(PathBuf::new()));
+        assert_eq!(route("/dav/LFPG/"), Route::Dav(PathBuf::from("LFPG")));
+        assert_eq!(route("/dav/../x"), Route::NotFound);
+        assert_eq!(route("/davx"), Route::NotFound);
+    }
+
+    #[test]
+    fn test_auth() {
+        assert!(Auth::default().allows(None));
+
+        let auth = Auth {
+            token: Some("s3cret".to_string()),
+            credentials: Some(("club".to_string(), "pass".to_string())),
+        };
+        assert!(!auth.allows(None));
+        assert!(auth.allows(Some("Bearer s3cret")));
+        assert!(auth.allows(Some("bearer s3cret")));
+        assert!(!auth.allows(Some("Bearer s3cre")));
+        assert!(!auth.allows(Some("Bearer s3cret2")));
+        // "club:pass" and "club:nope"
+        assert!(auth.allows(Some("Basic Y2x1YjpwYXNz")));
+        assert!(!auth.allows(Some("Basic Y2x1Yjpub3Bl")));
+        assert!(!auth.allows(Some("Basic !!!")));
+        assert!(!auth.allows(Some("Digest s3cret")));
+        assert_eq!(auth.challenge(), "Basic realm=\"VAC Downloader\"");
+
+        let auth = Auth {
+            token: Some("s3cret".to_string()),
+            credentials: None,
+        };
+        assert!(!auth.allows(Some("Basic Y2x1YjpwYXNz")));
+        assert_eq!(auth.challenge(), "Bearer realm=\"VAC Downloader\"");
+    }
+
+    #[test]
+    fn test_handle() {
+        let dir = tempfile::tempdir().unwrap();
+        let download_dir = dir.path().join("pdfs");
+        std::fs::create_dir(&download_dir).unwrap();
+        std::fs::write(download_dir.join("LFPG.pdf"), b"%PDF-1.4").unwrap();
+        let downloader =
+            VacDownloader::new(dir.path().join("vac_cache.db"), &download_dir).unwrap();
+        let state = ServerState {
+            downloader: Arc::new(downloader),
+            options: ServeOptions {
+                address: SocketAddr::from(([127, 0, 0, 1], 0)),
+                auth: Auth::default(),
+                download_dir,
+                oaci_codes: Vec::new(),
+            },
+            syncing: Arc::new(AtomicBool::new(false)),
+        };
+
+        let Reply::Json(200, status) = state.handle(&Method::Get, Route::Status, &[]) else {
+            panic!("status failed");
+        };
+        assert_eq!(status["syncing"], false);
+        assert_eq!(status["last_sync"], Value::Null);
+
+        assert!(matches!(
+            state.handle(&Method::Get, route("/pdfs/LFPG.pdf"), &[]),
+            Reply::File(_, "application/pdf")
+        ));
+        assert!(matches!(
+            state.handle(&Method::Get, route("/pdfs/LFPO.pdf"), &[]),
+            Reply::Json(404, _)
+        ));
+        assert!(matches!(
+            state.handle(&Method::Delete, Route::Status, &[]),
+            Reply::Json(405, _)
+        ));
+
+        let Reply::Json(200, document) = state.handle(&Method::Get, Route::OpenApi, &[]) else {
+            panic!("openapi failed");
+        };
+        assert!(document["paths"]["/api/v1/vacs/{oaci}"]["get"].is_object());
+        assert!(document["components"]["schemas"]["ChartObject"]["properties"]["type"].is_object());
+        assert!(document["components"]["schemas"]["SyncRun"].is_object());
+
+        let propfind = Method::from_str("PROPFIND").unwrap();
+        let depth = Header::from_bytes("Depth", "1").unwrap();
+        let Reply::MultiStatus(xml) = state.handle(&propfind, route("/dav/"), &[depth]) else {
+            panic!("propfind failed");
+        };
+        assert!(xml.contains("<D:href>/dav/LFPG.pdf</D:href>"));
+        assert!(matches!(
+            state.handle(&Method::Get, route("/dav/LFPG.pdf"), &[]),
+            Reply::File(..)
+        ));
+        assert!(matches!(
+            state.handle(&Method::Options, route("/dav"), &[]),
+            Reply::DavOptions
+        ));
+        assert!(matches!(
+            state.handle(&Method::Put, route("/dav/LFPO.pdf"), &[]),
+            Reply::Json(405, _)
+        ));
+
+        // A second sync is refused while one is running
+        state.syncing.store(true, Ordering::SeqCst);
+        assert!(matches!(
+            state.handle(&Method::Post, Route::Sync, &[]),
+            Reply::Json(409, _)
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Name of the systemd units and of the log file
+const SERVICE_NAME: &str = "vac-downloader";
+
+/// Label of the launchd agent
+const LAUNCHD_LABEL: &str = "io.github.jcorbier.vac-downloader";
+
+/// Name of the Windows scheduled task
+const TASK_NAME: &str = "VAC Downloader";
+
+/// Scheduler running the periodic syncs of a platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduler {
+    /// systemd user timer (Linux)
+    Systemd,
+    /// launchd user agent (macOS)
+    Launchd,
+    /// Task Scheduler through schtasks (Windows)
+    Schtasks,
+}
+
+impl Scheduler {
+    /// Scheduler of the current platform
+    pub fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Scheduler::Launchd)
+        } else if cfg!(windows) {
+            Ok(Scheduler::Schtasks)
+        } else if cfg!(target_os = "linux") {
+            Ok(Scheduler::Systemd)
+        } else {
+            anyhow::bail!(
+                "Installing a service is not supported on this platform, run `vac-downloader sync` from cron instead"
+            )
+        }
+    }
+}
+
+/// Periodic sync run by the scheduler of the platform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    /// Executable and arguments of the sync
+    pub command: Vec<String>,
+    /// Time between two syncs
+    pub interval: Duration,
+}
+
+impl Service {
+    /// Files defining the service, with their paths
+    pub fn files(&self, scheduler: Scheduler) -> Result<Vec<(PathBuf, String)>> {
+        Ok(match scheduler {
+            Scheduler::Systemd => {
+                let dir = systemd_user_dir()?;
+                vec![
+                    (
+                        dir.join(format!("{}.service", SERVICE_NAME)),
+                        self.systemd_service(),
+                    ),
+                    (
+                        dir.join(format!("{}.timer", SERVICE_NAME)),
+                        self.systemd_timer(),
+                    ),
+                ]
+            }
+            Scheduler::Launchd => {
+                let log = home_dir()?
+                    .join("Library/Logs")
+                    .join(format!("{}.log", SERVICE_NAME));
+                vec![(launchd_plist_path()?, self.launchd_plist(&log))]
+            }
+            Scheduler::Schtasks => Vec::new(),
+        })
+    }
+
+    /// Commands enabling the service once its files are written
+    pub fn activation(&self, scheduler: Scheduler) -> Result<Vec<Vec<String>>> {
+        Ok(match scheduler {
+            Scheduler::Systemd => vec![
+                args(&["systemctl", "--user", "daemon-reload"]),
+                args(&[
+                    "systemctl",
+                    "--user",
+                    "enable",
+                    "--now",
+                    &format!("{}.timer", SERVICE_NAME),
+                ]),
+            ],
+            Scheduler::Launchd => {
+                let plist = launchd_plist_path()?;
+                vec![args(&["launchctl", "load", "-w", &plist.to_string_lossy()])]
+            }
+            Scheduler::Schtasks => {
+                let (schedule, modifier) = task_schedule(self.interval)?;
+                vec![args(&[
+                    "schtasks",
+                    "/Create",
+                    "/F",
+                    "/TN",
+                    TASK_NAME,
+                    "/TR",
+                    &windows_command_line(&self.command),
+                    "/SC",
+                    schedule,
+                    "/MO",
+                    &modifier.to_string(),
+                ])]
+            }
+        })
+    }
+
+    /// systemd unit running one sync
+    fn systemd_service(&self) -> String {
+        let command: Vec<String> = self.command.iter().map(|arg| systemd_quote(arg)).collect();
+        format!(
+            "[Unit]\n\
+             Description=VAC Downloader sync\n\
+             Wants=network-online.target\n\
+             After=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             ExecStart={}\n",
+            command.join(" ")
+        )
+    }
+
+    /// systemd timer starting the sync unit periodically
+    fn systemd_timer(&self) -> String {
+        format!(
+            "[Unit]\n\
+             Description=Periodic VAC Downloader sync\n\
+             \n\
+             [Timer]\n\
+             OnBootSec=5min\n\
+             OnUnitActiveSec={}s\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            self.interval.as_secs()
+        )
+    }
+
+    /// launchd agent running the sync periodically, logging to `log`
+    fn launchd_plist(&self, log: &std::path::Path) -> String {
+        let arguments: String = self
+            .command
+            .iter()
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+            .collect();
+        let log = xml_escape(&log.to_string_lossy());
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{}</string>
+    <key>StandardErrorPath</key>
+    <string>{}</string>
+</dict>
+</plist>
+"#,
+            LAUNCHD_LABEL,
+            arguments,
+            self.interval.as_secs(),
+            log,
+            log
+        )
+    }
+}
+
+/// Commands disabling the service before its files are removed, and the files
+pub fn installed(scheduler: Scheduler) -> Result<(Vec<Vec<String>>, Vec<PathBuf>)> {
+    Ok(match scheduler {
+        Scheduler::Systemd => {
+            let dir = systemd_user_dir()?;
+            (
+                vec![args(&[
+                    "systemctl",
+                    "--user",
+                    "disable",
+                    "--now",
+                    &format!("{}.timer", SERVICE_NAME),
+                ])],
+                vec![
+                    dir.join(format!("{}.service", SERVICE_NAME)),
+                    dir.join(format!("{}.timer", SERVICE_NAME)),
+                ],
+            )
+        }
+        Scheduler::Launchd => {
+            let plist = launchd_plist_path()?;
+            (
+                vec![args(&[
+                    "launchctl",
+                    "unload",
+                    "-w",
+                    &plist.to_string_lossy(),
+                ])],
+                vec![plist],
+            )
+        }
+        Scheduler::Schtasks => (
+            vec![args(&["schtasks", "/Delete", "/F", "/TN", TASK_NAME])],
+            Vec::new(),
+        ),
+    })
+}
+
+/// Run a command of the scheduler, failing if it fails
+pub fn run(command: &[String]) -> Result<()> {
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .status()
+        .context(format!("Failed to run {}", command[0]))?;
+    if !status.success() {
+        anyhow::bail!("`{}` failed with {}", command.join(" "), status);
+    }
+    Ok(())
+}
+
+/// Interval in the units of the configuration file, e.g. "1h30m"
+pub fn format_interval(interval: Duration) -> String {
+    let seconds = interval.as_secs();
+    let mut text = String::new();
+    for (amount, unit) in [
+        (seconds / 3600, "h"),
+        (seconds % 3600 / 60, "m"),
+        (seconds % 60, "s"),
+    ] {
+        if amount > 0 {
+            text.push_str(&format!("{}{}", amount, unit));
+        }
+    }
+    if text.is_empty() {
+        text.push_str("0s");
+    }
+    text
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir().context("Unable to determine the home directory")
+}
+
+fn systemd_user_dir() -> Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .context("Unable to determine the configuration directory")?
+        .join("systemd/user"))
+}
+
+fn launchd_plist_path() -> Result<PathBuf> {
+    Ok(home_dir()?
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+/// Schedule type and modifier of `schtasks /SC ... /MO ...` for an interval
+fn task_schedule(interval: Duration) -> Result<(&'static str, u64)> {
+    let minutes = interval.as_secs() / 60;
+    match minutes {
+        0 => anyhow::bail!("The interval between syncs must be at least 1 minute"),
+        _ if minutes.is_multiple_of(24 * 60) => Ok(("DAILY", minutes / (24 * 60))),
+        _ if minutes.is_multiple_of(60) && minutes < 24 * 60 => Ok(("HOURLY", minutes / 60)),
+        _ if minutes < 24 * 60 => Ok(("MINUTE", minutes)),
+        _ => {
+            anyhow::bail!("Intervals of more than a day must be a whole number of days on Windows")
+        }
+    }
+}
+
+/// Quote an argument of a systemd `ExecStart=` line if needed
+fn systemd_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "\"'\\;$%".contains(c)) {
+        return arg.to_string();
+    }
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "$$")
+        .replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+/// Command line of a scheduled task, quoting the arguments with spaces
+fn windows_command_line(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("\"{}\"", arg)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> Service {
+        Service {
+            command: args(&["/opt/VAC Tools/vac-downloader", "--profile", "club", "sync"]),
+            interval: Duration::from_secs(6 * 3600),
+        }
+    }
+
+    #[test]
+    fn test_systemd_units() {
+        let unit = service().systemd_service();
+        assert!(unit.contains("ExecStart=\"/opt/VAC Tools/vac-downloader\" --profile club sync\n"));
+        assert!(unit.contains("Type=oneshot\n"));
+        assert!(service()
+            .systemd_timer()
+            .contains("OnUnitActiveSec=21600s\n"));
+        assert_eq!(systemd_quote("100%"), "\"100%%\"");
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = service().launchd_plist(std::path::Path::new("/Users/a&b/vac.log"));
+        assert!(plist.contains("<string>/opt/VAC Tools/vac-downloader</string>\n"));
+        assert!(plist.contains("<integer>21600</integer>"));
+        assert!(plist.contains("<string>/Users/a&amp;b/vac.log</string>"));
+    }
+
+    #[test]
+    fn test_task_schedule() {
+        let hours = |h: u64| Duration::from_secs(h * 3600);
+        assert_eq!(task_schedule(hours(6)).unwrap(), ("HOURLY", 6));
+        assert_eq!(task_schedule(hours(48)).unwrap(), ("DAILY", 2));
+        assert_eq!(
+            task_schedule(Duration::from_secs(90 * 60)).unwrap(),
+            ("MINUTE", 90)
+        );
+        assert!(task_schedule(Duration::from_secs(30)).is_err());
+        assert!(task_schedule(hours(30)).is_err());
+        assert_eq!(format_interval(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(
+            windows_command_line(&service().command),
+            "\"/opt/VAC Tools/vac-downloader\" --profile club sync"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Styling of the terminal output: status glyphs, colors and aligned columns
+//!
+//! Colors are only used on terminals, and never with `--no-color` or when the
+//! `NO_COLOR` environment variable is set (see <https://no-color.org>).
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COLOR_ALLOWED: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid colors, once at startup
+pub fn init(no_color: bool) {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    COLOR_ALLOWED.store(!no_color && !no_color_env, Ordering::Relaxed);
+}
+
+/// Whether text printed to stdout is colored
+pub fn color() -> bool {
+    COLOR_ALLOWED.load(Ordering::Relaxed) && std::io::stdout().is_terminal()
+}
+
+/// Whether text printed to stderr is colored
+pub fn stderr_color() -> bool {
+    COLOR_ALLOWED.load(Ordering::Relaxed) && std::io::stderr().is_terminal()
+}
+
+/// Status of a printed line, shown as a colored glyph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Warning,
+    Failure,
+    Info,
+}
+
+impl Status {
+    fn glyph(self) -> &'static str {
+        match self {
+            Status::Success => "✓",
+            Status::Warning => "!",
+            Status::Failure => "✗",
+            Status::Info => "•",
+        }
+    }
+
+    /// ANSI color code of the glyph
+    fn color_code(self) -> &'static str {
+        match self {
+            Status::Success => "32",
+            Status::Warning => "33",
+            Status::Failure => "31",
+            Status::Info => "36",
+        }
+    }
+
+    /// Status conveyed by the emoji leading a message
+    fn from_symbol(symbol: char) -> Self {
+        match symbol {
+            '✓' | '✔' | '✅' => Status::Success,
+            '⚠' => Status::Warning,
+            '✗' | '✘' | '❌' => Status::Failure,
+            _ => Status::Info,
+        }
+    }
+
+    fn paint(self, color: bool) -> String {
+        paint(self.glyph(), self.color_code(), color)
+    }
+}
+
+/// Wrap text in an ANSI style when colors are enabled
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether a character is an emoji or a pictograph rather than text
+fn is_symbol(c: char) -> bool {
+    matches!(c, '\u{2190}'..='\u{2BFF}' | '\u{1F000}'..='\u{1FAFF}')
+}
+
+/// A message preceded by the glyph of its status
+pub fn status(status: Status, message: impl Display) -> String {
+    format!("{} {}", status.paint(color()), message)
+}
+
+/// Bold text, for headings
+pub fn bold(text: &str) -> String {
+    paint(text, "1", color())
+}
+
+/// Replace the emoji leading a log message, if any, by the glyph of its status
+///
+/// Emoji are rendered with varying widths, or not at all, by terminals and
+/// log viewers; the glyphs are single-width.
+pub fn decorate(message: &str, color: bool) -> String {
+    let text = message.trim_start();
+    let indent = &message[..message.len() - text.len()];
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(symbol) if is_symbol(symbol) => {
+            let rest = chars.as_str().trim_start_matches('\u{fe0f}').trim_start();
+            let status = Status::from_symbol(symbol);
+            format!("{}{} {}", indent, status.paint(color), rest)
+        }
+        _ => message.to_string(),
+    }
+}
+
+/// Indented "label value" lines, with the values aligned
+pub fn fields(fields: &[(&str, String)]) -> String {
+    let width = fields
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or_default();
+    fields
+        .iter()
+        .map(|(label, value)| format!("   {:<width$} {}", label, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Line of a table
+enum Line {
+    Row(Vec<String>),
+    /// Text under the previous row, left out of the column widths
+    Note(Status, String),
+}
+
+/// Table with columns as wide as their widest cell
+pub struct Table {
+    headers: Vec<String>,
+    right_aligned: Vec<bool>,
+    lines: Vec<Line>,
+}
+
+impl Table {
+    /// Create a table with a header row, every column being left-aligned
+    pub fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        Self {
+            right_aligned: vec![false; headers.len()],
+            headers,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Right-align columns, typically numbers, by index
+    pub fn right_align(mut self, columns: &[usize]) -> Self {
+        for &column in columns {
+            self.right_aligned[column] = true;
+        }
+        self
+    }
+
+    /// Add a row, with one cell per column
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.lines.push(Line::Row(cells));
+    }
+
+    /// Add an indented message under the last row, e.g. its error
+    pub fn note(&mut self, status: Status, text: impl Display) {
+        self.lines.push(Line::Note(status, text.to_string()));
+    }
+
+    /// Render the table, without a trailing newline
+    pub fn render(&self) -> String {
+        self.render_colored(color())
+    }
+
+    /// Render the table with a bold header row when `color` is set
+    pub fn render_colored(&self, color: bool) -> String {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|column| {
+                self.lines
+                    .iter()
+                    .filter_map(|line| match line {
+                        Line::Row(cells) => cells.get(column),
+                        Line::Note(..) => None,
+                    })
+                    .chain(std::iter::once(&self.headers[column]))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let format_row = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .zip(&self.right_aligned)
+                .map(|((cell, &width), &right)| {
+                    if right {
+                        format!("{:>width$}", cell)
+                    } else {
+                        format!("{:<width$}", cell)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        std::iter::once(paint(&format_row(&self.headers), "1", color))
+            .chain(self.lines.iter().map(|line| match line {
+                Line::Row(cells) => format_row(cells),
+                Line::Note(status, text) => format!("  {} {}", status.paint(color), text),
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decorate() {
+        assert_eq!(decorate("📥 Downloading LFPN", false), "• Downloading LFPN");
+        assert_eq!(decorate("⚠️  Failed", false), "! Failed");
+        assert_eq!(decorate("  ✓ LFPN AD", false), "  ✓ LFPN AD");
+        assert_eq!(
+            decorate("✅ Sync complete!", true),
+            "\x1b[32m✓\x1b[0m Sync complete!"
+        );
+        assert_eq!(decorate("No emoji", true), "No emoji");
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(["OACI", "Charts", "Source"]).right_align(&[1]);
+        table.row(vec!["LFPN".into(), "12".into(), "api".into()]);
+        table.row(vec!["LFPT".into(), "3".into(), "".into()]);
+        table.note(Status::Failure, "timed out");
+        assert_eq!(
+            table.render_colored(false),
+            "OACI  Charts  Source\nLFPN      12  api\nLFPT       3\n  ✗ timed out"
+        );
+    }
+
+    #[test]
+    fn test_fields() {
+        let text = fields(&[
+            ("Total:", "2".to_string()),
+            ("Up to date:", "1".to_string()),
+        ]);
+        assert_eq!(text, "   Total:      2\n   Up to date: 1");
+    }
+}
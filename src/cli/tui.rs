@@ -0,0 +1,731 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use vac_downloader::{matching, ChartSummary, OacisEntry, SyncObserver, VacDownloader, VacEntry};
+
+/// Key bindings shown at the bottom of the dashboard
+const HELP: &str =
+    "↑↓ move  / filter  s sync airfield  u update cached  d delete  o open  r refresh  q quit";
+
+/// Event sent to the dashboard by background work
+enum Message {
+    /// The chart listing was loaded
+    Charts(Result<Vec<ChartSummary>, String>),
+    /// Details of an airfield were loaded, None when it is not in the listing
+    Details(String, Result<Option<OacisEntry>, String>),
+    /// A download of a sync starts
+    DownloadStarted {
+        label: String,
+        index: usize,
+        total: usize,
+    },
+    /// Part of the current download was received
+    DownloadProgress { received: u64, total: Option<u64> },
+    /// A download of a sync finished
+    DownloadFinished {
+        label: String,
+        error: Option<String>,
+    },
+    /// A sync finished, with its summary
+    SyncFinished(Result<String, String>),
+}
+
+/// Forwards the progress of syncs to the dashboard
+struct ChannelObserver(Sender<Message>);
+
+impl SyncObserver for ChannelObserver {
+    fn download_started(&self, entry: &VacEntry, index: usize, total: usize) {
+        let _ = self.0.send(Message::DownloadStarted {
+            label: chart_label(entry),
+            index,
+            total,
+        });
+    }
+
+    fn download_progress(&self, _entry: &VacEntry, received: u64, total: Option<u64>) {
+        let _ = self.0.send(Message::DownloadProgress { received, total });
+    }
+
+    fn download_finished(&self, entry: &VacEntry, error: Option<&anyhow::Error>) {
+        let _ = self.0.send(Message::DownloadFinished {
+            label: chart_label(entry),
+            error: error.map(|e| format!("{:#}", e)),
+        });
+    }
+}
+
+fn chart_label(entry: &VacEntry) -> String {
+    format!("{} {}", entry.oaci, entry.vac_type)
+}
+
+/// Progress of the running sync
+#[derive(Debug, Default)]
+struct SyncProgress {
+    current: Option<String>,
+    /// Received part of the current download, from 0 to 1
+    current_ratio: f64,
+    done: usize,
+    total: usize,
+    failed: usize,
+}
+
+/// State of the dashboard
+struct App {
+    downloader: Arc<VacDownloader>,
+    sender: Sender<Message>,
+    charts: Vec<ChartSummary>,
+    loading: bool,
+    filter: String,
+    editing_filter: bool,
+    table: TableState,
+    /// Loaded airfield details by OACI code
+    details: HashMap<String, Result<Option<OacisEntry>, String>>,
+    requested_details: HashSet<String>,
+    sync: Option<SyncProgress>,
+    /// OACI code awaiting confirmation of its deletion
+    pending_delete: Option<String>,
+    status: String,
+    /// Quitting during a sync was asked once
+    quit_requested: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new(downloader: Arc<VacDownloader>, sender: Sender<Message>) -> Self {
+        App {
+            downloader,
+            sender,
+            charts: Vec::new(),
+            loading: false,
+            filter: String::new(),
+            editing_filter: false,
+            table: TableState::default().with_selected(Some(0)),
+            details: HashMap::new(),
+            requested_details: HashSet::new(),
+            sync: None,
+            pending_delete: None,
+            status: String::new(),
+            quit_requested: false,
+            quit: false,
+        }
+    }
+
+    /// Reload the chart listing in the background
+    fn refresh(&mut self) {
+        self.loading = true;
+        let downloader = Arc::clone(&self.downloader);
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let charts = downloader.list_charts(None).map_err(|e| format!("{:#}", e));
+            let _ = sender.send(Message::Charts(charts));
+        });
+    }
+
+    /// Charts matching the filter, the best matches first
+    fn visible(&self) -> Vec<&ChartSummary> {
+        visible_charts(&self.charts, &self.filter)
+    }
+
+    fn selected(&self) -> Option<&ChartSummary> {
+        self.table
+            .selected()
+            .and_then(|index| self.visible().get(index).copied())
+    }
+
+    /// Load the details of the selected airfield in the background
+    fn request_details(&mut self) {
+        let Some(oaci) = self.selected().map(|chart| chart.oaci.clone()) else {
+            return;
+        };
+        if !self.requested_details.insert(oaci.clone()) {
+            return;
+        }
+        let downloader = Arc::clone(&self.downloader);
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let details = downloader.airfield(&oaci).map_err(|e| format!("{:#}", e));
+            let _ = sender.send(Message::Details(oaci, details));
+        });
+    }
+
+    /// Sync the given airfields in the background
+    fn start_sync(&mut self, codes: Vec<String>) {
+        if self.sync.is_some() {
+            self.status = "A sync is already running".to_string();
+            return;
+        }
+        self.status = format!("Syncing {}...", summarize_codes(&codes));
+        self.sync = Some(SyncProgress::default());
+        let downloader = Arc::clone(&self.downloader);
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let result = downloader
+                .sync(Some(&codes))
+                .map(|stats| {
+                    format!(
+                        "Sync done: {} downloaded, {} up to date, {} failed",
+                        stats.downloaded, stats.up_to_date, stats.failed
+                    )
+                })
+                .map_err(|e| format!("Sync failed: {:#}", e));
+            let _ = sender.send(Message::SyncFinished(result));
+        });
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Charts(Ok(charts)) => {
+                self.loading = false;
+                self.charts = charts;
+                self.clamp_selection();
+            }
+            Message::Charts(Err(e)) => {
+                self.loading = false;
+                self.status = format!("Failed to list charts: {}", e);
+            }
+            Message::Details(oaci, details) => {
+                self.details.insert(oaci, details);
+            }
+            Message::DownloadStarted {
+                label,
+                index,
+                total,
+            } => {
+                if let Some(sync) = &mut self.sync {
+                    sync.current = Some(label);
+                    sync.current_ratio = 0.0;
+                    sync.done = index;
+                    sync.total = total;
+                }
+            }
+            Message::DownloadProgress { received, total } => {
+                if let (Some(sync), Some(total)) = (&mut self.sync, total) {
+                    sync.current_ratio = received as f64 / total.max(1) as f64;
+                }
+            }
+            Message::DownloadFinished { label, error } => {
+                if let Some(sync) = &mut self.sync {
+                    sync.done += 1;
+                    sync.current_ratio = 0.0;
+                    if let Some(error) = error {
+                        sync.failed += 1;
+                        self.status = format!("✗ {}: {}", label, error);
+                    }
+                }
+            }
+            Message::SyncFinished(result) => {
+                self.sync = None;
+                self.status = result.unwrap_or_else(|e| e);
+                self.refresh();
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(oaci) = self.pending_delete.take() {
+            if key.code == KeyCode::Char('y') {
+                self.delete(&oaci);
+            } else {
+                self.status = "Deletion cancelled".to_string();
+            }
+            return;
+        }
+
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.editing_filter = false;
+                    self.filter.clear();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => {}
+            }
+            self.table.select(Some(0));
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.sync.is_some() && !self.quit_requested {
+                    self.status = "A sync is running, press q again to quit".to_string();
+                    self.quit_requested = true;
+                } else {
+                    self.quit = true;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Home => self.table.select(Some(0)),
+            KeyCode::End => self.move_selection(isize::MAX),
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Char('r') => self.refresh(),
+            KeyCode::Char('s') => {
+                if let Some(chart) = self.selected() {
+                    let oaci = chart.oaci.clone();
+                    self.start_sync(vec![oaci]);
+                }
+            }
+            KeyCode::Char('u') => {
+                let mut codes: Vec<String> = self
+                    .charts
+                    .iter()
+                    .filter(|chart| chart.cached_version.is_some())
+                    .map(|chart| chart.oaci.clone())
+                    .collect();
+                codes.dedup();
+                if codes.is_empty() {
+                    self.status = "No cached airfield to update".to_string();
+                } else {
+                    self.start_sync(codes);
+                }
+            }
+            KeyCode::Char('d') => {
+                if self.sync.is_some() {
+                    self.status = "Wait for the sync to finish before deleting".to_string();
+                } else if let Some(chart) = self.selected() {
+                    let oaci = chart.oaci.clone();
+                    self.status = format!("Delete the charts of {}? (y/n)", oaci);
+                    self.pending_delete = Some(oaci);
+                }
+            }
+            KeyCode::Char('o') => {
+                if let Some(chart) = self.selected() {
+                    let oaci = chart.oaci.clone();
+                    self.status = match crate::open_chart(&self.downloader, &oaci) {
+                        Ok(()) => format!("Opened the chart of {}", oaci),
+                        Err(e) => format!("{:#}", e),
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn delete(&mut self, oaci: &str) {
+        self.status = match self.downloader.delete(&[oaci.to_string()], None) {
+            Ok(results) => {
+                let deleted = results.iter().filter(|r| r.database_deleted).count();
+                format!("{} chart(s) of {} deleted", deleted, oaci)
+            }
+            Err(e) => format!("Failed to delete {}: {:#}", oaci, e),
+        };
+        self.refresh();
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let len = self.visible().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let index = current.saturating_add(offset).clamp(0, len as isize - 1);
+        self.table.select(Some(index as usize));
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.visible().len();
+        let index = self
+            .table
+            .selected()
+            .unwrap_or(0)
+            .min(len.saturating_sub(1));
+        self.table.select(Some(index));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let progress_height = if self.sync.is_some() { 3 } else { 0 };
+        let [header, main, progress, status, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(progress_height),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(main);
+
+        frame.render_widget(Paragraph::new(self.header()), header);
+
+        let visible = self.visible();
+        let rows: Vec<Row> = visible
+            .iter()
+            .map(|chart| {
+                let style = match (&chart.remote_version, &chart.cached_version) {
+                    (Some(remote), Some(cached)) if remote != cached => {
+                        Style::default().fg(Color::Yellow)
+                    }
+                    (_, Some(_)) => Style::default().fg(Color::Green),
+                    _ => Style::default(),
+                };
+                Row::new(vec![
+                    chart.oaci.clone(),
+                    chart.city.clone(),
+                    chart.vac_type.clone(),
+                    chart.local_status(),
+                ])
+                .style(style)
+            })
+            .collect();
+        let title = if self.editing_filter || !self.filter.is_empty() {
+            format!(" Charts (filter: {}) ", self.filter)
+        } else {
+            " Charts ".to_string()
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Fill(1),
+                Constraint::Length(4),
+                Constraint::Length(18),
+            ],
+        )
+        .header(Row::new(vec!["OACI", "City", "Type", "Local"]).style(Style::default().bold()))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, list, &mut self.table);
+
+        let lines = match self.selected() {
+            Some(chart) => {
+                let charts: Vec<&ChartSummary> = self
+                    .charts
+                    .iter()
+                    .filter(|other| other.oaci == chart.oaci)
+                    .collect();
+                match self.details.get(&chart.oaci) {
+                    Some(Ok(airfield)) => detail_lines(chart, airfield.as_ref(), &charts),
+                    Some(Err(e)) => vec![Line::from(format!("Failed to load details: {}", e))],
+                    None => vec![Line::from("Loading...")],
+                }
+            }
+            None => Vec::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(" Details ")),
+            details,
+        );
+
+        if let Some(sync) = &self.sync {
+            frame.render_widget(
+                Gauge::default()
+                    .block(Block::default().borders(Borders::ALL).title(" Sync "))
+                    .gauge_style(Style::default().fg(Color::Cyan))
+                    .ratio(progress_ratio(sync))
+                    .label(progress_label(sync)),
+                progress,
+            );
+        }
+
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+        frame.render_widget(
+            Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    /// Counts of the charts, cached charts and pending updates
+    fn header(&self) -> Line<'_> {
+        if self.loading && self.charts.is_empty() {
+            return Line::from("VAC Downloader - loading the listing...");
+        }
+        let cached = self
+            .charts
+            .iter()
+            .filter(|chart| chart.cached_version.is_some())
+            .count();
+        let outdated = self
+            .charts
+            .iter()
+            .filter(|chart| {
+                matches!((&chart.remote_version, &chart.cached_version),
+                    (Some(remote), Some(cached)) if remote != cached)
+            })
+            .count();
+        Line::from(vec![
+            Span::styled("VAC Downloader", Style::default().bold()),
+            Span::raw(format!(
+                " - {} charts, {} cached, {} update(s) pending",
+                self.charts.len(),
+                cached,
+                outdated
+            )),
+        ])
+    }
+}
+
+/// Charts matching a filter, the best matches first, see [`matching::search_rank`]
+fn visible_charts<'a>(charts: &'a [ChartSummary], filter: &str) -> Vec<&'a ChartSummary> {
+    if filter.trim().is_empty() {
+        return charts.iter().collect();
+    }
+    let mut ranked: Vec<(usize, &ChartSummary)> = charts
+        .iter()
+        .filter_map(|chart| {
+            matching::search_rank(filter, &chart.oaci, &chart.city).map(|rank| (rank, chart))
+        })
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, chart)| chart).collect()
+}
+
+/// Up to three codes, then their count
+fn summarize_codes(codes: &[String]) -> String {
+    if codes.len() <= 3 {
+        codes.join(", ")
+    } else {
+        format!("{} airfields", codes.len())
+    }
+}
+
+fn progress_ratio(sync: &SyncProgress) -> f64 {
+    if sync.total == 0 {
+        0.0
+    } else {
+        let current = if sync.done < sync.total {
+            sync.current_ratio.min(1.0)
+        } else {
+            0.0
+        };
+        ((sync.done as f64 + current) / sync.total as f64).min(1.0)
+    }
+}
+
+fn progress_label(sync: &SyncProgress) -> String {
+    let mut label = match &sync.current {
+        Some(current) if sync.done < sync.total => {
+            format!("Downloading {} ({}/{})", current, sync.done + 1, sync.total)
+        }
+        Some(_) => format!("{}/{} downloaded", sync.done, sync.total),
+        None => "Checking for updates...".to_string(),
+    };
+    if sync.failed > 0 {
+        label.push_str(&format!(", {} failed", sync.failed));
+    }
+    label
+}
+
+/// Lines describing an airfield: position, runways, frequencies and charts
+fn detail_lines(
+    chart: &ChartSummary,
+    airfield: Option<&OacisEntry>,
+    charts: &[&ChartSummary],
+) -> Vec<Line<'static>> {
+    let section = |title: &str| Line::styled(title.to_string(), Style::default().bold());
+    let mut lines = vec![
+        Line::styled(
+            format!("{} - {}", chart.oaci, chart.city),
+            Style::default().bold().fg(Color::Cyan),
+        ),
+        Line::default(),
+    ];
+
+    match airfield {
+        Some(airfield) => {
+            for ground in &airfield.grounds {
+                let mut line = ground.ground_type.clone();
+                if let Some(elevation) = ground.elevation {
+                    line.push_str(&format!(", elevation {} ft", elevation));
+                }
+                if let Some(coordinates) = &ground.coordinates {
+                    line.push_str(&format!(
+                        ", {:.4} {:.4}",
+                        coordinates.latitude, coordinates.longitude
+                    ));
+                }
+                lines.push(Line::from(line));
+            }
+
+            lines.push(Line::default());
+            lines.push(section("Runways"));
+            if airfield.runways.is_empty() {
+                lines.push(Line::from("  none listed"));
+            }
+            for runway in &airfield.runways {
+                lines.push(Line::from(format!(
+                    "  {:<8} {} x {} m  {}",
+                    runway.degrees, runway.length, runway.width, runway.runway_type
+                )));
+            }
+
+            lines.push(Line::default());
+            lines.push(section("Frequencies"));
+            let frequencies: Vec<(&str, &str)> = airfield
+                .frequencies
+                .iter()
+                .flat_map(|frequency| frequency.named())
+                .collect();
+            if frequencies.is_empty() {
+                lines.push(Line::from("  none listed"));
+            }
+            for (name, value) in frequencies {
+                lines.push(Line::from(format!("  {:<5} {}", name, value)));
+            }
+        }
+        None => lines.push(Line::from("Not in the remote listing")),
+    }
+
+    lines.push(Line::default());
+    lines.push(section("Charts"));
+    for chart in charts {
+        lines.push(Line::from(format!(
+            "  {:<4} {:<12} {}",
+            chart.vac_type,
+            chart.remote_version.as_deref().unwrap_or("-"),
+            chart.local_status()
+        )));
+    }
+    lines
+}
+
+/// Run the dashboard until the user quits
+pub fn run(downloader: VacDownloader) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let downloader = Arc::new(downloader.with_observer(Arc::new(ChannelObserver(sender.clone()))));
+    let mut app = App::new(downloader, sender);
+    app.refresh();
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &receiver);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    receiver: &Receiver<Message>,
+) -> Result<()> {
+    while !app.quit {
+        app.request_details();
+        terminal.draw(|frame| app.draw(frame))?;
+
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+        while let Ok(message) = receiver.try_recv() {
+            app.handle_message(message);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(oaci: &str, city: &str, remote: &str, cached: Option<&str>) -> ChartSummary {
+        ChartSummary {
+            oaci: oaci.to_string(),
+            city: city.to_string(),
+            vac_type: "AD".to_string(),
+            remote_version: Some(remote.to_string()),
+            cached_version: cached.map(str::to_string),
+            file_size: 1024,
+            last_updated: None,
+        }
+    }
+
+    #[test]
+    fn test_visible_charts() {
+        let charts = vec![
+            chart("LFPG", "PARIS CHARLES DE GAULLE", "1", None),
+            chart("LFPN", "TOUSSUS LE NOBLE", "1", Some("1")),
+            chart("LFOR", "CHARTRES MÉTROPOLE", "1", None),
+        ];
+        assert_eq!(visible_charts(&charts, "").len(), 3);
+        let oacis = |filter| -> Vec<String> {
+            visible_charts(&charts, filter)
+                .into_iter()
+                .map(|chart| chart.oaci.clone())
+                .collect()
+        };
+        assert_eq!(oacis("toussus"), vec!["LFPN"]);
+        assert_eq!(oacis("lfpn"), vec!["LFPN"]);
+        assert_eq!(oacis("lfp"), vec!["LFPG", "LFPN"]);
+        assert!(oacis("brest").is_empty());
+    }
+
+    #[test]
+    fn test_progress_label() {
+        let mut sync = SyncProgress::default();
+        assert_eq!(progress_label(&sync), "Checking for updates...");
+        assert_eq!(progress_ratio(&sync), 0.0);
+
+        sync.current = Some("LFPN AD".to_string());
+        sync.done = 1;
+        sync.total = 4;
+        sync.failed = 1;
+        assert_eq!(progress_label(&sync), "Downloading LFPN AD (2/4), 1 failed");
+        assert_eq!(progress_ratio(&sync), 0.25);
+        sync.current_ratio = 0.5;
+        assert_eq!(progress_ratio(&sync), 0.375);
+    }
+
+    #[test]
+    fn test_detail_lines() {
+        let airfield: OacisEntry = serde_json::from_value(serde_json::json!({
+            "code": "LFPN",
+            "city": "TOUSSUS LE NOBLE",
+            "grounds": [],
+            "maps": [],
+            "runways": [{"length": "1100", "width": "20", "type": "Revêtue", "degrees": "07L/25R"}],
+            "frequencies": [{"freqTWR": "119.300", "freqATIS": "127.475"}],
+            "information": []
+        }))
+        .unwrap();
+        let summary = chart("LFPN", "TOUSSUS LE NOBLE", "2", Some("1"));
+        let text: Vec<String> = detail_lines(&summary, Some(&airfield), &[&summary])
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(text[0], "LFPN - TOUSSUS LE NOBLE");
+        assert!(text.contains(&"  07L/25R  1100 x 20 m  Revêtue".to_string()));
+        assert!(text.contains(&"  TWR   119.300".to_string()));
+        assert!(text.contains(&"  ATIS  127.475".to_string()));
+        assert!(text.contains(&"  AD   2            outdated (1)".to_string()));
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+/// Methods answered under the WebDAV prefix, which is read-only
+pub const ALLOWED_METHODS: &str = "OPTIONS, GET, HEAD, PROPFIND";
+
+/// Whether a file is left out of listings and downloads, e.g. a download in progress
+pub fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// Content type of a served file, from its extension
+pub fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("pdf") => "application/pdf",
+        Some(e) if e.eq_ignore_ascii_case("html") => "text/html; charset=utf-8",
+        Some(e) if e.eq_ignore_ascii_case("json") => "application/json",
+        Some(e) if e.eq_ignore_ascii_case("ics") => "text/calendar",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Multi-status answer of a PROPFIND request on `path`, relative to `root`
+///
+/// Every property is listed, whatever the request asked for. With `children`,
+/// the members of a collection are listed too (`Depth: 1`); deeper listings
+/// are not supported, as allowed by RFC 4918.
+pub fn propfind(root: &Path, path: &Path, href_prefix: &str, children: bool) -> io::Result<String> {
+    let target = root.join(path);
+    let metadata = std::fs::metadata(&target)?;
+    let href = format!(
+        "{}/{}",
+        href_prefix,
+        percent_encode(&path.to_string_lossy())
+    );
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n",
+    );
+    write_response(&mut xml, &href, &target, &metadata);
+    if children && metadata.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(&target)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !is_hidden(&entry.file_name().to_string_lossy()))
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        let parent = href.trim_end_matches('/');
+        for entry in entries {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let name = entry.file_name();
+            let href = format!("{}/{}", parent, percent_encode(&name.to_string_lossy()));
+            write_response(&mut xml, &href, &entry.path(), &metadata);
+        }
+    }
+    xml.push_str("</D:multistatus>\n");
+    Ok(xml)
+}
+
+/// Append the `<D:response>` of a file or collection
+fn write_response(xml: &mut String, href: &str, path: &Path, metadata: &Metadata) {
+    xml.push_str("<D:response><D:href>");
+    xml.push_str(href);
+    if metadata.is_dir() && !href.ends_with('/') {
+        xml.push('/');
+    }
+    xml.push_str("</D:href><D:propstat><D:prop>");
+    if metadata.is_dir() {
+        xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        let _ = write!(
+            xml,
+            "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength><D:getcontenttype>{}</D:getcontenttype>",
+            metadata.len(),
+            content_type(path)
+        );
+    }
+    if let Ok(modified) = metadata.modified() {
+        let modified: DateTime<Utc> = modified.into();
+        let _ = write!(
+            xml,
+            "<D:getlastmodified>{}</D:getlastmodified>",
+            modified.format("%a, %d %b %Y %H:%M:%S GMT")
+        );
+    }
+    xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>\n");
+}
+
+/// Escape the characters of a path that cannot appear in a URL, keeping the separators
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_propfind() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("LFPG")).unwrap();
+        std::fs::write(dir.path().join("LFPG/AD 2.pdf"), b"%PDF-1.4").unwrap();
+        std::fs::write(dir.path().join(".vac-1.part"), b"").unwrap();
+
+        let xml = propfind(dir.path(), Path::new(""), "/dav", true).unwrap();
+        assert!(xml.contains("<D:href>/dav/</D:href>"));
+        assert!(xml.contains(
+            "<D:href>/dav/LFPG/</D:href><D:propstat><D:prop><D:resourcetype><D:collection/>"
+        ));
+        assert!(!xml.contains(".vac-1.part"));
+        assert!(!xml.contains("AD%202.pdf"));
+
+        let xml = propfind(dir.path(), Path::new("LFPG"), "/dav", true).unwrap();
+        assert!(xml.contains(
+            "<D:href>/dav/LFPG/AD%202.pdf</D:href><D:propstat><D:prop><D:resourcetype/><D:getcontentlength>8</D:getcontentlength><D:getcontenttype>application/pdf</D:getcontenttype><D:getlastmodified>"
+        ));
+        assert!(xml.contains(" GMT</D:getlastmodified>"));
+
+        // Without children, only the collection itself
+        let xml = propfind(dir.path(), Path::new("LFPG"), "/dav", false).unwrap();
+        assert_eq!(xml.matches("<D:response>").count(), 1);
+
+        let missing = propfind(dir.path(), Path::new("LFPO"), "/dav", true).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,255 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use chrono::{Datelike, Days, NaiveDate};
+use std::fmt;
+
+/// Length of an AIRAC cycle, in days
+const CYCLE_DAYS: i64 = 28;
+
+/// Effective date of AIRAC cycle 2001, the reference of the cycle arithmetic
+fn reference_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2020, 1, 2).expect("valid date")
+}
+
+/// AIRAC cycle, the 28-day period on which aeronautical information changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AiracCycle {
+    effective: NaiveDate,
+}
+
+impl AiracCycle {
+    /// Cycle in effect on a date
+    pub fn containing(date: NaiveDate) -> Self {
+        let days = (date - reference_date()).num_days();
+        let cycles = days.div_euclid(CYCLE_DAYS);
+        AiracCycle {
+            effective: reference_date() + chrono::Duration::days(cycles * CYCLE_DAYS),
+        }
+    }
+
+    /// Cycle identified as `YYNN`, e.g. "2501" for the first cycle of 2025
+    pub fn from_ident(ident: &str) -> Option<Self> {
+        if ident.len() != 4 || !ident.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let year = 2000 + ident[..2].parse::<i32>().ok()?;
+        let number = ident[2..].parse::<u64>().ok()?;
+        let first = Self::first_of_year(year)?;
+        let cycle = AiracCycle {
+            effective: first
+                .effective
+                .checked_add_days(Days::new((number.checked_sub(1)?) * CYCLE_DAYS as u64))?,
+        };
+        (cycle.effective.year() == year).then_some(cycle)
+    }
+
+    /// First cycle taking effect in a year
+    fn first_of_year(year: i32) -> Option<Self> {
+        let january = Self::containing(NaiveDate::from_ymd_opt(year, 1, 1)?);
+        Some(if january.effective.year() == year {
+            january
+        } else {
+            january.next()
+        })
+    }
+
+    /// First day of the cycle
+    pub fn effective_date(&self) -> NaiveDate {
+        self.effective
+    }
+
+    /// Last day of the cycle
+    pub fn last_date(&self) -> NaiveDate {
+        self.next().effective - chrono::Duration::days(1)
+    }
+
+    /// Cycle following this one
+    pub fn next(&self) -> Self {
+        AiracCycle {
+            effective: self.effective + chrono::Duration::days(CYCLE_DAYS),
+        }
+    }
+
+    /// Identifier of the cycle, e.g. "2501"
+    pub fn ident(&self) -> String {
+        let year = self.effective.year();
+        let first = Self::first_of_year(year).unwrap_or(*self);
+        let number = (self.effective - first.effective).num_days() / CYCLE_DAYS + 1;
+        format!("{:02}{:02}", year % 100, number)
+    }
+}
+
+impl fmt::Display for AiracCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AIRAC {} ({})", self.ident(), self.effective)
+    }
+}
+
+/// Effective date of a chart version
+///
+/// Versions holding a date ("2025-01-23", "23/01/2025", "20250123",
+/// "23 JAN 2025", "23 janv. 2025") or an AIRAC cycle ("AIRAC 2501", "2501")
+/// are recognized; other versions have no effective date.
+pub fn parse_effective_date(version: &str) -> Option<NaiveDate> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix(['v', 'V'])
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(version);
+
+    let tokens: Vec<String> = version
+        .split(|c: char| c.is_whitespace() || c == '_')
+        .filter(|token| !token.is_empty())
+        .map(|token| token.trim_end_matches(['.', ',']).to_string())
+        .collect();
+
+    // Day, month name and year
+    for window in tokens.windows(3) {
+        if let (Ok(day), Some(month), Ok(year)) = (
+            window[0].parse::<u32>(),
+            month_number(&window[1]),
+            window[2].parse::<i32>(),
+        ) {
+            if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+                return Some(date);
+            }
+        }
+    }
+
+    for token in &tokens {
+        for format in [
+            "%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y", "%Y%m%d",
+        ] {
+            if let Ok(date) = NaiveDate::parse_from_str(token, format) {
+                if token.len() >= 8 {
+                    return Some(date);
+                }
+            }
+        }
+    }
+
+    // AIRAC cycle identifier, alone or after "AIRAC"
+    let ident = match tokens.as_slice() {
+        [ident] => ident,
+        [airac, ident] if airac.eq_ignore_ascii_case("airac") => ident,
+        _ => return None,
+    };
+    AiracCycle::from_ident(ident).map(|cycle| cycle.effective_date())
+}
+
+/// Number of a month from its English or French name or abbreviation
+fn month_number(name: &str) -> Option<u32> {
+    let name = crate::matching::fold(name).to_lowercase();
+    if name.len() < 3 {
+        return None;
+    }
+    const MONTHS: [&[&str]; 12] = [
+        &["january", "janvier"],
+        &["february", "fevrier"],
+        &["march", "mars"],
+        &["april", "avril"],
+        &["may", "mai"],
+        &["june", "juin"],
+        &["july", "juillet"],
+        &["august", "aout"],
+        &["september", "septembre"],
+        &["october", "octobre"],
+        &["november", "novembre"],
+        &["december", "decembre"],
+    ];
+    // "jui" is ambiguous between juin and juillet
+    let candidates: Vec<u32> = MONTHS
+        .iter()
+        .enumerate()
+        .filter(|(_, names)| names.iter().any(|full| full.starts_with(&name)))
+        .map(|(index, _)| index as u32 + 1)
+        .collect();
+    match candidates.as_slice() {
+        [month] => Some(*month),
+        _ => match name.as_str() {
+            "jun" => Some(6),
+            "jul" => Some(7),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_cycles() {
+        let cycle = AiracCycle::containing(date(2025, 1, 30));
+        assert_eq!(cycle.effective_date(), date(2025, 1, 23));
+        assert_eq!(cycle.last_date(), date(2025, 2, 19));
+        assert_eq!(cycle.ident(), "2501");
+        assert_eq!(cycle.next().ident(), "2502");
+        assert_eq!(cycle.next().effective_date(), date(2025, 2, 20));
+
+        // Cycles before the reference date
+        assert_eq!(
+            AiracCycle::containing(date(2019, 12, 31)).effective_date(),
+            date(2019, 12, 5)
+        );
+        // 2020 has 14 cycles
+        assert_eq!(AiracCycle::containing(date(2020, 12, 31)).ident(), "2014");
+
+        assert_eq!(AiracCycle::from_ident("2501"), Some(cycle));
+        assert_eq!(
+            AiracCycle::from_ident("2513").map(|c| c.effective_date()),
+            Some(date(2025, 12, 25))
+        );
+        assert_eq!(AiracCycle::from_ident("2514"), None);
+        assert_eq!(AiracCycle::from_ident("2500"), None);
+    }
+
+    #[test]
+    fn test_parse_effective_date() {
+        let expected = Some(date(2025, 1, 23));
+        for version in [
+            "2025-01-23",
+            "23/01/2025",
+            "20250123",
+            "v2025-01-23",
+            "23 JAN 2025",
+            "23 janv. 2025",
+            "AD 2 LFPG 23 JAN 2025",
+            "AIRAC 2501",
+            "2501",
+        ] {
+            assert_eq!(parse_effective_date(version), expected, "{}", version);
+        }
+        assert_eq!(parse_effective_date("20 FÉV 2025"), Some(date(2025, 2, 20)));
+        assert_eq!(
+            parse_effective_date("12 JUIL 2025"),
+            Some(date(2025, 7, 12))
+        );
+        assert_eq!(parse_effective_date("1"), None);
+        assert_eq!(parse_effective_date("2024-11"), None);
+        assert_eq!(parse_effective_date("31/02/2025"), None);
+    }
+}
@@ -0,0 +1,654 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::auth::{AuthError, AuthGenerator, AuthScheme};
+use crate::models::{OacisEntry, OacisResponse, SupplementEntry, SupplementsResponse};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::transport::{
+    HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, TransportError,
+};
+use anyhow::{Context, Result};
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Base URL of the SIA API
+pub const SIA_API_URL: &str = "https://bo-prod-sofia-vac.sia-france.fr";
+const OACIS_ENDPOINT: &str = "/api/v1/oacis";
+const FILE_ENDPOINT: &str = "/api/v1/custom/file-path";
+const SUPPLEMENTS_ENDPOINT: &str = "/api/v1/sup-aips";
+
+/// Log the headers and body of an HTTP error response, returning its status
+pub(crate) fn debug_error_response(response: HttpResponse) -> StatusCode {
+    let status = response.status();
+    debug!(%status, headers = ?response.headers(), "Error response");
+    if let Ok(body) = response.text() {
+        debug!(%body, "Error response body");
+    }
+    status
+}
+
+/// Error of an unsuccessful response, built by `error` from its status
+///
+/// For a 401 or a 403, the error is given the context of an [`AuthError`]
+/// telling which credentials were rejected, quoting the start of the body.
+/// `basic_auth` tells whether the request carried the Basic authentication
+/// besides the signature.
+pub(crate) fn status_error<F>(response: HttpResponse, basic_auth: bool, error: F) -> anyhow::Error
+where
+    F: FnOnce(StatusCode) -> anyhow::Error,
+{
+    let status = response.status();
+    if status != StatusCode::UNAUTHORIZED && status != StatusCode::FORBIDDEN {
+        return error(debug_error_response(response));
+    }
+    let challenge = response
+        .headers()
+        .get(WWW_AUTHENTICATE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let scheme = if !basic_auth {
+        Some(AuthScheme::Signature)
+    } else if challenge.starts_with("basic") {
+        Some(AuthScheme::Basic)
+    } else {
+        None
+    };
+    debug!(%status, headers = ?response.headers(), "Error response");
+    let body = response.text().unwrap_or_default();
+    debug!(%body, "Error response body");
+    let mut body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.chars().count() > 2 * EXCERPT_RADIUS {
+        body = body.chars().take(2 * EXCERPT_RADIUS).collect();
+        body.push('…');
+    }
+    error(status).context(AuthError {
+        status,
+        scheme,
+        body,
+    })
+}
+
+/// Error status returned by a server for a file download
+#[derive(Debug, thiserror::Error)]
+#[error("{what} download failed with status: {status}")]
+pub struct DownloadStatusError {
+    /// What was downloaded, e.g. "PDF"
+    pub what: &'static str,
+    pub status: StatusCode,
+}
+
+/// Number of characters quoted on each side of a JSON parse error
+const EXCERPT_RADIUS: usize = 80;
+
+/// Quote a response body around the line and column of a parse error
+fn body_excerpt(body: &[u8], line: usize, column: usize) -> String {
+    let body = String::from_utf8_lossy(body);
+    let Some(text) = body.lines().nth(line.saturating_sub(1)) else {
+        return String::new();
+    };
+    let mut column = column.min(text.len());
+    while !text.is_char_boundary(column) {
+        column -= 1;
+    }
+    let start = text[..column]
+        .chars()
+        .count()
+        .saturating_sub(EXCERPT_RADIUS);
+    let excerpt: String = text.chars().skip(start).take(2 * EXCERPT_RADIUS).collect();
+    let ellipsis = |elided: bool| if elided { "…" } else { "" };
+    format!(
+        "{}{}{}",
+        ellipsis(start > 0),
+        excerpt,
+        ellipsis(start + 2 * EXCERPT_RADIUS < text.chars().count())
+    )
+}
+
+/// File name of the raw response of an API path, e.g. "api_v1_oacis_page_1.json"
+fn dump_file_name(api_path: &str) -> String {
+    let name: String = api_path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}.json", name.trim_matches('_'))
+}
+
+/// Low-level client for the SIA API
+///
+/// Requests are signed with the `AUTH` header expected by the API, retried
+/// according to the retry policy, and sent to the fallback servers when the
+/// primary one is unreachable. Requests go through an [`HttpTransport`],
+/// a reqwest client by default. Endpoints without a typed method can be
+/// queried with [`SiaApiClient::get_json`]:
+///
+/// ```no_run
+/// use vac_downloader::SiaApiClient;
+///
+/// let api = SiaApiClient::new(reqwest::blocking::Client::new());
+/// let configs: serde_json::Value = api.get_json("/api/v1/configs")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct SiaApiClient {
+    transport: Arc<dyn HttpTransport>,
+    /// API base URLs, the primary one first and then the fallbacks
+    base_urls: Vec<String>,
+    /// Index of the base URL that answered last
+    active_base_url: AtomicUsize,
+    retry_policy: RetryPolicy,
+    /// Directory where responses that fail to parse are written
+    dump_dir: Option<PathBuf>,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl SiaApiClient {
+    /// Create a client for the SIA API using the given HTTP client
+    pub fn new(client: Client) -> Self {
+        Self::with_transport(Arc::new(ReqwestTransport::new(client)))
+    }
+
+    /// Create a client for the SIA API sending its requests through a transport
+    pub fn with_transport(transport: Arc<dyn HttpTransport>) -> Self {
+        SiaApiClient {
+            transport,
+            base_urls: vec![SIA_API_URL.to_string()],
+            active_base_url: AtomicUsize::new(0),
+            retry_policy: RetryPolicy::default(),
+            dump_dir: None,
+            rate_limiter: None,
+        }
+    }
+
+    /// Send requests to these servers instead of the SIA one
+    ///
+    /// The first server is the primary one, the others are tried in order
+    /// when the previous ones are unreachable.
+    pub fn with_base_urls(mut self, base_urls: Vec<String>) -> Result<Self> {
+        if base_urls.is_empty() {
+            anyhow::bail!("At least one API base URL is required");
+        }
+        for base_url in &base_urls {
+            reqwest::Url::parse(base_url).context(format!("Invalid API base URL: {}", base_url))?;
+        }
+        self.base_urls = base_urls
+            .iter()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .collect();
+        self.active_base_url = AtomicUsize::new(0);
+        Ok(self)
+    }
+
+    /// Retry requests that failed with a transient error (no retries by default)
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Space requests, including retries, according to a rate limiter
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Write the raw responses that fail to parse into this directory
+    pub fn with_dump_dir<P: AsRef<Path>>(mut self, dump_dir: P) -> Self {
+        self.dump_dir = Some(dump_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// The transport sending the requests
+    pub fn transport(&self) -> &dyn HttpTransport {
+        self.transport.as_ref()
+    }
+
+    /// The retry policy applied to requests
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Base URL of the server that answered last
+    pub fn base_url(&self) -> &str {
+        &self.base_urls[self.active_base_url.load(Ordering::Relaxed)]
+    }
+
+    /// API path of a page of the OACIS listing, starting at 1
+    pub fn oacis_path(page: u32) -> String {
+        Self::oacis_query_path(page, None, &[])
+    }
+
+    /// API path of a page of the OACIS listing, with `items_per_page` entries
+    /// per page and restricted to the given OACI codes (all if empty)
+    pub fn oacis_query_path(page: u32, items_per_page: Option<u32>, codes: &[String]) -> String {
+        let mut path = format!("{}?page={}", OACIS_ENDPOINT, page);
+        if let Some(items_per_page) = items_per_page {
+            path.push_str(&format!("&itemsPerPage={}", items_per_page));
+        }
+        for code in codes {
+            path.push_str(&format!("&code[]={}", code));
+        }
+        path
+    }
+
+    /// API path of the PDF of an airfield
+    pub fn file_path(oaci: &str, vac_type: &str) -> String {
+        format!("{}/{}/{}", FILE_ENDPOINT, oaci, vac_type)
+    }
+
+    /// API path of a page of the supplements listing, starting at 1
+    pub fn supplements_path(page: u32) -> String {
+        format!("{}?page={}", SUPPLEMENTS_ENDPOINT, page)
+    }
+
+    /// API path of the PDF of a supplement
+    pub fn supplement_file_path(id: i64) -> String {
+        format!("{}/sup/{}", FILE_ENDPOINT, id)
+    }
+
+    /// Send a signed GET request to the API
+    ///
+    /// `build` can add headers to the request. When a server is unreachable,
+    /// the request is sent to the next fallback server, which is then used
+    /// for the following requests.
+    pub fn get<F>(&self, api_path: &str, build: F) -> Result<HttpResponse, TransportError>
+    where
+        F: Fn(HttpRequest) -> HttpRequest,
+    {
+        let auth_header = AuthGenerator::generate_auth_header(api_path, None);
+        let mut index = self.active_base_url.load(Ordering::Relaxed);
+        loop {
+            let url = format!("{}{}", self.base_urls[index], api_path);
+            let result = self.retry_policy.send(|| {
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    rate_limiter.acquire();
+                }
+                self.transport
+                    .get(build(HttpRequest::get(&url).header("AUTH", &auth_header)))
+            });
+            match result {
+                Err(e)
+                    if (e.is_connect() || e.is_timeout()) && index + 1 < self.base_urls.len() =>
+                {
+                    index += 1;
+                    warn!(
+                        "⚠️  {} is unreachable ({}), falling back to {}",
+                        self.base_urls[index - 1],
+                        e,
+                        self.base_urls[index]
+                    );
+                }
+                result => {
+                    self.active_base_url.store(index, Ordering::Relaxed);
+                    if let Ok(response) = &result {
+                        debug!(url = %response.url(), status = %response.status(), "GET");
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Fetch a JSON document from the API
+    pub fn get_json<T: DeserializeOwned>(&self, api_path: &str) -> Result<T> {
+        let response = self
+            .get(api_path, |request| {
+                request.header(CONTENT_TYPE, "application/json")
+            })
+            .context(format!("Failed to fetch {}", api_path))?;
+        if !response.status().is_success() {
+            return Err(status_error(response, false, |status| {
+                anyhow::anyhow!("API returned error status: {}", status)
+            }));
+        }
+        let body = response
+            .bytes()
+            .context(format!("Failed to read response of {}", api_path))?;
+        self.parse_body(&body, api_path)
+    }
+
+    /// Parse the JSON body of a response to an API path
+    ///
+    /// When the body does not match the expected schema, the error quotes it
+    /// around the offending position, and the whole body is written to the
+    /// dump directory, if any.
+    pub fn parse_body<T: DeserializeOwned>(&self, body: &[u8], api_path: &str) -> Result<T> {
+        serde_json::from_slice(body).map_err(|error| {
+            let excerpt = body_excerpt(body, error.line(), error.column());
+            let mut message = format!("Failed to parse response of {}", api_path);
+            if !excerpt.is_empty() {
+                message.push_str(&format!(" near {:?}", excerpt));
+            }
+            if let Some(dump_dir) = &self.dump_dir {
+                let path = dump_dir.join(dump_file_name(api_path));
+                match std::fs::create_dir_all(dump_dir).and_then(|_| std::fs::write(&path, body)) {
+                    Ok(()) => message.push_str(&format!(" (raw response written to {:?})", path)),
+                    Err(e) => warn!("⚠️  Failed to write the raw response to {:?}: {}", path, e),
+                }
+            }
+            anyhow::Error::new(error).context(message)
+        })
+    }
+
+    /// Fetch a page of the OACIS listing, starting at 1
+    pub fn list_oacis_page(&self, page: u32) -> Result<OacisResponse> {
+        self.get_json(&Self::oacis_path(page))
+    }
+
+    /// Fetch all the entries of the OACIS listing
+    pub fn list_oacis(&self) -> Result<Vec<OacisEntry>> {
+        paginate(|page| {
+            info!("Fetching page {} from OACIS API...", page);
+            let response = self.list_oacis_page(page)?;
+            Ok((response.members, response.total_items))
+        })
+    }
+
+    /// Fetch all the AIP supplements currently published
+    pub fn list_supplements(&self) -> Result<Vec<SupplementEntry>> {
+        paginate(|page| {
+            info!("Fetching page {} from supplements API...", page);
+            let response: SupplementsResponse = self.get_json(&Self::supplements_path(page))?;
+            Ok((response.members, response.total_items))
+        })
+    }
+
+    /// Send the request for the PDF of an airfield, with its Basic authentication
+    ///
+    /// `build` can add headers to the request, e.g. to make it conditional.
+    pub fn get_file_with<F>(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        build: F,
+    ) -> Result<HttpResponse, TransportError>
+    where
+        F: Fn(HttpRequest) -> HttpRequest,
+    {
+        let basic_auth = AuthGenerator::generate_basic_auth();
+        self.get(&Self::file_path(oaci, vac_type), |request| {
+            build(request.header("Authorization", &basic_auth))
+        })
+    }
+
+    /// Download the PDF of an airfield
+    pub fn get_file(&self, oaci: &str, vac_type: &str) -> Result<Vec<u8>> {
+        let response = self
+            .get_file_with(oaci, vac_type, |request| request)
+            .context(format!("Failed to download PDF for {}", oaci))?;
+        if !response.status().is_success() {
+            return Err(status_error(response, true, |status| {
+                DownloadStatusError {
+                    what: "PDF",
+                    status,
+                }
+                .into()
+            }));
+        }
+        response.bytes().context("Failed to read PDF bytes")
+    }
+
+    /// Download the PDF of a supplement
+    pub fn get_supplement_file(&self, id: i64) -> Result<Vec<u8>> {
+        let basic_auth = AuthGenerator::generate_basic_auth();
+        let response = self
+            .get(&Self::supplement_file_path(id), |request| {
+                request.header("Authorization", &basic_auth)
+            })
+            .context(format!("Failed to download supplement {}", id))?;
+        if !response.status().is_success() {
+            return Err(status_error(response, true, |status| {
+                anyhow::anyhow!("Supplement download failed with status: {}", status)
+            }));
+        }
+        response.bytes().context("Failed to read supplement bytes")
+    }
+
+    /// Check that the server accepts the credentials, with light requests
+    ///
+    /// The signature is checked with a page of the listing holding a single
+    /// airfield, then the Basic authentication with the request for the first
+    /// chart of that airfield, whose body is not read. The Basic authentication
+    /// is only checked once the signature is accepted, and when the listing
+    /// has a chart.
+    pub fn check_auth(&self) -> Vec<(AuthScheme, Result<()>)> {
+        let mut checks = Vec::new();
+        let page: Result<OacisResponse> = self.get_json(&Self::oacis_query_path(1, Some(1), &[]));
+        let chart = match page {
+            Ok(page) => {
+                checks.push((AuthScheme::Signature, Ok(())));
+                page.members.into_iter().find_map(|entry| {
+                    let map = entry.maps.into_iter().next()?;
+                    Some((entry.code, map.map_type))
+                })
+            }
+            Err(e) => {
+                checks.push((AuthScheme::Signature, Err(e)));
+                None
+            }
+        };
+        if let Some((oaci, vac_type)) = chart {
+            let result = match self.get_file_with(&oaci, &vac_type, |request| request) {
+                Ok(response) if response.status().is_success() => Ok(()),
+                Ok(response) => Err(status_error(response, true, |status| {
+                    DownloadStatusError {
+                        what: "PDF",
+                        status,
+                    }
+                    .into()
+                })),
+                Err(e) => Err(anyhow::Error::new(e).context(format!(
+                    "Failed to download the {} chart of {}",
+                    vac_type, oaci
+                ))),
+            };
+            checks.push((AuthScheme::Basic, result));
+        }
+        checks
+    }
+}
+
+/// Collect the members of a paginated listing
+///
+/// `fetch_page` returns the members of a page, starting at 1, and the total
+/// number of members.
+fn paginate<T, F>(mut fetch_page: F) -> Result<Vec<T>>
+where
+    F: FnMut(u32) -> Result<(Vec<T>, i32)>,
+{
+    let mut members = Vec::new();
+    let mut page = 1;
+    loop {
+        let (page_members, total_items) = fetch_page(page)?;
+        let page_size = page_members.len();
+        members.extend(page_members);
+        if page_size == 0 || members.len() >= total_items as usize {
+            return Ok(members);
+        }
+        page += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockApi;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(SiaApiClient::oacis_path(2), "/api/v1/oacis?page=2");
+        assert_eq!(
+            SiaApiClient::oacis_query_path(1, Some(100), &["LFPG".to_string(), "LFPN".to_string()]),
+            "/api/v1/oacis?page=1&itemsPerPage=100&code[]=LFPG&code[]=LFPN"
+        );
+        assert_eq!(
+            SiaApiClient::file_path("LFPG", "AD"),
+            "/api/v1/custom/file-path/LFPG/AD"
+        );
+    }
+
+    #[test]
+    fn test_paginate() {
+        let pages = [vec![1, 2], vec![3, 4], vec![5]];
+        let mut fetched = Vec::new();
+        let members = paginate(|page| {
+            fetched.push(page);
+            Ok((pages[page as usize - 1].clone(), 5))
+        })
+        .unwrap();
+        assert_eq!(members, vec![1, 2, 3, 4, 5]);
+        assert_eq!(fetched, vec![1, 2, 3]);
+
+        // An empty page ends the listing even if the total is not reached
+        let members: Vec<i32> = paginate(|_| Ok((Vec::new(), 10))).unwrap();
+        assert!(members.is_empty());
+    }
+
+    #[test]
+    fn test_with_base_urls() {
+        let api = SiaApiClient::new(Client::new())
+            .with_base_urls(vec!["http://localhost:8080/".to_string()])
+            .unwrap();
+        assert_eq!(api.base_url(), "http://localhost:8080");
+
+        assert!(SiaApiClient::new(Client::new())
+            .with_base_urls(vec!["not a url".to_string()])
+            .is_err());
+        assert!(SiaApiClient::new(Client::new())
+            .with_base_urls(Vec::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let api = SiaApiClient::new(Client::new()).with_dump_dir(dir.path());
+        let path = SiaApiClient::oacis_path(1);
+
+        // Unknown fields are kept and missing lists default to empty
+        let body = br#"{"hydra:member": [{"code": "LFPN", "city": "TOUSSUS", "maps": [],
+            "ifr": true}], "hydra:totalItems": 1}"#;
+        let response: OacisResponse = api.parse_body(body, &path).unwrap();
+        assert_eq!(response.members[0].extra["ifr"], serde_json::json!(true));
+        assert!(response.members[0].runways.is_empty());
+        assert!(!dir.path().join("api_v1_oacis_page_1.json").exists());
+
+        let body = br#"{"hydra:member": [{"code": "LFPN", "city": 42}], "hydra:totalItems": 1}"#;
+        let error = format!(
+            "{:#}",
+            api.parse_body::<OacisResponse>(body, &path).unwrap_err()
+        );
+        assert!(error.contains("Failed to parse response of /api/v1/oacis?page=1"));
+        assert!(error.contains(r#""city\": 42"#), "{}", error);
+        assert!(error.contains("invalid type"), "{}", error);
+        let dumped = std::fs::read(dir.path().join("api_v1_oacis_page_1.json")).unwrap();
+        assert_eq!(dumped, body);
+    }
+
+    #[test]
+    fn test_body_excerpt() {
+        let line = format!("{}X{}", "a".repeat(100), "b".repeat(100));
+        let excerpt = body_excerpt(format!("{{\n{}", line).as_bytes(), 2, 101);
+        assert_eq!(excerpt.chars().count(), 2 * EXCERPT_RADIUS + 2);
+        assert!(excerpt.starts_with('…') && excerpt.ends_with('…'));
+        assert!(excerpt.contains("aX"));
+        assert_eq!(body_excerpt(b"{}", 1, 1), "{}");
+        assert_eq!(body_excerpt(b"", 3, 1), "");
+    }
+
+    #[test]
+    fn test_check_auth() {
+        let mock = MockApi::new();
+        mock.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = mock.downloader(dir.path());
+
+        let checks = downloader.api().check_auth();
+        let schemes: Vec<AuthScheme> = checks.iter().map(|(scheme, _)| *scheme).collect();
+        assert_eq!(schemes, [AuthScheme::Signature, AuthScheme::Basic]);
+        assert!(checks.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(mock.file_requests(), ["/api/v1/custom/file-path/LFPN/AD"]);
+
+        // A rejected download tells it may be either credentials, and keeps its status
+        mock.fail("file-path", StatusCode::UNAUTHORIZED);
+        let checks = downloader.api().check_auth();
+        let error = checks[1].1.as_ref().unwrap_err();
+        let auth_error = error.downcast_ref::<AuthError>().unwrap();
+        assert_eq!(auth_error.scheme, None);
+        assert_eq!(
+            error
+                .root_cause()
+                .downcast_ref::<DownloadStatusError>()
+                .unwrap()
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // A rejected listing can only be the signature
+        mock.fail("/api/v1/oacis", StatusCode::FORBIDDEN);
+        let checks = downloader.api().check_auth();
+        assert_eq!(checks.len(), 1);
+        let error = checks[0].1.as_ref().unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<AuthError>().unwrap().scheme,
+            Some(AuthScheme::Signature)
+        );
+    }
+
+    #[test]
+    fn test_get_json_signs_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let read = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..read]).to_lowercase();
+            let body = r#"{"signed": true}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        let api = SiaApiClient::new(Client::new())
+            .with_base_urls(vec![url])
+            .unwrap();
+        let value: serde_json::Value = api.get_json("/api/v1/configs").unwrap();
+        assert_eq!(value["signed"], true);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("get /api/v1/configs "));
+        let expected = AuthGenerator::generate_auth_header("/api/v1/configs", None).to_lowercase();
+        assert!(request.contains(&format!("auth: {}", expected)));
+    }
+}
@@ -20,8 +20,10 @@
  */
 
 use base64::{engine::general_purpose, Engine as _};
+use reqwest::StatusCode;
 use serde_json::json;
 use sha2::{Digest, Sha512};
+use std::fmt;
 
 const SHARE_SECRET: &str = "Y9Q3Ve72nN3PnTXmEtKnS4sggmdsigRMWH9kCDGHpCHyenFKKGhDq5vgBWZ4";
 const BASIC_AUTH_USER: &str = "api";
@@ -79,6 +81,68 @@ impl AuthGenerator {
     }
 }
 
+/// Credentials sent to the SIA API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// `AUTH` header signing every API request
+    Signature,
+    /// Basic authentication of the PDF downloads, sent along with the signature
+    Basic,
+}
+
+impl fmt::Display for AuthScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthScheme::Signature => write!(f, "AUTH header signature"),
+            AuthScheme::Basic => write!(f, "Basic authentication"),
+        }
+    }
+}
+
+/// Credentials rejected by the server with a 401 or a 403
+#[derive(Debug)]
+pub struct AuthError {
+    pub status: StatusCode,
+    /// Rejected credentials, `None` when the response does not tell which
+    pub scheme: Option<AuthScheme>,
+    /// Start of the response body, which may give the reason
+    pub body: String,
+}
+
+impl AuthError {
+    /// What to check to solve the failure
+    pub fn hint(&self) -> &'static str {
+        if self.status == StatusCode::FORBIDDEN {
+            "the server denies access: the client may be blocked (see the rate_limit \
+             setting), or a proxy may reject the request"
+        } else {
+            "the credentials are built into vac-downloader and the API may have changed \
+             them: check for a newer release, and that api_url points to the SIA API"
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.scheme {
+            Some(scheme) => write!(f, "{} rejected", scheme)?,
+            None => write!(
+                f,
+                "{} or {} rejected",
+                AuthScheme::Signature,
+                AuthScheme::Basic
+            )?,
+        }
+        write!(f, " by the server ({})", self.status)?;
+        if !self.body.is_empty() {
+            write!(f, ", which answered \"{}\"", self.body)?;
+        }
+        write!(f, "; {}", self.hint())
+    }
+}
+
+impl std::error::Error for AuthError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +162,28 @@ mod tests {
         let auth = AuthGenerator::generate_basic_auth();
         assert!(auth.starts_with("Basic "));
     }
+
+    #[test]
+    fn test_auth_error() {
+        let error = AuthError {
+            status: StatusCode::UNAUTHORIZED,
+            scheme: Some(AuthScheme::Basic),
+            body: "Invalid credentials".to_string(),
+        };
+        let message = error.to_string();
+        assert!(message.starts_with(
+            "Basic authentication rejected by the server (401 Unauthorized), \
+             which answered \"Invalid credentials\"; the credentials"
+        ));
+
+        let error = AuthError {
+            status: StatusCode::FORBIDDEN,
+            scheme: None,
+            body: String::new(),
+        };
+        assert!(error.to_string().starts_with(
+            "AUTH header signature or Basic authentication rejected by the server \
+             (403 Forbidden); the server denies access"
+        ));
+    }
 }
@@ -0,0 +1,308 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::matching;
+use crate::storage::Storage;
+use crate::{VacDatabase, VacEntry};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Version of the bundle archive layout
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+const PDF_DIR: &str = "pdfs";
+
+/// Manifest describing the content of a sync-state bundle
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    /// Creation time (seconds since the Unix epoch)
+    pub created_at: u64,
+    /// Cache entries packaged in the bundle
+    pub entries: Vec<VacEntry>,
+}
+
+/// Result from a bundle import
+#[derive(Debug)]
+pub struct BundleImport {
+    pub manifest: BundleManifest,
+    /// Entries whose PDF was restored and recorded in the cache
+    pub imported: Vec<VacEntry>,
+    /// OACI codes that could not be imported, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+/// Check that a file name from a bundle cannot escape the download directory
+fn is_safe_relative(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Package cached entries and their PDFs into a tar archive
+pub(crate) fn export_bundle(
+    database: &VacDatabase,
+    storage: &dyn Storage,
+    output: &Path,
+    oaci_filter: Option<&[String]>,
+) -> Result<BundleManifest> {
+    let mut entries = database
+        .get_all_entries()
+        .context("Failed to read cache entries")?;
+
+    if let Some(codes) = oaci_filter {
+        matching::warn_unmatched_codes(codes, &entries);
+        entries.retain(|entry| codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci)));
+    }
+
+    // Only bundle entries whose PDF is actually present
+    let mut included = Vec::new();
+    let mut contents = Vec::new();
+    for mut entry in entries {
+        let Some(data) = storage.read(&entry.file_name)? else {
+            warn!("⚠️  File missing for {} - skipping", entry.oaci);
+            continue;
+        };
+        if entry.file_hash.is_none() {
+            let mut hasher = Sha256::new();
+            hasher.update(&data);
+            entry.file_hash = Some(format!("{:x}", hasher.finalize()));
+        }
+        included.push(entry);
+        contents.push(data);
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        entries: included,
+    };
+
+    let file = File::create(output).context(format!("Failed to create bundle at {:?}", output))?;
+    let mut builder = tar::Builder::new(file);
+
+    // The manifest always comes first so imports can validate files as they stream in
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())
+        .context("Failed to write bundle manifest")?;
+
+    for (entry, data) in manifest.entries.iter().zip(contents) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.created_at);
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                Path::new(PDF_DIR).join(&entry.file_name),
+                data.as_slice(),
+            )
+            .context(format!("Failed to add {} to bundle", entry.file_name))?;
+    }
+
+    builder.finish().context("Failed to finalize bundle")?;
+
+    Ok(manifest)
+}
+
+/// Restore PDFs and cache entries from a tar archive created by [`export_bundle`]
+pub(crate) fn import_bundle(
+    database: &VacDatabase,
+    storage: &dyn Storage,
+    bundle: &Path,
+) -> Result<BundleImport> {
+    let file = File::open(bundle).context(format!("Failed to open bundle {:?}", bundle))?;
+    let mut archive = tar::Archive::new(file);
+    let mut files = archive.entries().context("Failed to read bundle")?;
+
+    let manifest: BundleManifest = {
+        let mut first = files
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Bundle is empty"))?
+            .context("Failed to read bundle")?;
+        if first.path()?.as_ref() != Path::new(MANIFEST_NAME) {
+            anyhow::bail!("Bundle does not start with {}", MANIFEST_NAME);
+        }
+        let mut contents = String::new();
+        first
+            .read_to_string(&mut contents)
+            .context("Failed to read bundle manifest")?;
+        serde_json::from_str(&contents).context("Failed to parse bundle manifest")?
+    };
+
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        anyhow::bail!(
+            "Bundle format version {} is not supported (max {})",
+            manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        );
+    }
+
+    let by_file_name: HashMap<&str, &VacEntry> = manifest
+        .entries
+        .iter()
+        .map(|e| (e.file_name.as_str(), e))
+        .collect();
+
+    let mut imported = Vec::new();
+    let mut rejected = Vec::new();
+
+    for file in files {
+        let mut file = file.context("Failed to read bundle")?;
+        let path = file.path()?.into_owned();
+        let Ok(relative) = path.strip_prefix(PDF_DIR) else {
+            continue;
+        };
+        let Some(entry) = relative.to_str().and_then(|name| by_file_name.get(name)) else {
+            continue;
+        };
+
+        if !is_safe_relative(relative) {
+            rejected.push((entry.oaci.clone(), "unsafe file name".to_string()));
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .context(format!("Failed to read {:?} from bundle", path))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        let hash = format!("{:x}", hasher.finalize());
+        if entry.file_hash.as_deref() != Some(hash.as_str()) {
+            rejected.push((entry.oaci.clone(), "hash mismatch".to_string()));
+            continue;
+        }
+
+        storage
+            .write(&entry.file_name, &bytes)
+            .context(format!("Failed to store PDF for {}", entry.oaci))?;
+        info!(
+            "✓ Restored {} to {}",
+            entry.oaci,
+            storage.describe(&entry.file_name)
+        );
+
+        imported.push((*entry).clone());
+    }
+
+    for entry in &manifest.entries {
+        let restored = imported
+            .iter()
+            .any(|e| e.oaci == entry.oaci && e.vac_type == entry.vac_type);
+        let already_rejected = rejected.iter().any(|(oaci, _)| *oaci == entry.oaci);
+        if !restored && !already_rejected {
+            rejected.push((entry.oaci.clone(), "PDF missing from bundle".to_string()));
+        }
+    }
+
+    database
+        .upsert_entries(&imported)
+        .context("Failed to update cache")?;
+
+    Ok(BundleImport {
+        manifest,
+        imported,
+        rejected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::fs;
+
+    fn entry(oaci: &str, contents: &[u8]) -> VacEntry {
+        let mut hasher = Sha256::new();
+        hasher.update(contents);
+        VacEntry {
+            oaci: oaci.to_string(),
+            city: "Paris".to_string(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: format!("{}_AD.pdf", oaci),
+            file_size: contents.len() as i64,
+            file_hash: Some(format!("{:x}", hasher.finalize())),
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let bundle_path = source.path().join("charts.tar");
+        let source_storage = LocalStorage::new(source.path()).unwrap();
+        let target_storage = LocalStorage::new(target.path()).unwrap();
+
+        let source_db = VacDatabase::new(":memory:").unwrap();
+        for (oaci, contents) in [("LFPG", b"%PDF-lfpg".as_slice()), ("LFPO", b"%PDF-lfpo")] {
+            let entry = entry(oaci, contents);
+            fs::write(source.path().join(&entry.file_name), contents).unwrap();
+            source_db.upsert_entry(&entry).unwrap();
+        }
+
+        let filter = vec!["lfpg".to_string()];
+        let manifest =
+            export_bundle(&source_db, &source_storage, &bundle_path, Some(&filter)).unwrap();
+        assert_eq!(manifest.entries.len(), 1);
+
+        let target_db = VacDatabase::new(":memory:").unwrap();
+        let result = import_bundle(&target_db, &target_storage, &bundle_path).unwrap();
+        assert_eq!(result.imported.len(), 1);
+        assert!(result.rejected.is_empty());
+        assert_eq!(
+            fs::read(target.path().join("LFPG_AD.pdf")).unwrap(),
+            b"%PDF-lfpg"
+        );
+        assert_eq!(
+            target_db.get_cached_version("LFPG", "AD").unwrap(),
+            Some("1.0".to_string())
+        );
+        assert_eq!(target_db.get_cached_version("LFPO", "AD").unwrap(), None);
+    }
+
+    #[test]
+    fn test_unsafe_paths_are_rejected() {
+        assert!(is_safe_relative(Path::new("LFPG_AD.pdf")));
+        assert!(is_safe_relative(Path::new("LF/LFPG_AD.pdf")));
+        assert!(!is_safe_relative(Path::new("../LFPG_AD.pdf")));
+        assert!(!is_safe_relative(Path::new("/etc/passwd")));
+    }
+}
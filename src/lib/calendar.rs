@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::airac::AiracCycle;
+use crate::VacEntry;
+use chrono::{Duration, NaiveDateTime};
+use std::fmt::Write;
+
+/// Product identifier of the generated calendars
+const PRODID: &str = "-//vac-downloader//Chart updates//EN";
+
+/// Domain part of the event UIDs
+const UID_DOMAIN: &str = "vac-downloader";
+
+/// iCalendar feed of the upcoming AIRAC cycles and of the chart updates
+#[derive(Debug, Clone, Default)]
+pub struct ChartCalendar {
+    /// Cycles shown as all-day events on their effective date
+    pub cycles: Vec<AiracCycle>,
+    /// Cached charts, with the time (UTC) they were last updated
+    pub updates: Vec<(VacEntry, NaiveDateTime)>,
+}
+
+impl ChartCalendar {
+    /// Render the calendar as an `.ics` document (RFC 5545)
+    ///
+    /// Events get stable UIDs and timestamps, so calendar apps subscribed to a
+    /// regenerated file only see the events that changed.
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODID),
+            "CALSCALE:GREGORIAN".to_string(),
+            "X-WR-CALNAME:VAC chart updates".to_string(),
+        ];
+
+        for cycle in &self.cycles {
+            let start = cycle.effective_date();
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:airac-{}@{}", cycle.ident(), UID_DOMAIN),
+                format!(
+                    "DTSTAMP:{}",
+                    format_timestamp(start.and_time(Default::default()))
+                ),
+                format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+                format!(
+                    "DTEND;VALUE=DATE:{}",
+                    (start + Duration::days(1)).format("%Y%m%d")
+                ),
+                format!("SUMMARY:{}", escape(&format!("AIRAC {}", cycle.ident()))),
+                format!(
+                    "DESCRIPTION:{}",
+                    escape(&format!(
+                        "AIRAC cycle {} is effective from {} to {}.",
+                        cycle.ident(),
+                        start,
+                        cycle.last_date()
+                    ))
+                ),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+
+        for (entry, updated_at) in &self.updates {
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!(
+                    "UID:{}-{}-{}@{}",
+                    uid_part(&entry.oaci),
+                    uid_part(&entry.vac_type),
+                    uid_part(&entry.version),
+                    UID_DOMAIN
+                ),
+                format!("DTSTAMP:{}", format_timestamp(*updated_at)),
+                format!("DTSTART:{}", format_timestamp(*updated_at)),
+                format!(
+                    "SUMMARY:{}",
+                    escape(&format!("{} {} chart updated", entry.oaci, entry.vac_type))
+                ),
+                format!(
+                    "DESCRIPTION:{}",
+                    escape(&format!(
+                        "{} ({}) {} chart, version {}.",
+                        entry.oaci, entry.city, entry.vac_type, entry.version
+                    ))
+                ),
+                format!(
+                    "LOCATION:{}",
+                    escape(&format!("{} {}", entry.oaci, entry.city))
+                ),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut text = String::new();
+        for line in lines {
+            fold(&mut text, &line);
+        }
+        text
+    }
+}
+
+/// Format a UTC time as an iCalendar date-time
+fn format_timestamp(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Keep the characters of a value that are safe in an event UID
+fn uid_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escape a text value: backslashes, semicolons, commas and newlines
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded to 75 octets per line and ended with CRLF
+fn fold(text: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            text.push_str("\r\n ");
+            width = 1;
+        }
+        text.push(c);
+        width += c.len_utf8();
+    }
+    let _ = write!(text, "\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn entry(oaci: &str, city: &str, version: &str) -> VacEntry {
+        VacEntry {
+            oaci: oaci.to_string(),
+            vac_type: "AD".to_string(),
+            version: version.to_string(),
+            file_name: format!("{}.pdf", oaci),
+            file_size: 1000,
+            city: city.to_string(),
+            file_hash: None,
+            remote_file_name: None,
+            page_count: None,
+            available_locally: true,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let cycle = AiracCycle::from_ident("2501").unwrap();
+        let updated_at = NaiveDate::from_ymd_opt(2025, 1, 23)
+            .unwrap()
+            .and_hms_opt(6, 30, 0)
+            .unwrap();
+        let calendar = ChartCalendar {
+            cycles: vec![cycle],
+            updates: vec![(entry("LFPN", "TOUSSUS, LE NOBLE", "2025-01-23"), updated_at)],
+        };
+
+        let text = calendar.render();
+        assert!(text.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(text.matches("BEGIN:VEVENT").count(), 2);
+        assert!(text.contains("UID:airac-2501@vac-downloader\r\n"));
+        assert!(text.contains(&format!(
+            "DTSTART;VALUE=DATE:{}\r\n",
+            cycle.effective_date().format("%Y%m%d")
+        )));
+        assert!(text.contains("UID:LFPN-AD-2025_01_23@vac-downloader\r\n"));
+        assert!(text.contains("DTSTART:20250123T063000Z\r\n"));
+        assert!(text.contains("LOCATION:LFPN TOUSSUS\\, LE NOBLE\r\n"));
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut text = String::new();
+        fold(&mut text, &format!("DESCRIPTION:{}", "é".repeat(60)));
+        let lines: Vec<&str> = text.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            text.replace("\r\n ", ""),
+            format!("DESCRIPTION:{}\r\n", "é".repeat(60))
+        );
+    }
+}
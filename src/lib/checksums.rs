@@ -0,0 +1,264 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::storage::Storage;
+use crate::VacDatabase;
+use anyhow::{anyhow, Context, Result};
+use minisign::{PublicKey, SecretKey, SecretKeyBox};
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the checksum manifest written at the root of the download directory
+pub const CHECKSUMS_NAME: &str = "SHA256SUMS";
+
+/// Name of the minisign signature of the checksum manifest
+pub const CHECKSUMS_SIGNATURE_NAME: &str = "SHA256SUMS.minisig";
+
+/// minisign secret key signing the checksum manifest
+pub struct SigningKey {
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl SigningKey {
+    /// Load a minisign secret key, as written by `minisign -G`
+    ///
+    /// Keys protected by a password need it to be given; keys generated with
+    /// `minisign -G -W` are read as is. The password is never prompted for.
+    pub fn from_file<P: AsRef<Path>>(path: P, password: Option<&str>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read signing key {}", path.display()))?;
+        Self::from_box(&contents, password)
+            .with_context(|| format!("Invalid signing key {}", path.display()))
+    }
+
+    /// Decode a minisign secret key from the contents of its file
+    pub fn from_box(contents: &str, password: Option<&str>) -> Result<Self> {
+        let sk_box = SecretKeyBox::from_string(contents)?;
+        let secret = match password {
+            Some(password) => SecretKey::from_box(sk_box, Some(password.to_string()))?,
+            None => SecretKey::from_unencrypted_box(sk_box).map_err(|e| {
+                anyhow!(
+                    "{} (a key protected by a password needs signing_key_password)",
+                    e
+                )
+            })?,
+        };
+        let public = PublicKey::from_secret_key(&secret)?;
+        Ok(SigningKey { secret, public })
+    }
+
+    /// Public key verifying the signatures, in the format of `minisign.pub`
+    pub fn public_key(&self) -> String {
+        self.public
+            .to_box()
+            .map(|b| b.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Sign `data`, returning the contents of the `.minisig` file
+    pub fn sign(&self, data: &[u8], file_name: &str) -> Result<String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let trusted_comment = format!("timestamp:{}\tfile:{}\thashed", timestamp, file_name);
+        let signature = minisign::sign(
+            Some(&self.public),
+            &self.secret,
+            data,
+            Some(&trusted_comment),
+            Some("signature from vac-downloader secret key"),
+        )?;
+        Ok(signature.into_string())
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey").finish_non_exhaustive()
+    }
+}
+
+/// `SHA256SUMS` manifest of the cached PDFs, in the format of `sha256sum`
+#[derive(Debug)]
+pub struct ChecksumManifest {
+    /// Hash and file name of each PDF, sorted by file name
+    entries: Vec<(String, String)>,
+}
+
+impl ChecksumManifest {
+    /// Build a manifest from the cached charts and supplements present in storage
+    pub(crate) fn from_database(database: &VacDatabase, storage: &dyn Storage) -> Result<Self> {
+        let charts = database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .map(|entry| (entry.file_hash, entry.file_name));
+        let supplements = database
+            .get_supplements()
+            .context("Failed to read supplements")?
+            .into_iter()
+            .map(|supplement| (supplement.file_hash, supplement.file_name));
+
+        let mut entries = Vec::new();
+        for (file_hash, file_name) in charts.chain(supplements) {
+            let Some(file_hash) = file_hash else {
+                continue;
+            };
+            if storage.exists(&file_name)? {
+                entries.push((file_hash, file_name));
+            }
+        }
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        Ok(ChecksumManifest { entries })
+    }
+
+    /// Number of files listed
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the manifest lists no file
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Render the manifest, one `<hash>  <file name>` line per file
+    pub fn render(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(hash, file_name)| format!("{}  {}\n", hash, file_name))
+            .collect()
+    }
+
+    /// Write the manifest at the root of the storage, signed when a key is given
+    ///
+    /// Without a key, a signature left by a previous run is removed, as it
+    /// would no longer match.
+    pub(crate) fn write(&self, storage: &dyn Storage, key: Option<&SigningKey>) -> Result<()> {
+        let contents = self.render();
+        storage
+            .write(CHECKSUMS_NAME, contents.as_bytes())
+            .context("Failed to write checksum manifest")?;
+        match key {
+            Some(key) => {
+                let signature = key
+                    .sign(contents.as_bytes(), CHECKSUMS_NAME)
+                    .context("Failed to sign checksum manifest")?;
+                storage
+                    .write(CHECKSUMS_SIGNATURE_NAME, signature.as_bytes())
+                    .context("Failed to write checksum manifest signature")?;
+            }
+            None => {
+                storage.delete(CHECKSUMS_SIGNATURE_NAME)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use crate::VacEntry;
+    use minisign::{KeyPair, PublicKeyBox, SignatureBox};
+    use std::fs;
+    use std::io::Cursor;
+
+    fn entry(oaci: &str) -> VacEntry {
+        VacEntry {
+            oaci: oaci.to_string(),
+            city: "Paris".to_string(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: format!("{}_AD.pdf", oaci),
+            file_size: 4,
+            file_hash: Some(format!("{}hash", oaci.to_lowercase())),
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_manifest_lists_present_files_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let db = VacDatabase::new(":memory:").unwrap();
+        for oaci in ["LFPO", "LFPG", "LFPB"] {
+            db.upsert_entry(&entry(oaci)).unwrap();
+        }
+        fs::write(dir.path().join("LFPO_AD.pdf"), b"%PDF").unwrap();
+        fs::write(dir.path().join("LFPG_AD.pdf"), b"%PDF").unwrap();
+
+        let manifest = ChecksumManifest::from_database(&db, &storage).unwrap();
+        assert_eq!(
+            manifest.render(),
+            "lfpghash  LFPG_AD.pdf\nlfpohash  LFPO_AD.pdf\n"
+        );
+    }
+
+    #[test]
+    fn test_signed_manifest_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let db = VacDatabase::new(":memory:").unwrap();
+        db.upsert_entry(&entry("LFPG")).unwrap();
+        fs::write(dir.path().join("LFPG_AD.pdf"), b"%PDF").unwrap();
+
+        let pair = KeyPair::generate_unencrypted_keypair().unwrap();
+        let key = SigningKey::from_box(&pair.sk.to_box(None).unwrap().to_string(), None).unwrap();
+        let manifest = ChecksumManifest::from_database(&db, &storage).unwrap();
+        manifest.write(&storage, Some(&key)).unwrap();
+
+        let contents = fs::read(dir.path().join(CHECKSUMS_NAME)).unwrap();
+        let signature = SignatureBox::from_string(
+            &fs::read_to_string(dir.path().join(CHECKSUMS_SIGNATURE_NAME)).unwrap(),
+        )
+        .unwrap();
+        let public = PublicKeyBox::from_string(&key.public_key())
+            .unwrap()
+            .into_public_key()
+            .unwrap();
+        minisign::verify(
+            &public,
+            &signature,
+            Cursor::new(contents),
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        assert!(signature
+            .trusted_comment()
+            .unwrap()
+            .contains("file:SHA256SUMS"));
+
+        // Rewriting without a key drops the stale signature
+        manifest.write(&storage, None).unwrap();
+        assert!(!dir.path().join(CHECKSUMS_SIGNATURE_NAME).exists());
+    }
+}
@@ -0,0 +1,182 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::api::DownloadStatusError;
+use reqwest::StatusCode;
+use std::fmt;
+
+/// Number of consecutive identical failures stopping a sync by default
+pub const DEFAULT_FAILURE_THRESHOLD: usize = 5;
+
+/// Download failure that is likely to affect every following download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PersistentFailure {
+    /// The server rejected the credentials (401)
+    Unauthorized,
+    /// The server denied access (403), e.g. when the client is blocked
+    Forbidden,
+    /// The server name could not be resolved
+    Dns,
+}
+
+impl PersistentFailure {
+    /// Classify a download error, `None` when it may be specific to one file
+    pub(crate) fn of(error: &anyhow::Error) -> Option<Self> {
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<DownloadStatusError>() {
+                return match error.status {
+                    StatusCode::UNAUTHORIZED => Some(PersistentFailure::Unauthorized),
+                    StatusCode::FORBIDDEN => Some(PersistentFailure::Forbidden),
+                    _ => None,
+                };
+            }
+            if cause.to_string().starts_with("dns error") {
+                return Some(PersistentFailure::Dns);
+            }
+        }
+        None
+    }
+
+    /// What to check to solve the failure
+    pub(crate) fn hint(&self) -> &'static str {
+        match self {
+            PersistentFailure::Unauthorized => {
+                "the API may have changed its authentication, check for a newer release"
+            }
+            PersistentFailure::Forbidden => {
+                "the server denies access, the client may be blocked (see the rate_limit setting)"
+            }
+            PersistentFailure::Dns => "check the network connection and the API URL",
+        }
+    }
+}
+
+impl fmt::Display for PersistentFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PersistentFailure::Unauthorized => write!(f, "401 Unauthorized"),
+            PersistentFailure::Forbidden => write!(f, "403 Forbidden"),
+            PersistentFailure::Dns => write!(f, "a DNS resolution error"),
+        }
+    }
+}
+
+/// Trips once downloads failed the same persistent way several times in a row
+///
+/// Any success or other kind of failure starts the count over. A threshold
+/// of 0 never trips.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: usize,
+    failure: Option<PersistentFailure>,
+    count: usize,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: usize) -> Self {
+        CircuitBreaker {
+            threshold,
+            failure: None,
+            count: 0,
+        }
+    }
+
+    /// Record the outcome of a download, returning the failure that tripped the breaker
+    pub(crate) fn record(&mut self, error: Option<&anyhow::Error>) -> Option<PersistentFailure> {
+        let failure = error.and_then(PersistentFailure::of);
+        if failure.is_some() && failure == self.failure {
+            self.count += 1;
+        } else {
+            self.failure = failure;
+            self.count = usize::from(failure.is_some());
+        }
+        self.failure
+            .filter(|_| self.threshold > 0 && self.count >= self.threshold)
+    }
+
+    /// Number of consecutive failures counted so far
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_error(status: StatusCode) -> anyhow::Error {
+        anyhow::Error::new(DownloadStatusError {
+            what: "PDF",
+            status,
+        })
+        .context("Failed to download LFPN")
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            PersistentFailure::of(&status_error(StatusCode::UNAUTHORIZED)),
+            Some(PersistentFailure::Unauthorized)
+        );
+        assert_eq!(
+            PersistentFailure::of(&status_error(StatusCode::FORBIDDEN)),
+            Some(PersistentFailure::Forbidden)
+        );
+        assert_eq!(
+            PersistentFailure::of(&status_error(StatusCode::NOT_FOUND)),
+            None
+        );
+        assert_eq!(
+            PersistentFailure::of(&anyhow::anyhow!("Invalid PDF received for LFPN")),
+            None
+        );
+
+        let error = reqwest::blocking::get("http://vac-downloader.invalid/").unwrap_err();
+        assert_eq!(
+            PersistentFailure::of(&anyhow::Error::new(error)),
+            Some(PersistentFailure::Dns)
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let unauthorized = status_error(StatusCode::UNAUTHORIZED);
+        let forbidden = status_error(StatusCode::FORBIDDEN);
+        let mut breaker = CircuitBreaker::new(3);
+        assert_eq!(breaker.record(Some(&unauthorized)), None);
+        assert_eq!(breaker.record(Some(&unauthorized)), None);
+        // A success or another failure starts over
+        assert_eq!(breaker.record(None), None);
+        assert_eq!(breaker.record(Some(&unauthorized)), None);
+        assert_eq!(breaker.record(Some(&forbidden)), None);
+        assert_eq!(breaker.count(), 1);
+        assert_eq!(breaker.record(Some(&forbidden)), None);
+        assert_eq!(
+            breaker.record(Some(&forbidden)),
+            Some(PersistentFailure::Forbidden)
+        );
+
+        let mut disabled = CircuitBreaker::new(0);
+        for _ in 0..10 {
+            assert_eq!(disabled.record(Some(&forbidden)), None);
+        }
+    }
+}
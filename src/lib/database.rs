@@ -19,53 +19,448 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use crate::models::VacEntry;
-use rusqlite::{params, Connection, Result};
+use crate::airac::parse_effective_date;
+use crate::models::{
+    AuditEntry, ChartVersion, HttpCacheEntry, OacisEntry, PendingDownload, Supplement, SyncRun,
+    SyncTotals, TextMatch, VacEntry,
+};
+use chrono::NaiveDate;
+#[cfg(feature = "pool")]
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{ffi, params, Connection, OpenFlags, OptionalExtension, Result, Row};
+use std::collections::HashMap;
 use std::path::Path;
+#[cfg(not(feature = "pool"))]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use vac_downloader_core::is_outdated;
+
+/// Ordered schema migrations
+///
+/// The schema version of a database is the number of migrations applied to it.
+/// New migrations must only ever be appended to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema (0.1.0)
+    "CREATE TABLE IF NOT EXISTS vac_cache (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        version TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        file_size INTEGER NOT NULL,
+        city TEXT NOT NULL,
+        last_updated DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    )",
+    // 2: file hash verification (0.3.0)
+    "ALTER TABLE vac_cache ADD COLUMN file_hash TEXT",
+    // 3: original server file name
+    "ALTER TABLE vac_cache ADD COLUMN remote_file_name TEXT",
+    // 4: sync run history
+    "CREATE TABLE sync_runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        started_at DATETIME NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        total_entries INTEGER NOT NULL,
+        downloaded INTEGER NOT NULL,
+        failed INTEGER NOT NULL,
+        bytes_downloaded INTEGER NOT NULL,
+        error TEXT
+    )",
+    // 5: persistent download queue
+    "CREATE TABLE pending_downloads (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        city TEXT NOT NULL,
+        version TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        remote_file_name TEXT,
+        file_size INTEGER NOT NULL,
+        file_hash TEXT,
+        source TEXT NOT NULL,
+        priority INTEGER NOT NULL DEFAULT 0,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        last_error TEXT,
+        queued_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    )",
+    // 6: HTTP validators for conditional requests
+    "CREATE TABLE http_cache (
+        path TEXT PRIMARY KEY,
+        etag TEXT,
+        last_modified TEXT,
+        body BLOB,
+        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    )",
+    // 7: AIP supplements
+    "CREATE TABLE supplements (
+        number TEXT PRIMARY KEY,
+        remote_id INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        valid_from TEXT,
+        valid_until TEXT,
+        aerodromes TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        file_hash TEXT,
+        last_updated DATETIME DEFAULT CURRENT_TIMESTAMP
+    )",
+    // 8: page count of validated PDFs
+    "ALTER TABLE vac_cache ADD COLUMN page_count INTEGER",
+    // 9: full-text index of the charts
+    "CREATE TABLE text_index (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        file_hash TEXT NOT NULL,
+        indexed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    );
+    CREATE VIRTUAL TABLE chart_text USING fts5(
+        oaci UNINDEXED,
+        vac_type UNINDEXED,
+        page UNINDEXED,
+        text,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );",
+    // 10: airfield details of the last listing
+    "CREATE TABLE airfields (
+        oaci TEXT PRIMARY KEY,
+        city TEXT NOT NULL,
+        latitude REAL,
+        longitude REAL,
+        elevation REAL,
+        details TEXT NOT NULL,
+        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    )",
+    // 11: effective date of the chart versions, filled from existing versions
+    "ALTER TABLE vac_cache ADD COLUMN effective_date TEXT",
+    // 12: audit log of the changes to the cache and the stored files
+    "CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at DATETIME NOT NULL,
+        action TEXT NOT NULL,
+        oaci TEXT,
+        vac_type TEXT,
+        file_name TEXT,
+        reason TEXT NOT NULL
+    );
+    CREATE INDEX audit_log_oaci ON audit_log (oaci);",
+    // 13: versions downloaded in the past, filled from the cache, and charts
+    // pinned to one of them by a rollback
+    "CREATE TABLE chart_versions (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        version TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        file_size INTEGER NOT NULL,
+        file_hash TEXT,
+        page_count INTEGER,
+        downloaded_at DATETIME NOT NULL,
+        PRIMARY KEY (oaci, vac_type, version)
+    );
+    INSERT INTO chart_versions
+        SELECT oaci, vac_type, version, file_name, file_size, file_hash, page_count,
+               COALESCE(last_updated, CURRENT_TIMESTAMP)
+        FROM vac_cache WHERE file_hash IS NOT NULL;
+    CREATE TABLE pinned_charts (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        version TEXT NOT NULL,
+        pinned_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    );",
+    // 14: previous versions compressed with zstd
+    "ALTER TABLE chart_versions ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0",
+    // 15: time spent downloading in each sync run
+    "ALTER TABLE sync_runs ADD COLUMN download_ms INTEGER NOT NULL DEFAULT 0",
+    // 16: charts whose file the server reported missing
+    "CREATE TABLE missing_charts (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        version TEXT NOT NULL,
+        checked_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    )",
+    // 17: consecutive failed downloads of each chart version
+    "CREATE TABLE failures (
+        oaci TEXT NOT NULL,
+        vac_type TEXT NOT NULL,
+        version TEXT NOT NULL,
+        consecutive_failures INTEGER NOT NULL,
+        last_error TEXT NOT NULL,
+        last_failed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (oaci, vac_type)
+    )",
+];
+
+/// Migration adding `effective_date`, after which existing rows are backfilled
+const EFFECTIVE_DATE_MIGRATION: usize = 11;
+
+/// Insert or replace a cache entry
+///
+/// The page count of the previous row is kept when the new entry has none
+/// and the file hash did not change.
+const UPSERT_ENTRY: &str = "INSERT OR REPLACE INTO vac_cache
+     (oaci, vac_type, version, file_name, file_size, city, file_hash, remote_file_name, page_count,
+      effective_date, last_updated)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+             COALESCE(?9, (SELECT page_count FROM vac_cache
+                           WHERE oaci = ?1 AND vac_type = ?2 AND file_hash IS ?7)),
+             ?10, CURRENT_TIMESTAMP)";
+
+/// Last schema version of databases created before `schema_version` existed
+const LEGACY_SCHEMA_VERSION: u32 = 2;
+
+/// Connections kept by the pool of the `pool` feature, i.e. concurrent queries
+#[cfg(feature = "pool")]
+const POOL_SIZE: u32 = 8;
 
 /// SQLite database for caching VAC versions
+///
+/// The connection is guarded by a mutex, so the database can be shared between
+/// threads. With the `pool` feature, a pool of connections lets threads query
+/// the database concurrently instead, e.g. to list charts during a sync.
 pub struct VacDatabase {
-    conn: Connection,
+    #[cfg(not(feature = "pool"))]
+    conn: Mutex<Connection>,
+    #[cfg(feature = "pool")]
+    pool: r2d2::Pool<SqliteConnectionManager>,
 }
 
 impl VacDatabase {
     /// Create or open the SQLite database
+    ///
+    /// Pending schema migrations are applied automatically.
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
+        Self::open(db_path.as_ref(), OpenFlags::default(), None)
+    }
+
+    /// Open an existing database without ever writing to it, e.g. on read-only media
+    ///
+    /// Statements writing to the database fail. As migrations cannot be
+    /// applied, the schema must be up to date: an outdated database fails
+    /// with [`rusqlite::ErrorCode::ReadOnly`], and a missing one with
+    /// [`rusqlite::ErrorCode::CannotOpen`].
+    pub fn open_read_only<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::open(
+            db_path.as_ref(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            None,
+        )
+    }
 
-        // Create table if it doesn't exist
+    /// Create or open a database encrypted with SQLCipher
+    ///
+    /// A new database is encrypted with `passphrase`; an existing one must have
+    /// been created with the same passphrase. Requires the `sqlcipher` feature.
+    #[cfg(feature = "sqlcipher")]
+    pub fn open_encrypted<P: AsRef<Path>>(db_path: P, passphrase: &str) -> Result<Self> {
+        Self::open(db_path.as_ref(), OpenFlags::default(), Some(passphrase))
+    }
+
+    /// Write an encrypted copy of the database to `path`
+    ///
+    /// The copy is opened with [`VacDatabase::open_encrypted`] and the same
+    /// passphrase. Requires the `sqlcipher` feature.
+    #[cfg(feature = "sqlcipher")]
+    pub fn export_encrypted<P: AsRef<Path>>(&self, path: P, passphrase: &str) -> Result<()> {
+        let conn = self.conn()?;
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS vac_cache (
-                oaci TEXT NOT NULL,
-                vac_type TEXT NOT NULL,
-                version TEXT NOT NULL,
-                file_name TEXT NOT NULL,
-                file_size INTEGER NOT NULL,
-                city TEXT NOT NULL,
-                file_hash TEXT,
-                last_updated DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (oaci, vac_type)
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![path.as_ref().to_string_lossy(), passphrase],
+        )?;
+        let exported = conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()));
+        conn.execute("DETACH DATABASE encrypted", [])?;
+        exported
+    }
+
+    /// Open a database, bringing its schema up to date unless it is read-only
+    fn open(db_path: &Path, flags: OpenFlags, passphrase: Option<&str>) -> Result<Self> {
+        let read_only = flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let prepare = |conn: &Connection| {
+            unlock(conn, passphrase)?;
+            if read_only {
+                Self::check_schema(conn)
+            } else {
+                Self::migrate(conn)
+            }
+        };
+
+        #[cfg(not(feature = "pool"))]
+        {
+            let conn = Connection::open_with_flags(db_path, flags)?;
+            prepare(&conn)?;
+            Ok(VacDatabase {
+                conn: Mutex::new(conn),
+            })
+        }
+
+        #[cfg(feature = "pool")]
+        {
+            // Every connection to an in-memory database opens a distinct database
+            let in_memory = db_path == Path::new(":memory:");
+            if !in_memory {
+                // Prepared on its own, as the pool only reports errors as messages
+                prepare(&Connection::open_with_flags(db_path, flags)?)?;
+            }
+            let passphrase = passphrase.map(str::to_string);
+            let manager = SqliteConnectionManager::file(db_path)
+                .with_flags(flags)
+                .with_init(move |conn| unlock(conn, passphrase.as_deref()));
+            let pool = r2d2::Pool::builder()
+                .max_size(if in_memory { 1 } else { POOL_SIZE })
+                .build(manager)
+                .map_err(pool_error)?;
+            let database = VacDatabase { pool };
+            if in_memory {
+                Self::migrate(&*database.conn()?)?;
+            }
+            Ok(database)
+        }
+    }
+
+    /// Lock the connection for the duration of a statement or transaction
+    #[cfg(not(feature = "pool"))]
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        // A panic while holding the lock leaves SQLite in a consistent state
+        Ok(self.conn.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Take a connection from the pool for the duration of a statement or transaction
+    #[cfg(feature = "pool")]
+    fn conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+        self.pool.get().map_err(pool_error)
+    }
+
+    /// Fail unless the schema is up to date, for databases that cannot be migrated
+    fn check_schema(conn: &Connection) -> Result<()> {
+        let version = Self::read_schema_version(conn)?;
+        if version < Self::latest_schema_version() {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_READONLY),
+                Some(format!(
+                    "database schema version {} is older than {}, it must be opened \
+                     with write access once to upgrade it",
+                    version,
+                    Self::latest_schema_version()
+                )),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Latest schema version known to this build
+    pub fn latest_schema_version() -> u32 {
+        MIGRATIONS.len() as u32
+    }
+
+    /// Get the schema version of the open database
+    pub fn schema_version(&self) -> Result<u32> {
+        Self::read_schema_version(&*self.conn()?)
+    }
+
+    /// Read the schema version, detecting databases created before versioning
+    fn read_schema_version(conn: &Connection) -> Result<u32> {
+        let versioned: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if versioned {
+            return conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+                Ok(row.get::<_, Option<u32>>(0)?.unwrap_or(0))
+            });
+        }
+
+        // Unversioned database: infer the version from the existing layout
+        let has_cache: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'vac_cache'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_cache {
+            return Ok(0);
+        }
+
+        let has_hash: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('vac_cache') WHERE name = 'file_hash'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(if has_hash { LEGACY_SCHEMA_VERSION } else { 1 })
+    }
+
+    /// Apply all pending migrations, each in its own transaction
+    fn migrate(conn: &Connection) -> Result<()> {
+        let current = Self::read_schema_version(conn)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER NOT NULL,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )",
             [],
         )?;
 
-        // Add file_hash column if it doesn't exist (for existing databases)
-        let _ = conn.execute("ALTER TABLE vac_cache ADD COLUMN file_hash TEXT", []);
+        // Record the inferred version of legacy databases
+        if current > 0 {
+            conn.execute(
+                "INSERT INTO schema_version (version)
+                 SELECT ?1 WHERE NOT EXISTS (SELECT 1 FROM schema_version)",
+                params![current],
+            )?;
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(migration)?;
+            if index + 1 == EFFECTIVE_DATE_MIGRATION {
+                Self::backfill_effective_dates(&tx)?;
+            }
+            tx.execute(
+                "INSERT INTO schema_version (version) VALUES (?1)",
+                params![index as u32 + 1],
+            )?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
 
-        Ok(VacDatabase { conn })
+    /// Fill the effective dates of the cached charts from their versions
+    fn backfill_effective_dates(conn: &Connection) -> Result<()> {
+        let versions = conn
+            .prepare("SELECT oaci, vac_type, version FROM vac_cache")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let mut update = conn.prepare(
+            "UPDATE vac_cache SET effective_date = ?3 WHERE oaci = ?1 AND vac_type = ?2",
+        )?;
+        for (oaci, vac_type, version) in versions {
+            update.execute(params![oaci, vac_type, effective_date(&version)])?;
+        }
+        Ok(())
     }
 
     /// Check if database is empty
     pub fn is_empty(&self) -> Result<bool> {
         let count: i64 = self
-            .conn
+            .conn()?
             .query_row("SELECT COUNT(*) FROM vac_cache", [], |row| row.get(0))?;
         Ok(count == 0)
     }
 
     /// Get cached version for a specific OACI code and type
     pub fn get_cached_version(&self, oaci: &str, vac_type: &str) -> Result<Option<String>> {
-        let result = self.conn.query_row(
+        let result = self.conn()?.query_row(
             "SELECT version FROM vac_cache WHERE oaci = ?1 AND vac_type = ?2",
             params![oaci, vac_type],
             |row| row.get(0),
@@ -80,10 +475,8 @@ impl VacDatabase {
 
     /// Update or insert a VAC entry in the cache
     pub fn upsert_entry(&self, entry: &VacEntry) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO vac_cache 
-             (oaci, vac_type, version, file_name, file_size, city, file_hash, last_updated)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CURRENT_TIMESTAMP)",
+        self.conn()?.execute(
+            UPSERT_ENTRY,
             params![
                 &entry.oaci,
                 &entry.vac_type,
@@ -92,14 +485,424 @@ impl VacDatabase {
                 &entry.file_size,
                 &entry.city,
                 &entry.file_hash,
+                &entry.remote_file_name,
+                &entry.page_count,
+                effective_date(&entry.version),
             ],
         )?;
         Ok(())
     }
 
+    /// Update or insert several VAC entries in a single transaction
+    ///
+    /// Either all entries are written or none of them are.
+    pub fn upsert_entries(&self, entries: &[VacEntry]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(UPSERT_ENTRY)?;
+            for entry in entries {
+                stmt.execute(params![
+                    &entry.oaci,
+                    &entry.vac_type,
+                    &entry.version,
+                    &entry.file_name,
+                    &entry.file_size,
+                    &entry.city,
+                    &entry.file_hash,
+                    &entry.remote_file_name,
+                    &entry.page_count,
+                    effective_date(&entry.version),
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Write downloaded entries to the cache and remove them from the download queue
+    ///
+    /// The versions are also recorded in the version history, and a chart
+    /// pinned to another version is released. All changes are committed in a
+    /// single transaction.
+    pub fn complete_downloads(&self, entries: &[VacEntry]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut upsert = tx.prepare(UPSERT_ENTRY)?;
+            let mut dequeue =
+                tx.prepare("DELETE FROM pending_downloads WHERE oaci = ?1 AND vac_type = ?2")?;
+            let mut record_version = tx.prepare(
+                "INSERT INTO chart_versions
+                 (oaci, vac_type, version, file_name, file_size, file_hash, page_count, downloaded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CURRENT_TIMESTAMP)
+                 ON CONFLICT (oaci, vac_type, version) DO UPDATE SET
+                     file_name = excluded.file_name,
+                     file_size = excluded.file_size,
+                     file_hash = excluded.file_hash,
+                     page_count = COALESCE(excluded.page_count, page_count),
+                     compressed = 0,
+                     downloaded_at = CASE WHEN file_hash IS excluded.file_hash
+                                          THEN downloaded_at ELSE excluded.downloaded_at END",
+            )?;
+            let mut unpin = tx.prepare(
+                "DELETE FROM pinned_charts WHERE oaci = ?1 AND vac_type = ?2 AND version != ?3",
+            )?;
+            let mut found =
+                tx.prepare("DELETE FROM missing_charts WHERE oaci = ?1 AND vac_type = ?2")?;
+            let mut succeeded =
+                tx.prepare("DELETE FROM failures WHERE oaci = ?1 AND vac_type = ?2")?;
+            for entry in entries {
+                upsert.execute(params![
+                    &entry.oaci,
+                    &entry.vac_type,
+                    &entry.version,
+                    &entry.file_name,
+                    &entry.file_size,
+                    &entry.city,
+                    &entry.file_hash,
+                    &entry.remote_file_name,
+                    &entry.page_count,
+                    effective_date(&entry.version),
+                ])?;
+                dequeue.execute(params![&entry.oaci, &entry.vac_type])?;
+                found.execute(params![&entry.oaci, &entry.vac_type])?;
+                succeeded.execute(params![&entry.oaci, &entry.vac_type])?;
+                if entry.file_hash.is_some() {
+                    record_version.execute(params![
+                        &entry.oaci,
+                        &entry.vac_type,
+                        &entry.version,
+                        &entry.file_name,
+                        &entry.file_size,
+                        &entry.file_hash,
+                        &entry.page_count,
+                    ])?;
+                }
+                unpin.execute(params![&entry.oaci, &entry.vac_type, &entry.version])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Get the versions of a chart downloaded in the past, most recent first
+    pub fn get_chart_versions(&self, oaci: &str, vac_type: &str) -> Result<Vec<ChartVersion>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, version, file_name, file_size, file_hash, page_count,
+                    downloaded_at, compressed
+             FROM chart_versions
+             WHERE oaci = ?1 AND vac_type = ?2
+             ORDER BY downloaded_at DESC, rowid DESC",
+        )?;
+        let versions = stmt.query_map(params![oaci, vac_type], chart_version_from_row)?;
+        versions.collect()
+    }
+
+    /// Get the versions of the version history other than the cached ones,
+    /// grouped by chart, most recent first
+    pub fn get_previous_versions(&self) -> Result<Vec<ChartVersion>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT v.oaci, v.vac_type, v.version, v.file_name, v.file_size, v.file_hash,
+                    v.page_count, v.downloaded_at, v.compressed
+             FROM chart_versions v
+             JOIN vac_cache c ON c.oaci = v.oaci AND c.vac_type = v.vac_type
+             WHERE v.version != c.version
+             ORDER BY v.oaci, v.vac_type, v.downloaded_at DESC, v.rowid DESC",
+        )?;
+        let versions = stmt.query_map([], chart_version_from_row)?;
+        versions.collect()
+    }
+
+    /// Record that the file of the cached version of a chart was moved to
+    /// `file_name`, adding the version to the version history if needed
+    pub fn archive_cached_version(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        file_name: &str,
+    ) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO chart_versions
+             (oaci, vac_type, version, file_name, file_size, file_hash, page_count, downloaded_at)
+             SELECT oaci, vac_type, version, ?3, file_size, file_hash, page_count,
+                    COALESCE(last_updated, CURRENT_TIMESTAMP)
+             FROM vac_cache WHERE oaci = ?1 AND vac_type = ?2
+             ON CONFLICT (oaci, vac_type, version) DO UPDATE SET
+                 file_name = excluded.file_name,
+                 compressed = 0",
+            params![oaci, vac_type, file_name],
+        )?;
+        Ok(())
+    }
+
+    /// Record where the file of a version of the version history is stored
+    pub fn set_version_file_name(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        version: &str,
+        file_name: &str,
+    ) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE chart_versions SET file_name = ?4, compressed = 0
+             WHERE oaci = ?1 AND vac_type = ?2 AND version = ?3",
+            params![oaci, vac_type, version, file_name],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a version from the version history
+    pub fn delete_chart_version(&self, oaci: &str, vac_type: &str, version: &str) -> Result<()> {
+        self.conn()?.execute(
+            "DELETE FROM chart_versions WHERE oaci = ?1 AND vac_type = ?2 AND version = ?3",
+            params![oaci, vac_type, version],
+        )?;
+        Ok(())
+    }
+
+    /// Record whether the stored file of a version is compressed
+    pub fn set_version_compressed(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        version: &str,
+        compressed: bool,
+    ) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE chart_versions SET compressed = ?4
+             WHERE oaci = ?1 AND vac_type = ?2 AND version = ?3",
+            params![oaci, vac_type, version, compressed],
+        )?;
+        Ok(())
+    }
+
+    /// Get the stored file names of every version of the version history
+    pub fn get_version_file_names(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT CASE WHEN compressed THEN file_name || '{}' ELSE file_name END
+             FROM chart_versions",
+            ChartVersion::COMPRESSED_EXTENSION
+        ))?;
+        let names = stmt.query_map([], |row| row.get(0))?;
+        names.collect()
+    }
+
+    /// Keep a chart at a version until a download of another one completes
+    pub fn pin_version(&self, oaci: &str, vac_type: &str, version: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO pinned_charts (oaci, vac_type, version) VALUES (?1, ?2, ?3)",
+            params![oaci, vac_type, version],
+        )?;
+        Ok(())
+    }
+
+    /// Get the version a chart is pinned to, if any
+    pub fn get_pinned_version(&self, oaci: &str, vac_type: &str) -> Result<Option<String>> {
+        self.conn()?
+            .query_row(
+                "SELECT version FROM pinned_charts WHERE oaci = ?1 AND vac_type = ?2",
+                params![oaci, vac_type],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Replace the queued downloads of a source with the result of a new diff
+    ///
+    /// Queued downloads of `source` within `scope` (all of them if None) that
+    /// are not in `pending` are removed. Entries of `pending` are queued, keeping
+    /// their attempt count if they were already queued for the same version.
+    pub fn replace_pending_downloads(
+        &self,
+        source: &str,
+        scope: Option<&[(String, String)]>,
+        pending: &[PendingDownload],
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let queued: Vec<(String, String)> = tx
+                .prepare("SELECT oaci, vac_type FROM pending_downloads WHERE source = ?1")?
+                .query_map(params![source], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_>>()?;
+
+            let mut dequeue =
+                tx.prepare("DELETE FROM pending_downloads WHERE oaci = ?1 AND vac_type = ?2")?;
+            for (oaci, vac_type) in queued {
+                let in_scope =
+                    scope.is_none_or(|keys| keys.iter().any(|(o, t)| *o == oaci && *t == vac_type));
+                let still_pending = pending
+                    .iter()
+                    .any(|p| p.entry.oaci == oaci && p.entry.vac_type == vac_type);
+                if in_scope && !still_pending {
+                    dequeue.execute(params![oaci, vac_type])?;
+                }
+            }
+
+            let mut enqueue = tx.prepare(
+                "INSERT INTO pending_downloads
+                 (oaci, vac_type, city, version, file_name, remote_file_name, file_size, file_hash, source, priority)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT (oaci, vac_type) DO UPDATE SET
+                    attempts = CASE WHEN version = excluded.version THEN attempts ELSE 0 END,
+                    last_error = CASE WHEN version = excluded.version THEN last_error ELSE NULL END,
+                    city = excluded.city,
+                    version = excluded.version,
+                    file_name = excluded.file_name,
+                    remote_file_name = excluded.remote_file_name,
+                    file_size = excluded.file_size,
+                    file_hash = excluded.file_hash,
+                    source = excluded.source,
+                    priority = excluded.priority",
+            )?;
+            for download in pending {
+                let entry = &download.entry;
+                enqueue.execute(params![
+                    &entry.oaci,
+                    &entry.vac_type,
+                    &entry.city,
+                    &entry.version,
+                    &entry.file_name,
+                    &entry.remote_file_name,
+                    &entry.file_size,
+                    &entry.file_hash,
+                    &download.source,
+                    download.priority,
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Get queued downloads, highest priority first
+    ///
+    /// # Arguments
+    /// * `source` - Only return downloads queued by this source, if specified
+    pub fn get_pending_downloads(&self, source: Option<&str>) -> Result<Vec<PendingDownload>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, city, version, file_name, remote_file_name, file_size, file_hash,
+                    source, priority, attempts, last_error
+             FROM pending_downloads
+             WHERE ?1 IS NULL OR source = ?1
+             ORDER BY priority DESC, queued_at, oaci",
+        )?;
+
+        let downloads = stmt.query_map(params![source], |row| {
+            Ok(PendingDownload {
+                entry: VacEntry {
+                    oaci: row.get(0)?,
+                    vac_type: row.get(1)?,
+                    city: row.get(2)?,
+                    version: row.get(3)?,
+                    file_name: row.get(4)?,
+                    remote_file_name: row.get(5)?,
+                    file_size: row.get(6)?,
+                    file_hash: row.get(7)?,
+                    page_count: None,
+                    available_locally: false,
+                },
+                source: row.get(8)?,
+                priority: row.get(9)?,
+                attempts: row.get(10)?,
+                last_error: row.get(11)?,
+            })
+        })?;
+
+        downloads.collect()
+    }
+
+    /// Record a failed attempt to download a queued entry
+    pub fn record_download_failure(&self, oaci: &str, vac_type: &str, error: &str) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE pending_downloads SET attempts = attempts + 1, last_error = ?3
+             WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type, error],
+        )?;
+        Ok(())
+    }
+
+    /// Record that the server answered a download of a chart version with a 404
+    pub fn record_missing_chart(&self, oaci: &str, vac_type: &str, version: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO missing_charts (oaci, vac_type, version, checked_at)
+             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+             ON CONFLICT (oaci, vac_type) DO UPDATE SET
+                 version = excluded.version,
+                 checked_at = excluded.checked_at",
+            params![oaci, vac_type, version],
+        )?;
+        Ok(())
+    }
+
+    /// Check whether the server reported this chart version missing within `period`
+    pub fn is_missing_chart(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        version: &str,
+        period: Duration,
+    ) -> Result<bool> {
+        self.conn()?.query_row(
+            "SELECT EXISTS (
+                 SELECT 1 FROM missing_charts
+                 WHERE oaci = ?1 AND vac_type = ?2 AND version = ?3
+                   AND checked_at > datetime('now', ?4)
+             )",
+            params![
+                oaci,
+                vac_type,
+                version,
+                format!("-{} seconds", period.as_secs())
+            ],
+            |row| row.get(0),
+        )
+    }
+
+    /// Count a failed download of a chart version, returning the number of
+    /// downloads of that version that failed in a row
+    ///
+    /// The count starts over with a new version, and once a download completes.
+    pub fn record_chart_failure(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        version: &str,
+        error: &str,
+    ) -> Result<u32> {
+        self.conn()?.query_row(
+            "INSERT INTO failures (oaci, vac_type, version, consecutive_failures, last_error, last_failed_at)
+             VALUES (?1, ?2, ?3, 1, ?4, CURRENT_TIMESTAMP)
+             ON CONFLICT (oaci, vac_type) DO UPDATE SET
+                 consecutive_failures = CASE WHEN version = excluded.version
+                                             THEN consecutive_failures + 1 ELSE 1 END,
+                 version = excluded.version,
+                 last_error = excluded.last_error,
+                 last_failed_at = excluded.last_failed_at
+             RETURNING consecutive_failures",
+            params![oaci, vac_type, version, error],
+            |row| row.get(0),
+        )
+    }
+
+    /// Get the number of downloads of a chart version that failed in a row
+    pub fn get_chart_failures(&self, oaci: &str, vac_type: &str, version: &str) -> Result<u32> {
+        self.conn()?
+            .query_row(
+                "SELECT consecutive_failures FROM failures
+                 WHERE oaci = ?1 AND vac_type = ?2 AND version = ?3",
+                params![oaci, vac_type, version],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::unwrap_or_default)
+    }
+
     /// Get cached hash for a specific OACI code and type
     pub fn get_cached_hash(&self, oaci: &str, vac_type: &str) -> Result<Option<String>> {
-        let result = self.conn.query_row(
+        let result = self.conn()?.query_row(
             "SELECT file_hash FROM vac_cache WHERE oaci = ?1 AND vac_type = ?2",
             params![oaci, vac_type],
             |row| row.get(0),
@@ -112,10 +915,50 @@ impl VacDatabase {
         }
     }
 
+    /// Record the page count of a validated PDF
+    pub fn set_page_count(&self, oaci: &str, vac_type: &str, page_count: u32) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE vac_cache SET page_count = ?3 WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type, page_count],
+        )?;
+        Ok(())
+    }
+
+    /// Get the stored file name for a specific OACI code and type
+    pub fn get_cached_file_name(&self, oaci: &str, vac_type: &str) -> Result<Option<String>> {
+        let result = self.conn()?.query_row(
+            "SELECT file_name FROM vac_cache WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(name) => Ok(Some(name)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Find the entry (OACI code and type) whose PDF is stored under a file name
+    pub fn get_file_name_owner(&self, file_name: &str) -> Result<Option<(String, String)>> {
+        let result = self.conn()?.query_row(
+            "SELECT oaci, vac_type FROM vac_cache WHERE file_name = ?1",
+            params![file_name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+
+        match result {
+            Ok(owner) => Ok(Some(owner)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Get all cached entries
     pub fn get_all_entries(&self) -> Result<Vec<VacEntry>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT oaci, vac_type, version, file_name, file_size, city, file_hash 
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, version, file_name, file_size, city, file_hash, remote_file_name, page_count
              FROM vac_cache 
              ORDER BY oaci",
         )?;
@@ -129,6 +972,8 @@ impl VacDatabase {
                 file_size: row.get(4)?,
                 city: row.get(5)?,
                 file_hash: row.get(6)?,
+                remote_file_name: row.get(7)?,
+                page_count: row.get(8)?,
                 available_locally: true, // Retrieved from local database
             })
         })?;
@@ -136,17 +981,103 @@ impl VacDatabase {
         entries.collect()
     }
 
+    /// Get all cached entries, least recently updated first
+    pub fn get_entries_by_last_updated(&self) -> Result<Vec<VacEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, version, file_name, file_size, city, file_hash, remote_file_name, page_count
+             FROM vac_cache 
+             ORDER BY last_updated, oaci",
+        )?;
+
+        let entries = stmt.query_map([], |row| {
+            Ok(VacEntry {
+                oaci: row.get(0)?,
+                vac_type: row.get(1)?,
+                version: row.get(2)?,
+                file_name: row.get(3)?,
+                file_size: row.get(4)?,
+                city: row.get(5)?,
+                file_hash: row.get(6)?,
+                remote_file_name: row.get(7)?,
+                page_count: row.get(8)?,
+                available_locally: true,
+            })
+        })?;
+
+        entries.collect()
+    }
+
+    /// Get all cached entries with the effective date of their version, earliest first
+    pub fn get_entries_with_effective_date(&self) -> Result<Vec<(VacEntry, Option<NaiveDate>)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, version, file_name, file_size, city, file_hash, remote_file_name, page_count, effective_date
+             FROM vac_cache
+             ORDER BY effective_date IS NULL, effective_date, oaci",
+        )?;
+
+        let entries = stmt.query_map([], |row| {
+            Ok((
+                VacEntry {
+                    oaci: row.get(0)?,
+                    vac_type: row.get(1)?,
+                    version: row.get(2)?,
+                    file_name: row.get(3)?,
+                    file_size: row.get(4)?,
+                    city: row.get(5)?,
+                    file_hash: row.get(6)?,
+                    remote_file_name: row.get(7)?,
+                    page_count: row.get(8)?,
+                    available_locally: true,
+                },
+                row.get::<_, Option<String>>(9)?
+                    .and_then(|date| date.parse().ok()),
+            ))
+        })?;
+
+        entries.collect()
+    }
+
+    /// Get all cached entries with the time of their last update
+    pub fn get_entries_with_last_updated(&self) -> Result<Vec<(VacEntry, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, version, file_name, file_size, city, file_hash, remote_file_name, page_count, last_updated
+             FROM vac_cache
+             ORDER BY oaci",
+        )?;
+
+        let entries = stmt.query_map([], |row| {
+            Ok((
+                VacEntry {
+                    oaci: row.get(0)?,
+                    vac_type: row.get(1)?,
+                    version: row.get(2)?,
+                    file_name: row.get(3)?,
+                    file_size: row.get(4)?,
+                    city: row.get(5)?,
+                    file_hash: row.get(6)?,
+                    remote_file_name: row.get(7)?,
+                    page_count: row.get(8)?,
+                    available_locally: true,
+                },
+                row.get(9)?,
+            ))
+        })?;
+
+        entries.collect()
+    }
+
     /// Check if a newer version is available
     pub fn needs_update(&self, entry: &VacEntry) -> Result<bool> {
-        match self.get_cached_version(&entry.oaci, &entry.vac_type)? {
-            Some(cached_version) => Ok(cached_version != entry.version),
-            None => Ok(true), // Not in cache, needs download
-        }
+        let cached_version = self.get_cached_version(&entry.oaci, &entry.vac_type)?;
+        Ok(is_outdated(cached_version.as_deref(), &entry.version))
     }
 
     /// Check if a VAC entry exists in the local cache
     pub fn has_entry(&self, oaci: &str) -> Result<bool> {
-        let result = self.conn.query_row(
+        let result = self.conn()?.query_row(
             "SELECT 1 FROM vac_cache WHERE oaci = ?1",
             params![oaci],
             |_| Ok(()),
@@ -159,33 +1090,38 @@ impl VacDatabase {
         }
     }
 
-    /// Delete an entry from the cache
-    /// Returns the file name if the entry existed, None otherwise
-    pub fn delete_entry(&self, oaci: &str) -> Result<Option<String>> {
-        // First, get the file name before deleting
-        let file_name = self.conn.query_row(
-            "SELECT file_name FROM vac_cache WHERE oaci = ?1",
-            params![oaci],
-            |row| row.get(0),
-        );
-
-        match file_name {
-            Ok(name) => {
-                // Entry exists, delete it
-                self.conn
-                    .execute("DELETE FROM vac_cache WHERE oaci = ?1", params![oaci])?;
-                Ok(Some(name))
-            }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
+    /// Delete the entries of an airfield from the cache, of every map type
+    /// unless `vac_type` is given, with their version history and pin
+    ///
+    /// Returns the file names of the deleted entries.
+    pub fn delete_entry(&self, oaci: &str, vac_type: Option<&str>) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let file_names = {
+            let mut stmt = tx.prepare(
+                "SELECT file_name FROM vac_cache WHERE oaci = ?1 AND (?2 IS NULL OR vac_type = ?2)",
+            )?;
+            let rows = stmt.query_map(params![oaci, vac_type], |row| row.get(0))?;
+            rows.collect::<Result<Vec<String>>>()?
+        };
+        for table in ["vac_cache", "chart_versions", "pinned_charts"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE oaci = ?1 AND (?2 IS NULL OR vac_type = ?2)",
+                    table
+                ),
+                params![oaci, vac_type],
+            )?;
         }
+        tx.commit()?;
+        Ok(file_names)
     }
 
     /// Get the file name for a given OACI code
     /// Returns the file name if the entry exists, None otherwise
     pub fn get_file_name(&self, oaci: &str) -> Result<Option<String>> {
-        let result = self.conn.query_row(
-            "SELECT file_name FROM vac_cache WHERE oaci = ?1",
+        let result = self.conn()?.query_row(
+            "SELECT file_name FROM vac_cache WHERE oaci = ?1 ORDER BY vac_type",
             params![oaci],
             |row| row.get(0),
         );
@@ -197,21 +1133,375 @@ impl VacDatabase {
         }
     }
 
+    /// Size of the database file in bytes
+    pub fn database_size(&self) -> Result<u64> {
+        self.conn()?.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get::<_, i64>(0).map(|size| size as u64),
+        )
+    }
+
+    /// Record a sync run in the history
+    pub fn record_sync_run(&self, run: &SyncRun) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO sync_runs
+             (started_at, duration_ms, source, total_entries, downloaded, failed, bytes_downloaded,
+              download_ms, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                &run.started_at,
+                run.duration_ms as i64,
+                &run.source,
+                run.total_entries as i64,
+                run.downloaded as i64,
+                run.failed as i64,
+                run.bytes_downloaded as i64,
+                run.download_ms as i64,
+                &run.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record changes in the audit log
+    pub fn record_audit(&self, entries: &[AuditEntry]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO audit_log (at, action, oaci, vac_type, file_name, reason)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for entry in entries {
+                stmt.execute(params![
+                    &entry.at,
+                    entry.action.as_str(),
+                    &entry.oaci,
+                    &entry.vac_type,
+                    &entry.file_name,
+                    &entry.reason,
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Get the most recent changes of the audit log, newest first, only
+    /// those of an airfield if `oaci` is given
+    pub fn get_audit_log(&self, oaci: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT at, action, oaci, vac_type, file_name, reason
+             FROM audit_log
+             WHERE ?1 IS NULL OR oaci = ?1 COLLATE NOCASE
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+
+        let entries = stmt.query_map(params![oaci, limit as i64], audit_entry_from_row)?;
+        entries.collect()
+    }
+
+    /// Get the most recent sync runs, newest first
+    pub fn get_sync_runs(&self, limit: usize) -> Result<Vec<SyncRun>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT started_at, duration_ms, source, total_entries, downloaded, failed, bytes_downloaded,
+                    download_ms, error
+             FROM sync_runs
+             ORDER BY id DESC
+             LIMIT ?1",
+        )?;
+
+        let runs = stmt.query_map(params![limit as i64], sync_run_from_row)?;
+        runs.collect()
+    }
+
+    /// Get the totals over the whole sync history
+    pub fn get_sync_totals(&self) -> Result<SyncTotals> {
+        let conn = self.conn()?;
+        let mut totals = conn.query_row(
+            "SELECT COUNT(*), COUNT(error), COALESCE(SUM(downloaded), 0),
+                    COALESCE(SUM(failed), 0), COALESCE(SUM(bytes_downloaded), 0)
+             FROM sync_runs",
+            [],
+            |row| {
+                Ok(SyncTotals {
+                    runs: row.get::<_, i64>(0)? as u64,
+                    failed_runs: row.get::<_, i64>(1)? as u64,
+                    downloaded: row.get::<_, i64>(2)? as u64,
+                    failed: row.get::<_, i64>(3)? as u64,
+                    bytes_downloaded: row.get::<_, i64>(4)? as u64,
+                    last_success: None,
+                })
+            },
+        )?;
+        totals.last_success = conn
+            .query_row(
+                "SELECT started_at, duration_ms, source, total_entries, downloaded, failed,
+                        bytes_downloaded, download_ms, error
+                 FROM sync_runs
+                 WHERE error IS NULL
+                 ORDER BY id DESC
+                 LIMIT 1",
+                [],
+                sync_run_from_row,
+            )
+            .optional()?;
+        Ok(totals)
+    }
+
+    /// Get the validators of the last response received for an API path
+    pub fn get_http_cache(&self, path: &str) -> Result<Option<HttpCacheEntry>> {
+        let result = self.conn()?.query_row(
+            "SELECT etag, last_modified, body FROM http_cache WHERE path = ?1",
+            params![path],
+            |row| {
+                Ok(HttpCacheEntry {
+                    etag: row.get(0)?,
+                    last_modified: row.get(1)?,
+                    body: row.get(2)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store the validators of a response received for an API path
+    pub fn set_http_cache(&self, path: &str, entry: &HttpCacheEntry) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO http_cache (path, etag, last_modified, body, updated_at)
+             VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)",
+            params![path, &entry.etag, &entry.last_modified, &entry.body],
+        )?;
+        Ok(())
+    }
+
+    /// Insert or update a cached supplement
+    pub fn upsert_supplement(&self, supplement: &Supplement) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO supplements
+             (number, remote_id, title, valid_from, valid_until, aerodromes, file_name, file_hash, last_updated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CURRENT_TIMESTAMP)",
+            params![
+                &supplement.number,
+                supplement.remote_id,
+                &supplement.title,
+                &supplement.valid_from,
+                &supplement.valid_until,
+                supplement.aerodromes.join(","),
+                &supplement.file_name,
+                &supplement.file_hash,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get all cached supplements
+    pub fn get_supplements(&self) -> Result<Vec<Supplement>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT number, remote_id, title, valid_from, valid_until, aerodromes, file_name, file_hash
+             FROM supplements
+             ORDER BY number",
+        )?;
+
+        let supplements = stmt.query_map([], |row| {
+            let aerodromes: String = row.get(5)?;
+            Ok(Supplement {
+                number: row.get(0)?,
+                remote_id: row.get(1)?,
+                title: row.get(2)?,
+                valid_from: row.get(3)?,
+                valid_until: row.get(4)?,
+                aerodromes: aerodromes
+                    .split(',')
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                file_name: row.get(6)?,
+                file_hash: row.get(7)?,
+            })
+        })?;
+
+        supplements.collect()
+    }
+
+    /// Remove a supplement from the cache
+    pub fn delete_supplement(&self, number: &str) -> Result<()> {
+        self.conn()?
+            .execute("DELETE FROM supplements WHERE number = ?1", params![number])?;
+        Ok(())
+    }
+
+    /// Store the details of listed airfields, replacing the previous ones
+    ///
+    /// The position and elevation are kept in their own columns, and the
+    /// whole listing entry as JSON.
+    pub fn upsert_airfields(&self, airfields: &[OacisEntry]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO airfields
+                 (oaci, city, latitude, longitude, elevation, details, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, CURRENT_TIMESTAMP)",
+            )?;
+            for airfield in airfields {
+                let details = serde_json::to_string(airfield)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                let coordinates = airfield.coordinates();
+                stmt.execute(params![
+                    &airfield.code,
+                    &airfield.city,
+                    coordinates.map(|c| c.latitude),
+                    coordinates.map(|c| c.longitude),
+                    airfield.elevation(),
+                    details,
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Get the stored details of all listed airfields
+    pub fn get_airfields(&self) -> Result<Vec<OacisEntry>> {
+        self.query_airfields("SELECT details FROM airfields ORDER BY oaci", [])
+    }
+
+    /// Get the stored details of a listed airfield
+    pub fn get_airfield(&self, oaci: &str) -> Result<Option<OacisEntry>> {
+        let mut airfields =
+            self.query_airfields("SELECT details FROM airfields WHERE oaci = ?1", [oaci])?;
+        Ok(airfields.pop())
+    }
+
+    /// Get the stored details of the airfields with cached charts
+    pub fn get_cached_airfields(&self) -> Result<Vec<OacisEntry>> {
+        self.query_airfields(
+            "SELECT details FROM airfields
+             WHERE oaci IN (SELECT oaci FROM vac_cache)
+             ORDER BY oaci",
+            [],
+        )
+    }
+
+    /// Run a query selecting the JSON details of airfields
+    fn query_airfields(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<OacisEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(sql)?;
+        let airfields = stmt.query_map(params, |row| {
+            let details: String = row.get(0)?;
+            serde_json::from_str(&details).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+        })?;
+        airfields.collect()
+    }
+
+    /// Get the file hashes of the charts in the text index
+    pub fn get_text_index(&self) -> Result<HashMap<(String, String), String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT oaci, vac_type, file_hash FROM text_index")?;
+        let indexed = stmt.query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))?;
+        indexed.collect()
+    }
+
+    /// Replace the indexed text of a chart, one string per page
+    pub fn set_chart_text(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        file_hash: &str,
+        pages: &[String],
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM chart_text WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type],
+        )?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO chart_text (oaci, vac_type, page, text) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (index, text) in pages.iter().enumerate() {
+                insert.execute(params![oaci, vac_type, index as u32 + 1, text])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO text_index (oaci, vac_type, file_hash, indexed_at)
+             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+            params![oaci, vac_type, file_hash],
+        )?;
+        tx.commit()
+    }
+
+    /// Remove a chart from the text index
+    pub fn delete_chart_text(&self, oaci: &str, vac_type: &str) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM chart_text WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type],
+        )?;
+        tx.execute(
+            "DELETE FROM text_index WHERE oaci = ?1 AND vac_type = ?2",
+            params![oaci, vac_type],
+        )?;
+        tx.commit()
+    }
+
+    /// Search the text index for pages containing a phrase
+    ///
+    /// The query is matched as a phrase, ignoring case and accents, so
+    /// punctuation such as the dot of a frequency needs no escaping.
+    pub fn search_text(&self, query: &str, limit: usize) -> Result<Vec<TextMatch>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT oaci, vac_type, page, snippet(chart_text, 3, '[', ']', '…', 12)
+             FROM chart_text
+             WHERE chart_text MATCH ?1
+             ORDER BY oaci, vac_type, page
+             LIMIT ?2",
+        )?;
+        let phrase = format!("\"{}\"", query.replace('"', "\"\""));
+        let matches = stmt.query_map(params![phrase, limit as i64], |row| {
+            Ok(TextMatch {
+                oaci: row.get(0)?,
+                vac_type: row.get(1)?,
+                page: row.get(2)?,
+                snippet: row.get(3)?,
+            })
+        })?;
+        matches.collect()
+    }
+
     /// Get statistics about the cache
     pub fn get_stats(&self) -> Result<(i64, String, String)> {
         let count: i64 = self
-            .conn
+            .conn()?
             .query_row("SELECT COUNT(*) FROM vac_cache", [], |row| row.get(0))?;
 
         let oldest: String = self
-            .conn
+            .conn()?
             .query_row("SELECT MIN(last_updated) FROM vac_cache", [], |row| {
                 row.get(0)
             })
             .unwrap_or_else(|_| "N/A".to_string());
 
         let newest: String = self
-            .conn
+            .conn()?
             .query_row("SELECT MAX(last_updated) FROM vac_cache", [], |row| {
                 row.get(0)
             })
@@ -221,14 +1511,164 @@ impl VacDatabase {
     }
 }
 
+/// Give SQLCipher the passphrase of an encrypted database
+fn unlock(conn: &Connection, passphrase: Option<&str>) -> Result<()> {
+    match passphrase {
+        Some(passphrase) => {
+            conn.pragma_update(None, "key", passphrase)?;
+            // A wrong passphrase only shows on the first read
+            conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Report a connection that the pool failed to provide as an SQLite error
+#[cfg(feature = "pool")]
+fn pool_error(e: r2d2::Error) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), Some(e.to_string()))
+}
+
+/// Effective date column of a chart version, as `YYYY-MM-DD`
+fn effective_date(version: &str) -> Option<String> {
+    parse_effective_date(version).map(|date| date.to_string())
+}
+
+/// Read a sync run selected with the columns of `sync_runs` in order
+fn chart_version_from_row(row: &Row) -> Result<ChartVersion> {
+    Ok(ChartVersion {
+        oaci: row.get(0)?,
+        vac_type: row.get(1)?,
+        version: row.get(2)?,
+        file_name: row.get(3)?,
+        file_size: row.get(4)?,
+        file_hash: row.get(5)?,
+        page_count: row.get(6)?,
+        downloaded_at: row.get(7)?,
+        compressed: row.get(8)?,
+    })
+}
+
+fn audit_entry_from_row(row: &Row) -> Result<AuditEntry> {
+    let action: String = row.get(1)?;
+    Ok(AuditEntry {
+        at: row.get(0)?,
+        action: action.parse().map_err(|e: anyhow::Error| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, e.into())
+        })?,
+        oaci: row.get(2)?,
+        vac_type: row.get(3)?,
+        file_name: row.get(4)?,
+        reason: row.get(5)?,
+    })
+}
+
+fn sync_run_from_row(row: &Row) -> Result<SyncRun> {
+    Ok(SyncRun {
+        started_at: row.get(0)?,
+        duration_ms: row.get::<_, i64>(1)? as u64,
+        source: row.get(2)?,
+        total_entries: row.get::<_, i64>(3)? as usize,
+        downloaded: row.get::<_, i64>(4)? as usize,
+        failed: row.get::<_, i64>(5)? as usize,
+        bytes_downloaded: row.get::<_, i64>(6)? as u64,
+        download_ms: row.get::<_, i64>(7)? as u64,
+        error: row.get(8)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::AuditAction;
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn test_encrypted_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = VacDatabase::new(dir.path().join("plain.db")).unwrap();
+        plain
+            .upsert_entry(&VacEntry {
+                oaci: "LFPG".to_string(),
+                city: "Paris".to_string(),
+                vac_type: "AD".to_string(),
+                version: "1.0".to_string(),
+                file_name: "LFPG_AD.pdf".to_string(),
+                file_size: 1024,
+                file_hash: None,
+                remote_file_name: None,
+                page_count: None,
+                available_locally: false,
+            })
+            .unwrap();
+
+        let path = dir.path().join("encrypted.db");
+        plain.export_encrypted(&path, "secret").unwrap();
+        assert!(VacDatabase::new(&path).is_err());
+        assert!(VacDatabase::open_encrypted(&path, "wrong").is_err());
+        let encrypted = VacDatabase::open_encrypted(&path, "secret").unwrap();
+        assert_eq!(encrypted.get_all_entries().unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "pool")]
+    #[test]
+    fn test_pooled_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = VacDatabase::new(dir.path().join("vac_cache.db")).unwrap();
+        db.upsert_entry(&queued("LFPN", "1", 0).entry).unwrap();
+
+        // Readers are not blocked by a connection in the middle of a write
+        let writer = db.conn().unwrap();
+        writer
+            .execute_batch("BEGIN; UPDATE vac_cache SET city = 'Toussus'")
+            .unwrap();
+        let entries = std::thread::scope(|scope| {
+            scope
+                .spawn(|| db.get_all_entries().unwrap())
+                .join()
+                .unwrap()
+        });
+        assert_eq!(entries.len(), 1);
+        assert_ne!(entries[0].city, "Toussus");
+        writer.execute_batch("ROLLBACK").unwrap();
+    }
 
     #[test]
     fn test_database_creation() {
         let db = VacDatabase::new(":memory:").unwrap();
         assert!(db.is_empty().unwrap());
+        assert!(db.database_size().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vac_cache.db");
+        let code = |result: Result<VacDatabase>| result.err().and_then(|e| e.sqlite_error_code());
+        assert_eq!(
+            code(VacDatabase::open_read_only(&path)),
+            Some(rusqlite::ErrorCode::CannotOpen)
+        );
+
+        let entry = queued("LFPN", "1", 0).entry;
+        VacDatabase::new(&path)
+            .unwrap()
+            .upsert_entry(&entry)
+            .unwrap();
+        let db = VacDatabase::open_read_only(&path).unwrap();
+        assert_eq!(db.get_all_entries().unwrap().len(), 1);
+        assert!(db.upsert_entry(&entry).is_err());
+
+        // An outdated schema cannot be upgraded
+        let legacy = dir.path().join("legacy.db");
+        Connection::open(&legacy)
+            .unwrap()
+            .execute("CREATE TABLE vac_cache (oaci TEXT)", [])
+            .unwrap();
+        assert_eq!(
+            code(VacDatabase::open_read_only(&legacy)),
+            Some(rusqlite::ErrorCode::ReadOnly)
+        );
     }
 
     #[test]
@@ -243,6 +1683,8 @@ mod tests {
             file_name: "LFPG_AD.pdf".to_string(),
             file_size: 1024,
             file_hash: Some("abc123".to_string()),
+            remote_file_name: None,
+            page_count: None,
             available_locally: false,
         };
 
@@ -266,6 +1708,8 @@ mod tests {
             file_name: "LFPG_AD.pdf".to_string(),
             file_size: 1024,
             file_hash: Some("abc123".to_string()),
+            remote_file_name: None,
+            page_count: None,
             available_locally: false,
         };
 
@@ -274,12 +1718,561 @@ mod tests {
         assert!(!db.is_empty().unwrap());
 
         // Delete entry
-        let result = db.delete_entry("LFPG").unwrap();
-        assert_eq!(result, Some("LFPG_AD.pdf".to_string()));
+        let result = db.delete_entry("LFPG", None).unwrap();
+        assert_eq!(result, vec!["LFPG_AD.pdf".to_string()]);
         assert!(db.is_empty().unwrap());
 
         // Try to delete non-existent entry
-        let result = db.delete_entry("LFPO").unwrap();
-        assert_eq!(result, None);
+        let result = db.delete_entry("LFPO", None).unwrap();
+        assert!(result.is_empty());
+
+        // Only delete the entry of the given type
+        db.upsert_entry(&entry).unwrap();
+        db.upsert_entry(&VacEntry {
+            vac_type: "HEL".to_string(),
+            file_name: "LFPG_HEL.pdf".to_string(),
+            ..entry.clone()
+        })
+        .unwrap();
+        let result = db.delete_entry("LFPG", Some("HEL")).unwrap();
+        assert_eq!(result, vec!["LFPG_HEL.pdf".to_string()]);
+        assert_eq!(
+            db.get_cached_file_name("LFPG", "AD").unwrap(),
+            Some("LFPG_AD.pdf".to_string())
+        );
+        assert_eq!(db.get_cached_file_name("LFPG", "HEL").unwrap(), None);
+    }
+
+    fn legacy_row_count(db: &VacDatabase) -> i64 {
+        db.conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM vac_cache", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fresh_database_is_latest_version() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        assert_eq!(
+            db.schema_version().unwrap(),
+            VacDatabase::latest_schema_version()
+        );
+    }
+
+    #[test]
+    fn test_migrate_from_each_historical_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        for version in 1..=VacDatabase::latest_schema_version() {
+            let path = dir.path().join(format!("v{}.db", version));
+            let conn = Connection::open(&path).unwrap();
+
+            // Recreate the schema as it existed at that version
+            for migration in &MIGRATIONS[..version as usize] {
+                conn.execute_batch(migration).unwrap();
+            }
+            if version > LEGACY_SCHEMA_VERSION {
+                conn.execute_batch(&format!(
+                    "CREATE TABLE schema_version (version INTEGER NOT NULL, applied_at DATETIME);
+                     INSERT INTO schema_version (version) VALUES ({});",
+                    version
+                ))
+                .unwrap();
+            }
+            conn.execute(
+                "INSERT INTO vac_cache (oaci, vac_type, version, file_name, file_size, city)
+                 VALUES ('LFPG', 'AD', '1.0', 'LFPG_AD.pdf', 1024, 'Paris')",
+                [],
+            )
+            .unwrap();
+
+            drop(conn);
+            let db = VacDatabase::new(&path).unwrap();
+            assert_eq!(
+                db.schema_version().unwrap(),
+                VacDatabase::latest_schema_version()
+            );
+            assert_eq!(legacy_row_count(&db), 1);
+            assert_eq!(
+                db.get_cached_version("LFPG", "AD").unwrap(),
+                Some("1.0".to_string())
+            );
+            assert_eq!(db.get_cached_hash("LFPG", "AD").unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_effective_dates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vac_cache.db");
+        let conn = Connection::open(&path).unwrap();
+        for migration in &MIGRATIONS[..EFFECTIVE_DATE_MIGRATION - 1] {
+            conn.execute_batch(migration).unwrap();
+        }
+        conn.execute_batch(&format!(
+            "CREATE TABLE schema_version (version INTEGER NOT NULL, applied_at DATETIME);
+             INSERT INTO schema_version (version) VALUES ({});
+             INSERT INTO vac_cache (oaci, vac_type, version, file_name, file_size, city)
+             VALUES ('LFPG', 'AD', 'AIRAC 2501', 'LFPG_AD.pdf', 1024, 'Paris'),
+                    ('LFPO', 'AD', '1.0', 'LFPO_AD.pdf', 1024, 'Paris');",
+            EFFECTIVE_DATE_MIGRATION - 1
+        ))
+        .unwrap();
+
+        // Existing versions are backfilled by the migration
+        drop(conn);
+        let db = VacDatabase::new(&path).unwrap();
+        let mut entry = db.get_all_entries().unwrap().remove(1);
+        assert_eq!(entry.oaci, "LFPO");
+        entry.version = "20 FEB 2025".to_string();
+        db.upsert_entry(&entry).unwrap();
+
+        let dates: Vec<(String, Option<NaiveDate>)> = db
+            .get_entries_with_effective_date()
+            .unwrap()
+            .into_iter()
+            .map(|(entry, date)| (entry.oaci, date))
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                ("LFPG".to_string(), NaiveDate::from_ymd_opt(2025, 1, 23)),
+                ("LFPO".to_string(), NaiveDate::from_ymd_opt(2025, 2, 20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        VacDatabase::migrate(&db.conn().unwrap()).unwrap();
+        assert_eq!(
+            db.schema_version().unwrap(),
+            VacDatabase::latest_schema_version()
+        );
+    }
+
+    #[test]
+    fn test_upsert_entries() {
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        let entries: Vec<VacEntry> = ["LFPG", "LFPO", "LFPB"]
+            .iter()
+            .map(|oaci| VacEntry {
+                oaci: oaci.to_string(),
+                city: "Paris".to_string(),
+                vac_type: "AD".to_string(),
+                version: "1.0".to_string(),
+                file_name: format!("{}_AD.pdf", oaci),
+                file_size: 1024,
+                file_hash: None,
+                remote_file_name: None,
+                page_count: None,
+                available_locally: false,
+            })
+            .collect();
+
+        db.upsert_entries(&entries).unwrap();
+        assert_eq!(db.get_all_entries().unwrap().len(), 3);
+
+        // Empty batches are a no-op
+        db.upsert_entries(&[]).unwrap();
+        assert_eq!(db.get_all_entries().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_sync_runs() {
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        for (started_at, error) in [
+            ("2025-01-01 02:00:00", None),
+            ("2025-01-02 02:00:00", Some("API unreachable".to_string())),
+        ] {
+            db.record_sync_run(&SyncRun {
+                started_at: started_at.to_string(),
+                duration_ms: 1500,
+                source: "api".to_string(),
+                total_entries: 312,
+                downloaded: 2,
+                failed: 0,
+                bytes_downloaded: 2048,
+                download_ms: 500,
+                error,
+            })
+            .unwrap();
+        }
+
+        let runs = db.get_sync_runs(10).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].started_at, "2025-01-02 02:00:00");
+        assert_eq!(runs[0].error.as_deref(), Some("API unreachable"));
+        assert_eq!(runs[1].bytes_downloaded, 2048);
+        assert_eq!(runs[1].throughput(), Some(4096));
+        assert_eq!(db.get_sync_runs(1).unwrap().len(), 1);
+
+        let totals = db.get_sync_totals().unwrap();
+        assert_eq!((totals.runs, totals.failed_runs), (2, 1));
+        assert_eq!((totals.downloaded, totals.bytes_downloaded), (4, 4096));
+        assert_eq!(
+            totals.last_success.map(|run| run.started_at).as_deref(),
+            Some("2025-01-01 02:00:00")
+        );
+    }
+
+    #[test]
+    fn test_chart_versions() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        let mut entry = queued("LFPN", "1", 0).entry;
+        entry.file_name = "LFPN_v1.pdf".to_string();
+        entry.file_hash = Some("hash1".to_string());
+        db.complete_downloads(std::slice::from_ref(&entry)).unwrap();
+        db.pin_version("LFPN", "AD", "1").unwrap();
+
+        // The pin is kept while the pinned version is written back
+        db.complete_downloads(std::slice::from_ref(&entry)).unwrap();
+        assert_eq!(
+            db.get_pinned_version("LFPN", "AD").unwrap().as_deref(),
+            Some("1")
+        );
+
+        // A download of another version records it and releases the pin
+        entry.version = "2".to_string();
+        entry.file_name = "LFPN_v2.pdf".to_string();
+        entry.file_hash = Some("hash2".to_string());
+        db.complete_downloads(std::slice::from_ref(&entry)).unwrap();
+        assert_eq!(db.get_pinned_version("LFPN", "AD").unwrap(), None);
+        let versions: Vec<String> = db
+            .get_chart_versions("LFPN", "AD")
+            .unwrap()
+            .into_iter()
+            .map(|version| version.file_name)
+            .collect();
+        assert_eq!(versions, ["LFPN_v2.pdf", "LFPN_v1.pdf"]);
+        let mut names = db.get_version_file_names().unwrap();
+        names.sort();
+        assert_eq!(names, ["LFPN_v1.pdf", "LFPN_v2.pdf"]);
+        db.set_version_compressed("LFPN", "AD", "1", true).unwrap();
+        let mut names = db.get_version_file_names().unwrap();
+        names.sort();
+        assert_eq!(names, ["LFPN_v1.pdf.zst", "LFPN_v2.pdf"]);
+        assert!(db.get_previous_versions().unwrap()[0].compressed);
+
+        db.delete_entry("LFPN", None).unwrap();
+        assert!(db.get_chart_versions("LFPN", "AD").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        let entry = queued("LFPN", "1", 0).entry;
+        db.record_audit(&[
+            AuditEntry::chart(AuditAction::Download, &entry, "new chart"),
+            AuditEntry::file(AuditAction::Clean, "stray.pdf", "not in the cache"),
+            AuditEntry::chart(AuditAction::Delete, &entry, "deleted by the delete command"),
+        ])
+        .unwrap();
+
+        let log = db.get_audit_log(None, 10).unwrap();
+        let actions: Vec<AuditAction> = log.iter().map(|entry| entry.action).collect();
+        assert_eq!(
+            actions,
+            [
+                AuditAction::Delete,
+                AuditAction::Clean,
+                AuditAction::Download
+            ]
+        );
+        assert_eq!(log[1].oaci, None);
+        assert_eq!(log[1].file_name.as_deref(), Some("stray.pdf"));
+
+        let log = db.get_audit_log(Some("lfpn"), 1).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].reason, "deleted by the delete command");
+        assert!(db.get_audit_log(Some("LFPG"), 10).unwrap().is_empty());
+    }
+
+    fn queued(oaci: &str, version: &str, priority: i64) -> PendingDownload {
+        PendingDownload {
+            entry: VacEntry {
+                oaci: oaci.to_string(),
+                city: "Paris".to_string(),
+                vac_type: "AD".to_string(),
+                version: version.to_string(),
+                file_name: format!("{}_AD.pdf", oaci),
+                file_size: 1024,
+                file_hash: None,
+                remote_file_name: None,
+                page_count: None,
+                available_locally: false,
+            },
+            source: "api".to_string(),
+            priority,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_pending_downloads() {
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        db.replace_pending_downloads(
+            "api",
+            None,
+            &[
+                queued("LFPG", "1", 0),
+                queued("LFPN", "1", 1),
+                queued("LFPO", "1", 0),
+            ],
+        )
+        .unwrap();
+        let oacis = |db: &VacDatabase| -> Vec<String> {
+            db.get_pending_downloads(Some("api"))
+                .unwrap()
+                .into_iter()
+                .map(|d| d.entry.oaci)
+                .collect()
+        };
+        // Favorites first
+        assert_eq!(oacis(&db), vec!["LFPN", "LFPG", "LFPO"]);
+
+        db.record_download_failure("LFPG", "AD", "timeout").unwrap();
+
+        // A filtered diff only replaces the entries it processed
+        let scope = vec![
+            ("LFPG".to_string(), "AD".to_string()),
+            ("LFPN".to_string(), "AD".to_string()),
+        ];
+        db.replace_pending_downloads("api", Some(&scope), &[queued("LFPG", "1", 0)])
+            .unwrap();
+        assert_eq!(oacis(&db), vec!["LFPG", "LFPO"]);
+        let lfpg = &db.get_pending_downloads(None).unwrap()[0];
+        assert_eq!(lfpg.attempts, 1);
+        assert_eq!(lfpg.last_error.as_deref(), Some("timeout"));
+
+        // A new version resets the attempts
+        db.replace_pending_downloads("api", Some(&scope), &[queued("LFPG", "2", 0)])
+            .unwrap();
+        assert_eq!(db.get_pending_downloads(None).unwrap()[0].attempts, 0);
+
+        // Completed downloads leave the queue
+        let mut entry = queued("LFPO", "1", 0).entry;
+        entry.file_hash = Some("abc".to_string());
+        db.complete_downloads(&[entry]).unwrap();
+        assert_eq!(oacis(&db), vec!["LFPG"]);
+        assert!(db.has_entry("LFPO").unwrap());
+        assert!(db
+            .get_pending_downloads(Some("https://mirror/"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_chart_failures() {
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        assert_eq!(db.get_chart_failures("LFPG", "AD", "1").unwrap(), 0);
+        assert_eq!(
+            db.record_chart_failure("LFPG", "AD", "1", "HTTP 500")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            db.record_chart_failure("LFPG", "AD", "1", "HTTP 500")
+                .unwrap(),
+            2
+        );
+        assert_eq!(db.get_chart_failures("LFPG", "AD", "1").unwrap(), 2);
+
+        // A new version starts over
+        assert_eq!(
+            db.record_chart_failure("LFPG", "AD", "2", "HTTP 500")
+                .unwrap(),
+            1
+        );
+        assert_eq!(db.get_chart_failures("LFPG", "AD", "1").unwrap(), 0);
+
+        // So does a completed download
+        let mut entry = queued("LFPG", "2", 0).entry;
+        entry.file_hash = Some("abc".to_string());
+        db.complete_downloads(&[entry]).unwrap();
+        assert_eq!(db.get_chart_failures("LFPG", "AD", "2").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_missing_charts() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        let day = Duration::from_secs(86400);
+
+        db.record_missing_chart("LFPG", "AD", "1").unwrap();
+        assert!(db.is_missing_chart("LFPG", "AD", "1", day).unwrap());
+        assert!(!db
+            .is_missing_chart("LFPG", "AD", "1", Duration::ZERO)
+            .unwrap());
+        // A new version is tried again
+        assert!(!db.is_missing_chart("LFPG", "AD", "2", day).unwrap());
+
+        // A completed download clears the record
+        let mut entry = queued("LFPG", "1", 0).entry;
+        entry.file_hash = Some("abc".to_string());
+        db.complete_downloads(&[entry]).unwrap();
+        assert!(!db.is_missing_chart("LFPG", "AD", "1", day).unwrap());
+    }
+
+    #[test]
+    fn test_http_cache() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        assert!(db.get_http_cache("/api/v1/oacis?page=1").unwrap().is_none());
+
+        let entry = HttpCacheEntry {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: Some(b"{}".to_vec()),
+        };
+        db.set_http_cache("/api/v1/oacis?page=1", &entry).unwrap();
+        assert_eq!(
+            db.get_http_cache("/api/v1/oacis?page=1").unwrap(),
+            Some(entry)
+        );
+
+        let updated = HttpCacheEntry {
+            etag: None,
+            last_modified: Some("Wed, 21 Oct 2026 07:28:00 GMT".to_string()),
+            body: None,
+        };
+        db.set_http_cache("/api/v1/oacis?page=1", &updated).unwrap();
+        assert_eq!(
+            db.get_http_cache("/api/v1/oacis?page=1").unwrap(),
+            Some(updated)
+        );
+    }
+
+    #[test]
+    fn test_supplements() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        assert!(db.get_supplements().unwrap().is_empty());
+
+        let mut supplement = Supplement {
+            number: "SUP 042/26".to_string(),
+            remote_id: 42,
+            title: "Travaux piste 09/27".to_string(),
+            valid_from: Some("2026-10-01T00:00:00+00:00".to_string()),
+            valid_until: None,
+            aerodromes: vec!["LFPN".to_string(), "LFPT".to_string()],
+            file_name: "SUP/SUP_042-26.pdf".to_string(),
+            file_hash: None,
+        };
+        db.upsert_supplement(&supplement).unwrap();
+        supplement.file_hash = Some("abc".to_string());
+        db.upsert_supplement(&supplement).unwrap();
+        assert_eq!(db.get_supplements().unwrap(), vec![supplement]);
+
+        db.delete_supplement("SUP 042/26").unwrap();
+        assert!(db.get_supplements().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_entries_by_last_updated() {
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        for oaci in ["LFPG", "LFPO", "LFPB"] {
+            db.upsert_entry(&VacEntry {
+                oaci: oaci.to_string(),
+                city: "Paris".to_string(),
+                vac_type: "AD".to_string(),
+                version: "1.0".to_string(),
+                file_name: format!("{}_AD.pdf", oaci),
+                file_size: 1024,
+                file_hash: None,
+                remote_file_name: None,
+                page_count: None,
+                available_locally: false,
+            })
+            .unwrap();
+        }
+        db.conn()
+            .unwrap()
+            .execute(
+                "UPDATE vac_cache SET last_updated = '2024-01-01 00:00:00' WHERE oaci = 'LFPO'",
+                [],
+            )
+            .unwrap();
+
+        let oacis: Vec<String> = db
+            .get_entries_by_last_updated()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.oaci)
+            .collect();
+        assert_eq!(oacis, vec!["LFPO", "LFPB", "LFPG"]);
+    }
+
+    #[test]
+    fn test_text_index() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        assert!(db.get_text_index().unwrap().is_empty());
+
+        db.set_chart_text(
+            "LFPN",
+            "AD",
+            "abc123",
+            &["Activité ULM intense".to_string(), "TWR 118.5".to_string()],
+        )
+        .unwrap();
+        db.set_chart_text("LFPZ", "AD", "def456", &["Parachutage".to_string()])
+            .unwrap();
+
+        let matches = db.search_text("activite ulm", 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].oaci, "LFPN");
+        assert_eq!(matches[0].page, 1);
+        assert_eq!(matches[0].snippet, "[Activité ULM] intense");
+
+        let matches = db.search_text("118.5", 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].page, 2);
+        assert!(db.search_text("\"unbalanced", 10).unwrap().is_empty());
+
+        // Reindexing replaces the previous text
+        db.set_chart_text("LFPN", "AD", "abc124", &["Piste 07/25".to_string()])
+            .unwrap();
+        assert!(db.search_text("ULM", 10).unwrap().is_empty());
+        assert_eq!(
+            db.get_text_index().unwrap()[&("LFPN".to_string(), "AD".to_string())],
+            "abc124"
+        );
+
+        db.delete_chart_text("LFPZ", "AD").unwrap();
+        assert!(db.search_text("parachutage", 10).unwrap().is_empty());
+        assert_eq!(db.get_text_index().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_page_count() {
+        let db = VacDatabase::new(":memory:").unwrap();
+        let mut entry = VacEntry {
+            oaci: "LFPG".to_string(),
+            city: "Paris".to_string(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: "LFPG_AD.pdf".to_string(),
+            file_size: 1024,
+            file_hash: Some("abc123".to_string()),
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        };
+        db.upsert_entry(&entry).unwrap();
+        db.set_page_count("LFPG", "AD", 3).unwrap();
+        assert_eq!(db.get_all_entries().unwrap()[0].page_count, Some(3));
+
+        // Kept while the file is unchanged
+        entry.file_name = "LFPG/LFPG_AD.pdf".to_string();
+        db.upsert_entry(&entry).unwrap();
+        assert_eq!(db.get_all_entries().unwrap()[0].page_count, Some(3));
+
+        // Dropped when the file changes
+        entry.file_hash = Some("def456".to_string());
+        db.upsert_entry(&entry).unwrap();
+        assert_eq!(db.get_all_entries().unwrap()[0].page_count, None);
     }
 }
@@ -19,129 +19,947 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use crate::{AuthGenerator, OacisResponse, VacDatabase, VacEntry};
+use crate::airac::AiracCycle;
+use crate::api::{
+    debug_error_response, status_error, DownloadStatusError, SiaApiClient, SIA_API_URL,
+};
+use crate::bundle::{self, BundleImport, BundleManifest};
+use crate::calendar::ChartCalendar;
+use crate::cancel::CancellationToken;
+use crate::checksums::{ChecksumManifest, SigningKey, CHECKSUMS_NAME, CHECKSUMS_SIGNATURE_NAME};
+use crate::circuit::{CircuitBreaker, PersistentFailure, DEFAULT_FAILURE_THRESHOLD};
+use crate::export::{AirfieldExport, ExportFormat};
+use crate::hooks::{self, SyncHooks};
+use crate::html_index::{HtmlIndex, HTML_INDEX_NAME};
+use crate::import::{self, DirectoryImport};
+use crate::lock::InstanceLock;
+use crate::matching::{self, matches_code};
+use crate::metrics::Metrics;
+use crate::mirror::{self, MirrorManifest, MIRROR_MANIFEST_NAME};
+use crate::models::throughput;
+use crate::observer::SyncObserver;
+use crate::pack::{self, ContentPack};
+use crate::pdf;
+use crate::rate_limit::RateLimiter;
+use crate::regions::{self, Territory};
+use crate::retry::RetryPolicy;
+use crate::route;
+use crate::storage::{LocalStorage, Storage};
+use crate::template::{with_collision_suffix, FileNameTemplate};
+use crate::transport::{
+    HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, TransportError,
+};
+use crate::trip::{self, TripChart, TripLayout, TripPdf};
+use crate::units::format_size;
+use crate::{
+    AuditAction, AuditEntry, BoundingBox, ChartVersion, HttpCacheEntry, OacisEntry, OacisResponse,
+    PendingDownload, Supplement, SyncRun, TextMatch, VacDatabase, VacEntry,
+};
 use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
 use reqwest::blocking::Client;
+use reqwest::header::{
+    CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
-use std::cell::RefCell;
-use std::fs;
-use std::io::Read;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn};
+use vac_downloader_core::diff::{self, LocalChart};
+use vac_downloader_core::is_outdated;
 
-const API_BASE_URL: &str = "https://bo-prod-sofia-vac.sia-france.fr";
-const OACIS_ENDPOINT: &str = "/api/v1/oacis";
-const FILE_ENDPOINT: &str = "/api/v1/custom/file-path";
-const CACHE_TTL_SECONDS: u64 = 600; // 10 minutes
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(600); // 10 minutes
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Source recorded for syncs against the SIA API
+const API_SOURCE: &str = "api";
+
+/// Directory of the download directory where the files of superseded
+/// versions are moved, when a new version is stored under the same name
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// zstd level of compressed previous versions, favoring size as they are rarely read
+const ZSTD_LEVEL: i32 = 19;
+
+/// Files generated by the tool itself, which are never considered orphans
+const GENERATED_FILES: &[&str] = &[
+    MIRROR_MANIFEST_NAME,
+    HTML_INDEX_NAME,
+    CHECKSUMS_NAME,
+    CHECKSUMS_SIGNATURE_NAME,
+];
+
+/// Find a version by its name, or by a beginning matching a single version
+fn find_version<'a>(versions: &'a [ChartVersion], version: &str) -> Option<&'a ChartVersion> {
+    versions.iter().find(|v| v.version == version).or_else(|| {
+        let mut matches = versions.iter().filter(|v| v.version.starts_with(version));
+        matches.next().filter(|_| matches.next().is_none())
+    })
+}
+
+/// Send the validators of a cached response, so the server can answer `304 Not Modified`
+fn conditional(mut request: HttpRequest, cached: Option<&HttpCacheEntry>) -> HttpRequest {
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    request
+}
+
+/// Reason of a download recorded in the audit log
+fn download_reason(previous_version: Option<&str>, version: &str) -> String {
+    match previous_version {
+        None => format!("new chart, version {}", version),
+        Some(previous) if previous != version => {
+            format!("version {} updated to {}", previous, version)
+        }
+        Some(_) => format!("version {} downloaded again", version),
+    }
+}
+
+/// Codes of an OACI filter that can be sent to the server, when they are all
+/// plain codes rather than glob patterns
+fn server_filter_codes(oaci_filter: Option<&[String]>) -> Option<Vec<String>> {
+    let codes = oaci_filter.filter(|codes| !codes.is_empty())?;
+    codes
+        .iter()
+        .all(|code| code.chars().all(|c| c.is_ascii_alphanumeric()))
+        .then(|| codes.iter().map(|code| code.to_uppercase()).collect())
+}
+
+/// Validators of a response, if the server sent any
+fn response_validators(response: &HttpResponse) -> Option<HttpCacheEntry> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    if etag.is_none() && last_modified.is_none() {
+        return None;
+    }
+    Some(HttpCacheEntry {
+        etag,
+        last_modified,
+        body: None,
+    })
+}
+
+/// Outcome of a PDF download
+enum PdfDownload {
+    /// The PDF was downloaded, with its hash and size
+    Downloaded(String, u64),
+    /// The server confirmed the stored PDF, with this hash, is current
+    NotModified(String),
+}
 
 /// Cached OACIS data with timestamp
+///
+/// The listing is shared with its users rather than copied for each of them.
+#[derive(Clone)]
 struct CachedOacisData {
-    entries: Vec<VacEntry>,
+    entries: Arc<Vec<VacEntry>>,
+    /// Airfields of the listing, with their runways and frequencies
+    airfields: Arc<Vec<OacisEntry>>,
     fetched_at: Instant,
 }
 
+/// State of a sync against the API, carried across the batches of entries it
+/// processes
+struct ApiSync {
+    started: Instant,
+    is_first_run: bool,
+    stats: SyncStats,
+    /// Stored file names given to the processed entries
+    claimed_names: HashMap<String, (String, String)>,
+    /// OACI codes of the downloaded charts
+    downloaded: HashSet<String>,
+    breaker: CircuitBreaker,
+    /// Failure tripping the circuit breaker, with the downloads left
+    tripped: Option<(PersistentFailure, usize)>,
+}
+
+impl ApiSync {
+    /// Whether the sync stopped before the end of its downloads
+    fn stopped(&self) -> bool {
+        self.stats.deferred > 0 || self.tripped.is_some()
+    }
+}
+
 /// Main VAC downloader with caching and version management
+///
+/// The downloader is `Send + Sync` and can be shared between threads behind an
+/// `Arc`. Operations changing the cache still run one at a time: while one
+/// holds the lock, the others fail unless [`VacDownloader::with_wait_for_lock`]
+/// is set.
 pub struct VacDownloader {
-    client: Client,
+    api: SiaApiClient,
     database: VacDatabase,
+    storage: Box<dyn Storage>,
+    file_name_template: FileNameTemplate,
+    max_cache_size: Option<u64>,
+    keep_versions: Option<usize>,
+    keep_days: Option<u32>,
+    compress_versions: bool,
+    max_duration: Option<Duration>,
+    offline: bool,
+    file_timeout: Option<Duration>,
+    skip_not_found: Option<Duration>,
+    quarantine_threshold: u32,
+    retry_quarantined: bool,
+    failure_threshold: usize,
+    signing_key: Option<SigningKey>,
+    force_all: bool,
+    force_oacis: Vec<String>,
+    favorites: Vec<String>,
+    cache_ttl: Duration,
+    page_size: Option<u32>,
+    stream_listing: bool,
+    include_supplements: bool,
+    /// Lock file guarding the cache against concurrent changes, unless the database is in memory
+    lock_path: Option<PathBuf>,
+    wait_for_lock: bool,
+    oacis_cache: Mutex<Option<CachedOacisData>>,
+    observer: Option<Arc<dyn SyncObserver>>,
+    cancellation: Option<CancellationToken>,
+    hooks: SyncHooks,
+    /// Territories of the processed airfields, all of them when empty
+    territories: Vec<Territory>,
+}
+
+/// Builder for a [`VacDownloader`] with custom HTTP settings
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use vac_downloader::{RetryPolicy, VacDownloader};
+///
+/// let downloader = VacDownloader::builder("vac_cache.db", "./downloads")
+///     .timeout(Duration::from_secs(60))
+///     .user_agent("my-app/1.0")
+///     .retry_policy(RetryPolicy::exponential(3, Duration::from_secs(1)))
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct VacDownloaderBuilder {
+    db_path: PathBuf,
     download_dir: PathBuf,
-    oacis_cache: RefCell<Option<CachedOacisData>>,
+    base_url: String,
+    fallback_base_urls: Vec<String>,
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<reqwest::Proxy>,
+    retry_policy: RetryPolicy,
+    transport: Option<Arc<dyn HttpTransport>>,
+    dump_dir: Option<PathBuf>,
+    page_size: Option<u32>,
+    rate_limit: Option<f64>,
+    db_passphrase: Option<String>,
+    read_only: bool,
+}
+
+impl VacDownloaderBuilder {
+    /// Start building a downloader using the given database and download directory
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(db_path: P, download_dir: Q) -> Self {
+        VacDownloaderBuilder {
+            db_path: db_path.as_ref().to_path_buf(),
+            download_dir: download_dir.as_ref().to_path_buf(),
+            base_url: SIA_API_URL.to_string(),
+            fallback_base_urls: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: None,
+            user_agent: None,
+            proxy: None,
+            retry_policy: RetryPolicy::default(),
+            transport: None,
+            dump_dir: None,
+            page_size: None,
+            rate_limit: None,
+            db_passphrase: None,
+            read_only: false,
+        }
+    }
+
+    /// Send API requests to this server instead of the SIA one, e.g. a mock server in tests
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Try these API servers in order when the previous ones are unreachable
+    pub fn fallback_base_urls(mut self, base_urls: Vec<String>) -> Self {
+        self.fallback_base_urls = base_urls
+            .iter()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .collect();
+        self
+    }
+
+    /// Timeout of each HTTP request (30 seconds by default)
+    ///
+    /// It bounds the wait for the response, then each read of its body, so
+    /// that a stalled transfer fails without bounding a slow one: see
+    /// [`VacDownloader::with_file_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout for establishing connections
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// `User-Agent` header sent with every request
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Route requests through a proxy
+    ///
+    /// Without one, the `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY`
+    /// environment variables are honored.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Retry requests that failed with a transient error (no retries by default)
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Send at most this many API requests per second, with a random jitter
+    ///
+    /// Applies to the listing and the PDF downloads, retries included. There
+    /// is no limit by default.
+    pub fn rate_limit(mut self, requests_per_second: f64) -> Self {
+        self.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Encrypt the database with SQLCipher, using this passphrase
+    ///
+    /// Requires the `sqlcipher` feature; without it, `build` fails. See
+    /// [`VacDatabase::open_encrypted`].
+    pub fn db_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.db_passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// Open the database read-only, for commands that only query it, e.g. on
+    /// read-only media
+    ///
+    /// A database that does not exist yet or whose schema is outdated is
+    /// opened read-write, to be created or upgraded. Encrypted databases are
+    /// always opened read-write. See [`VacDatabase::open_read_only`].
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Number of airfields requested per page of the OACIS listing
+    ///
+    /// By default, the server chooses the page size.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Write the raw API responses that fail to parse into this directory
+    pub fn dump_dir<P: AsRef<Path>>(mut self, dump_dir: P) -> Self {
+        self.dump_dir = Some(dump_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Use this HTTP client instead of building one
+    ///
+    /// The timeout, user agent and proxy settings of the builder are ignored,
+    /// the client is used as configured.
+    pub fn client(mut self, client: Client) -> Self {
+        self.transport = Some(Arc::new(ReqwestTransport::new(client)));
+        self
+    }
+
+    /// Send the API requests through this transport instead of an HTTP client,
+    /// e.g. one answering with canned responses in tests
+    ///
+    /// The timeout, user agent and proxy settings of the builder are ignored.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Open the database and create the downloader
+    pub fn build(self) -> Result<VacDownloader> {
+        let base_urls: Vec<String> = std::iter::once(self.base_url)
+            .chain(self.fallback_base_urls)
+            .collect();
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let mut builder = Client::builder().timeout(self.timeout);
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(user_agent) = &self.user_agent {
+                    builder = builder.user_agent(user_agent.as_str());
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                let client = builder.build().context("Failed to create HTTP client")?;
+                Arc::new(ReqwestTransport::new(client))
+            }
+        };
+
+        let mut api = SiaApiClient::with_transport(transport)
+            .with_base_urls(base_urls)?
+            .with_retry_policy(self.retry_policy);
+        if let Some(dump_dir) = self.dump_dir {
+            api = api.with_dump_dir(dump_dir);
+        }
+        if let Some(requests_per_second) = self.rate_limit {
+            api = api.with_rate_limiter(RateLimiter::new(requests_per_second)?);
+        }
+
+        let database = match &self.db_passphrase {
+            None if self.read_only => match VacDatabase::open_read_only(&self.db_path) {
+                Ok(database) => database,
+                Err(e)
+                    if matches!(
+                        e.sqlite_error_code(),
+                        Some(rusqlite::ErrorCode::CannotOpen | rusqlite::ErrorCode::ReadOnly)
+                    ) =>
+                {
+                    debug!("Opening the database read-write: {}", e);
+                    VacDatabase::new(&self.db_path).context("Failed to initialize database")?
+                }
+                Err(e) => return Err(e).context("Failed to open the database read-only"),
+            },
+            None => VacDatabase::new(&self.db_path).context("Failed to initialize database")?,
+            #[cfg(feature = "sqlcipher")]
+            Some(passphrase) => VacDatabase::open_encrypted(&self.db_path, passphrase)
+                .context("Failed to open the encrypted database (wrong passphrase?)")?,
+            #[cfg(not(feature = "sqlcipher"))]
+            Some(_) => anyhow::bail!(
+                "Encrypted databases require building vac-downloader with the sqlcipher feature"
+            ),
+        };
+        let lock_path =
+            (self.db_path != Path::new(":memory:")).then(|| InstanceLock::path_for(&self.db_path));
+
+        let storage = LocalStorage::new(&self.download_dir)?;
+
+        Ok(VacDownloader {
+            api,
+            database,
+            storage: Box::new(storage),
+            file_name_template: FileNameTemplate::default(),
+            max_cache_size: None,
+            keep_versions: None,
+            keep_days: None,
+            compress_versions: false,
+            max_duration: None,
+            offline: false,
+            file_timeout: None,
+            skip_not_found: None,
+            quarantine_threshold: 0,
+            retry_quarantined: false,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            signing_key: None,
+            force_all: false,
+            force_oacis: Vec::new(),
+            favorites: Vec::new(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            page_size: self.page_size,
+            stream_listing: false,
+            include_supplements: false,
+            lock_path,
+            wait_for_lock: false,
+            oacis_cache: Mutex::new(None),
+            observer: None,
+            cancellation: None,
+            hooks: SyncHooks::default(),
+            territories: Vec::new(),
+        })
+    }
 }
 
 impl VacDownloader {
-    /// Create a new VAC downloader
+    /// Create a new VAC downloader with the default HTTP settings
     ///
     /// # Arguments
     /// * `db_path` - Path to SQLite database file
     /// * `download_dir` - Directory to save downloaded PDFs
     pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(db_path: P, download_dir: Q) -> Result<Self> {
-        let database = VacDatabase::new(db_path).context("Failed to initialize database")?;
+        Self::builder(db_path, download_dir).build()
+    }
 
-        let download_dir = download_dir.as_ref().to_path_buf();
-        fs::create_dir_all(&download_dir).context("Failed to create download directory")?;
+    /// Create a downloader with custom HTTP settings, see [`VacDownloaderBuilder`]
+    pub fn builder<P: AsRef<Path>, Q: AsRef<Path>>(
+        db_path: P,
+        download_dir: Q,
+    ) -> VacDownloaderBuilder {
+        VacDownloaderBuilder::new(db_path, download_dir)
+    }
 
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .context("Failed to create HTTP client")?;
+    /// Store downloaded PDFs in the given storage backend instead of the download directory
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
 
-        Ok(VacDownloader {
-            client,
-            database,
-            download_dir,
-            oacis_cache: RefCell::new(None),
-        })
+    /// Lay out downloaded PDFs according to a path template
+    ///
+    /// Files already in the cache are moved to their new location on the next sync.
+    pub fn with_file_name_template(mut self, template: FileNameTemplate) -> Self {
+        self.file_name_template = template;
+        self
     }
 
-    /// Calculate SHA-256 hash of a file
-    fn calculate_file_hash(path: &Path) -> Result<String> {
-        let mut file =
-            fs::File::open(path).context(format!("Failed to open file for hashing: {:?}", path))?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0u8; 8192];
+    /// List and search the cached charts only, without fetching the listing
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
 
-        loop {
-            let bytes_read = file
-                .read(&mut buffer)
-                .context("Failed to read file for hashing")?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
+    /// Stop a sync once it has run for this long
+    ///
+    /// The download in progress is abandoned. It stays in the download queue
+    /// with the ones that were not started, and all are reported in
+    /// [`SyncStats::deferred`].
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Abandon the download of a file once it has run for this long
+    ///
+    /// Unlike the request timeout, which applies to each read, this bounds a
+    /// slow transfer as a whole. It fails like any other download.
+    pub fn with_file_timeout(mut self, file_timeout: Duration) -> Self {
+        self.file_timeout = Some(file_timeout);
+        self
+    }
+
+    /// Skip the downloads the server answered with a 404 less than `period` ago
+    ///
+    /// Stale listings reference files that no longer exist: instead of failing
+    /// every sync, such charts stay queued and are tried again once the period
+    /// is over, or as soon as the listing publishes another version.
+    pub fn with_skip_not_found(mut self, period: Duration) -> Self {
+        self.skip_not_found = Some(period);
+        self
+    }
+
+    /// Quarantine a chart once this many downloads of its version failed in a
+    /// row (0, the default, never quarantines)
+    ///
+    /// Quarantined charts stay queued but are skipped with a warning, so that
+    /// one broken chart does not fail every sync. A new version, a forced
+    /// download or [`VacDownloader::with_retry_quarantined`] tries them again.
+    pub fn with_quarantine_after(mut self, threshold: u32) -> Self {
+        self.quarantine_threshold = threshold;
+        self
+    }
+
+    /// Try the downloads of quarantined charts again
+    pub fn with_retry_quarantined(mut self) -> Self {
+        self.retry_quarantined = true;
+        self
+    }
+
+    /// Re-download every entry processed by a sync, ignoring version and hash checks
+    pub fn with_force(mut self) -> Self {
+        self.force_all = true;
+        self
+    }
+
+    /// Re-download the charts of these airfields, ignoring version and hash checks
+    ///
+    /// Useful when a corrected PDF is published without a new version.
+    pub fn with_force_oacis(mut self, oacis: Vec<String>) -> Self {
+        self.force_oacis = oacis;
+        self
+    }
+
+    /// Download the charts of these airfields first
+    pub fn with_favorites(mut self, oacis: Vec<String>) -> Self {
+        self.favorites = oacis;
+        self
+    }
+
+    /// Wait for other instances to finish instead of failing
+    ///
+    /// Operations changing the cache (syncs, imports, deletions and cleanups)
+    /// hold a lock on a `.lock` file next to the database.
+    pub fn with_wait_for_lock(mut self) -> Self {
+        self.wait_for_lock = true;
+        self
+    }
+
+    /// Stop a sync once this many downloads in a row failed the same way
+    /// with a 401, a 403 or a DNS error (5 by default, 0 never stops)
+    ///
+    /// The remaining downloads stay in the download queue for the next run.
+    pub fn with_max_consecutive_failures(mut self, threshold: usize) -> Self {
+        self.failure_threshold = threshold;
+        self
+    }
+
+    /// Sign the `SHA256SUMS` manifest with this minisign key
+    ///
+    /// See [`VacDownloader::write_checksums`].
+    pub fn with_signing_key(mut self, key: SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Only process the airfields of some territories, e.g. to leave the
+    /// overseas charts out of full syncs
+    ///
+    /// Airfields of other territories are dropped from the listing, so that
+    /// no command sees them. An empty list keeps every territory.
+    pub fn with_territories(mut self, territories: Vec<Territory>) -> Self {
+        self.territories = territories;
+        self
+    }
+
+    /// Whether an airfield belongs to the territories being processed
+    fn in_territories(&self, oaci: &str) -> bool {
+        self.territories.is_empty()
+            || Territory::of(oaci).is_some_and(|territory| self.territories.contains(&territory))
+    }
+
+    /// Reuse the fetched OACIS listing for this long (10 minutes by default)
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Process the entries of each page of the listing during syncs as soon
+    /// as it is fetched, downloading their charts before fetching the next
+    /// page, instead of fetching the whole listing first
+    ///
+    /// The whole listing is then never held in memory, which suits small
+    /// devices, but it is not cached for the following operations. A fresh
+    /// cached listing is still used.
+    pub fn with_stream_listing(mut self, stream_listing: bool) -> Self {
+        self.stream_listing = stream_listing;
+        self
+    }
+
+    /// Limit the total size of cached PDFs
+    ///
+    /// After each sync, the least recently updated airfields are evicted until
+    /// the cache fits. Entries downloaded during the sync are never evicted.
+    pub fn with_max_cache_size(mut self, max_bytes: u64) -> Self {
+        self.max_cache_size = Some(max_bytes);
+        self
+    }
+
+    /// Keep at most this many previous versions of each chart
+    ///
+    /// Previous versions are the ones moved to [`ARCHIVE_DIR`] or kept by
+    /// versioned file names. After each sync, the oldest beyond this number are
+    /// removed, see [`VacDownloader::gc`]. With 0, superseded files are
+    /// overwritten rather than archived.
+    pub fn with_keep_versions(mut self, keep_versions: usize) -> Self {
+        self.keep_versions = Some(keep_versions);
+        self
+    }
+
+    /// Remove the previous versions of charts downloaded more than this many days ago
+    ///
+    /// Applied after each sync, along with [`VacDownloader::with_keep_versions`].
+    pub fn with_keep_days(mut self, keep_days: u32) -> Self {
+        self.keep_days = Some(keep_days);
+        self
+    }
+
+    /// Compress the previous versions of charts with zstd
+    ///
+    /// Applied after each sync, along with the retention policy. Compressed
+    /// versions are decompressed by [`VacDownloader::rollback`] and
+    /// [`VacDownloader::extract_version`].
+    pub fn with_compress_versions(mut self, compress_versions: bool) -> Self {
+        self.compress_versions = compress_versions;
+        self
+    }
+
+    /// Also download the AIP supplements affecting cached airfields during API syncs
+    ///
+    /// Supplements are stored under `SUP/` and removed once they are withdrawn,
+    /// expired or no longer affect a cached airfield.
+    pub fn with_supplements(mut self) -> Self {
+        self.include_supplements = true;
+        self
+    }
+
+    /// Client used for the requests to the SIA API
+    ///
+    /// It shares the HTTP settings of the downloader and can reach endpoints
+    /// the downloader does not use.
+    pub fn api(&self) -> &SiaApiClient {
+        &self.api
+    }
+
+    /// Report the progress of syncs to the given observer
+    pub fn with_observer(mut self, observer: Arc<dyn SyncObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stop the operations of the downloader once the given token is cancelled
+    ///
+    /// Syncs check the token before each download and between the reads of
+    /// the download in progress, which is abandoned. Like with
+    /// [`VacDownloader::with_max_duration`], the remaining downloads stay in
+    /// the download queue and are reported in [`SyncStats::deferred`].
+    /// Listings fail before fetching their next page.
+    ///
+    /// ```no_run
+    /// use vac_downloader::{CancellationToken, VacDownloader};
+    ///
+    /// let cancel = CancellationToken::new();
+    /// let downloader = VacDownloader::new("vac_cache.db", "./downloads")?
+    ///     .with_cancellation(cancel.clone());
+    /// // e.g. from the Cancel button of a GUI
+    /// cancel.cancel();
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Run external commands before and after syncs and downloads
+    pub fn with_hooks(mut self, hooks: SyncHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Run the post_download hook, if any, for a downloaded chart
+    ///
+    /// A failing hook is reported but does not fail the download.
+    fn run_post_download_hook(&self, entry: &VacEntry, previous_version: Option<&str>) {
+        let Some(command) = &self.hooks.post_download else {
+            return;
+        };
+        let mut env = vec![
+            ("VAC_OACI", entry.oaci.clone()),
+            ("VAC_TYPE", entry.vac_type.clone()),
+            ("VAC_CITY", entry.city.clone()),
+            ("VAC_VERSION", entry.version.clone()),
+            (
+                "VAC_PREVIOUS_VERSION",
+                previous_version.unwrap_or_default().to_string(),
+            ),
+            ("VAC_FILE_NAME", entry.file_name.clone()),
+        ];
+        if let Some(path) = self.storage.local_path(&entry.file_name) {
+            env.push(("VAC_PATH", path.display().to_string()));
         }
+        if let Err(e) = hooks::run("post_download", command, &env) {
+            warn!("⚠️  {:#}", e);
+        }
+    }
 
-        Ok(format!("{:x}", hasher.finalize()))
+    /// Notify the observer, if any, that the stored file of a chart was checked
+    fn notify_verified(&self, entry: &VacEntry, problem: Option<&str>) {
+        if let Some(observer) = &self.observer {
+            observer.chart_verified(entry, problem);
+        }
     }
 
-    /// Fetch all OACIS entries from the API (with pagination and caching)
-    fn fetch_oacis_data(&self) -> Result<Vec<VacEntry>> {
-        // Check if we have valid cached data
-        {
-            let cache = self.oacis_cache.borrow();
-            if let Some(cached) = cache.as_ref() {
-                let age = cached.fetched_at.elapsed();
-                if age < Duration::from_secs(CACHE_TTL_SECONDS) {
-                    let remaining = Duration::from_secs(CACHE_TTL_SECONDS) - age;
-                    println!(
-                        "📦 Using cached OACIS data ({} entries, cache expires in {}s)",
-                        cached.entries.len(),
-                        remaining.as_secs()
-                    );
-                    return Ok(cached.entries.clone());
-                } else {
-                    println!(
-                        "⏰ Cache expired (age: {}s), fetching fresh data",
-                        age.as_secs()
-                    );
-                }
-            }
+    /// Notify the observer, if any, that a download starts
+    fn notify_started(&self, entry: &VacEntry, index: usize, total: usize) {
+        if let Some(observer) = &self.observer {
+            observer.download_started(entry, index, total);
+        }
+    }
+
+    /// Notify the observer, if any, of the bytes received by a download
+    fn notify_progress(&self, entry: &VacEntry, received: u64, total: Option<u64>) {
+        if let Some(observer) = &self.observer {
+            observer.download_progress(entry, received, total);
+        }
+    }
+
+    /// Notify the observer, if any, that a download finished
+    fn notify_finished(&self, entry: &VacEntry, error: Option<&anyhow::Error>) {
+        if let Some(observer) = &self.observer {
+            observer.download_finished(entry, error);
+        }
+    }
+
+    /// Lock the cache against changes by other instances
+    fn lock(&self) -> Result<Option<InstanceLock>> {
+        self.lock_path
+            .as_deref()
+            .map(|path| InstanceLock::acquire(path, self.wait_for_lock))
+            .transpose()
+    }
+
+    /// Fetch the OACIS entries from the API (with pagination and caching),
+    /// filtered by the server to the given OACI codes when possible
+    fn fetch_oacis_data(&self, oaci_filter: Option<&[String]>) -> Result<Arc<Vec<VacEntry>>> {
+        self.fetch_oacis_listing_for(oaci_filter)
+            .map(|listing| listing.entries)
+    }
+
+    /// Fetch the OACIS listing from the API, or reuse the cached one
+    fn fetch_oacis_listing(&self) -> Result<CachedOacisData> {
+        if let Some(cached) = self.cached_oacis_listing() {
+            return Ok(cached);
         }
 
         // Cache miss or expired, fetch fresh data
-        let mut all_entries = Vec::new();
-        let mut page = 1;
+        let listing = self.fetch_oacis_pages(&[])?;
+        *self
+            .oacis_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(listing.clone());
+        info!("💾 Cached OACIS data (TTL: {}s)", self.cache_ttl.as_secs());
 
-        loop {
-            let api_path = format!("{}?page={}", OACIS_ENDPOINT, page);
-            let url = format!("{}{}", API_BASE_URL, api_path);
-            let auth_header = AuthGenerator::generate_auth_header(&api_path, None);
+        Ok(listing)
+    }
+
+    /// Fetch the airfields of the OACIS listing matching a filter
+    ///
+    /// A filter of plain OACI codes is sent to the server, so that only their
+    /// entries are downloaded; glob patterns need the whole listing, as does a
+    /// server rejecting the filter. The result may hold more airfields than
+    /// asked for, callers still filter it. A fresh cached listing is reused.
+    fn fetch_oacis_listing_for(&self, oaci_filter: Option<&[String]>) -> Result<CachedOacisData> {
+        let Some(codes) = server_filter_codes(oaci_filter) else {
+            return self.fetch_oacis_listing();
+        };
+        if let Some(cached) = self.cached_oacis_listing() {
+            return Ok(cached);
+        }
+
+        info!("🔍 Fetching the OACIS entries of {} only", codes.join(", "));
+        self.fetch_oacis_pages(&codes).or_else(|e| {
+            warn!(
+                "⚠️  Failed to fetch a filtered listing ({:#}), fetching the whole listing",
+                e
+            );
+            self.fetch_oacis_listing()
+        })
+    }
+
+    /// The cached OACIS listing, unless it expired
+    fn cached_oacis_listing(&self) -> Option<CachedOacisData> {
+        let cache = self
+            .oacis_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let cached = cache.as_ref()?;
+        let age = cached.fetched_at.elapsed();
+        if age < self.cache_ttl {
+            let remaining = self.cache_ttl - age;
+            info!(
+                "📦 Using cached OACIS data ({} entries, cache expires in {}s)",
+                cached.entries.len(),
+                remaining.as_secs()
+            );
+            Some(cached.clone())
+        } else {
+            info!(
+                "⏰ Cache expired (age: {}s), fetching fresh data",
+                age.as_secs()
+            );
+            None
+        }
+    }
+
+    /// Iterate over the airfields of the OACIS listing, fetching its pages as they are needed
+    ///
+    /// Airfields are yielded as soon as their page is fetched, without
+    /// building the whole listing in memory, so that processing can start
+    /// right away. Unlike [`VacDownloader::list_vacs`], the listing cached in
+    /// memory is neither used nor filled. Airfields outside the territories of
+    /// [`VacDownloader::with_territories`] are skipped. The iterator ends after
+    /// yielding an error.
+    pub fn iter_oacis(&self) -> OacisIter<'_> {
+        OacisIter {
+            downloader: self,
+            page: 1,
+            fetched: 0,
+            members: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Fetch a page of the OACIS listing, restricted to the given codes (all if empty)
+    fn fetch_oacis_page(&self, page: u32, codes: &[String]) -> Result<OacisResponse> {
+        if self.is_cancelled() {
+            return Err(CancellationToken::error().into());
+        }
+        let api_path = SiaApiClient::oacis_query_path(page, self.page_size, codes);
 
-            println!("Fetching page {} from OACIS API...", page);
+        info!("Fetching page {} from OACIS API...", page);
 
-            let response = self
-                .client
-                .get(&url)
-                .header("AUTH", auth_header)
-                .header("Content-Type", "application/json")
-                .send()
-                .context(format!("Failed to fetch OACIS page {}", page))?;
+        // A 304 can only be answered when the previous page was kept
+        let cached = self
+            .database
+            .get_http_cache(&api_path)?
+            .filter(|cached| cached.body.is_some());
+        let response = self
+            .api
+            .get(&api_path, |request| {
+                conditional(
+                    request.header(CONTENT_TYPE, "application/json"),
+                    cached.as_ref(),
+                )
+            })
+            .context(format!("Failed to fetch OACIS page {}", page))?;
 
-            if !response.status().is_success() {
-                anyhow::bail!("API returned error status: {}", response.status());
+        if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            info!("Page {} unchanged since the last fetch", page);
+            let body = cached.and_then(|cached| cached.body).unwrap_or_default();
+            self.api.parse_body(&body, &api_path)
+        } else if response.status().is_success() {
+            let validators = response_validators(&response);
+            let body = response.bytes().context("Failed to read OACIS response")?;
+            let oacis_response = self.api.parse_body(&body, &api_path)?;
+            if let Some(mut validators) = validators {
+                validators.body = Some(body.to_vec());
+                self.database.set_http_cache(&api_path, &validators)?;
             }
+            Ok(oacis_response)
+        } else {
+            Err(status_error(response, false, |status| {
+                anyhow::anyhow!("API returned error status: {}", status)
+            }))
+        }
+    }
+
+    /// Fetch every page of the OACIS listing, restricted to the given codes (all if empty)
+    fn fetch_oacis_pages(&self, codes: &[String]) -> Result<CachedOacisData> {
+        let mut all_entries = Vec::new();
+        let mut airfields = Vec::new();
+        let mut page = 1;
 
-            let oacis_response: OacisResponse =
-                response.json().context("Failed to parse OACIS response")?;
+        loop {
+            let oacis_response = self.fetch_oacis_page(page, codes)?;
 
             // Extract AD entries from this page
             for entry in &oacis_response.members {
@@ -149,10 +967,11 @@ impl VacDownloader {
                 all_entries.extend(vac_entries);
             }
 
-            println!("  Found {} total AD entries so far", all_entries.len());
+            info!("Found {} total AD entries so far", all_entries.len());
 
             // Check if we've fetched all pages
             let items_per_page = oacis_response.members.len() as i32;
+            airfields.extend(oacis_response.members);
             if items_per_page == 0 || all_entries.len() >= oacis_response.total_items as usize {
                 break;
             }
@@ -160,64 +979,150 @@ impl VacDownloader {
             page += 1;
         }
 
-        println!("Total AD entries fetched: {}", all_entries.len());
+        info!("Total AD entries fetched: {}", all_entries.len());
 
-        // Update cache
-        *self.oacis_cache.borrow_mut() = Some(CachedOacisData {
-            entries: all_entries.clone(),
-            fetched_at: Instant::now(),
-        });
-        println!("💾 Cached OACIS data (TTL: {}s)", CACHE_TTL_SECONDS);
+        if !self.territories.is_empty() {
+            all_entries.retain(|entry| self.in_territories(&entry.oaci));
+            airfields.retain(|airfield| self.in_territories(&airfield.code));
+            let names: Vec<&str> = self.territories.iter().map(|t| t.name()).collect();
+            info!(
+                "🌍 Kept {} entries in {}",
+                all_entries.len(),
+                names.join(", ")
+            );
+        }
 
-        Ok(all_entries)
+        Ok(CachedOacisData {
+            entries: Arc::new(all_entries),
+            airfields: Arc::new(airfields),
+            fetched_at: Instant::now(),
+        })
     }
 
-    /// Download a PDF file for a VAC entry and return the file hash
-    fn download_pdf(&self, entry: &VacEntry) -> Result<(PathBuf, String)> {
-        let api_path = format!("{}/{}/{}", FILE_ENDPOINT, entry.oaci, entry.vac_type);
-        let url = format!("{}{}", API_BASE_URL, api_path);
+    /// Download a PDF file for a VAC entry
+    ///
+    /// When the stored file is intact, the request is conditional and the file
+    /// is kept if the server reports it unchanged. The body is written to a
+    /// staging file as it arrives, reporting the progress to the observer, and
+    /// replaces the stored file once checked.
+    fn download_pdf(&self, entry: &VacEntry, deadline: Option<Instant>) -> Result<PdfDownload> {
+        let api_path = SiaApiClient::file_path(&entry.oaci, &entry.vac_type);
 
-        // Generate both auth headers
-        let auth_header = AuthGenerator::generate_auth_header(&api_path, None);
-        let basic_auth = AuthGenerator::generate_basic_auth();
+        info!("Downloading {} ({})...", entry.oaci, entry.file_name);
 
-        println!("  Downloading {} ({})...", entry.oaci, entry.file_name);
+        // Forced downloads are never conditional
+        let cached_hash = self
+            .database
+            .get_cached_hash(&entry.oaci, &entry.vac_type)?
+            .filter(|_| !self.is_forced(entry))
+            .filter(|cached_hash| {
+                matches!(self.storage.hash(&entry.file_name, Some(cached_hash)), Ok(Some(hash)) if hash == *cached_hash)
+            });
+        let cached = match &cached_hash {
+            Some(_) => self.database.get_http_cache(&api_path)?,
+            None => None,
+        };
 
         let response = self
-            .client
-            .get(&url)
-            .header("AUTH", auth_header)
-            .header("Authorization", basic_auth)
-            .send()
+            .api
+            .get_file_with(&entry.oaci, &entry.vac_type, |request| {
+                conditional(request, cached.as_ref())
+            })
             .context(format!("Failed to download PDF for {}", entry.oaci))?;
 
+        if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            if let Some(hash) = cached_hash {
+                info!("✓ Unchanged on the server, keeping {}", entry.file_name);
+                return Ok(PdfDownload::NotModified(hash));
+            }
+        }
+
         if !response.status().is_success() {
-            anyhow::bail!("PDF download failed with status: {}", response.status());
+            return Err(status_error(response, true, |status| {
+                DownloadStatusError {
+                    what: "PDF",
+                    status,
+                }
+                .into()
+            }));
         }
 
-        let bytes = response.bytes().context("Failed to read PDF bytes")?;
+        let validators = response_validators(&response);
+        let total = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .or(u64::try_from(entry.file_size).ok().filter(|&size| size > 0));
+
+        // Stage local files next to their destination, so that they are moved in place
+        let staging_dir = match self.storage.local_path(&entry.file_name) {
+            Some(path) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => std::env::temp_dir(),
+        };
+        std::fs::create_dir_all(&staging_dir)
+            .context(format!("Failed to create {:?}", staging_dir))?;
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".vac-").suffix(".part");
+        // Stored files get the usual permissions rather than the private ones
+        // of temporary files
+        #[cfg(unix)]
+        builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o666));
+        let mut staging = builder
+            .tempfile_in(&staging_dir)
+            .context(format!("Failed to create a file in {:?}", staging_dir))?;
 
-        // Calculate hash of downloaded bytes
+        // Hash and check the bytes as they are received; the pages are only
+        // counted by `verify`, as parsing needs the whole PDF in memory
         let mut hasher = Sha256::new();
-        hasher.update(&bytes);
+        let mut check = pdf::StreamCheck::default();
+        let mut received = 0;
+        response
+            .read_chunks(deadline, self.cancellation.as_ref(), |chunk| {
+                staging.write_all(chunk)?;
+                hasher.update(chunk);
+                check.update(chunk);
+                received += chunk.len() as u64;
+                self.notify_progress(entry, received, total);
+                Ok(())
+            })
+            .context("Failed to read PDF bytes")?;
         let hash = format!("{:x}", hasher.finalize());
+        check
+            .finish()
+            .with_context(|| format!("Invalid PDF received for {}", entry.oaci))?;
 
-        // Save to file
-        let file_path = self.download_dir.join(&entry.file_name);
-        fs::write(&file_path, bytes).context(format!("Failed to write PDF to {:?}", file_path))?;
+        // Save to storage
+        self.archive_superseded(entry)?;
+        self.storage
+            .write_file(&entry.file_name, &staging.into_temp_path())
+            .context(format!("Failed to store PDF for {}", entry.oaci))?;
+
+        info!(
+            "✓ Saved to {} ({} bytes)",
+            self.storage.describe(&entry.file_name),
+            received
+        );
 
-        println!("  ✓ Saved to {:?} ({} bytes)", file_path, entry.file_size);
+        if let Some(validators) = validators {
+            self.database.set_http_cache(&api_path, &validators)?;
+        }
 
-        Ok((file_path, hash))
+        Ok(PdfDownload::Downloaded(hash, received))
     }
 
     /// Main sync operation: fetch, filter, cache, and download
     ///
+    /// The run is recorded in the sync history, including when it fails.
+    ///
     /// # Arguments
     /// * `oaci_filter` - Optional list of OACI codes to filter downloads. If None, all entries are processed.
     pub fn sync(&self, oaci_filter: Option<&[String]>) -> Result<SyncStats> {
-        let mut stats = SyncStats::default();
+        let _lock = self.lock()?;
+        self.record_sync_run(API_SOURCE, || self.sync_from_api(oaci_filter))
+    }
 
+    /// Sync against the SIA API
+    fn sync_from_api(&self, oaci_filter: Option<&[String]>) -> Result<SyncStats> {
         // Check if database is empty
         let is_first_run = self
             .database
@@ -225,45 +1130,214 @@ impl VacDownloader {
             .context("Failed to check database status")?;
 
         if is_first_run {
-            println!("📦 First run detected - database is empty");
-            println!("   Will download ALL AD entries\n");
+            info!("📦 First run detected - database is empty");
+            info!("Will download ALL AD entries");
         } else {
             let (count, oldest, newest) = self.database.get_stats()?;
-            println!("📊 Database contains {} cached entries", count);
-            println!("   Oldest: {}", oldest);
-            println!("   Newest: {}\n", newest);
+            info!("📊 Database contains {} cached entries", count);
+            info!("Oldest: {}", oldest);
+            info!("Newest: {}", newest);
         }
 
+        let mut run = ApiSync {
+            started: Instant::now(),
+            is_first_run,
+            stats: SyncStats::default(),
+            claimed_names: HashMap::new(),
+            downloaded: HashSet::new(),
+            breaker: CircuitBreaker::new(self.failure_threshold),
+            tripped: None,
+        };
+
         // Fetch all OACIS data
-        println!("🌐 Fetching OACIS data from API...");
-        let mut entries = self.fetch_oacis_data()?;
+        info!("🌐 Fetching OACIS data from API...");
+        if self.stream_listing && self.cached_oacis_listing().is_none() {
+            self.sync_listing_pages(oaci_filter, &mut run)?;
+        } else {
+            let listing = self.fetch_oacis_listing_for(oaci_filter)?;
+            if let Some(observer) = &self.observer {
+                observer.listing_fetched(listing.entries.len());
+            }
+            self.database
+                .upsert_airfields(&listing.airfields)
+                .context("Failed to store airfield details")?;
+            let mut entries: Vec<&VacEntry> = listing.entries.iter().collect();
 
-        // Filter by OACI codes if specified
-        if let Some(codes) = oaci_filter {
-            let original_count = entries.len();
-            let codes_upper: Vec<String> = codes.iter().map(|c| c.to_uppercase()).collect();
-            entries.retain(|entry| codes_upper.contains(&entry.oaci.to_uppercase()));
-
-            println!("\n🔍 Filtering by OACI codes: {}", codes_upper.join(", "));
-            println!(
-                "   Matched {} out of {} total entries",
-                entries.len(),
-                original_count
-            );
+            // Filter by OACI codes if specified
+            if let Some(codes) = oaci_filter {
+                let original_count = entries.len();
+                let codes_upper: Vec<String> = codes.iter().map(|c| c.to_uppercase()).collect();
+                matching::warn_unmatched_codes(&codes_upper, &listing.entries);
+                entries.retain(|entry| codes_upper.contains(&entry.oaci.to_uppercase()));
 
-            if entries.is_empty() {
-                println!("\n⚠️  No entries found matching the specified OACI codes");
-                return Ok(stats);
+                info!("🔍 Filtering by OACI codes: {}", codes_upper.join(", "));
+                info!(
+                    "Matched {} out of {} total entries",
+                    entries.len(),
+                    original_count
+                );
+
+                if entries.is_empty() {
+                    warn!("⚠️  No entries found matching the specified OACI codes");
+                    return Ok(run.stats);
+                }
             }
+
+            run.stats.total_entries = entries.len();
+            self.sync_entries(entries.into_iter().cloned(), oaci_filter, &mut run)?;
         }
 
-        stats.total_entries = entries.len();
+        let ApiSync {
+            mut stats,
+            downloaded,
+            breaker,
+            tripped,
+            ..
+        } = run;
+        if let Some((failure, remaining)) = tripped {
+            return Err(circuit_open_error(failure, breaker.count(), remaining));
+        }
+
+        stats.evicted = self.enforce_max_cache_size(&downloaded)?.len();
+        self.collect_previous_versions(false)?;
+
+        if self.include_supplements {
+            self.sync_supplements(&mut stats)?;
+        }
+
+        // Keep an existing mirror manifest, HTML index, checksum manifest and
+        // text index in step with the cache
+        if self.storage.exists(MIRROR_MANIFEST_NAME)? {
+            self.write_mirror_manifest()?;
+        }
+        if self.storage.exists(HTML_INDEX_NAME)? {
+            self.write_html_index()?;
+        }
+        if self.storage.exists(CHECKSUMS_NAME)? {
+            self.write_checksums()?;
+        }
+        if !self.database.get_text_index()?.is_empty() {
+            self.index_text()?;
+        }
+
+        stats.log_summary();
+
+        Ok(stats)
+    }
+
+    /// Sync the entries of each page of the listing as soon as it is fetched,
+    /// without holding the whole listing in memory
+    ///
+    /// The listing is not cached. The sync stops fetching pages once it is
+    /// cancelled, runs out of time or trips the circuit breaker.
+    fn sync_listing_pages(&self, oaci_filter: Option<&[String]>, run: &mut ApiSync) -> Result<()> {
+        let mut codes = server_filter_codes(oaci_filter).unwrap_or_default();
+        let mut processed = HashSet::new();
+        let mut fetched = 0;
+        let mut page = 1;
+        loop {
+            let response = match self.fetch_oacis_page(page, &codes) {
+                Ok(response) => response,
+                Err(e) if page == 1 && !codes.is_empty() => {
+                    warn!(
+                        "⚠️  Failed to fetch a filtered listing ({:#}), fetching the whole listing",
+                        e
+                    );
+                    codes.clear();
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            fetched += response.members.len();
+            let last = response.members.is_empty() || fetched >= response.total_items as usize;
+
+            let mut airfields = response.members;
+            airfields.retain(|airfield| self.in_territories(&airfield.code));
+            self.database
+                .upsert_airfields(&airfields)
+                .context("Failed to store airfield details")?;
+            let entries: Vec<VacEntry> = airfields
+                .iter()
+                .flat_map(VacEntry::from_oacis_entry)
+                .filter(|entry| {
+                    oaci_filter.is_none_or(|codes| {
+                        codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci))
+                    })
+                })
+                .collect();
+            info!("Page {}: {} AD entries to process", page, entries.len());
+            if let Some(observer) = &self.observer {
+                observer.listing_fetched(entries.len());
+            }
+            run.stats.total_entries += entries.len();
+            processed.extend(
+                entries
+                    .iter()
+                    .map(|entry| (entry.oaci.clone(), entry.vac_type.clone())),
+            );
+
+            // The downloads queued for the airfields of the page are drained with it
+            let page_codes: Vec<String> = airfields
+                .into_iter()
+                .map(|airfield| airfield.code)
+                .collect();
+            self.sync_entries(entries.into_iter(), Some(&page_codes), run)?;
+            if run.stopped() {
+                return Ok(());
+            }
+            if last {
+                break;
+            }
+            page += 1;
+        }
+
+        // Without a filter, the whole listing was processed: drop the downloads
+        // queued for charts that left it
+        if oaci_filter.is_none() {
+            let queued: Vec<PendingDownload> = self
+                .database
+                .get_pending_downloads(Some(API_SOURCE))?
+                .into_iter()
+                .filter(|download| {
+                    processed
+                        .contains(&(download.entry.oaci.clone(), download.entry.vac_type.clone()))
+                })
+                .collect();
+            self.database
+                .replace_pending_downloads(API_SOURCE, None, &queued)
+                .context("Failed to update the download queue")?;
+        }
+        Ok(())
+    }
 
-        println!("\n🔍 Checking for updates...");
+    /// Check a batch of listing entries against the cache and download the
+    /// ones needing it, with the queued downloads within `queue_filter`
+    ///
+    /// Downloads are committed to the cache before returning, and counted in
+    /// the statistics of the run.
+    fn sync_entries(
+        &self,
+        entries: impl Iterator<Item = VacEntry>,
+        queue_filter: Option<&[String]>,
+        run: &mut ApiSync,
+    ) -> Result<()> {
+        let stats = &mut run.stats;
+        let is_first_run = run.is_first_run;
+
+        info!("🔍 Checking for updates...");
 
+        // Entries to write back to the cache, committed in a single transaction
+        let mut updated_entries = Vec::new();
+        let mut pending = Vec::new();
+        let mut processed = Vec::new();
         // Process each entry
         for mut entry in entries {
-            let needs_version_update = if is_first_run {
+            let _span = info_span!("entry", oaci = %entry.oaci).entered();
+            processed.push((entry.oaci.clone(), entry.vac_type.clone()));
+            // Resolve where the PDF is stored
+            entry.file_name = self.resolve_file_name(&entry, &mut run.claimed_names)?;
+
+            let needs_version_update = if is_first_run || self.is_forced(&entry) {
                 true
             } else {
                 self.database
@@ -271,251 +1345,4646 @@ impl VacDownloader {
                     .context(format!("Failed to check update status for {}", entry.oaci))?
             };
 
+            if needs_version_update && !is_first_run && self.is_pinned(&entry)? {
+                stats.up_to_date += 1;
+                stats.record_result(&entry, EntryOutcome::UpToDate);
+                continue;
+            }
+
             let mut needs_download = needs_version_update;
 
             // If no version update needed, verify file integrity
             if !needs_version_update && !is_first_run {
-                let file_path = self.download_dir.join(&entry.file_name);
-
-                if file_path.exists() {
-                    // File exists, verify hash
-                    match Self::calculate_file_hash(&file_path) {
-                        Ok(current_hash) => {
-                            if let Ok(Some(cached_hash)) =
-                                self.database.get_cached_hash(&entry.oaci, &entry.vac_type)
-                            {
-                                if current_hash != cached_hash {
-                                    println!("  ⚠️  Hash mismatch for {} - file corrupted, redownloading", entry.oaci);
-                                    needs_download = true;
-                                    stats.redownloaded_corrupted += 1;
-                                } else {
-                                    stats.verified += 1;
-                                }
+                let relocated = self.relocate_stored_file(&mut entry);
+
+                // Verify hash of the stored file
+                let cached_hash = self
+                    .database
+                    .get_cached_hash(&entry.oaci, &entry.vac_type)
+                    .ok()
+                    .flatten();
+                match self.storage.hash(&entry.file_name, cached_hash.as_deref()) {
+                    Ok(Some(current_hash)) => {
+                        if let Some(cached_hash) = cached_hash {
+                            if current_hash != cached_hash {
+                                warn!(
+                                    "⚠️  Hash mismatch for {} - file corrupted, redownloading",
+                                    entry.oaci
+                                );
+                                self.notify_verified(&entry, Some("hash mismatch"));
+                                needs_download = true;
+                                stats.redownloaded_corrupted += 1;
                             } else {
-                                // No hash in database, calculate and store it
-                                entry.file_hash = Some(current_hash);
-                                let _ = self.database.upsert_entry(&entry);
+                                self.notify_verified(&entry, None);
                                 stats.verified += 1;
+                                stats.record_result(&entry, EntryOutcome::Verified);
+                                if relocated {
+                                    entry.file_hash = Some(cached_hash);
+                                    updated_entries.push(entry.clone());
+                                }
                             }
-                        }
-                        Err(e) => {
-                            eprintln!("  ✗ Failed to calculate hash for {}: {}", entry.oaci, e);
-                            stats.verified += 1; // Count as verified even if hash calc failed
+                        } else {
+                            // No hash in database, calculate and store it
+                            self.notify_verified(&entry, None);
+                            entry.file_hash = Some(current_hash);
+                            updated_entries.push(entry.clone());
+                            stats.verified += 1;
+                            stats.record_result(&entry, EntryOutcome::Verified);
                         }
                     }
-                } else {
-                    // File missing, redownload
-                    println!("  ⚠️  File missing for {} - redownloading", entry.oaci);
-                    needs_download = true;
-                    stats.redownloaded_corrupted += 1;
-                }
-            }
-
-            if needs_download {
-                stats.to_download += 1;
-
-                // Download the PDF
-                match self.download_pdf(&entry) {
-                    Ok((_path, hash)) => {
-                        // Update entry with hash
-                        entry.file_hash = Some(hash);
-
-                        // Update cache
-                        self.database
-                            .upsert_entry(&entry)
-                            .context(format!("Failed to update cache for {}", entry.oaci))?;
-                        stats.downloaded += 1;
+                    Ok(None) => {
+                        // File missing, redownload
+                        warn!("⚠️  File missing for {} - redownloading", entry.oaci);
+                        self.notify_verified(&entry, Some("file missing"));
+                        needs_download = true;
+                        stats.redownloaded_corrupted += 1;
                     }
                     Err(e) => {
-                        eprintln!("  ✗ Failed to download {}: {}", entry.oaci, e);
-                        stats.failed += 1;
+                        error!("✗ Failed to calculate hash for {}: {}", entry.oaci, e);
+                        stats.verified += 1; // Count as verified even if hash calc failed
+                        stats.record_result(&entry, EntryOutcome::Verified);
                     }
                 }
+            }
+
+            if needs_download {
+                pending.push(entry);
             } else if !needs_version_update {
                 stats.up_to_date += 1;
             }
         }
 
-        println!("\n✅ Sync complete!");
-        println!("   Total entries: {}", stats.total_entries);
-        println!("   Up to date: {}", stats.up_to_date);
-        println!("   Verified: {}", stats.verified);
-        println!("   Downloaded: {}", stats.downloaded);
-        println!(
-            "   Redownloaded (corrupted/missing): {}",
-            stats.redownloaded_corrupted
+        let pending = self.queue_downloads(API_SOURCE, queue_filter, &processed, pending)?;
+        let offset = stats.to_download;
+        stats.to_download += pending.len();
+        self.check_available_space(&pending)?;
+
+        let queued = pending.len();
+        for (index, mut entry) in pending.into_iter().enumerate() {
+            if self.budget_exhausted(run.started, queued - index) {
+                stats.deferred = queued - index;
+                break;
+            }
+            let _span = info_span!("entry", oaci = %entry.oaci).entered();
+            if self.skip_queued(&entry, stats)? {
+                continue;
+            }
+            self.notify_started(&entry, offset + index, stats.to_download);
+            // Download the PDF
+            let download_started = Instant::now();
+            let result = self.download_pdf(&entry, self.download_deadline(run.started));
+            let elapsed = download_started.elapsed();
+            if result.is_err() && self.budget_exhausted(run.started, queued - index) {
+                stats.deferred = queued - index;
+                break;
+            }
+            self.notify_finished(&entry, result.as_ref().err());
+            if let Some(failure) = run.breaker.record(result.as_ref().err()) {
+                run.tripped = Some((failure, queued - index - 1));
+            }
+            match result {
+                Ok(PdfDownload::Downloaded(hash, size)) => {
+                    // Update entry with hash
+                    entry.file_hash = Some(hash);
+                    self.record_downloaded(&entry, size, elapsed, None, stats)?;
+                    run.downloaded.insert(entry.oaci.clone());
+                    updated_entries.push(entry);
+                }
+                Ok(PdfDownload::NotModified(hash)) => {
+                    entry.file_hash = Some(hash);
+                    stats.up_to_date += 1;
+                    stats.record_result(&entry, EntryOutcome::UpToDate);
+                    updated_entries.push(entry);
+                }
+                Err(e) => self.record_failed(&entry, &e, elapsed, stats),
+            }
+            if run.tripped.is_some() {
+                break;
+            }
+        }
+
+        // Update cache
+        self.database
+            .complete_downloads(&updated_entries)
+            .context("Failed to update cache")
+    }
+
+    /// Download the AIP supplements affecting cached airfields, and remove the
+    /// ones that no longer apply
+    fn sync_supplements(&self, stats: &mut SyncStats) -> Result<()> {
+        let _span = info_span!("supplements").entered();
+        info!("📑 Fetching AIP supplements...");
+        let listing_started = Instant::now();
+        let listed = match self.api.list_supplements() {
+            Ok(listed) => listed,
+            Err(e) => {
+                error!("✗ Failed to fetch AIP supplements: {:#}", e);
+                stats.record_failure("SUP AIP", "SUP", &e, listing_started.elapsed());
+                return Ok(());
+            }
+        };
+
+        let airfields: HashSet<String> = self
+            .database
+            .get_all_entries()?
+            .into_iter()
+            .map(|entry| entry.oaci.to_uppercase())
+            .collect();
+        let now = chrono::Utc::now();
+        let relevant: Vec<Supplement> = listed
+            .iter()
+            .filter(|entry| {
+                entry
+                    .aerodromes
+                    .iter()
+                    .any(|oaci| airfields.contains(&oaci.to_uppercase()))
+            })
+            .filter(|entry| {
+                // Keep supplements whose end of validity cannot be parsed
+                entry
+                    .valid_until
+                    .as_deref()
+                    .and_then(|until| chrono::DateTime::parse_from_rfc3339(until).ok())
+                    .is_none_or(|until| until > now)
+            })
+            .map(Supplement::from_supplement_entry)
+            .collect();
+        info!(
+            "{} supplement(s) affect cached airfields, out of {} published",
+            relevant.len(),
+            listed.len()
         );
-        println!("   Failed: {}", stats.failed);
 
-        Ok(stats)
+        let cached: HashMap<String, Supplement> = self
+            .database
+            .get_supplements()?
+            .into_iter()
+            .map(|supplement| (supplement.number.clone(), supplement))
+            .collect();
+
+        for mut supplement in relevant.iter().cloned() {
+            if let Some(cached) = cached.get(&supplement.number) {
+                let intact = cached.file_hash.is_some()
+                    && matches!(self.storage.hash(&cached.file_name, cached.file_hash.as_deref()), Ok(hash) if hash == cached.file_hash);
+                if intact && cached.remote_id == supplement.remote_id {
+                    continue;
+                }
+            }
+
+            info!(
+                "Downloading {} ({})...",
+                supplement.number, supplement.title
+            );
+            let download_started = Instant::now();
+            let bytes = match self.api.get_supplement_file(supplement.remote_id) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!("✗ Failed to download {}: {:#}", supplement.number, e);
+                    stats.record_failure(&supplement.number, "SUP", &e, download_started.elapsed());
+                    continue;
+                }
+            };
+            self.storage
+                .write(&supplement.file_name, &bytes)
+                .context(format!("Failed to store {}", supplement.number))?;
+            supplement.file_hash = Some(format!("{:x}", Sha256::digest(&bytes)));
+            self.database.upsert_supplement(&supplement)?;
+            info!(
+                "✓ Saved to {}",
+                self.storage.describe(&supplement.file_name)
+            );
+            stats.supplements_downloaded += 1;
+            stats.bytes_downloaded += bytes.len() as u64;
+        }
+
+        // Withdrawn, expired or no longer relevant
+        let relevant_numbers: HashSet<&str> = relevant
+            .iter()
+            .map(|supplement| supplement.number.as_str())
+            .collect();
+        for supplement in cached.values() {
+            if relevant_numbers.contains(supplement.number.as_str()) {
+                continue;
+            }
+            if let Err(e) = self.storage.delete(&supplement.file_name) {
+                warn!("⚠️  Failed to remove {}: {}", supplement.file_name, e);
+            }
+            self.database.delete_supplement(&supplement.number)?;
+            self.audit(vec![AuditEntry::file(
+                AuditAction::Delete,
+                &supplement.file_name,
+                format!("AIP supplement {} withdrawn or expired", supplement.number),
+            )]);
+            info!("🗑️  Removed {} ({})", supplement.number, supplement.title);
+            stats.supplements_removed += 1;
+        }
+
+        Ok(())
     }
 
-    /// Get a list of all remotely available VACs with local availability status
+    /// Sync from a mirror manifest instead of the SIA API
     ///
-    /// # Arguments
-    /// * `oaci_filter` - Optional list of OACI codes to filter results. If None, all entries are returned.
+    /// Reads `manifest.json` from the mirror, compares versions and hashes with
+    /// the local cache, and downloads only the charts that changed. Downloaded
+    /// files are verified against the hash published in the manifest.
     ///
-    /// # Returns
-    /// A vector of VacEntry containing remote VAC information and local availability
-    pub fn list_vacs(&self, oaci_filter: Option<&[String]>) -> Result<Vec<VacEntry>> {
-        println!("🌐 Fetching OACIS data from API...");
-        let mut entries = self.fetch_oacis_data()?;
+    /// # Arguments
+    /// * `mirror_url` - Base URL of the mirrored download directory
+    /// * `oaci_filter` - Optional list of OACI codes to filter downloads. If None, all entries are processed.
+    pub fn sync_from_mirror(
+        &self,
+        mirror_url: &str,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<SyncStats> {
+        let _lock = self.lock()?;
+        self.record_sync_run(mirror_url, || {
+            self.sync_mirror_entries(mirror_url, oaci_filter)
+        })
+    }
+
+    /// Sync against a mirror manifest
+    fn sync_mirror_entries(
+        &self,
+        mirror_url: &str,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<SyncStats> {
+        let started = Instant::now();
+        let mut stats = SyncStats::default();
+
+        // Make sure relative file names resolve inside the mirror directory
+        let mut base_url = reqwest::Url::parse(mirror_url)
+            .context(format!("Invalid mirror URL: {}", mirror_url))?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        info!("🪞 Fetching mirror manifest from {}...", base_url);
+        let manifest_url = base_url.join(MIRROR_MANIFEST_NAME)?;
+        let response = self
+            .api
+            .transport()
+            .get(HttpRequest::get(manifest_url.as_str()))
+            .context("Failed to fetch mirror manifest")?;
+        debug!(url = %manifest_url, status = %response.status(), "GET");
+        if !response.status().is_success() {
+            let status = debug_error_response(response);
+            anyhow::bail!("Mirror returned error status: {}", status);
+        }
+        let body = response.bytes().context("Failed to read mirror manifest")?;
+        let manifest: MirrorManifest =
+            serde_json::from_slice(&body).context("Failed to parse mirror manifest")?;
+        let mut entries = manifest.entries;
+        info!("Mirror lists {} entries", entries.len());
+        if let Some(observer) = &self.observer {
+            observer.listing_fetched(entries.len());
+        }
 
+        entries.retain(|entry| self.in_territories(&entry.oaci));
         // Filter by OACI codes if specified
         if let Some(codes) = oaci_filter {
-            let original_count = entries.len();
-            let codes_upper: Vec<String> = codes.iter().map(|c| c.to_uppercase()).collect();
-            entries.retain(|entry| codes_upper.contains(&entry.oaci.to_uppercase()));
-
-            println!("\n🔍 Filtering by OACI codes: {}", codes_upper.join(", "));
-            println!(
-                "   Matched {} out of {} total entries",
-                entries.len(),
-                original_count
+            entries.retain(|entry| codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci)));
+            info!("Matched {} entries", entries.len());
+        }
+
+        stats.total_entries = entries.len();
+
+        info!("🔍 Checking for updates...");
+
+        let mut updated_entries = Vec::new();
+        let mut pending = Vec::new();
+        let mut processed = Vec::new();
+        let mut claimed_names = HashMap::new();
+
+        for mirror_entry in entries {
+            let _span = info_span!("entry", oaci = %mirror_entry.oaci).entered();
+            processed.push((mirror_entry.oaci.clone(), mirror_entry.vac_type.clone()));
+            let cached_version = self
+                .database
+                .get_cached_version(&mirror_entry.oaci, &mirror_entry.vac_type)?;
+            let cached_hash = self
+                .database
+                .get_cached_hash(&mirror_entry.oaci, &mirror_entry.vac_type)?;
+            let cached = cached_version.clone().map(|version| LocalChart {
+                oaci: mirror_entry.oaci.clone(),
+                vac_type: mirror_entry.vac_type.clone(),
+                version,
+                file_hash: cached_hash,
+            });
+            // A cached chart of unknown hash cannot be trusted to match the mirror
+            let current = cached
+                .as_ref()
+                .is_some_and(|cached| cached.file_hash.is_some())
+                && diff::change_reason(&mirror_entry, cached.as_ref()).is_none();
+
+            let remote_name = mirror_entry.file_name.clone();
+            let mut entry = VacEntry::from(mirror_entry);
+            entry.remote_file_name = Some(remote_name.clone());
+            entry.file_name = self.resolve_file_name(&entry, &mut claimed_names)?;
+
+            if current && !self.is_forced(&entry) {
+                let relocated = self.relocate_stored_file(&mut entry);
+                if self.storage.exists(&entry.file_name)? {
+                    stats.up_to_date += 1;
+                    stats.record_result(&entry, EntryOutcome::UpToDate);
+                    if relocated {
+                        updated_entries.push(entry);
+                    }
+                    continue;
+                }
+            }
+            if cached_version.is_some()
+                && is_outdated(cached_version.as_deref(), &entry.version)
+                && self.is_pinned(&entry)?
+            {
+                stats.up_to_date += 1;
+                stats.record_result(&entry, EntryOutcome::UpToDate);
+                continue;
+            }
+
+            pending.push(entry);
+        }
+
+        let pending = self.queue_downloads(base_url.as_str(), oaci_filter, &processed, pending)?;
+        stats.to_download = pending.len();
+        self.check_available_space(&pending)?;
+
+        let mut downloaded = HashSet::new();
+        let mut breaker = CircuitBreaker::new(self.failure_threshold);
+        let mut tripped = None;
+        for (index, mut entry) in pending.into_iter().enumerate() {
+            if self.budget_exhausted(started, stats.to_download - index) {
+                stats.deferred = stats.to_download - index;
+                break;
+            }
+            let _span = info_span!("entry", oaci = %entry.oaci).entered();
+            if self.skip_queued(&entry, &mut stats)? {
+                continue;
+            }
+            let remote_name = entry.remote_file_name.clone().unwrap_or_default();
+            info!(
+                "Downloading {} ({}) from mirror...",
+                entry.oaci, remote_name
             );
+            self.notify_started(&entry, index, stats.to_download);
 
-            if entries.is_empty() {
-                println!("\n⚠️  No entries found matching the specified OACI codes");
-                return Ok(entries);
+            let download_started = Instant::now();
+            // Storage errors fail the chart like download errors, as in the API sync
+            let result = self
+                .fetch_from_mirror(
+                    &base_url,
+                    &remote_name,
+                    entry.file_hash.as_deref(),
+                    self.download_deadline(started),
+                )
+                .and_then(|(bytes, pages)| {
+                    entry.page_count = Some(pages);
+                    self.archive_superseded(&entry)?;
+                    self.storage
+                        .write(&entry.file_name, &bytes)
+                        .context(format!("Failed to store PDF for {}", entry.oaci))?;
+                    info!(
+                        "✓ Saved to {} ({} bytes)",
+                        self.storage.describe(&entry.file_name),
+                        bytes.len()
+                    );
+                    Ok(bytes.len() as u64)
+                });
+            let elapsed = download_started.elapsed();
+            if result.is_err() && self.budget_exhausted(started, stats.to_download - index) {
+                stats.deferred = stats.to_download - index;
+                break;
+            }
+            let result = result.and_then(|size| {
+                self.record_downloaded(&entry, size, elapsed, Some("a mirror"), &mut stats)
+            });
+            self.notify_finished(&entry, result.as_ref().err());
+            if let Some(failure) = breaker.record(result.as_ref().err()) {
+                tripped = Some((failure, stats.to_download - index - 1));
+            }
+            match result {
+                Ok(()) => {
+                    downloaded.insert(entry.oaci.clone());
+                    updated_entries.push(entry);
+                }
+                Err(e) => self.record_failed(&entry, &e, elapsed, &mut stats),
+            }
+            if tripped.is_some() {
+                break;
             }
         }
 
-        println!("\n🔍 Checking local availability...");
+        self.database
+            .complete_downloads(&updated_entries)
+            .context("Failed to update cache")?;
+        if let Some((failure, remaining)) = tripped {
+            return Err(circuit_open_error(failure, breaker.count(), remaining));
+        }
+
+        stats.evicted = self.enforce_max_cache_size(&downloaded)?.len();
+        self.collect_previous_versions(false)?;
 
-        // Check local availability for each entry
-        for entry in &mut entries {
-            entry.available_locally = self.database.has_entry(&entry.oaci).unwrap_or(false);
+        // Keep an existing HTML index, checksum manifest and text index in step with the cache
+        if self.storage.exists(HTML_INDEX_NAME)? {
+            self.write_html_index()?;
+        }
+        if self.storage.exists(CHECKSUMS_NAME)? {
+            self.write_checksums()?;
+        }
+        if !self.database.get_text_index()?.is_empty() {
+            self.index_text()?;
         }
 
-        let local_count = entries.iter().filter(|e| e.available_locally).count();
-        println!(
-            "   {} out of {} entries are available locally",
-            local_count,
-            entries.len()
-        );
+        stats.log_summary();
 
-        Ok(entries)
+        Ok(stats)
     }
 
-    /// Check if a VAC entry needs an update
-    ///
-    /// # Arguments
-    /// * `oaci` - OACI code of the entry to check
+    /// Persist the downloads found by a diff and return the queue to drain
     ///
-    /// # Returns
-    /// `Ok(true)` if the entry needs an update, `Ok(false)` if it's up to date,
-    /// or an error if the check fails
-    pub fn needs_update(&self, oaci: &str) -> Result<bool> {
-        // Fetch remote entries to get the latest version
-        let entries = self.fetch_oacis_data()?;
-
-        // Find the entry for this OACI code
-        let remote_entry = entries
-            .iter()
-            .find(|e| e.oaci.eq_ignore_ascii_case(oaci))
-            .ok_or_else(|| anyhow::anyhow!("OACI code {} not found in remote data", oaci))?;
+    /// The queued downloads of `source` for the processed entries are replaced
+    /// by `pending`. The returned queue is ordered by priority (favorites first)
+    /// and restricted to `oaci_filter`.
+    fn queue_downloads(
+        &self,
+        source: &str,
+        oaci_filter: Option<&[String]>,
+        processed: &[(String, String)],
+        pending: Vec<VacEntry>,
+    ) -> Result<Vec<VacEntry>> {
+        let downloads: Vec<PendingDownload> = pending
+            .into_iter()
+            .map(|entry| PendingDownload {
+                priority: self.download_priority(&entry),
+                entry,
+                source: source.to_string(),
+                attempts: 0,
+                last_error: None,
+            })
+            .collect();
 
-        // Check if it needs an update using the database method
+        // Without a filter, the whole listing was processed
+        let scope = oaci_filter.map(|_| processed);
         self.database
-            .needs_update(remote_entry)
-            .context(format!("Failed to check update status for {}", oaci))
+            .replace_pending_downloads(source, scope, &downloads)
+            .context("Failed to update the download queue")?;
+
+        let mut queue: Vec<VacEntry> = self
+            .database
+            .get_pending_downloads(Some(source))
+            .context("Failed to read the download queue")?
+            .into_iter()
+            .map(|download| download.entry)
+            .collect();
+        if let Some(codes) = oaci_filter {
+            queue.retain(|entry| codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci)));
+        }
+        Ok(queue)
     }
 
-    /// Delete a VAC entry from the cache and remove the PDF file
+    /// Check whether a sync was cancelled or its time budget is exhausted
     ///
     /// # Arguments
-    /// * `oaci` - OACI code of the entry to delete
-    pub fn delete(&self, oaci: &str) -> Result<DeleteResult> {
-        let mut result = DeleteResult {
-            oaci: oaci.to_string(),
-            database_deleted: false,
-            file_deleted: false,
-            file_name: None,
-        };
-
-        // Delete from database
-        match self.database.delete_entry(oaci) {
-            Ok(Some(file_name)) => {
-                result.database_deleted = true;
-                result.file_name = Some(file_name.clone());
-
-                // Delete the PDF file
-                let file_path = self.download_dir.join(&file_name);
-                if file_path.exists() {
-                    match fs::remove_file(&file_path) {
-                        Ok(_) => {
-                            result.file_deleted = true;
-                            println!("✓ Deleted {} from database and filesystem", oaci);
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "✗ Deleted {} from database but failed to delete file: {}",
-                                oaci, e
-                            );
-                        }
-                    }
-                } else {
-                    println!(
-                        "✓ Deleted {} from database (file was already missing)",
-                        oaci
-                    );
-                }
-            }
-            Ok(None) => {
-                println!("⚠️  Entry {} (AD) not found in database", oaci);
-            }
-            Err(e) => {
-                anyhow::bail!("Failed to delete entry from database: {}", e);
+    /// * `started` - Start of the sync
+    /// * `remaining` - Number of downloads not started yet, for the message
+    fn budget_exhausted(&self, started: Instant, remaining: usize) -> bool {
+        if self.is_cancelled() {
+            warn!(
+                "🛑 Sync cancelled, {} download(s) left in the queue",
+                remaining
+            );
+            return true;
+        }
+        match self.max_duration {
+            Some(max_duration) if started.elapsed() >= max_duration => {
+                warn!(
+                    "⏱️  Time budget of {}s exhausted, {} download(s) left in the queue",
+                    max_duration.as_secs(),
+                    remaining
+                );
+                true
             }
+            _ => false,
         }
+    }
 
-        Ok(result)
+    /// Check whether the token of [`VacDownloader::with_cancellation`] was cancelled
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
     }
 
-    /// Get the PDF file path for a given OACI code
-    ///
-    /// # Arguments
-    /// * `oaci` - OACI code of the entry
-    ///
-    /// # Returns
-    /// The full path to the PDF file if it exists locally, or an error if not found
-    pub fn get_pdf_path(&self, oaci: &str) -> Result<PathBuf> {
-        // Check if the entry exists in the database
-        let file_name = self
+    /// Deadline of a download starting now, from the file timeout and the
+    /// time budget of the sync started at `started`
+    fn download_deadline(&self, started: Instant) -> Option<Instant> {
+        let file_deadline = self.file_timeout.map(|timeout| Instant::now() + timeout);
+        let sync_deadline = self.max_duration.map(|budget| started + budget);
+        match (file_deadline, sync_deadline) {
+            (Some(file), Some(sync)) => Some(file.min(sync)),
+            (file, sync) => file.or(sync),
+        }
+    }
+
+    /// Check whether the cached chart of an entry is pinned to another version
+    /// by a rollback, and must be kept unless forced
+    fn is_pinned(&self, entry: &VacEntry) -> Result<bool> {
+        if self.is_forced(entry) {
+            return Ok(false);
+        }
+        match self
             .database
-            .get_file_name(oaci)
-            .context(format!("Failed to query database for {}", oaci))?
-            .ok_or_else(|| anyhow::anyhow!("OACI code {} not found in database", oaci))?;
+            .get_pinned_version(&entry.oaci, &entry.vac_type)?
+        {
+            Some(pinned) if pinned != entry.version => {
+                info!(
+                    "📌 {} is pinned to version {} by a rollback, skipping version {} \
+                     (sync --force-oaci {} to update)",
+                    entry.oaci, pinned, entry.version, entry.oaci
+                );
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 
-        // Construct the full path
-        let file_path = self.download_dir.join(&file_name);
+    /// Check whether enough downloads of the entry failed in a row for it to
+    /// be skipped, unless forced or retried
+    fn is_quarantined(&self, entry: &VacEntry) -> Result<bool> {
+        if self.quarantine_threshold == 0 || self.retry_quarantined || self.is_forced(entry) {
+            return Ok(false);
+        }
+        let failures =
+            self.database
+                .get_chart_failures(&entry.oaci, &entry.vac_type, &entry.version)?;
+        if failures < self.quarantine_threshold {
+            return Ok(false);
+        }
+        warn!(
+            "⚠️  {} {} is quarantined after {} failed downloads in a row, skipping \
+             (sync --retry-quarantined to try again)",
+            entry.oaci, entry.vac_type, failures
+        );
+        Ok(true)
+    }
 
-        // Verify the file exists
-        if !file_path.exists() {
-            anyhow::bail!("PDF file for {} not found at {:?}", oaci, file_path);
+    /// Check whether the server answered a download of the entry with a 404
+    /// recently enough for it to be skipped, unless forced
+    fn is_known_missing(&self, entry: &VacEntry) -> Result<bool> {
+        let Some(period) = self.skip_not_found else {
+            return Ok(false);
+        };
+        if self.is_forced(entry)
+            || !self.database.is_missing_chart(
+                &entry.oaci,
+                &entry.vac_type,
+                &entry.version,
+                period,
+            )?
+        {
+            return Ok(false);
         }
+        info!(
+            "⏭️  Skipping {}, not found on the server in a recent sync",
+            entry.oaci
+        );
+        Ok(true)
+    }
 
-        Ok(file_path)
+    /// Check whether an entry must be downloaded again regardless of the cache
+    fn is_forced(&self, entry: &VacEntry) -> bool {
+        self.force_all
+            || self
+                .force_oacis
+                .iter()
+                .any(|oaci| oaci.eq_ignore_ascii_case(&entry.oaci))
     }
-}
 
-/// Statistics from a sync operation
-#[derive(Debug, Default)]
-pub struct SyncStats {
-    pub total_entries: usize,
-    pub to_download: usize,
-    pub downloaded: usize,
-    pub failed: usize,
-    pub up_to_date: usize,
-    pub verified: usize,
-    pub redownloaded_corrupted: usize,
-}
+    /// Priority of an entry in the download queue
+    fn download_priority(&self, entry: &VacEntry) -> i64 {
+        if self
+            .favorites
+            .iter()
+            .any(|oaci| oaci.eq_ignore_ascii_case(&entry.oaci))
+        {
+            1
+        } else {
+            0
+        }
+    }
 
-/// Result from a delete operation
-#[derive(Debug)]
-pub struct DeleteResult {
+    /// Skip a queued entry that is quarantined or known to be missing on the server
+    fn skip_queued(&self, entry: &VacEntry, stats: &mut SyncStats) -> Result<bool> {
+        if self.is_quarantined(entry)? {
+            stats.quarantined += 1;
+            stats.record_result(entry, EntryOutcome::Quarantined);
+            return Ok(true);
+        }
+        if self.is_known_missing(entry)? {
+            stats.skipped_not_found += 1;
+            stats.record_result(entry, EntryOutcome::Skipped);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Run the post-download hook, audit and count a downloaded entry
+    ///
+    /// `source` names where the file came from when it is not the SIA API.
+    fn record_downloaded(
+        &self,
+        entry: &VacEntry,
+        size: u64,
+        elapsed: Duration,
+        source: Option<&str>,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        let previous_version = self
+            .database
+            .get_cached_version(&entry.oaci, &entry.vac_type)?;
+        self.run_post_download_hook(entry, previous_version.as_deref());
+        let mut reason = download_reason(previous_version.as_deref(), &entry.version);
+        if let Some(source) = source {
+            reason = format!("{} from {}", reason, source);
+        }
+        self.audit(vec![AuditEntry::chart(
+            AuditAction::Download,
+            entry,
+            reason,
+        )]);
+        stats.record_download(entry, previous_version, size, elapsed);
+        Ok(())
+    }
+
+    /// Log and count a failed download, and record it in the queue
+    fn record_failed(
+        &self,
+        entry: &VacEntry,
+        error: &anyhow::Error,
+        elapsed: Duration,
+        stats: &mut SyncStats,
+    ) {
+        error!("✗ Failed to download {}: {:#}", entry.oaci, error);
+        self.record_download_failure(entry, error);
+        stats.record_failure(&entry.oaci, &entry.vac_type, error, elapsed);
+    }
+
+    /// Record a failed download in the queue, so it can be inspected later
+    fn record_download_failure(&self, entry: &VacEntry, error: &anyhow::Error) {
+        if let Err(e) = self.database.record_download_failure(
+            &entry.oaci,
+            &entry.vac_type,
+            &format!("{:#}", error),
+        ) {
+            warn!("⚠️  Failed to record download failure: {}", e);
+        }
+        match self.database.record_chart_failure(
+            &entry.oaci,
+            &entry.vac_type,
+            &entry.version,
+            &format!("{:#}", error),
+        ) {
+            Ok(failures)
+                if self.quarantine_threshold > 0 && failures == self.quarantine_threshold =>
+            {
+                warn!(
+                    "⚠️  {} {} failed {} times in a row and is quarantined: the next syncs skip it",
+                    entry.oaci, entry.vac_type, failures
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️  Failed to record download failure: {}", e),
+        }
+        if FailureKind::of(error) == FailureKind::NotFound {
+            if let Err(e) =
+                self.database
+                    .record_missing_chart(&entry.oaci, &entry.vac_type, &entry.version)
+            {
+                warn!("⚠️  Failed to record missing chart: {}", e);
+            }
+        }
+    }
+
+    /// Record changes in the audit log, only warning when it fails
+    fn audit(&self, entries: Vec<AuditEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        if let Err(e) = self.database.record_audit(&entries) {
+            warn!("⚠️  Failed to record changes in the audit log: {}", e);
+        }
+    }
+
+    /// Get the most recent changes to the cache and the stored files, newest
+    /// first, only those of an airfield if `oaci` is given
+    pub fn audit_log(&self, oaci: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>> {
+        self.database
+            .get_audit_log(oaci, limit)
+            .context("Failed to read the audit log")
+    }
+
+    /// Get the downloads waiting in the queue, highest priority first
+    pub fn pending_downloads(&self) -> Result<Vec<PendingDownload>> {
+        self.database
+            .get_pending_downloads(None)
+            .context("Failed to read the download queue")
+    }
+
+    /// Run a sync between its hooks and record it in the sync history
+    ///
+    /// A failing pre_sync hook fails the sync. Failing to record the run or to
+    /// run the post_sync hook is reported but does not fail the sync.
+    fn record_sync_run<F>(&self, source: &str, sync: F) -> Result<SyncStats>
+    where
+        F: FnOnce() -> Result<SyncStats>,
+    {
+        let started_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let start = Instant::now();
+        if let Some(observer) = &self.observer {
+            observer.sync_started(source);
+        }
+
+        let mut result = info_span!("sync", source).in_scope(|| {
+            if let Some(command) = &self.hooks.pre_sync {
+                hooks::run("pre_sync", command, &[])?;
+            }
+            sync()
+        });
+        if let Ok(stats) = &mut result {
+            stats.duration = start.elapsed();
+        }
+
+        let stats = result.as_ref().ok();
+        let run = SyncRun {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            source: source.to_string(),
+            total_entries: stats.map_or(0, |s| s.total_entries),
+            downloaded: stats.map_or(0, |s| s.downloaded),
+            failed: stats.map_or(0, |s| s.failed),
+            bytes_downloaded: stats.map_or(0, |s| s.bytes_downloaded),
+            download_ms: stats.map_or(0, |s| s.download_duration.as_millis() as u64),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+        };
+        if let Err(e) = self.database.record_sync_run(&run) {
+            warn!("⚠️  Failed to record sync run: {}", e);
+        }
+        if let Some(observer) = &self.observer {
+            observer.sync_finished(result.as_ref());
+        }
+
+        if let Some(command) = &self.hooks.post_sync {
+            let mut env = vec![
+                (
+                    "VAC_STATUS",
+                    if run.error.is_none() {
+                        "success"
+                    } else {
+                        "failure"
+                    }
+                    .to_string(),
+                ),
+                ("VAC_DOWNLOADED", run.downloaded.to_string()),
+                ("VAC_FAILED", run.failed.to_string()),
+                ("VAC_BYTES_DOWNLOADED", run.bytes_downloaded.to_string()),
+            ];
+            if let Some(error) = &run.error {
+                env.push(("VAC_ERROR", error.clone()));
+            }
+            if let Err(e) = hooks::run("post_sync", command, &env) {
+                warn!("⚠️  {:#}", e);
+            }
+        }
+
+        result
+    }
+
+    /// Current state of the cache and totals of the sync history
+    ///
+    /// Rendered with [`Metrics::render`] for Prometheus, or written to a file
+    /// for the textfile collector of node_exporter with [`Metrics::write`].
+    pub fn metrics(&self) -> Result<Metrics> {
+        let entries = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?;
+        Ok(Metrics {
+            cached_charts: entries.len() as u64,
+            cache_size: entries
+                .iter()
+                .map(|entry| entry.file_size.max(0) as u64)
+                .sum(),
+            pending_downloads: self.database.get_pending_downloads(None)?.len() as u64,
+            totals: self
+                .database
+                .get_sync_totals()
+                .context("Failed to read sync history")?,
+            last_run: self.database.get_sync_runs(1)?.pop(),
+        })
+    }
+
+    /// Get the most recent sync runs, newest first
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of runs to return
+    pub fn sync_history(&self, limit: usize) -> Result<Vec<SyncRun>> {
+        self.database
+            .get_sync_runs(limit)
+            .context("Failed to read sync history")
+    }
+
+    /// Make sure the storage can hold the PDFs about to be downloaded
+    ///
+    /// Fails early when the free space of the storage is smaller than the
+    /// total size announced for the pending downloads.
+    fn check_available_space(&self, pending: &[VacEntry]) -> Result<()> {
+        let needed: u64 = pending
+            .iter()
+            .map(|entry| entry.file_size.max(0) as u64)
+            .sum();
+        if needed == 0 {
+            return Ok(());
+        }
+
+        if let Some(available) = self.storage.available_space()? {
+            if needed > available {
+                anyhow::bail!(
+                    "Not enough free space: {} PDFs need {}, only {} available",
+                    pending.len(),
+                    format_size(needed),
+                    format_size(available)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the previous versions of charts beyond the retention policy
+    ///
+    /// A previous version is removed once it is beyond the `keep_versions`
+    /// most recent ones of its chart, or was downloaded more than `keep_days`
+    /// days ago. The cached versions are always kept, and so are files still
+    /// used by them. The remaining previous versions are then compressed if
+    /// enabled with [`VacDownloader::with_compress_versions`].
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only report the versions beyond the policy without removing them
+    pub fn gc(&self, dry_run: bool) -> Result<GcResult> {
+        let _lock = if dry_run { None } else { self.lock()? };
+        self.collect_previous_versions(dry_run)
+    }
+
+    fn collect_previous_versions(&self, dry_run: bool) -> Result<GcResult> {
+        let mut result = self.expire_previous_versions(dry_run)?;
+        if self.compress_versions {
+            self.compress_previous_versions(dry_run, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    fn expire_previous_versions(&self, dry_run: bool) -> Result<GcResult> {
+        let mut result = GcResult::default();
+        if self.keep_versions.is_none() && self.keep_days.is_none() {
+            return Ok(result);
+        }
+
+        let cached_files: HashSet<String> = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .map(|entry| entry.file_name)
+            .collect();
+        let oldest = self.keep_days.map(|days| {
+            (chrono::Utc::now() - chrono::Duration::days(days.into()))
+                .naive_utc()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+
+        let mut rank = 0;
+        let mut chart = None;
+        for version in self.database.get_previous_versions()? {
+            let this_chart = (version.oaci.clone(), version.vac_type.clone());
+            if chart.as_ref() != Some(&this_chart) {
+                chart = Some(this_chart);
+                rank = 0;
+            }
+            rank += 1;
+
+            let reason = if self.keep_versions.is_some_and(|keep| rank > keep) {
+                format!(
+                    "previous version beyond the {} kept",
+                    self.keep_versions.unwrap_or_default()
+                )
+            } else if oldest
+                .as_ref()
+                .is_some_and(|oldest| version.downloaded_at < *oldest)
+            {
+                format!(
+                    "previous version downloaded more than {} days ago",
+                    self.keep_days.unwrap_or_default()
+                )
+            } else {
+                continue;
+            };
+
+            let shared = cached_files.contains(&version.file_name);
+            let size = if version.compressed {
+                self.storage.size(&version.stored_name())?.unwrap_or(0)
+            } else {
+                version.file_size.max(0) as u64
+            };
+            if dry_run {
+                info!("Would remove {} version {}", version.oaci, version.version);
+            } else {
+                if !shared {
+                    if let Err(e) = self.storage.delete(&version.stored_name()) {
+                        error!("✗ Failed to remove {}: {}", version.stored_name(), e);
+                        continue;
+                    }
+                }
+                self.database.delete_chart_version(
+                    &version.oaci,
+                    &version.vac_type,
+                    &version.version,
+                )?;
+                self.audit(vec![AuditEntry {
+                    oaci: Some(version.oaci.clone()),
+                    vac_type: Some(version.vac_type.clone()),
+                    ..AuditEntry::file(AuditAction::Delete, &version.stored_name(), reason)
+                }]);
+                info!("🗑️  Removed {} version {}", version.oaci, version.version);
+            }
+            if !shared {
+                result.freed += size;
+            }
+            result.expired.push(version);
+        }
+
+        Ok(result)
+    }
+
+    /// Compress the previous versions that are not yet, skipping the ones
+    /// expired by the retention policy and files still used by cached charts
+    fn compress_previous_versions(&self, dry_run: bool, result: &mut GcResult) -> Result<()> {
+        let cached_files: HashSet<String> = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .map(|entry| entry.file_name)
+            .collect();
+        let expired: HashSet<(&str, &str, &str)> = result
+            .expired
+            .iter()
+            .map(|v| (v.oaci.as_str(), v.vac_type.as_str(), v.version.as_str()))
+            .collect();
+
+        let mut compressed = Vec::new();
+        for version in self.database.get_previous_versions()? {
+            if version.compressed
+                || cached_files.contains(&version.file_name)
+                || expired.contains(&(&version.oaci, &version.vac_type, &version.version))
+            {
+                continue;
+            }
+            if dry_run {
+                if self.storage.exists(&version.file_name)? {
+                    info!(
+                        "Would compress {} version {}",
+                        version.oaci, version.version
+                    );
+                    compressed.push(version);
+                }
+                continue;
+            }
+            let Some(data) = self.storage.read(&version.file_name)? else {
+                continue;
+            };
+            let compressed_data = zstd::encode_all(data.as_slice(), ZSTD_LEVEL)
+                .context("Failed to compress a previous version")?;
+            let mut stored = version.clone();
+            stored.compressed = true;
+            self.storage
+                .write(&stored.stored_name(), &compressed_data)?;
+            self.database.set_version_compressed(
+                &version.oaci,
+                &version.vac_type,
+                &version.version,
+                true,
+            )?;
+            self.storage.delete(&version.file_name)?;
+            info!(
+                "📦 Compressed {} version {} ({} → {})",
+                version.oaci,
+                version.version,
+                format_size(data.len() as u64),
+                format_size(compressed_data.len() as u64)
+            );
+            result.freed += (data.len() as u64).saturating_sub(compressed_data.len() as u64);
+            compressed.push(stored);
+        }
+        result.compressed = compressed;
+        Ok(())
+    }
+
+    /// Evict the least recently updated airfields until the cache fits in `max_cache_size`
+    ///
+    /// Airfields listed in `protected` (typically those downloaded by the
+    /// current sync) are kept even if the cache remains over the limit.
+    ///
+    /// # Returns
+    /// The evicted entries
+    fn enforce_max_cache_size(&self, protected: &HashSet<String>) -> Result<Vec<VacEntry>> {
+        let Some(max_bytes) = self.max_cache_size else {
+            return Ok(Vec::new());
+        };
+
+        let entries = self
+            .database
+            .get_entries_by_last_updated()
+            .context("Failed to read cache entries")?;
+        let mut total: u64 = entries.iter().map(|e| e.file_size.max(0) as u64).sum();
+        if total <= max_bytes {
+            return Ok(Vec::new());
+        }
+
+        info!(
+            "🧹 Cache size {} exceeds the {} limit, evicting old airfields...",
+            format_size(total),
+            format_size(max_bytes)
+        );
+
+        // Evict whole airfields, in the order of their least recent update
+        let mut oacis: Vec<&str> = Vec::new();
+        for entry in &entries {
+            if !protected.contains(&entry.oaci) && !oacis.contains(&entry.oaci.as_str()) {
+                oacis.push(&entry.oaci);
+            }
+        }
+
+        let mut evicted = Vec::new();
+        for oaci in oacis {
+            if total <= max_bytes {
+                break;
+            }
+            let airfield: Vec<&VacEntry> = entries.iter().filter(|e| e.oaci == oaci).collect();
+            if let Some(Err(e)) = airfield
+                .iter()
+                .map(|entry| self.storage.delete(&entry.file_name))
+                .find(|result| result.is_err())
+            {
+                // Keep the entry so a later run can retry
+                error!("✗ Failed to evict {}: {}", oaci, e);
+                continue;
+            }
+            self.database
+                .delete_entry(oaci, None)
+                .context(format!("Failed to evict {}", oaci))?;
+            let reason = format!(
+                "least recently updated while the cache exceeded the {} limit",
+                format_size(max_bytes)
+            );
+            self.audit(
+                airfield
+                    .iter()
+                    .map(|entry| AuditEntry::chart(AuditAction::Evict, entry, reason.as_str()))
+                    .collect(),
+            );
+            for entry in airfield {
+                total = total.saturating_sub(entry.file_size.max(0) as u64);
+                evicted.push(entry.clone());
+            }
+            info!("🗑️  Evicted {}", oaci);
+        }
+
+        if total > max_bytes {
+            warn!(
+                "⚠️  Cache size {} is still over the limit",
+                format_size(total)
+            );
+        }
+
+        Ok(evicted)
+    }
+
+    /// Resolve the stored file name of an entry from the template
+    ///
+    /// When the sanitized name is already used by another entry, either in the
+    /// cache or earlier in the current run, a numeric suffix is appended.
+    fn resolve_file_name(
+        &self,
+        entry: &VacEntry,
+        claimed_names: &mut HashMap<String, (String, String)>,
+    ) -> Result<String> {
+        let owner = (entry.oaci.clone(), entry.vac_type.clone());
+        let region = if self.file_name_template.uses_region() {
+            // Airfields of the listing are stored before their charts are processed
+            self.database
+                .get_airfield(&entry.oaci)?
+                .as_ref()
+                .and_then(regions::airfield_region)
+        } else {
+            None
+        };
+        let base = self.file_name_template.render_in_region(entry, region);
+        let mut candidate = base.clone();
+        let mut index = 2;
+
+        loop {
+            let claimed_by = match claimed_names.get(&candidate) {
+                Some(claimed_by) => Some(claimed_by.clone()),
+                None => self.database.get_file_name_owner(&candidate)?,
+            };
+            match claimed_by {
+                Some(other) if other != owner => {
+                    candidate = with_collision_suffix(&base, index);
+                    index += 1;
+                }
+                _ => break,
+            }
+        }
+
+        claimed_names.insert(candidate.clone(), owner);
+        Ok(candidate)
+    }
+
+    /// Move the file of the cached version of a chart to [`ARCHIVE_DIR`] before
+    /// a new version is stored under the same name
+    ///
+    /// The archived file is named after its version, as with versioned file
+    /// names, and its location is recorded in the version history so that it
+    /// can be rolled back to. A file that no longer matches its cached hash is
+    /// left to be overwritten.
+    fn archive_superseded(&self, entry: &VacEntry) -> Result<()> {
+        if self.keep_versions == Some(0) {
+            return Ok(());
+        }
+        let Some(version) = self
+            .database
+            .get_cached_version(&entry.oaci, &entry.vac_type)?
+        else {
+            return Ok(());
+        };
+        if version == entry.version
+            || self
+                .database
+                .get_cached_file_name(&entry.oaci, &entry.vac_type)?
+                .as_deref()
+                != Some(entry.file_name.as_str())
+        {
+            return Ok(());
+        }
+        let cached_hash = self
+            .database
+            .get_cached_hash(&entry.oaci, &entry.vac_type)?;
+        if cached_hash.is_none()
+            || self
+                .storage
+                .hash(&entry.file_name, cached_hash.as_deref())?
+                != cached_hash
+        {
+            return Ok(());
+        }
+
+        let superseded = VacEntry {
+            version,
+            ..entry.clone()
+        };
+        let archived = format!(
+            "{}/{}",
+            ARCHIVE_DIR,
+            FileNameTemplate::versioned().render(&superseded)
+        );
+        self.storage
+            .rename(&entry.file_name, &archived)
+            .context(format!("Failed to archive {}", entry.file_name))?;
+        self.database
+            .archive_cached_version(&entry.oaci, &entry.vac_type, &archived)?;
+        info!(
+            "📁 Archived {} version {} to {}",
+            entry.oaci, superseded.version, archived
+        );
+        self.audit(vec![AuditEntry {
+            oaci: Some(entry.oaci.clone()),
+            vac_type: Some(entry.vac_type.clone()),
+            ..AuditEntry::file(
+                AuditAction::Move,
+                &archived,
+                format!(
+                    "version {} archived, superseded by {}",
+                    superseded.version, entry.version
+                ),
+            )
+        }]);
+        Ok(())
+    }
+
+    /// Move a cached PDF stored under another name to the entry's resolved name
+    ///
+    /// Returns true if the file was moved. If the move fails, the entry keeps
+    /// pointing at the previous location.
+    fn relocate_stored_file(&self, entry: &mut VacEntry) -> bool {
+        let stored_name = match self
+            .database
+            .get_cached_file_name(&entry.oaci, &entry.vac_type)
+        {
+            Ok(Some(name)) if name != entry.file_name => name,
+            _ => return false,
+        };
+
+        match self.storage.exists(&stored_name) {
+            Ok(true) => {}
+            _ => return false,
+        }
+
+        match self.storage.rename(&stored_name, &entry.file_name) {
+            Ok(()) => {
+                info!("📁 Moved {} to {}", stored_name, entry.file_name);
+                self.audit(vec![AuditEntry::chart(
+                    AuditAction::Move,
+                    entry,
+                    format!(
+                        "moved from {} to follow the file name template",
+                        stored_name
+                    ),
+                )]);
+                true
+            }
+            Err(e) => {
+                error!("✗ Failed to move {}: {}", stored_name, e);
+                entry.file_name = stored_name;
+                false
+            }
+        }
+    }
+
+    /// Download a file of a mirror, checking it against the hash published in
+    /// the manifest and returning it along with its page count
+    fn fetch_from_mirror(
+        &self,
+        base_url: &reqwest::Url,
+        file_name: &str,
+        file_hash: Option<&str>,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<u8>, u32)> {
+        let bytes = self.download_from_mirror(base_url, file_name, deadline)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        if Some(format!("{:x}", hasher.finalize()).as_str()) != file_hash {
+            anyhow::bail!("Hash mismatch with the mirror manifest, the mirror file is corrupted");
+        }
+        let pages = pdf::page_count(&bytes).context("Invalid PDF received from the mirror")?;
+        Ok((bytes, pages))
+    }
+
+    /// Download a single file from a mirror
+    fn download_from_mirror(
+        &self,
+        base_url: &reqwest::Url,
+        file_name: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<u8>> {
+        let url = base_url
+            .join(file_name)
+            .context(format!("Invalid file name in manifest: {}", file_name))?;
+        if !url.as_str().starts_with(base_url.as_str()) {
+            anyhow::bail!("File name {} points outside of the mirror", file_name);
+        }
+
+        let response = self
+            .api
+            .retry_policy()
+            .send(|| self.api.transport().get(HttpRequest::get(url.as_str())))?;
+        debug!(%url, status = %response.status(), "GET");
+        if !response.status().is_success() {
+            let status = debug_error_response(response);
+            return Err(DownloadStatusError {
+                what: "Mirror",
+                status,
+            }
+            .into());
+        }
+
+        Ok(response.bytes_until(deadline, self.cancellation.as_ref())?)
+    }
+
+    /// Get a list of all remotely available VACs with local availability status
+    ///
+    /// # Arguments
+    /// * `oaci_filter` - Optional list of OACI codes to filter results. If None, all entries are returned.
+    ///
+    /// # Returns
+    /// A vector of VacEntry containing remote VAC information and local availability
+    pub fn list_vacs(&self, oaci_filter: Option<&[String]>) -> Result<Vec<VacEntry>> {
+        info!("🌐 Fetching OACIS data from API...");
+        let listing = self.fetch_oacis_data(oaci_filter)?;
+
+        // Filter by OACI codes if specified, only copying the matching entries
+        // out of the shared listing
+        let mut entries = match oaci_filter {
+            Some(codes) => {
+                let codes_upper: Vec<String> = codes.iter().map(|c| c.to_uppercase()).collect();
+                matching::warn_unmatched_codes(&codes_upper, &listing);
+                let entries: Vec<VacEntry> = listing
+                    .iter()
+                    .filter(|entry| codes_upper.contains(&entry.oaci.to_uppercase()))
+                    .cloned()
+                    .collect();
+
+                info!("🔍 Filtering by OACI codes: {}", codes_upper.join(", "));
+                info!(
+                    "Matched {} out of {} total entries",
+                    entries.len(),
+                    listing.len()
+                );
+
+                if entries.is_empty() {
+                    warn!("⚠️  No entries found matching the specified OACI codes");
+                    return Ok(entries);
+                }
+                entries
+            }
+            None => listing.to_vec(),
+        };
+
+        info!("🔍 Checking local availability...");
+
+        // Check local availability for each entry
+        for entry in &mut entries {
+            entry.available_locally = self.database.has_entry(&entry.oaci).unwrap_or(false);
+        }
+
+        let local_count = entries.iter().filter(|e| e.available_locally).count();
+        info!(
+            "{} out of {} entries are available locally",
+            local_count,
+            entries.len()
+        );
+
+        Ok(entries)
+    }
+
+    /// List the airfields of the remote listing with the state of their charts in the local cache
+    ///
+    /// Unlike [`VacDownloader::list_vacs`], the airfields keep everything the
+    /// listing publishes, such as their runways, frequencies and coordinates.
+    /// Cached charts withdrawn from the listing are listed with their airfield.
+    ///
+    /// # Arguments
+    /// * `oaci_filter` - Optional list of OACI codes or glob patterns. If None, all airfields are listed.
+    ///
+    /// # Returns
+    /// The airfields sorted by OACI code
+    pub fn list_airports(&self, oaci_filter: Option<&[String]>) -> Result<Vec<Airport>> {
+        let listing = self.fetch_oacis_listing_for(oaci_filter)?;
+        let mut cached: HashMap<String, Vec<(VacEntry, String)>> = HashMap::new();
+        for (entry, last_updated) in self
+            .database
+            .get_entries_with_last_updated()
+            .context("Failed to read cache entries")?
+        {
+            cached
+                .entry(entry.oaci.clone())
+                .or_default()
+                .push((entry, last_updated));
+        }
+
+        let mut airports: Vec<Airport> = listing
+            .airfields
+            .iter()
+            .filter(|airfield| {
+                oaci_filter
+                    .is_none_or(|codes| codes.iter().any(|code| matches_code(code, &airfield.code)))
+            })
+            .cloned()
+            .map(|airfield| {
+                let mut local = cached.remove(&airfield.code).unwrap_or_default();
+                let mut charts = Vec::new();
+                for remote in VacEntry::from_oacis_entry(&airfield) {
+                    let cached = local
+                        .iter()
+                        .position(|(entry, _)| entry.vac_type == remote.vac_type)
+                        .map(|index| local.swap_remove(index));
+                    charts.push(ChartSummary {
+                        remote_version: Some(remote.version),
+                        cached_version: cached.as_ref().map(|(entry, _)| entry.version.clone()),
+                        last_updated: cached.map(|(_, last_updated)| last_updated),
+                        oaci: remote.oaci,
+                        city: remote.city,
+                        vac_type: remote.vac_type,
+                        file_size: remote.file_size,
+                    });
+                }
+                // Cached charts withdrawn from the listing
+                for (entry, last_updated) in local {
+                    charts.push(ChartSummary {
+                        remote_version: None,
+                        cached_version: Some(entry.version),
+                        last_updated: Some(last_updated),
+                        oaci: entry.oaci,
+                        city: entry.city,
+                        vac_type: entry.vac_type,
+                        file_size: entry.file_size,
+                    });
+                }
+                Airport { airfield, charts }
+            })
+            .collect();
+        airports.sort_by(|a, b| a.airfield.code.cmp(&b.airfield.code));
+        Ok(airports)
+    }
+
+    /// Search airfields by city or OACI code in the remote listing and the local cache
+    ///
+    /// Matching ignores case and accents and tolerates typos, see
+    /// [`matching::search`]. Results are sorted by relevance. When the
+    /// listing cannot be fetched, only the local cache is searched.
+    pub fn search(&self, query: &str) -> Result<Vec<ChartSummary>> {
+        self.chart_summaries(|entries| matching::search(query, entries))
+    }
+
+    /// Details of an airfield from the remote listing, such as its runways and frequencies
+    ///
+    /// # Returns
+    /// The airfield, or None if the listing has no airfield with this OACI code
+    pub fn airfield(&self, oaci: &str) -> Result<Option<OacisEntry>> {
+        Ok(self
+            .fetch_oacis_listing()?
+            .airfields
+            .iter()
+            .find(|airfield| airfield.code.eq_ignore_ascii_case(oaci))
+            .cloned())
+    }
+
+    /// List the charts of the remote listing and the local cache, sorted by OACI code
+    ///
+    /// When the listing cannot be fetched, or with [`VacDownloader::with_offline`],
+    /// only the local cache is listed.
+    ///
+    /// # Arguments
+    /// * `oaci_filter` - Optional list of OACI codes or glob patterns. If None, all charts are listed.
+    pub fn list_charts(&self, oaci_filter: Option<&[String]>) -> Result<Vec<ChartSummary>> {
+        self.chart_summaries(|entries| {
+            if let Some(codes) = oaci_filter {
+                matching::warn_unmatched_codes(codes, entries);
+            }
+            let mut selected: Vec<&VacEntry> = entries
+                .iter()
+                .filter(|entry| {
+                    oaci_filter.is_none_or(|codes| {
+                        codes.iter().any(|code| matches_code(code, &entry.oaci))
+                    })
+                })
+                .collect();
+            selected.sort_by(|a, b| a.oaci.cmp(&b.oaci).then(a.vac_type.cmp(&b.vac_type)));
+            selected
+        })
+    }
+
+    /// Charts of the local cache, to compare with a mirror manifest with [`diff::changes`]
+    pub fn local_charts(&self) -> Result<Vec<LocalChart>> {
+        Ok(self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .iter()
+            .map(LocalChart::from)
+            .collect())
+    }
+
+    /// List the cached charts of which a newer version is published, sorted by OACI code
+    ///
+    /// These are the charts a sync would update, charts that are not cached yet aside.
+    /// Unlike `list_charts`, fails when the listing cannot be fetched.
+    ///
+    /// # Arguments
+    /// * `oaci_filter` - Optional list of OACI codes or glob patterns. If None, all charts are listed.
+    pub fn list_outdated(&self, oaci_filter: Option<&[String]>) -> Result<Vec<ChartSummary>> {
+        // Fetched first, so that the listing below reuses it instead of falling back to the cache
+        self.fetch_oacis_listing()?;
+        let mut charts = self.list_charts(oaci_filter)?;
+        charts.retain(ChartSummary::is_outdated);
+        Ok(charts)
+    }
+
+    /// Summarize the remote and cached charts picked by `select`, in its order
+    fn chart_summaries<F>(&self, select: F) -> Result<Vec<ChartSummary>>
+    where
+        F: for<'a> FnOnce(&'a [VacEntry]) -> Vec<&'a VacEntry>,
+    {
+        let mut entries = if self.offline {
+            Vec::new()
+        } else {
+            match self.fetch_oacis_data(None) {
+                Ok(entries) => entries.to_vec(),
+                Err(e) => {
+                    warn!(
+                        "⚠️  Failed to fetch OACIS data, using the local cache only: {:#}",
+                        e
+                    );
+                    Vec::new()
+                }
+            }
+        };
+        let remote: HashSet<(String, String)> = entries
+            .iter()
+            .map(|entry| (entry.oaci.clone(), entry.vac_type.clone()))
+            .collect();
+        let cached: HashMap<(String, String), (VacEntry, String)> = self
+            .database
+            .get_entries_with_last_updated()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .map(|(entry, last_updated)| {
+                (
+                    (entry.oaci.clone(), entry.vac_type.clone()),
+                    (entry, last_updated),
+                )
+            })
+            .collect();
+
+        // Charts only found in the cache, e.g. withdrawn from the listing
+        let mut cache_only: Vec<VacEntry> = cached
+            .iter()
+            .filter(|(key, _)| !remote.contains(*key))
+            .map(|(_, (entry, _))| entry.clone())
+            .collect();
+        cache_only.sort_by(|a, b| a.oaci.cmp(&b.oaci).then(a.vac_type.cmp(&b.vac_type)));
+        entries.extend(cache_only);
+
+        Ok(select(&entries)
+            .into_iter()
+            .map(|entry| {
+                let key = (entry.oaci.clone(), entry.vac_type.clone());
+                let cached = cached.get(&key);
+                ChartSummary {
+                    oaci: entry.oaci.clone(),
+                    city: entry.city.clone(),
+                    vac_type: entry.vac_type.clone(),
+                    remote_version: remote.contains(&key).then(|| entry.version.clone()),
+                    cached_version: cached.map(|(cached, _)| cached.version.clone()),
+                    file_size: entry.file_size,
+                    last_updated: cached.map(|(_, last_updated)| last_updated.clone()),
+                }
+            })
+            .collect())
+    }
+
+    /// Check which charts a sync would download, without changing anything
+    ///
+    /// # Arguments
+    /// * `oaci_filter` - Optional list of OACI codes to check. If None, all entries are checked.
+    ///
+    /// # Returns
+    /// The number of up to date charts and the updates that are available
+    pub fn check(&self, oaci_filter: Option<&[String]>) -> Result<CheckResult> {
+        let listing = self.fetch_oacis_data(oaci_filter)?;
+        if let Some(codes) = oaci_filter {
+            matching::warn_unmatched_codes(codes, &listing);
+        }
+        let entries = listing.iter().filter(|entry| {
+            oaci_filter
+                .is_none_or(|codes| codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci)))
+        });
+
+        let mut result = CheckResult::default();
+        for entry in entries {
+            let reason = match self
+                .database
+                .get_cached_version(&entry.oaci, &entry.vac_type)?
+            {
+                None => Some(UpdateReason::New),
+                Some(cached_version) if is_outdated(Some(&cached_version), &entry.version) => {
+                    Some(UpdateReason::Outdated { cached_version })
+                }
+                Some(_) => {
+                    let stored_name = self
+                        .database
+                        .get_cached_file_name(&entry.oaci, &entry.vac_type)?
+                        .unwrap_or_else(|| entry.file_name.clone());
+                    let cached_hash = self
+                        .database
+                        .get_cached_hash(&entry.oaci, &entry.vac_type)?;
+                    match self.storage.hash(&stored_name, cached_hash.as_deref())? {
+                        None => Some(UpdateReason::Missing),
+                        Some(hash) if cached_hash.is_some_and(|cached| cached != hash) => {
+                            Some(UpdateReason::Corrupted)
+                        }
+                        Some(_) => None,
+                    }
+                }
+            };
+
+            match reason {
+                Some(reason) => result.updates.push(AvailableUpdate {
+                    entry: entry.clone(),
+                    reason,
+                }),
+                None => result.up_to_date += 1,
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Place the cached charts on the AIRAC cycle calendar
+    ///
+    /// The effective date of each chart is read from its version. Nothing is
+    /// fetched from the API.
+    ///
+    /// # Arguments
+    /// * `today` - Date whose cycle is the current one
+    /// * `oaci_filter` - Optional list of OACI codes or patterns to report on
+    pub fn next_cycle(
+        &self,
+        today: NaiveDate,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<CycleOutlook> {
+        let current = AiracCycle::containing(today);
+        let mut outlook = CycleOutlook {
+            current,
+            next: current.next(),
+            expiring: Vec::new(),
+            upcoming: Vec::new(),
+            undated: Vec::new(),
+        };
+
+        for (entry, effective_date) in self
+            .database
+            .get_entries_with_effective_date()
+            .context("Failed to read cache entries")?
+        {
+            if let Some(codes) = oaci_filter {
+                if !codes.iter().any(|code| matches_code(code, &entry.oaci)) {
+                    continue;
+                }
+            }
+            match effective_date {
+                Some(date) if date < outlook.next.effective_date() => {
+                    outlook.expiring.push((entry, date))
+                }
+                Some(date) => outlook.upcoming.push((entry, date)),
+                None => outlook.undated.push(entry),
+            }
+        }
+        Ok(outlook)
+    }
+
+    /// Build an iCalendar feed of the upcoming AIRAC cycles and of the last
+    /// update of the cached charts
+    ///
+    /// Nothing is fetched from the API.
+    ///
+    /// # Arguments
+    /// * `today` - Date whose cycle is the first one of the feed
+    /// * `cycles` - Number of cycles in the feed, starting with the current one
+    /// * `oaci_filter` - Optional list of OACI codes or patterns of the watched airfields
+    pub fn calendar(
+        &self,
+        today: NaiveDate,
+        cycles: usize,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<ChartCalendar> {
+        let mut calendar = ChartCalendar::default();
+
+        let mut cycle = AiracCycle::containing(today);
+        for _ in 0..cycles {
+            calendar.cycles.push(cycle);
+            cycle = cycle.next();
+        }
+
+        for (entry, last_updated) in self
+            .database
+            .get_entries_with_last_updated()
+            .context("Failed to read cache entries")?
+        {
+            if let Some(codes) = oaci_filter {
+                if !codes.iter().any(|code| matches_code(code, &entry.oaci)) {
+                    continue;
+                }
+            }
+            match NaiveDateTime::parse_from_str(&last_updated, "%Y-%m-%d %H:%M:%S") {
+                Ok(updated_at) => calendar.updates.push((entry, updated_at)),
+                Err(e) => warn!(
+                    "Ignoring {} {}: invalid update time {:?}: {}",
+                    entry.oaci, entry.vac_type, last_updated, e
+                ),
+            }
+        }
+        Ok(calendar)
+    }
+
+    /// Check if a VAC entry needs an update
+    ///
+    /// # Arguments
+    /// * `oaci` - OACI code of the entry to check
+    ///
+    /// # Returns
+    /// `Ok(true)` if the entry needs an update, `Ok(false)` if it's up to date,
+    /// or an error if the check fails
+    pub fn needs_update(&self, oaci: &str) -> Result<bool> {
+        // Fetch remote entries to get the latest version
+        let entries = self.fetch_oacis_data(Some(&[oaci.to_string()]))?;
+
+        // Find the entry for this OACI code
+        let remote_entry = entries
+            .iter()
+            .find(|e| e.oaci.eq_ignore_ascii_case(oaci))
+            .ok_or_else(|| anyhow::anyhow!("OACI code {} not found in remote data", oaci))?;
+
+        // Check if it needs an update using the database method
+        self.database
+            .needs_update(remote_entry)
+            .context(format!("Failed to check update status for {}", oaci))
+    }
+
+    /// Delete entries from the cache and remove their PDF files
+    ///
+    /// Each entry deleted is reported in the result, as well as each pattern
+    /// that matched no cached entry.
+    ///
+    /// # Arguments
+    /// * `patterns` - OACI codes or glob patterns (`*` and `?`), e.g. `LFPG` or `LFP*`
+    /// * `vac_type` - Only delete entries of this map type, e.g. `AD`. If None, all types are deleted.
+    pub fn delete(&self, patterns: &[String], vac_type: Option<&str>) -> Result<Vec<DeleteResult>> {
+        let _lock = self.lock()?;
+        let entries = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?;
+
+        let mut results = Vec::new();
+        let mut deleted = HashSet::new();
+        for pattern in patterns {
+            let matched: Vec<&VacEntry> = entries
+                .iter()
+                .filter(|entry| matches_code(pattern, &entry.oaci))
+                .filter(|entry| vac_type.is_none_or(|t| t.eq_ignore_ascii_case(&entry.vac_type)))
+                .collect();
+            if matched.is_empty() {
+                match vac_type {
+                    Some(vac_type) => warn!(
+                        "⚠️  No {} entry matches {} in the database",
+                        vac_type, pattern
+                    ),
+                    None => warn!("⚠️  No entry matches {} in the database", pattern),
+                }
+                if let Some(suggestions) = matching::format_suggestions(pattern, &entries) {
+                    warn!("   Did you mean {}?", suggestions);
+                }
+                results.push(DeleteResult {
+                    oaci: pattern.to_uppercase(),
+                    vac_type: vac_type.map(str::to_uppercase),
+                    database_deleted: false,
+                    file_deleted: false,
+                    file_name: None,
+                });
+                continue;
+            }
+
+            for entry in matched {
+                if deleted.insert((&entry.oaci, &entry.vac_type)) {
+                    results.push(self.delete_entry(entry)?);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Delete a single entry from the cache and remove its PDF file
+    fn delete_entry(&self, entry: &VacEntry) -> Result<DeleteResult> {
+        let mut result = DeleteResult {
+            oaci: entry.oaci.clone(),
+            vac_type: Some(entry.vac_type.clone()),
+            database_deleted: false,
+            file_deleted: false,
+            file_name: None,
+        };
+
+        // Delete from database
+        match self
+            .database
+            .delete_entry(&entry.oaci, Some(&entry.vac_type))
+        {
+            Ok(file_names) => {
+                result.database_deleted = !file_names.is_empty();
+                result.file_name = file_names.into_iter().next();
+                if let Some(file_name) = &result.file_name {
+                    self.audit(vec![AuditEntry::chart(
+                        AuditAction::Delete,
+                        &VacEntry {
+                            file_name: file_name.clone(),
+                            ..entry.clone()
+                        },
+                        "deleted by the delete command",
+                    )]);
+                }
+            }
+            Err(e) => {
+                anyhow::bail!("Failed to delete entry from database: {}", e);
+            }
+        }
+
+        // Delete the PDF file
+        if let Some(file_name) = &result.file_name {
+            match self.storage.delete(file_name) {
+                Ok(true) => {
+                    result.file_deleted = true;
+                    info!(
+                        "✓ Deleted {} ({}) from database and filesystem",
+                        entry.oaci, entry.vac_type
+                    );
+                }
+                Ok(false) => {
+                    info!(
+                        "✓ Deleted {} ({}) from database (file was already missing)",
+                        entry.oaci, entry.vac_type
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        "✗ Deleted {} ({}) from database but failed to delete file: {}",
+                        entry.oaci, entry.vac_type, e
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Restore a version of a chart downloaded in the past as the cached one
+    ///
+    /// The chart is then pinned to that version: syncs keep it until a
+    /// download of another version is forced. Previous versions are restorable
+    /// while their file is still stored, under a versioned file name or in
+    /// [`ARCHIVE_DIR`]. An archived version is moved back to the name of the
+    /// cached chart, whose file is archived in turn.
+    ///
+    /// # Arguments
+    /// * `oaci` - OACI code of the airfield
+    /// * `vac_type` - Map type of the chart, e.g. `AD`
+    /// * `to` - Version to restore, or its beginning, e.g. `2024-10`. If None,
+    ///   the most recent version other than the cached one is restored.
+    pub fn rollback(&self, oaci: &str, vac_type: &str, to: Option<&str>) -> Result<RollbackResult> {
+        let _lock = self.lock()?;
+        let oaci = oaci.to_uppercase();
+        let vac_type = vac_type.to_uppercase();
+        let current = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .find(|entry| entry.oaci == oaci && entry.vac_type == vac_type)
+            .ok_or_else(|| anyhow::anyhow!("No {} chart of {} in the cache", vac_type, oaci))?;
+
+        let previous: Vec<ChartVersion> = self
+            .database
+            .get_chart_versions(&oaci, &vac_type)?
+            .into_iter()
+            .filter(|version| version.version != current.version)
+            .collect();
+        let known = || {
+            let versions: Vec<&str> = previous.iter().map(|v| v.version.as_str()).collect();
+            if versions.is_empty() {
+                "no previous version was downloaded".to_string()
+            } else {
+                format!("previous versions: {}", versions.join(", "))
+            }
+        };
+        let target = match to {
+            None => previous.first(),
+            Some(to) => find_version(&previous, to),
+        }
+        .ok_or_else(|| match to {
+            Some(to) => anyhow::anyhow!("No single version {} of {} ({})", to, oaci, known()),
+            None => anyhow::anyhow!("Nothing to roll back for {} ({})", oaci, known()),
+        })?;
+
+        let Some((data, hash)) = self.read_version_file(target)? else {
+            anyhow::bail!(
+                "The file of version {} of {} is no longer stored",
+                target.version,
+                oaci
+            );
+        };
+        let file_name = if target.file_name.starts_with(&format!("{}/", ARCHIVE_DIR)) {
+            current.file_name.clone()
+        } else {
+            target.file_name.clone()
+        };
+        if target.compressed || file_name != target.file_name {
+            self.archive_superseded(&VacEntry {
+                version: target.version.clone(),
+                file_name: file_name.clone(),
+                ..current.clone()
+            })?;
+            self.storage.write(&file_name, &data)?;
+            self.storage.delete(&target.stored_name())?;
+            self.database.set_version_file_name(
+                &target.oaci,
+                &target.vac_type,
+                &target.version,
+                &file_name,
+            )?;
+        }
+
+        let restored = VacEntry {
+            version: target.version.clone(),
+            file_name,
+            file_size: target.file_size,
+            file_hash: Some(hash),
+            page_count: target.page_count,
+            ..current.clone()
+        };
+        self.database
+            .upsert_entry(&restored)
+            .context("Failed to update cache")?;
+        self.database
+            .pin_version(&oaci, &vac_type, &restored.version)?;
+        self.audit(vec![AuditEntry::chart(
+            AuditAction::Rollback,
+            &restored,
+            format!(
+                "version {} rolled back to {}",
+                current.version, restored.version
+            ),
+        )]);
+        info!(
+            "✓ Rolled {} back from version {} to {}",
+            oaci, current.version, restored.version
+        );
+
+        Ok(RollbackResult {
+            entry: restored,
+            from_version: current.version,
+        })
+    }
+
+    /// Read the file of a version of a chart, decompressing it if needed
+    ///
+    /// # Arguments
+    /// * `oaci` - OACI code of the airfield
+    /// * `vac_type` - Map type of the chart
+    /// * `version` - Version to read, or its beginning; defaults to the most
+    ///   recently downloaded one
+    pub fn extract_version(
+        &self,
+        oaci: &str,
+        vac_type: &str,
+        version: Option<&str>,
+    ) -> Result<(ChartVersion, Vec<u8>)> {
+        let oaci = oaci.to_uppercase();
+        let versions = self
+            .database
+            .get_chart_versions(&oaci, &vac_type.to_uppercase())?;
+        let target = match version {
+            None => versions.first(),
+            Some(version) => find_version(&versions, version),
+        }
+        .ok_or_else(|| {
+            let known: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+            anyhow::anyhow!(
+                "No single version {} of {} (versions: {})",
+                version.unwrap_or_default(),
+                oaci,
+                known.join(", ")
+            )
+        })?;
+        let (data, _) = self.read_version_file(target)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "The file of version {} of {} is no longer stored",
+                target.version,
+                oaci
+            )
+        })?;
+        Ok((target.clone(), data))
+    }
+
+    /// Read the stored file of a version and its hash, None if it is missing
+    /// or no longer matches the recorded hash
+    fn read_version_file(&self, version: &ChartVersion) -> Result<Option<(Vec<u8>, String)>> {
+        let Some(mut data) = self.storage.read(&version.stored_name())? else {
+            return Ok(None);
+        };
+        if version.compressed {
+            data = zstd::decode_all(data.as_slice()).context(format!(
+                "Failed to decompress {}",
+                self.storage.describe(&version.stored_name())
+            ))?;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(&data);
+        let hash = format!("{:x}", hasher.finalize());
+        if version
+            .file_hash
+            .as_ref()
+            .is_some_and(|recorded| *recorded != hash)
+        {
+            return Ok(None);
+        }
+        Ok(Some((data, hash)))
+    }
+
+    /// Get the PDF file path for a given OACI code
+    ///
+    /// # Arguments
+    /// * `oaci` - OACI code of the entry
+    ///
+    /// # Returns
+    /// The full path to the PDF file if it exists locally, or an error if not found
+    /// or if the storage backend is not a local directory
+    pub fn get_pdf_path(&self, oaci: &str) -> Result<PathBuf> {
+        // Check if the entry exists in the database
+        let file_name = self
+            .database
+            .get_file_name(oaci)
+            .context(format!("Failed to query database for {}", oaci))?
+            .ok_or_else(|| anyhow::anyhow!("OACI code {} not found in database", oaci))?;
+
+        // Construct the full path
+        let file_path = self.storage.local_path(&file_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "PDF file for {} is not stored locally ({})",
+                oaci,
+                self.storage.describe(&file_name)
+            )
+        })?;
+
+        // Verify the file exists
+        if !file_path.exists() {
+            anyhow::bail!("PDF file for {} not found at {:?}", oaci, file_path);
+        }
+
+        Ok(file_path)
+    }
+
+    /// Find files in the download directory that no cache entry refers to
+    ///
+    /// # Returns
+    /// The relative names of orphaned files, sorted
+    pub fn find_orphans(&self) -> Result<Vec<String>> {
+        let mut known: HashSet<String> = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+            .into_iter()
+            .map(|entry| entry.file_name)
+            .collect();
+        known.extend(
+            self.database
+                .get_supplements()
+                .context("Failed to read cached supplements")?
+                .into_iter()
+                .map(|supplement| supplement.file_name),
+        );
+        known.extend(
+            self.database
+                .get_version_file_names()
+                .context("Failed to read the version history")?,
+        );
+
+        Ok(self
+            .storage
+            .list()?
+            .into_iter()
+            .filter(|name| !known.contains(name) && !GENERATED_FILES.contains(&name.as_str()))
+            .collect())
+    }
+
+    /// Remove files in the download directory that no cache entry refers to
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only report orphaned files without deleting them
+    pub fn clean(&self, dry_run: bool) -> Result<CleanResult> {
+        let _lock = if dry_run { None } else { self.lock()? };
+        let orphans = self.find_orphans()?;
+        let mut removed = Vec::new();
+
+        if orphans.is_empty() {
+            info!("✓ No orphaned files found");
+        }
+
+        for name in &orphans {
+            if dry_run {
+                info!("Would remove {}", self.storage.describe(name));
+                continue;
+            }
+            match self.storage.delete(name) {
+                Ok(_) => {
+                    info!("✓ Removed {}", self.storage.describe(name));
+                    self.audit(vec![AuditEntry::file(
+                        AuditAction::Clean,
+                        name,
+                        "orphaned file, referred to by no cache entry",
+                    )]);
+                    removed.push(name.clone());
+                }
+                Err(e) => error!("✗ Failed to remove {}: {}", name, e),
+            }
+        }
+
+        Ok(CleanResult { orphans, removed })
+    }
+
+    /// Replace stored files identical to another one with links to it
+    ///
+    /// Cached charts and previous versions with the same recorded hash are
+    /// identical files, e.g. a chart that did not change between two
+    /// versions. All but one are replaced with hard links to it, once their
+    /// stored content is checked against the hash. Compressed previous versions
+    /// are left alone. Requires a storage backend supporting links, such as
+    /// the local download directory.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only report the files that would be linked
+    pub fn dedupe(&self, dry_run: bool) -> Result<DedupeResult> {
+        let _lock = if dry_run { None } else { self.lock()? };
+        let mut files: BTreeMap<String, (String, u64)> = BTreeMap::new();
+        for entry in self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+        {
+            if let Some(hash) = entry.file_hash {
+                files.insert(entry.file_name, (hash, entry.file_size.max(0) as u64));
+            }
+        }
+        for version in self.database.get_previous_versions()? {
+            if version.compressed {
+                continue;
+            }
+            if let Some(hash) = version.file_hash {
+                files
+                    .entry(version.file_name)
+                    .or_insert((hash, version.file_size.max(0) as u64));
+            }
+        }
+
+        let mut groups: BTreeMap<String, Vec<(String, u64)>> = BTreeMap::new();
+        for (name, (hash, size)) in files {
+            groups.entry(hash).or_default().push((name, size));
+        }
+
+        let mut result = DedupeResult::default();
+        for (hash, names) in groups.into_iter().filter(|(_, names)| names.len() > 1) {
+            // Only link files whose stored content still matches the hash
+            let mut names =
+                names
+                    .into_iter()
+                    .filter(|(name, _)| match self.storage.hash(name, Some(&hash)) {
+                        Ok(stored) => stored.as_deref() == Some(hash.as_str()),
+                        Err(e) => {
+                            warn!("Failed to hash {}: {}", self.storage.describe(name), e);
+                            false
+                        }
+                    });
+            let Some((original, _)) = names.next() else {
+                continue;
+            };
+            for (name, size) in names {
+                if self.storage.is_linked(&original, &name)? {
+                    continue;
+                }
+                if dry_run {
+                    info!("Would link {} to {}", name, original);
+                } else {
+                    self.storage.link(&original, &name)?;
+                    self.audit(vec![AuditEntry::file(
+                        AuditAction::Link,
+                        &name,
+                        format!("identical to {}", original),
+                    )]);
+                    info!("🔗 Linked {} to {}", name, original);
+                }
+                result.saved += size;
+                result.linked.push((name, original.clone()));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Check every cached chart against its stored file
+    ///
+    /// A chart is valid when its file exists, matches the recorded hash and is
+    /// a readable PDF with at least one page. The page count of valid charts
+    /// is recorded in the cache.
+    pub fn verify(&self) -> Result<VerifyResult> {
+        let mut result = VerifyResult {
+            verified: 0,
+            problems: Vec::new(),
+        };
+
+        for entry in self.database.get_all_entries()? {
+            let _span = info_span!("entry", oaci = %entry.oaci).entered();
+            let problem = match self.storage.read(&entry.file_name)? {
+                None => Some(VerifyProblem::Missing),
+                Some(bytes) => {
+                    let mut hasher = Sha256::new();
+                    hasher.update(&bytes);
+                    let hash = format!("{:x}", hasher.finalize());
+                    if entry
+                        .file_hash
+                        .as_ref()
+                        .is_some_and(|cached| *cached != hash)
+                    {
+                        Some(VerifyProblem::HashMismatch)
+                    } else {
+                        match pdf::page_count(&bytes) {
+                            Ok(pages) => {
+                                if entry.page_count != Some(pages) {
+                                    self.database.set_page_count(
+                                        &entry.oaci,
+                                        &entry.vac_type,
+                                        pages,
+                                    )?;
+                                }
+                                None
+                            }
+                            Err(e) => Some(VerifyProblem::InvalidPdf(format!("{:#}", e))),
+                        }
+                    }
+                }
+            };
+
+            match problem {
+                Some(problem) => {
+                    warn!("⚠️  {} ({}): {}", entry.oaci, entry.file_name, problem);
+                    result.problems.push((entry, problem));
+                }
+                None => result.verified += 1,
+            }
+        }
+
+        info!(
+            "✓ Verified {} charts, {} with problems",
+            result.verified + result.problems.len(),
+            result.problems.len()
+        );
+        Ok(result)
+    }
+
+    /// Write a mirror manifest (`manifest.json`) at the root of the storage
+    ///
+    /// The manifest lists every cached chart present on disk with its version,
+    /// hash and size, so the directory can be served by a static web host.
+    /// Once written, the manifest is refreshed by every subsequent sync.
+    pub fn write_mirror_manifest(&self) -> Result<MirrorManifest> {
+        let manifest = mirror::manifest_from_database(&self.database, self.storage.as_ref())?;
+        mirror::write_manifest(&manifest, self.storage.as_ref())?;
+        info!(
+            "🪞 Wrote mirror manifest with {} entries",
+            manifest.entries.len()
+        );
+        Ok(manifest)
+    }
+
+    /// Write `index.html` at the root of the download directory
+    ///
+    /// The index links every cached chart present on disk, grouped by the
+    /// initial of its city, with its version and update date, so the directory
+    /// can be browsed offline. Once written, the index is refreshed by every
+    /// subsequent sync.
+    ///
+    /// # Returns
+    /// The number of charts in the index
+    pub fn write_html_index(&self) -> Result<usize> {
+        let index = HtmlIndex::from_database(&self.database, self.storage.as_ref())?;
+        index.write(self.storage.as_ref())?;
+        info!(
+            "🗂️  Wrote {} with {} charts",
+            self.storage.describe(HTML_INDEX_NAME),
+            index.len()
+        );
+        Ok(index.len())
+    }
+
+    /// Write a `SHA256SUMS` manifest of the cached PDFs at the root of the storage
+    ///
+    /// The manifest lists the SHA-256 hash of every chart and supplement present
+    /// on disk, in the format checked by `sha256sum -c`. With a signing key, it
+    /// is signed into `SHA256SUMS.minisig`, checked by `minisign -Vm SHA256SUMS`.
+    /// Once written, the manifest is refreshed by every subsequent sync.
+    ///
+    /// # Returns
+    /// The number of files in the manifest
+    pub fn write_checksums(&self) -> Result<usize> {
+        let manifest = ChecksumManifest::from_database(&self.database, self.storage.as_ref())?;
+        manifest.write(self.storage.as_ref(), self.signing_key.as_ref())?;
+        info!(
+            "🔏 Wrote {} with {} files{}",
+            self.storage.describe(CHECKSUMS_NAME),
+            manifest.len(),
+            if self.signing_key.is_some() {
+                " (signed)"
+            } else {
+                ""
+            }
+        );
+        Ok(manifest.len())
+    }
+
+    /// Index the text of the cached charts for full-text search
+    ///
+    /// Only the charts added or changed since the last indexing are read, and
+    /// charts no longer in the cache are removed from the index. Once built,
+    /// the index is refreshed by every subsequent sync.
+    ///
+    /// # Returns
+    /// The number of charts indexed
+    pub fn index_text(&self) -> Result<usize> {
+        let mut stale = self.database.get_text_index()?;
+        let mut indexed = 0;
+
+        for entry in self.database.get_all_entries()? {
+            let previous = stale.remove(&(entry.oaci.clone(), entry.vac_type.clone()));
+            let Some(hash) = &entry.file_hash else {
+                continue;
+            };
+            if previous.as_ref() == Some(hash) {
+                continue;
+            }
+            let Some(bytes) = self.storage.read(&entry.file_name)? else {
+                continue;
+            };
+            match pdf::extract_text(&bytes) {
+                Ok(pages) => {
+                    self.database
+                        .set_chart_text(&entry.oaci, &entry.vac_type, hash, &pages)?;
+                    indexed += 1;
+                }
+                Err(e) => warn!("⚠️  Failed to extract the text of {}: {}", entry.oaci, e),
+            }
+        }
+
+        for (oaci, vac_type) in stale.into_keys() {
+            self.database.delete_chart_text(&oaci, &vac_type)?;
+        }
+
+        info!("🔎 Indexed the text of {} charts", indexed);
+        Ok(indexed)
+    }
+
+    /// Find the pages of the cached charts containing a phrase
+    ///
+    /// The search ignores case and accents, and needs the text index built by
+    /// [`index_text`](Self::index_text).
+    pub fn search_text(&self, query: &str, limit: usize) -> Result<Vec<TextMatch>> {
+        if self.database.get_text_index()?.is_empty() {
+            anyhow::bail!("The text index is empty, build it with `sync --index-text`");
+        }
+        Ok(self.database.search_text(query, limit)?)
+    }
+
+    /// Find the airfields of a flight plan, in route order
+    ///
+    /// Accepts GPX, Garmin `.fpl` and SkyDemon flight plans (see
+    /// [`parse_route`](crate::parse_route)). Waypoints are resolved by
+    /// identifier, or by position to an airfield within 3 km, using the
+    /// airfield details stored by the last sync or else the remote listing.
+    ///
+    /// # Returns
+    /// The OACI codes of the airfields along the route
+    pub fn route_airfields<P: AsRef<Path>>(&self, flight_plan: P) -> Result<Vec<String>> {
+        let path = flight_plan.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .context(format!("Failed to read flight plan {}", path.display()))?;
+        let points = route::parse_route(&contents)
+            .context(format!("Failed to read flight plan {}", path.display()))?;
+
+        let mut airfields = self.database.get_airfields()?;
+        if airfields.is_empty() {
+            airfields = self.fetch_oacis_listing()?.airfields.to_vec();
+        }
+        let codes = route::resolve_airfields(&points, &airfields);
+        if codes.is_empty() {
+            anyhow::bail!("No airfield found along the route of {}", path.display());
+        }
+        info!(
+            "🧭 {} waypoints, airfields along the route: {}",
+            points.len(),
+            codes.join(", ")
+        );
+        Ok(codes)
+    }
+
+    /// Find the airfields of French regions or departments, sorted by OACI code
+    ///
+    /// Regions are given by name, e.g. "bretagne", and departments by number
+    /// or name, e.g. "44" or "loire-atlantique". Airfields are placed in a
+    /// department from their position (see [`crate::regions`]), using
+    /// the remote listing or else the airfield details stored by the last sync.
+    ///
+    /// # Returns
+    /// The OACI codes of the airfields in any of the regions or departments
+    pub fn area_airfields(
+        &self,
+        region_names: &[String],
+        department_names: &[String],
+    ) -> Result<Vec<String>> {
+        let selected_regions = region_names
+            .iter()
+            .map(|name| {
+                regions::find_region(name).with_context(|| {
+                    format!(
+                        "Unknown region {}, expected one of: {}",
+                        name,
+                        regions::REGIONS.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let selected_departments = department_names
+            .iter()
+            .map(|name| {
+                regions::find_department(name).with_context(|| {
+                    format!("Unknown department {}, give its number or name", name)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let codes: Vec<String> = self
+            .located_airfields()?
+            .into_iter()
+            .filter(|airfield| {
+                regions::airfield_department(airfield).is_some_and(|department| {
+                    selected_regions.contains(&department.region)
+                        || selected_departments.contains(&department)
+                })
+            })
+            .map(|airfield| airfield.code)
+            .collect();
+
+        let names: Vec<&str> = selected_regions
+            .iter()
+            .copied()
+            .chain(
+                selected_departments
+                    .iter()
+                    .map(|department| department.name),
+            )
+            .collect();
+        if codes.is_empty() {
+            anyhow::bail!("No airfield found in {}", names.join(", "));
+        }
+        info!(
+            "🗺️  Airfields in {}: {}",
+            names.join(", "),
+            codes.join(", ")
+        );
+        Ok(codes)
+    }
+
+    /// Find the airfields located in a rectangle, sorted by OACI code
+    ///
+    /// Positions come from the remote listing or else from the airfield
+    /// details stored by the last sync.
+    ///
+    /// # Returns
+    /// The OACI codes of the airfields in the rectangle
+    pub fn bbox_airfields(&self, bbox: &BoundingBox) -> Result<Vec<String>> {
+        let codes: Vec<String> = self
+            .located_airfields()?
+            .into_iter()
+            .filter(|airfield| {
+                airfield
+                    .coordinates()
+                    .is_some_and(|position| bbox.contains(position))
+            })
+            .map(|airfield| airfield.code)
+            .collect();
+        if codes.is_empty() {
+            anyhow::bail!("No airfield found in the bounding box {}", bbox);
+        }
+        info!("🗺️  Airfields in {}: {}", bbox, codes.join(", "));
+        Ok(codes)
+    }
+
+    /// Airfields of the remote listing, or else the stored ones, sorted by OACI code
+    fn located_airfields(&self) -> Result<Vec<OacisEntry>> {
+        let mut airfields = match self.fetch_oacis_listing() {
+            Ok(listing) => listing.airfields.to_vec(),
+            Err(e) => {
+                warn!(
+                    "⚠️  Failed to fetch OACIS data, using the stored airfields: {:#}",
+                    e
+                );
+                self.database.get_airfields()?
+            }
+        };
+        airfields.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(airfields)
+    }
+
+    /// Export the cached airfields for mapping tools
+    ///
+    /// Positions and other details come from the listing stored by the last
+    /// sync, so airfields cached before it are left out until the next one.
+    ///
+    /// # Returns
+    /// The number of exported airfields
+    pub fn export_airfields(&self, format: ExportFormat, output: &Path) -> Result<usize> {
+        let export = AirfieldExport::from_database(&self.database, self.storage.as_ref())?;
+        if !export.unknown.is_empty() {
+            warn!(
+                "⚠️  No details stored for {}, sync to fetch them",
+                export.unknown.join(", ")
+            );
+        }
+        let unlocated = export.unlocated();
+        if format.needs_position() && !unlocated.is_empty() {
+            warn!("⚠️  No known position for {}", unlocated.join(", "));
+        }
+        std::fs::write(output, export.render(format)?)
+            .context(format!("Failed to write {}", output.display()))?;
+        info!(
+            "🗺️  Exported {} airfields to {}",
+            export.len(format),
+            output.display()
+        );
+        Ok(export.len(format))
+    }
+
+    /// Merge the cached charts of airfields into a single PDF for a trip
+    ///
+    /// The PDF starts with a table of contents and has one bookmark per chart.
+    /// Airfields appear in the given order, e.g. the order of a route, with
+    /// their charts sorted by map type.
+    ///
+    /// # Arguments
+    /// * `oacis` - OACI codes of the airfields, which must all be cached
+    /// * `output` - Path of the PDF to create
+    /// * `layout` - Page layout, e.g. [`TripLayout::Booklet`] to print an A5 booklet
+    pub fn write_trip_pdf<P: AsRef<Path>>(
+        &self,
+        oacis: &[String],
+        output: P,
+        layout: TripLayout,
+    ) -> Result<TripPdf> {
+        let output = output.as_ref();
+        let cached = self.database.get_all_entries()?;
+
+        let mut charts = Vec::new();
+        let mut missing = Vec::new();
+        for oaci in oacis {
+            let mut entries: Vec<&VacEntry> = cached
+                .iter()
+                .filter(|entry| entry.oaci.eq_ignore_ascii_case(oaci))
+                .collect();
+            if entries.is_empty() {
+                missing.push(oaci.to_uppercase());
+                continue;
+            }
+            entries.sort_by(|a, b| a.vac_type.cmp(&b.vac_type));
+            for entry in entries {
+                let pdf = self.storage.read(&entry.file_name)?.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "PDF file for {} not found at {}",
+                        entry.oaci,
+                        self.storage.describe(&entry.file_name)
+                    )
+                })?;
+                charts.push(TripChart {
+                    title: format!("{} - {} ({})", entry.oaci, entry.city, entry.vac_type),
+                    pdf,
+                });
+            }
+        }
+        if !missing.is_empty() {
+            anyhow::bail!("Not in the cache, sync them first: {}", missing.join(", "));
+        }
+
+        // Long routes would not fit on the cover page
+        let heading = if oacis.len() <= 6 {
+            let route: Vec<String> = oacis.iter().map(|oaci| oaci.to_uppercase()).collect();
+            format!("VAC - {}", route.join(" - "))
+        } else {
+            format!("VAC - {} airfields", oacis.len())
+        };
+        let (pdf, summary) = trip::merge(charts, &heading, layout)?;
+        std::fs::write(output, pdf).context(format!("Failed to write {:?}", output))?;
+        info!(
+            "📚 Wrote {:?} with {} charts ({} pages)",
+            output,
+            summary.charts.len(),
+            summary.pages
+        );
+        Ok(summary)
+    }
+
+    /// Export cached entries and their PDFs into a single bundle archive
+    ///
+    /// # Arguments
+    /// * `output` - Path of the archive to create
+    /// * `oaci_filter` - Optional list of OACI codes to include. If None, all cached entries are exported.
+    ///
+    /// # Returns
+    /// The manifest written into the bundle
+    pub fn export_bundle<P: AsRef<Path>>(
+        &self,
+        output: P,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<BundleManifest> {
+        info!("📦 Exporting bundle to {:?}...", output.as_ref());
+        let manifest = bundle::export_bundle(
+            &self.database,
+            self.storage.as_ref(),
+            output.as_ref(),
+            oaci_filter,
+        )?;
+        info!("✓ Exported {} entries", manifest.entries.len());
+        Ok(manifest)
+    }
+
+    /// Export cached charts as a zipped content pack for EFB apps
+    ///
+    /// The pack follows the ForeFlight layout: a `manifest.json` and the
+    /// charts in the Bring Your Own Plates folder, named so that the app files
+    /// them under their airport.
+    ///
+    /// # Arguments
+    /// * `output` - Path of the zip file to create
+    /// * `name` - Name of the pack shown in the app
+    /// * `abbreviation` - Short name of the pack
+    /// * `oaci_filter` - Optional list of OACI codes to include. If None, all cached entries are exported.
+    pub fn export_pack<P: AsRef<Path>>(
+        &self,
+        output: P,
+        name: &str,
+        abbreviation: &str,
+        oaci_filter: Option<&[String]>,
+    ) -> Result<ContentPack> {
+        info!("📦 Exporting content pack to {:?}...", output.as_ref());
+        let pack = pack::export_pack(
+            &self.database,
+            self.storage.as_ref(),
+            ContentPack::new(name, abbreviation),
+            output.as_ref(),
+            oaci_filter,
+        )?;
+        info!("✓ Exported {} charts", pack.plates.len());
+        Ok(pack)
+    }
+
+    /// Import a bundle archive created by [`VacDownloader::export_bundle`]
+    ///
+    /// PDFs are verified against the hashes recorded in the bundle manifest
+    /// before being written to the download directory.
+    ///
+    /// # Arguments
+    /// * `bundle` - Path of the archive to import
+    pub fn import_bundle<P: AsRef<Path>>(&self, bundle: P) -> Result<BundleImport> {
+        let _lock = self.lock()?;
+        info!("📦 Importing bundle from {:?}...", bundle.as_ref());
+        let result = bundle::import_bundle(&self.database, self.storage.as_ref(), bundle.as_ref())?;
+        self.audit(
+            result
+                .imported
+                .iter()
+                .map(|entry| {
+                    AuditEntry::chart(
+                        AuditAction::Download,
+                        entry,
+                        format!("restored from the bundle {}", bundle.as_ref().display()),
+                    )
+                })
+                .collect(),
+        );
+        info!("✓ Imported {} entries", result.imported.len());
+        for (oaci, reason) in &result.rejected {
+            warn!("✗ Skipped {}: {}", oaci, reason);
+        }
+        Ok(result)
+    }
+
+    /// Report how much storage the cache uses
+    ///
+    /// Sizes are given both as recorded in the cache and as actually stored,
+    /// which differ when files are missing or were modified.
+    pub fn storage_report(&self) -> Result<StorageReport> {
+        let entries = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?;
+
+        let mut report = StorageReport {
+            database_size: self.database.database_size()?,
+            ..Default::default()
+        };
+        let mut airfields: BTreeMap<String, AirfieldUsage> = BTreeMap::new();
+
+        for entry in entries {
+            let cached_size = entry.file_size.max(0) as u64;
+            let stored_size = self.storage.size(&entry.file_name)?;
+
+            let usage = airfields
+                .entry(entry.oaci.clone())
+                .or_insert_with(|| AirfieldUsage {
+                    oaci: entry.oaci.clone(),
+                    city: entry.city.clone(),
+                    entries: 0,
+                    cached_size: 0,
+                    stored_size: 0,
+                    missing_files: 0,
+                });
+            usage.entries += 1;
+            usage.cached_size += cached_size;
+            usage.stored_size += stored_size.unwrap_or(0);
+            if stored_size.is_none() {
+                usage.missing_files += 1;
+            }
+
+            *report.entries_per_type.entry(entry.vac_type).or_default() += 1;
+            report.cached_size += cached_size;
+            report.stored_size += stored_size.unwrap_or(0);
+        }
+
+        let located: HashMap<String, OacisEntry> = self
+            .database
+            .get_cached_airfields()?
+            .into_iter()
+            .map(|airfield| (airfield.code.clone(), airfield))
+            .collect();
+        for usage in airfields.values() {
+            let region = located
+                .get(&usage.oaci)
+                .and_then(regions::airfield_region)
+                .unwrap_or(regions::UNKNOWN_REGION);
+            let region = report.by_region.entry(region.to_string()).or_default();
+            region.airfields += 1;
+            region.entries += usage.entries;
+            region.stored_size += usage.stored_size;
+        }
+
+        report.airfields = airfields.into_values().collect();
+        report
+            .airfields
+            .sort_by_key(|airfield| std::cmp::Reverse(airfield.stored_size));
+
+        Ok(report)
+    }
+
+    /// Import a directory of previously downloaded PDFs into the cache
+    ///
+    /// Files are matched against the remote listing by file name, or by hash
+    /// against the cache, then copied into storage under their resolved name.
+    /// Entries that are already cached with the same version are left untouched.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory containing the PDFs, scanned recursively
+    ///
+    /// # Returns
+    /// The imported entries and the files that could not be matched
+    pub fn import_directory<P: AsRef<Path>>(&self, dir: P) -> Result<DirectoryImport> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            anyhow::bail!("{:?} is not a directory", dir);
+        }
+        let _lock = self.lock()?;
+
+        info!("🌐 Fetching OACIS data from API...");
+        let remote = self.fetch_oacis_data(None)?;
+        let cached = self
+            .database
+            .get_all_entries()
+            .context("Failed to read cache entries")?;
+
+        info!("🔍 Matching PDFs in {:?}...", dir);
+        let matches = import::match_directory(dir, &remote, &cached, &self.file_name_template)?;
+
+        let mut result = DirectoryImport {
+            unmatched: matches.unmatched,
+            duplicates: matches.duplicates,
+            ..Default::default()
+        };
+        let mut claimed_names = HashMap::new();
+
+        for matched in matches.matched {
+            let mut entry = matched.entry;
+            let _span = info_span!("entry", oaci = %entry.oaci).entered();
+
+            let already_cached = cached.iter().any(|c| {
+                c.oaci == entry.oaci
+                    && c.vac_type == entry.vac_type
+                    && c.version == entry.version
+                    && c.file_hash.as_deref() == Some(matched.hash.as_str())
+            });
+            if already_cached {
+                info!("✓ {} already cached", entry.oaci);
+                continue;
+            }
+
+            entry.file_name = self.resolve_file_name(&entry, &mut claimed_names)?;
+            let data = std::fs::read(&matched.path)
+                .context(format!("Failed to read {:?}", matched.path))?;
+            self.archive_superseded(&entry)?;
+            self.storage
+                .write(&entry.file_name, &data)
+                .context(format!("Failed to store {}", entry.file_name))?;
+
+            info!(
+                "✓ {} → {} ({} v{})",
+                matched.path.display(),
+                entry.file_name,
+                entry.oaci,
+                entry.version
+            );
+            entry.file_hash = Some(matched.hash);
+            result.imported.push(entry);
+        }
+
+        self.database
+            .upsert_entries(&result.imported)
+            .context("Failed to update cache")?;
+        self.audit(
+            result
+                .imported
+                .iter()
+                .map(|entry| {
+                    AuditEntry::chart(
+                        AuditAction::Download,
+                        entry,
+                        format!("imported from {}", dir.display()),
+                    )
+                })
+                .collect(),
+        );
+
+        info!("✅ Imported {} entries", result.imported.len());
+        for path in &result.duplicates {
+            warn!("⚠️  Skipped duplicate {}", path.display());
+        }
+        for path in &result.unmatched {
+            warn!("✗ No match for {}", path.display());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Statistics from a sync operation
+#[derive(Debug, Default, Serialize)]
+pub struct SyncStats {
+    pub total_entries: usize,
+    pub to_download: usize,
+    pub downloaded: usize,
+    pub failed: usize,
+    pub up_to_date: usize,
+    pub verified: usize,
+    pub redownloaded_corrupted: usize,
+    /// Total size of the downloaded PDFs
+    pub bytes_downloaded: u64,
+    /// Time spent downloading the PDFs, over which the throughput is computed
+    #[serde(rename = "download_duration_ms", serialize_with = "serialize_millis")]
+    pub download_duration: Duration,
+    /// Entries evicted to respect the maximum cache size
+    pub evicted: usize,
+    /// Downloads skipped because the server answered them with a 404 recently,
+    /// see [`VacDownloader::with_skip_not_found`]
+    pub skipped_not_found: usize,
+    /// Downloads skipped because too many of them failed in a row, see
+    /// [`VacDownloader::with_quarantine_after`]
+    pub quarantined: usize,
+    /// Downloads left in the queue because the time budget was exhausted or
+    /// the sync was cancelled, including the one abandoned when it happened
+    pub deferred: usize,
+    /// AIP supplements downloaded
+    pub supplements_downloaded: usize,
+    /// AIP supplements removed because they no longer apply
+    pub supplements_removed: usize,
+    /// Charts downloaded, with the version they replace
+    pub updates: Vec<ChartUpdate>,
+    /// Downloads that failed, with their error
+    pub failures: Vec<ChartFailure>,
+    /// Outcome of each chart checked, in the order they were processed
+    pub results: Vec<EntryResult>,
+    /// Time taken by the sync
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+/// Outcome of a chart in a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryOutcome {
+    /// A new version, or a copy of a corrupted or missing file, was downloaded
+    Downloaded,
+    /// The cached version is current, or pinned
+    UpToDate,
+    /// The cached version is current and its file was checked against its hash
+    Verified,
+    /// The download failed
+    Failed,
+    /// The download was skipped, the server having reported the file missing recently
+    Skipped,
+    /// The download was skipped, too many of them having failed in a row
+    Quarantined,
+}
+
+/// Result of a chart in a sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryResult {
+    /// OACI code of the airfield, or number of the AIP supplement
+    pub oaci: String,
+    pub vac_type: String,
+    pub outcome: EntryOutcome,
+    /// Error of a failed download
+    pub error: Option<String>,
+    /// Kind of the error of a failed download
+    pub failure: Option<FailureKind>,
+    /// Size of the downloaded PDF
+    pub bytes: u64,
+    /// Time taken by the download, zero for charts not downloaded
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+/// Serialize a duration as a number of milliseconds
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Chart downloaded by a sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartUpdate {
+    pub oaci: String,
+    pub city: String,
+    pub vac_type: String,
+    pub version: String,
+    /// Cached version replaced by the download, None for a new chart
+    pub previous_version: Option<String>,
+    pub file_size: u64,
+}
+
+/// Download that failed during a sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartFailure {
+    /// OACI code of the airfield, or number of the AIP supplement
+    pub oaci: String,
+    pub vac_type: String,
+    pub kind: FailureKind,
+    pub error: String,
+}
+
+/// Kind of a failed download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The server has no file for the chart (404 or 410), e.g. from a stale listing
+    NotFound,
+    /// The server rejected the credentials or denied access (401, 403 or 407)
+    Auth,
+    /// No complete response was received: DNS, connection or timeout error
+    Network,
+    /// The server failed (5xx) or asked to slow down (429)
+    Server,
+    /// Any other failure, e.g. an invalid PDF or a storage error
+    Other,
+}
+
+impl FailureKind {
+    /// Classify a download error
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<DownloadStatusError>() {
+                return match error.status {
+                    StatusCode::NOT_FOUND | StatusCode::GONE => FailureKind::NotFound,
+                    StatusCode::UNAUTHORIZED
+                    | StatusCode::FORBIDDEN
+                    | StatusCode::PROXY_AUTHENTICATION_REQUIRED => FailureKind::Auth,
+                    StatusCode::TOO_MANY_REQUESTS => FailureKind::Server,
+                    status if status.is_server_error() => FailureKind::Server,
+                    _ => FailureKind::Other,
+                };
+            }
+            if cause.is::<TransportError>() {
+                return FailureKind::Network;
+            }
+            if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+                use std::io::ErrorKind;
+                if matches!(
+                    error.kind(),
+                    ErrorKind::TimedOut
+                        | ErrorKind::ConnectionReset
+                        | ErrorKind::ConnectionAborted
+                        | ErrorKind::UnexpectedEof
+                ) {
+                    return FailureKind::Network;
+                }
+            }
+        }
+        FailureKind::Other
+    }
+
+    /// Short description, e.g. "not found"
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::NotFound => "not found",
+            FailureKind::Auth => "authentication",
+            FailureKind::Network => "network",
+            FailureKind::Server => "server error",
+            FailureKind::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl SyncStats {
+    /// Record the outcome of a chart that was not downloaded
+    fn record_result(&mut self, entry: &VacEntry, outcome: EntryOutcome) {
+        self.results.push(EntryResult {
+            oaci: entry.oaci.clone(),
+            vac_type: entry.vac_type.clone(),
+            outcome,
+            error: None,
+            failure: None,
+            bytes: 0,
+            duration: Duration::ZERO,
+        });
+    }
+
+    /// Count a downloaded chart
+    fn record_download(
+        &mut self,
+        entry: &VacEntry,
+        previous_version: Option<String>,
+        size: u64,
+        duration: Duration,
+    ) {
+        self.downloaded += 1;
+        self.bytes_downloaded += size;
+        self.download_duration += duration;
+        self.results.push(EntryResult {
+            oaci: entry.oaci.clone(),
+            vac_type: entry.vac_type.clone(),
+            outcome: EntryOutcome::Downloaded,
+            error: None,
+            failure: None,
+            bytes: size,
+            duration,
+        });
+        self.updates.push(ChartUpdate {
+            oaci: entry.oaci.clone(),
+            city: entry.city.clone(),
+            vac_type: entry.vac_type.clone(),
+            version: entry.version.clone(),
+            previous_version,
+            file_size: size,
+        });
+    }
+
+    /// Count a failed download
+    fn record_failure(
+        &mut self,
+        oaci: &str,
+        vac_type: &str,
+        error: &anyhow::Error,
+        duration: Duration,
+    ) {
+        let kind = FailureKind::of(error);
+        let error = format!("{:#}", error);
+        self.failed += 1;
+        self.failures.push(ChartFailure {
+            oaci: oaci.to_string(),
+            vac_type: vac_type.to_string(),
+            kind,
+            error: error.clone(),
+        });
+        self.results.push(EntryResult {
+            oaci: oaci.to_string(),
+            vac_type: vac_type.to_string(),
+            outcome: EntryOutcome::Failed,
+            error: Some(error),
+            failure: Some(kind),
+            bytes: 0,
+            duration,
+        });
+    }
+
+    /// Number of failed downloads of each kind
+    pub fn failures_by_kind(&self) -> BTreeMap<FailureKind, usize> {
+        let mut counts = BTreeMap::new();
+        for failure in &self.failures {
+            *counts.entry(failure.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Average download throughput in bytes per second, None when nothing was downloaded
+    pub fn throughput(&self) -> Option<u64> {
+        throughput(self.bytes_downloaded, self.download_duration)
+    }
+
+    /// Log the end-of-sync counters as a single event
+    fn log_summary(&self) {
+        info!(
+            total_entries = self.total_entries,
+            up_to_date = self.up_to_date,
+            verified = self.verified,
+            downloaded = self.downloaded,
+            redownloaded_corrupted = self.redownloaded_corrupted,
+            failed = self.failed,
+            skipped_not_found = self.skipped_not_found,
+            quarantined = self.quarantined,
+            evicted = self.evicted,
+            deferred = self.deferred,
+            supplements_downloaded = self.supplements_downloaded,
+            supplements_removed = self.supplements_removed,
+            bytes_downloaded = self.bytes_downloaded,
+            download_ms = self.download_duration.as_millis() as u64,
+            duration_ms = self.duration.as_millis() as u64,
+            "✅ Sync complete!"
+        );
+    }
+}
+
+/// Reason why a sync would download a chart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateReason {
+    /// The chart is not in the cache
+    New,
+    /// A newer version is published
+    Outdated { cached_version: String },
+    /// The cached file is missing from storage
+    Missing,
+    /// The stored file does not match its cached hash
+    Corrupted,
+}
+
+impl std::fmt::Display for UpdateReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateReason::New => write!(f, "new"),
+            UpdateReason::Outdated { cached_version } => {
+                write!(f, "outdated, cached {}", cached_version)
+            }
+            UpdateReason::Missing => write!(f, "file missing"),
+            UpdateReason::Corrupted => write!(f, "file corrupted"),
+        }
+    }
+}
+
+/// Chart that a sync would download
+#[derive(Debug)]
+pub struct AvailableUpdate {
+    /// Entry as published remotely
+    pub entry: VacEntry,
+    pub reason: UpdateReason,
+}
+
+/// Airfield of the remote listing with the state of its charts in the local cache,
+/// as listed by [`VacDownloader::list_airports`]
+#[derive(Debug, Clone, Serialize)]
+pub struct Airport {
+    /// Airfield as published, with its runways, frequencies and coordinates
+    #[serde(flatten)]
+    pub airfield: OacisEntry,
+    /// Charts of the airfield, published or cached
+    pub charts: Vec<ChartSummary>,
+}
+
+impl Airport {
+    /// Whether a chart of the airfield is in the local cache
+    pub fn available_locally(&self) -> bool {
+        self.charts
+            .iter()
+            .any(|chart| chart.cached_version.is_some())
+    }
+}
+
+/// Remote and local state of a chart, as listed by a search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartSummary {
+    pub oaci: String,
+    pub city: String,
+    pub vac_type: String,
+    /// Version published on the server, or None when the chart is only in the cache
+    pub remote_version: Option<String>,
+    /// Version in the local cache, or None when the chart is not cached
+    pub cached_version: Option<String>,
+    /// Size of the PDF, as published or as cached
+    pub file_size: i64,
+    /// Time of the last cache update (UTC, "YYYY-MM-DD HH:MM:SS"), if the chart is cached
+    pub last_updated: Option<String>,
+}
+
+impl ChartSummary {
+    /// Local status of the chart, e.g. "up to date" or "outdated (v1)"
+    pub fn local_status(&self) -> String {
+        match (&self.remote_version, &self.cached_version) {
+            (_, None) => "not cached".to_string(),
+            (None, Some(cached)) => format!("cached ({})", cached),
+            (Some(remote), Some(cached)) if remote == cached => "up to date".to_string(),
+            (Some(_), Some(cached)) => format!("outdated ({})", cached),
+        }
+    }
+
+    /// Whether the chart is cached and a different version is published
+    pub fn is_outdated(&self) -> bool {
+        matches!(
+            (&self.remote_version, &self.cached_version),
+            (Some(remote), Some(cached)) if remote != cached
+        )
+    }
+}
+
+/// Result from a check operation
+#[derive(Debug, Default)]
+pub struct CheckResult {
+    /// Number of charts that are up to date
+    pub up_to_date: usize,
+    /// Charts that a sync would download
+    pub updates: Vec<AvailableUpdate>,
+}
+
+/// Cached charts placed on the AIRAC cycle calendar
+#[derive(Debug)]
+pub struct CycleOutlook {
+    /// Cycle in effect
+    pub current: AiracCycle,
+    /// Cycle beginning after the current one
+    pub next: AiracCycle,
+    /// Charts effective before the next cycle, earliest first; they expire
+    /// when it begins if a new edition is published for it
+    pub expiring: Vec<(VacEntry, NaiveDate)>,
+    /// Charts already published for the next cycle or a later one
+    pub upcoming: Vec<(VacEntry, NaiveDate)>,
+    /// Charts whose version holds no effective date
+    pub undated: Vec<VacEntry>,
+}
+
+/// Storage used by a cached airfield
+#[derive(Debug)]
+pub struct AirfieldUsage {
+    pub oaci: String,
+    pub city: String,
+    /// Number of cached charts
+    pub entries: usize,
+    /// Total size recorded in the cache
+    pub cached_size: u64,
+    /// Total size of the stored files
+    pub stored_size: u64,
+    /// Number of cached charts whose file is missing from storage
+    pub missing_files: usize,
+}
+
+/// Storage usage of the airfields of a region
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// Number of airfields with cached charts
+    pub airfields: usize,
+    /// Number of cached charts
+    pub entries: usize,
+    /// Total size of the stored files
+    pub stored_size: u64,
+}
+
+/// Storage usage report of the cache
+#[derive(Debug, Default)]
+pub struct StorageReport {
+    /// Usage per airfield, largest first
+    pub airfields: Vec<AirfieldUsage>,
+    /// Usage per French region (see [`crate::regions`]), airfields outside
+    /// of the regions or of unknown position being counted in
+    /// [`regions::UNKNOWN_REGION`]
+    pub by_region: BTreeMap<String, RegionUsage>,
+    /// Number of cached charts per VAC type
+    pub entries_per_type: BTreeMap<String, usize>,
+    /// Total size recorded in the cache
+    pub cached_size: u64,
+    /// Total size of the stored files
+    pub stored_size: u64,
+    /// Size of the SQLite database
+    pub database_size: u64,
+}
+
+/// Iterator over the airfields of the OACIS listing, returned by [`VacDownloader::iter_oacis`]
+pub struct OacisIter<'a> {
+    downloader: &'a VacDownloader,
+    /// Next page to fetch
+    page: u32,
+    /// Airfields fetched so far, before the territory filter
+    fetched: usize,
+    /// Airfields of the last page not yielded yet
+    members: std::vec::IntoIter<OacisEntry>,
+    done: bool,
+}
+
+impl Iterator for OacisIter<'_> {
+    type Item = Result<OacisEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(airfield) = self.members.next() {
+                if self.downloader.in_territories(&airfield.code) {
+                    return Some(Ok(airfield));
+                }
+                continue;
+            }
+            if self.done {
+                return None;
+            }
+            match self.downloader.fetch_oacis_page(self.page, &[]) {
+                Ok(response) => {
+                    self.fetched += response.members.len();
+                    self.done = response.members.is_empty()
+                        || self.fetched >= response.total_items as usize;
+                    self.page += 1;
+                    self.members = response.members.into_iter();
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Result from a gc operation
+#[derive(Debug, Default)]
+pub struct GcResult {
+    /// Previous versions beyond the retention policy
+    pub expired: Vec<ChartVersion>,
+    /// Previous versions compressed, or that would be by a dry run
+    pub compressed: Vec<ChartVersion>,
+    /// Size of the files removed and saved by compression; a dry run only
+    /// counts the files it would remove
+    pub freed: u64,
+}
+
+/// Result from a clean operation
+#[derive(Debug)]
+pub struct CleanResult {
+    /// Files with no corresponding cache entry
+    pub orphans: Vec<String>,
+    /// Orphaned files that were deleted
+    pub removed: Vec<String>,
+}
+
+/// Result from a dedupe operation
+#[derive(Debug, Default)]
+pub struct DedupeResult {
+    /// Files replaced with a link, or that would be by a dry run, with the
+    /// file they are linked to
+    pub linked: Vec<(String, String)>,
+    /// Size of the linked files, saved on disk
+    pub saved: u64,
+}
+
+/// Result from a verify operation
+#[derive(Debug)]
+pub struct VerifyResult {
+    /// Number of charts found valid
+    pub verified: usize,
+    /// Charts failing verification
+    pub problems: Vec<(VacEntry, VerifyProblem)>,
+}
+
+/// Why a cached chart failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyProblem {
+    /// The file is not in the storage
+    Missing,
+    /// The file does not match the recorded hash
+    HashMismatch,
+    /// The file is not a readable PDF with at least one page
+    InvalidPdf(String),
+}
+
+impl std::fmt::Display for VerifyProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyProblem::Missing => write!(f, "file missing"),
+            VerifyProblem::HashMismatch => write!(f, "hash mismatch"),
+            VerifyProblem::InvalidPdf(reason) => write!(f, "invalid PDF ({})", reason),
+        }
+    }
+}
+
+/// Error ending a sync stopped by the circuit breaker
+fn circuit_open_error(failure: PersistentFailure, count: usize, remaining: usize) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Stopped the sync after {} downloads in a row failed with {}, {} left in the download queue: {}",
+        count,
+        failure,
+        remaining,
+        failure.hint()
+    )
+}
+
+/// Result of a rollback
+#[derive(Debug)]
+pub struct RollbackResult {
+    /// Restored entry, now cached
+    pub entry: VacEntry,
+    /// Version that was cached before the rollback
+    pub from_version: String,
+}
+
+/// Result from a delete operation, for one entry
+#[derive(Debug)]
+pub struct DeleteResult {
     pub oaci: String,
+    /// Map type of the entry, or the requested one (if any) when nothing matched
+    pub vac_type: Option<String>,
     pub database_deleted: bool,
     pub file_deleted: bool,
     pub file_name: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::MirrorEntry;
+    use crate::testing::MockApi;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_delete_by_pattern_and_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            VacDownloader::new(dir.path().join("vac_cache.db"), dir.path().join("pdfs")).unwrap();
+        for (oaci, vac_type) in [
+            ("LFPG", "AD"),
+            ("LFPG", "HEL"),
+            ("LFPO", "AD"),
+            ("LFRB", "AD"),
+        ] {
+            let file_name = format!("{}_{}.pdf", oaci, vac_type);
+            downloader.storage.write(&file_name, b"%PDF").unwrap();
+            downloader
+                .database
+                .upsert_entry(&VacEntry {
+                    oaci: oaci.to_string(),
+                    city: "Paris".to_string(),
+                    vac_type: vac_type.to_string(),
+                    version: "1.0".to_string(),
+                    file_name,
+                    remote_file_name: None,
+                    file_size: 4,
+                    file_hash: None,
+                    page_count: None,
+                    available_locally: false,
+                })
+                .unwrap();
+        }
+
+        let results = downloader
+            .delete(&["LFP*".to_string(), "LFXX".to_string()], Some("ad"))
+            .unwrap();
+        let deleted: Vec<(&str, bool, bool)> = results
+            .iter()
+            .map(|r| (r.oaci.as_str(), r.database_deleted, r.file_deleted))
+            .collect();
+        assert_eq!(
+            deleted,
+            vec![
+                ("LFPG", true, true),
+                ("LFPO", true, true),
+                ("LFXX", false, false)
+            ]
+        );
+
+        let remaining: Vec<(String, String)> = downloader
+            .database
+            .get_all_entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.oaci, entry.vac_type))
+            .collect();
+        assert_eq!(
+            remaining,
+            vec![
+                ("LFPG".to_string(), "HEL".to_string()),
+                ("LFRB".to_string(), "AD".to_string())
+            ]
+        );
+        assert!(downloader.storage.exists("LFPG_HEL.pdf").unwrap());
+        assert!(!downloader.storage.exists("LFPG_AD.pdf").unwrap());
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            VacDownloader::new(dir.path().join("vac_cache.db"), dir.path().join("pdfs")).unwrap();
+
+        let valid = crate::pdf::tests::sample_pdf(2);
+        let files: [(&str, Option<&[u8]>); 4] = [
+            ("LFPG", Some(&valid)),
+            ("LFPO", Some(b"<html>Error</html>")),
+            ("LFPB", Some(b"%PDF-tampered")),
+            ("LFPN", None),
+        ];
+        for (oaci, contents) in files {
+            let file_name = format!("{}_AD.pdf", oaci);
+            let mut hasher = Sha256::new();
+            hasher.update(contents.unwrap_or_default());
+            if let Some(contents) = contents {
+                downloader.storage.write(&file_name, contents).unwrap();
+            }
+            downloader
+                .database
+                .upsert_entry(&VacEntry {
+                    oaci: oaci.to_string(),
+                    city: "Paris".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1.0".to_string(),
+                    file_name,
+                    remote_file_name: None,
+                    file_size: 4,
+                    file_hash: Some(format!("{:x}", hasher.finalize())),
+                    page_count: None,
+                    available_locally: false,
+                })
+                .unwrap();
+        }
+        downloader
+            .storage
+            .write("LFPB_AD.pdf", b"%PDF-corrupted")
+            .unwrap();
+
+        let result = downloader.verify().unwrap();
+        assert_eq!(result.verified, 1);
+        let problems: Vec<(&str, &VerifyProblem)> = result
+            .problems
+            .iter()
+            .map(|(entry, problem)| (entry.oaci.as_str(), problem))
+            .collect();
+        assert_eq!(problems.len(), 3);
+        assert_eq!(problems[0], ("LFPB", &VerifyProblem::HashMismatch));
+        assert_eq!(problems[1], ("LFPN", &VerifyProblem::Missing));
+        assert_eq!(problems[2].0, "LFPO");
+        assert!(matches!(problems[2].1, VerifyProblem::InvalidPdf(_)));
+
+        let entries = downloader.database.get_all_entries().unwrap();
+        let lfpg = entries.iter().find(|entry| entry.oaci == "LFPG").unwrap();
+        assert_eq!(lfpg.page_count, Some(2));
+    }
+
+    #[test]
+    fn test_index_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            VacDownloader::new(dir.path().join("vac_cache.db"), dir.path().join("pdfs")).unwrap();
+        assert!(downloader.search_text("ULM", 10).is_err());
+
+        let pdf = crate::pdf::tests::sample_pdf(1);
+        let mut entry = VacEntry {
+            oaci: "LFPN".to_string(),
+            city: "Toussus le Noble".to_string(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: "LFPN_AD.pdf".to_string(),
+            remote_file_name: None,
+            file_size: pdf.len() as i64,
+            file_hash: Some("abc123".to_string()),
+            page_count: None,
+            available_locally: false,
+        };
+        downloader.storage.write(&entry.file_name, &pdf).unwrap();
+        downloader.database.upsert_entry(&entry).unwrap();
+
+        assert_eq!(downloader.index_text().unwrap(), 1);
+        assert_eq!(downloader.index_text().unwrap(), 0);
+        assert!(downloader.search_text("ULM", 10).unwrap().is_empty());
+
+        entry.file_hash = Some("def456".to_string());
+        downloader.database.upsert_entry(&entry).unwrap();
+        assert_eq!(downloader.index_text().unwrap(), 1);
+
+        downloader.database.delete_entry("LFPN", None).unwrap();
+        downloader.index_text().unwrap();
+        assert!(downloader.database.get_text_index().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_airports() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        api.publish("LFPT", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+        downloader.sync(Some(&["LFPN".to_string()])).unwrap();
+        api.publish("LFPN", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+
+        let airports = downloader.list_airports(None).unwrap();
+        let codes: Vec<&str> = airports.iter().map(|a| a.airfield.code.as_str()).collect();
+        assert_eq!(codes, ["LFPN", "LFPT"]);
+        assert!(airports[0].available_locally());
+        assert!(airports[0].charts[0].is_outdated());
+        assert!(!airports[1].available_locally());
+        assert_eq!(airports[1].charts[0].remote_version.as_deref(), Some("1"));
+
+        let airports = downloader
+            .list_airports(Some(&["LFPT".to_string()]))
+            .unwrap();
+        assert_eq!(airports.len(), 1);
+        let json = serde_json::to_value(&airports[0]).unwrap();
+        assert_eq!(json["code"], "LFPT");
+        assert_eq!(json["charts"][0]["vac_type"], "AD");
+    }
+
+    #[test]
+    fn test_iter_oacis() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            api.publish(oaci, "1");
+        }
+        api.set_page_size(2);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+
+        // Pages are only fetched when their airfields are needed
+        let first = downloader.iter_oacis().next().unwrap().unwrap();
+        assert_eq!(first.code, "LFPN");
+        assert_eq!(api.requests(), ["/api/v1/oacis?page=1"]);
+
+        let codes: Vec<String> = downloader
+            .iter_oacis()
+            .map(|airfield| airfield.unwrap().code)
+            .collect();
+        assert_eq!(codes, ["LFPN", "LFPT", "LFPZ"]);
+        assert_eq!(api.requests().len(), 3);
+
+        api.fail("/api/v1/oacis?page=2", StatusCode::INTERNAL_SERVER_ERROR);
+        let results: Vec<Result<OacisEntry>> = downloader.iter_oacis().collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        /// Cancels the first sync when its second download starts
+        struct CancelSecond(CancellationToken, std::sync::Once);
+        impl SyncObserver for CancelSecond {
+            fn download_started(&self, _entry: &VacEntry, index: usize, _total: usize) {
+                if index == 1 {
+                    self.1.call_once(|| self.0.cancel());
+                }
+            }
+        }
+
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            api.publish(oaci, "1");
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let cancel = CancellationToken::new();
+        let downloader = api
+            .downloader(dir.path())
+            .with_cancellation(cancel.clone())
+            .with_observer(Arc::new(CancelSecond(
+                cancel.clone(),
+                std::sync::Once::new(),
+            )));
+
+        // The download in progress is abandoned and stays queued with the next one
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.downloaded, stats.deferred), (1, 2));
+        assert_eq!(downloader.pending_downloads().unwrap().len(), 2);
+
+        // Listings stop before fetching a page
+        downloader.oacis_cache.lock().unwrap().take();
+        let requests = api.requests().len();
+        assert!(downloader.list_vacs(None).is_err());
+        assert!(downloader.iter_oacis().next().unwrap().is_err());
+        assert_eq!(api.requests().len(), requests);
+
+        // The abandoned download left no staging file behind
+        let stored = LocalStorage::new(dir.path().join("pdfs")).unwrap().list();
+        assert_eq!(stored.unwrap(), ["AD-2.LFPN.pdf"]);
+
+        cancel.reset();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.downloaded, stats.deferred), (2, 0));
+        assert!(downloader.pending_downloads().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stream_listing() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            api.publish(oaci, "1");
+        }
+        api.set_page_size(2);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_stream_listing(true);
+
+        // The charts of each page are downloaded before the next page is fetched
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.total_entries, stats.downloaded), (3, 3));
+        assert_eq!(
+            api.requests(),
+            [
+                "/api/v1/oacis?page=1",
+                "/api/v1/custom/file-path/LFPN/AD",
+                "/api/v1/custom/file-path/LFPT/AD",
+                "/api/v1/oacis?page=2",
+                "/api/v1/custom/file-path/LFPZ/AD",
+            ]
+        );
+        assert!(downloader.oacis_cache.lock().unwrap().is_none());
+
+        api.publish("LFPZ", "2");
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.up_to_date, stats.downloaded), (2, 1));
+        assert!(downloader.pending_downloads().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_download_progress() {
+        /// Records the progress of each download
+        #[derive(Default)]
+        struct Progress(Mutex<Vec<(String, u64, Option<u64>)>>);
+        impl SyncObserver for Progress {
+            fn download_progress(&self, entry: &VacEntry, received: u64, total: Option<u64>) {
+                let mut events = self.0.lock().unwrap();
+                events.push((entry.oaci.clone(), received, total));
+            }
+        }
+
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT"] {
+            api.publish(oaci, "1");
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let progress = Arc::new(Progress::default());
+        let downloader = api.downloader(dir.path()).with_observer(progress.clone());
+        downloader.sync(None).unwrap();
+
+        let events = progress.0.lock().unwrap();
+        for oaci in ["LFPN", "LFPT"] {
+            let (_, received, total) = events.iter().rev().find(|e| e.0 == oaci).unwrap();
+            let size = std::fs::metadata(dir.path().join(format!("pdfs/AD-2.{}.pdf", oaci)))
+                .unwrap()
+                .len();
+            assert_eq!((*received, *total), (size, Some(size)));
+        }
+        let stored = LocalStorage::new(dir.path().join("pdfs")).unwrap().list();
+        assert_eq!(stored.unwrap(), ["AD-2.LFPN.pdf", "AD-2.LFPT.pdf"]);
+    }
+
+    #[test]
+    fn test_sync_against_mock_api() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            api.publish(oaci, "1");
+        }
+        api.set_page_size(2);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+
+        // The first sync reads every page of the listing and downloads every chart
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.total_entries, stats.downloaded), (3, 3));
+        assert_eq!(
+            api.requests()[..2],
+            ["/api/v1/oacis?page=1", "/api/v1/oacis?page=2"]
+        );
+        assert!(dir.path().join("pdfs/AD-2.LFPT.pdf").exists());
+
+        // Nothing changed, nothing is downloaded
+        downloader.oacis_cache.lock().unwrap().take();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.up_to_date, stats.downloaded), (3, 0));
+        assert_eq!(api.file_requests().len(), 3);
+
+        // A new version is reported by check, then downloaded
+        api.publish("LFPT", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        let check = downloader.check(None).unwrap();
+        assert_eq!(check.updates.len(), 1);
+        assert_eq!(check.updates[0].entry.oaci, "LFPT");
+        let outdated = downloader.list_outdated(None).unwrap();
+        assert_eq!(outdated.len(), 1);
+        assert_eq!(
+            (
+                outdated[0].cached_version.as_deref(),
+                outdated[0].remote_version.as_deref()
+            ),
+            (Some("1"), Some("2"))
+        );
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.up_to_date, stats.downloaded), (2, 1));
+        let outcomes: Vec<(&str, EntryOutcome)> = stats
+            .results
+            .iter()
+            .map(|result| (result.oaci.as_str(), result.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("LFPN", EntryOutcome::Verified),
+                ("LFPZ", EntryOutcome::Verified),
+                ("LFPT", EntryOutcome::Downloaded),
+            ]
+        );
+        assert!(stats.results[2].bytes > 0);
+        assert_eq!(stats.download_duration, stats.results[2].duration);
+        assert!(stats.download_duration.is_zero() || stats.throughput().is_some());
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["results"][2]["outcome"], "downloaded");
+        assert_eq!(json["results"][2]["error"], serde_json::Value::Null);
+
+        // A corrupted file is found by verify and downloaded again
+        std::fs::write(dir.path().join("pdfs/AD-2.LFPN.pdf"), b"garbage").unwrap();
+        let verify = downloader.verify().unwrap();
+        assert_eq!(verify.verified, 2);
+        assert_eq!(verify.problems[0].0.oaci, "LFPN");
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.redownloaded_corrupted, stats.downloaded), (1, 1));
+        assert!(downloader.verify().unwrap().problems.is_empty());
+
+        // Every change is recorded in the audit log with its reason
+        downloader.delete(&["LFPZ".to_string()], None).unwrap();
+        let reasons = |oaci: &str| -> Vec<(AuditAction, String)> {
+            let log = downloader.audit_log(Some(oaci), 10).unwrap();
+            log.into_iter()
+                .map(|entry| (entry.action, entry.reason))
+                .collect()
+        };
+        assert_eq!(
+            reasons("LFPT"),
+            [
+                (AuditAction::Download, "version 1 updated to 2".to_string()),
+                (
+                    AuditAction::Move,
+                    "version 1 archived, superseded by 2".to_string()
+                ),
+                (AuditAction::Download, "new chart, version 1".to_string()),
+            ]
+        );
+        assert_eq!(reasons("LFPN")[0].1, "version 1 downloaded again");
+        assert_eq!(reasons("LFPZ")[0].0, AuditAction::Delete);
+        assert_eq!(downloader.audit_log(None, 100).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let versioned = || {
+            api.downloader(dir.path())
+                .with_file_name_template(FileNameTemplate::versioned())
+        };
+        let downloader = versioned();
+        downloader.sync(None).unwrap();
+        api.publish("LFPN", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader.sync(None).unwrap();
+
+        let err = downloader.rollback("LFPN", "AD", Some("3")).unwrap_err();
+        assert!(err.to_string().contains("previous versions: 1"), "{}", err);
+        let result = downloader.rollback("lfpn", "AD", None).unwrap();
+        assert_eq!(
+            (result.from_version.as_str(), result.entry.version.as_str()),
+            ("2", "1")
+        );
+        assert_eq!(
+            downloader
+                .database
+                .get_cached_version("LFPN", "AD")
+                .unwrap()
+                .as_deref(),
+            Some("1")
+        );
+
+        // Syncs keep the restored version, and clean keeps the newer file
+        downloader.oacis_cache.lock().unwrap().take();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.up_to_date, stats.downloaded), (1, 0));
+        assert_eq!(api.file_requests().len(), 2);
+        assert!(downloader.find_orphans().unwrap().is_empty());
+
+        // A forced download releases the pin
+        let forced = versioned().with_force_oacis(vec!["LFPN".to_string()]);
+        assert_eq!(forced.sync(None).unwrap().downloaded, 1);
+        assert_eq!(
+            forced.database.get_pinned_version("LFPN", "AD").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rollback_archived() {
+        let api = MockApi::new();
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+        let read = |name: &str| downloader.storage.read(name).unwrap();
+        api.publish("LFPN", "1");
+        downloader.sync(None).unwrap();
+        let first = read("AD-2.LFPN.pdf").unwrap();
+
+        // The superseded version is moved to the archive before being overwritten
+        api.publish("LFPN", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader.sync(None).unwrap();
+        let second = read("AD-2.LFPN.pdf").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(read("archive/LFPN_AD_v1.pdf"), Some(first.clone()));
+        let versions = downloader
+            .database
+            .get_chart_versions("LFPN", "AD")
+            .unwrap();
+        let file_names: Vec<(&str, &str)> = versions
+            .iter()
+            .map(|v| (v.version.as_str(), v.file_name.as_str()))
+            .collect();
+        assert_eq!(
+            file_names,
+            [("2", "AD-2.LFPN.pdf"), ("1", "archive/LFPN_AD_v1.pdf")]
+        );
+        assert!(downloader.find_orphans().unwrap().is_empty());
+
+        // Rolling back swaps the archived and the cached files
+        let result = downloader.rollback("LFPN", "AD", Some("1")).unwrap();
+        assert_eq!(result.entry.file_name, "AD-2.LFPN.pdf");
+        assert_eq!(read("AD-2.LFPN.pdf"), Some(first));
+        assert_eq!(read("archive/LFPN_AD_v2.pdf"), Some(second));
+        assert_eq!(read("archive/LFPN_AD_v1.pdf"), None);
+        assert!(downloader.verify().unwrap().problems.is_empty());
+        assert!(downloader.find_orphans().unwrap().is_empty());
+
+        downloader.oacis_cache.lock().unwrap().take();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.up_to_date, stats.downloaded), (1, 0));
+    }
+
+    #[test]
+    fn test_gc_archive() {
+        let api = MockApi::new();
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_keep_versions(2);
+        for version in ["1", "2", "3", "4"] {
+            api.publish("LFPN", version);
+            downloader.oacis_cache.lock().unwrap().take();
+            downloader.sync(None).unwrap();
+        }
+
+        // The policy is applied to the archive after each sync
+        let mut stored = downloader.storage.list().unwrap();
+        stored.sort();
+        assert_eq!(
+            stored,
+            [
+                "AD-2.LFPN.pdf",
+                "archive/LFPN_AD_v2.pdf",
+                "archive/LFPN_AD_v3.pdf"
+            ]
+        );
+        let versions: Vec<String> = downloader
+            .database
+            .get_chart_versions("LFPN", "AD")
+            .unwrap()
+            .into_iter()
+            .map(|v| v.version)
+            .collect();
+        assert_eq!(versions, ["4", "3", "2"]);
+        assert!(downloader.find_orphans().unwrap().is_empty());
+        assert_eq!(
+            downloader
+                .rollback("LFPN", "AD", Some("2"))
+                .unwrap()
+                .from_version,
+            "4"
+        );
+
+        // Without previous versions to keep, nothing is archived anymore
+        let downloader = downloader.with_keep_versions(0);
+        let result = downloader.gc(false).unwrap();
+        assert_eq!(result.expired.len(), 2);
+        assert!(result.freed > 0);
+        assert_eq!(downloader.storage.list().unwrap(), ["AD-2.LFPN.pdf"]);
+        let forced = api
+            .downloader(dir.path())
+            .with_keep_versions(0)
+            .with_force_oacis(vec!["LFPN".to_string()]);
+        forced.sync(None).unwrap();
+        assert_eq!(forced.storage.list().unwrap(), ["AD-2.LFPN.pdf"]);
+        assert!(forced.find_orphans().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gc() {
+        let api = MockApi::new();
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api
+            .downloader(dir.path())
+            .with_file_name_template(FileNameTemplate::versioned());
+        for version in ["1", "2", "3"] {
+            api.publish("LFPN", version);
+            downloader.oacis_cache.lock().unwrap().take();
+            downloader.sync(None).unwrap();
+        }
+        let file_names = downloader.database.get_version_file_names().unwrap();
+        assert_eq!(file_names.len(), 3);
+
+        // Without a policy, every previous version is kept
+        assert!(downloader.gc(false).unwrap().expired.is_empty());
+
+        // Recent versions are within keep_days
+        let downloader = downloader.with_keep_days(1);
+        assert!(downloader.gc(false).unwrap().expired.is_empty());
+
+        let downloader = downloader.with_keep_versions(1);
+        let result = downloader.gc(true).unwrap();
+        let expired: Vec<&str> = result.expired.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(expired, ["1"]);
+        assert!(result.freed > 0);
+        assert_eq!(
+            downloader.database.get_version_file_names().unwrap().len(),
+            3
+        );
+
+        let result = downloader.gc(false).unwrap();
+        assert_eq!(result.expired.len(), 1);
+        let remaining = downloader.database.get_version_file_names().unwrap();
+        assert_eq!(remaining.len(), 2);
+        for name in &file_names {
+            assert_eq!(
+                dir.path().join("pdfs").join(name).exists(),
+                remaining.contains(name),
+                "{}",
+                name
+            );
+        }
+        assert!(downloader.rollback("LFPN", "AD", Some("1")).is_err());
+        assert!(downloader.gc(false).unwrap().expired.is_empty());
+    }
+
+    #[test]
+    fn test_read_only() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+
+        // A missing database is created
+        let downloader = api.builder(dir.path()).read_only(true).build().unwrap();
+        downloader.sync(None).unwrap();
+        drop(downloader);
+
+        let downloader = api
+            .builder(dir.path())
+            .read_only(true)
+            .build()
+            .unwrap()
+            .with_offline(true);
+        let requests = api.requests().len();
+        let charts = downloader.list_charts(None).unwrap();
+        assert_eq!(charts.len(), 1);
+        assert_eq!(charts[0].cached_version.as_deref(), Some("1"));
+        assert_eq!(downloader.search("LFPN").unwrap().len(), 1);
+        assert_eq!(api.requests().len(), requests);
+        assert!(downloader.get_pdf_path("LFPN").is_ok());
+
+        api.publish("LFPN", "2");
+        assert!(downloader.sync(None).is_err());
+        assert_eq!(
+            downloader
+                .database
+                .get_cached_version("LFPN", "AD")
+                .unwrap()
+                .as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_compress_versions() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api
+            .downloader(dir.path())
+            .with_file_name_template(FileNameTemplate::versioned())
+            .with_compress_versions(true);
+        downloader.sync(None).unwrap();
+        let content = downloader.storage.read("LFPN_AD_v1.pdf").unwrap().unwrap();
+        api.publish("LFPN", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader.sync(None).unwrap();
+
+        // The previous version is compressed after the sync
+        assert!(!downloader.storage.exists("LFPN_AD_v1.pdf").unwrap());
+        assert!(downloader.storage.exists("LFPN_AD_v1.pdf.zst").unwrap());
+        assert!(downloader.find_orphans().unwrap().is_empty());
+        let (version, data) = downloader.extract_version("lfpn", "AD", Some("1")).unwrap();
+        assert!(version.compressed);
+        assert_eq!(data, content);
+
+        // A rollback decompresses the restored version
+        downloader.rollback("LFPN", "AD", None).unwrap();
+        assert_eq!(
+            downloader.storage.read("LFPN_AD_v1.pdf").unwrap(),
+            Some(content)
+        );
+        assert!(!downloader.storage.exists("LFPN_AD_v1.pdf.zst").unwrap());
+        let result = downloader.gc(true).unwrap();
+        assert_eq!(result.compressed.len(), 1);
+        assert!(downloader.storage.exists("LFPN_AD_v2.pdf").unwrap());
+        let result = downloader.gc(false).unwrap();
+        assert_eq!(result.compressed[0].version, "2");
+        assert!(downloader.storage.exists("LFPN_AD_v2.pdf.zst").unwrap());
+        assert!(downloader.gc(false).unwrap().compressed.is_empty());
+        assert!(downloader.verify().unwrap().problems.is_empty());
+    }
+
+    #[test]
+    fn test_compress_archive() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_compress_versions(true);
+        downloader.sync(None).unwrap();
+        let first = downloader.storage.read("AD-2.LFPN.pdf").unwrap().unwrap();
+        api.publish("LFPN", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader.sync(None).unwrap();
+        let second = downloader.storage.read("AD-2.LFPN.pdf").unwrap().unwrap();
+
+        // The archived version is compressed after the sync
+        assert_eq!(
+            downloader.storage.list().unwrap(),
+            ["AD-2.LFPN.pdf", "archive/LFPN_AD_v1.pdf.zst"]
+        );
+        assert!(downloader.find_orphans().unwrap().is_empty());
+        let (version, data) = downloader.extract_version("LFPN", "AD", Some("1")).unwrap();
+        assert!(version.compressed);
+        assert_eq!(data, first);
+
+        // A rollback decompresses it in place of the cached file, archived in turn
+        downloader.rollback("LFPN", "AD", Some("1")).unwrap();
+        assert_eq!(
+            downloader.storage.read("AD-2.LFPN.pdf").unwrap(),
+            Some(first)
+        );
+        assert_eq!(
+            downloader.storage.list().unwrap(),
+            ["AD-2.LFPN.pdf", "archive/LFPN_AD_v2.pdf"]
+        );
+        assert_eq!(downloader.gc(false).unwrap().compressed.len(), 1);
+        let (_, data) = downloader.extract_version("LFPN", "AD", Some("2")).unwrap();
+        assert_eq!(data, second);
+        assert!(downloader.find_orphans().unwrap().is_empty());
+        assert!(downloader.verify().unwrap().problems.is_empty());
+    }
+
+    #[test]
+    fn test_dedupe() {
+        // Versions 1 and 8 of the mock charts have the same content
+        let api = MockApi::new();
+        api.publish("LFPG", "1");
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api
+            .downloader(dir.path())
+            .with_file_name_template(FileNameTemplate::versioned());
+        downloader.sync(None).unwrap();
+        api.publish("LFPN", "8");
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader.sync(None).unwrap();
+
+        // A file no longer matching its hash is left alone
+        let content = downloader.storage.read("LFPG_AD_v1.pdf").unwrap().unwrap();
+        downloader.storage.write("LFPG_AD_v1.pdf", b"%PDF").unwrap();
+        let result = downloader.dedupe(true).unwrap();
+        assert_eq!(
+            result.linked,
+            [("LFPN_AD_v8.pdf".to_string(), "LFPN_AD_v1.pdf".to_string())]
+        );
+        assert_eq!(result.saved, content.len() as u64);
+
+        downloader
+            .storage
+            .write("LFPG_AD_v1.pdf", &content)
+            .unwrap();
+        let result = downloader.dedupe(false).unwrap();
+        assert_eq!(result.linked.len(), 2);
+        #[cfg(unix)]
+        {
+            assert!(downloader
+                .storage
+                .is_linked("LFPG_AD_v1.pdf", "LFPN_AD_v8.pdf")
+                .unwrap());
+            assert!(downloader.dedupe(false).unwrap().linked.is_empty());
+        }
+        assert!(downloader.verify().unwrap().problems.is_empty());
+    }
+
+    #[test]
+    fn test_server_side_filter() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        api.publish("LFPT", "1");
+        let dir = tempfile::tempdir().unwrap();
+
+        // The filter is sent along with the page size
+        let downloader = VacDownloader::builder(dir.path().join("a.db"), dir.path())
+            .base_url("http://sia.test")
+            .transport(api.clone())
+            .page_size(50)
+            .build()
+            .unwrap();
+        let entries = downloader
+            .fetch_oacis_data(Some(&["lfpn".to_string()]))
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            api.requests(),
+            vec!["/api/v1/oacis?page=1&itemsPerPage=50&code[]=LFPN"]
+        );
+
+        // A rejected filter falls back to the whole listing, patterns are never sent
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        api.fail("code[]", StatusCode::BAD_REQUEST);
+        let downloader = api.downloader(dir.path());
+        assert_eq!(
+            downloader
+                .fetch_oacis_data(Some(&["LFPN".to_string()]))
+                .unwrap()
+                .len(),
+            1
+        );
+        downloader.oacis_cache.lock().unwrap().take();
+        downloader
+            .fetch_oacis_data(Some(&["LFP*".to_string()]))
+            .unwrap();
+        assert_eq!(
+            api.requests(),
+            vec![
+                "/api/v1/oacis?page=1&code[]=LFPN",
+                "/api/v1/oacis?page=1",
+                "/api/v1/oacis?page=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker_stops_sync() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            api.publish(oaci, "1");
+        }
+        api.fail("/api/v1/custom/file-path/", StatusCode::UNAUTHORIZED);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_max_consecutive_failures(2);
+
+        let error = downloader.sync(None).unwrap_err().to_string();
+        assert!(
+            error.starts_with(
+                "Stopped the sync after 2 downloads in a row failed with 401 Unauthorized, 1 left"
+            ),
+            "{}",
+            error
+        );
+        assert_eq!(api.file_requests().len(), 2);
+        assert_eq!(downloader.pending_downloads().unwrap().len(), 3);
+    }
+
+    /// Serve a mirror listing `files` with the hash of their expected contents
+    fn serve_mirror(api: &MockApi, files: &[(&str, &[u8], &[u8])]) {
+        let entries = files
+            .iter()
+            .map(|(oaci, expected, served)| {
+                let mut hasher = Sha256::new();
+                hasher.update(expected);
+                api.serve(&format!("/mirror/{}.pdf", oaci), served.to_vec());
+                MirrorEntry {
+                    oaci: oaci.to_string(),
+                    city: "Paris".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1".to_string(),
+                    file_name: format!("{}.pdf", oaci),
+                    file_hash: format!("{:x}", hasher.finalize()),
+                    file_size: expected.len() as i64,
+                }
+            })
+            .collect();
+        let manifest = MirrorManifest::new(0, entries);
+        api.serve(
+            &format!("/mirror/{}", MIRROR_MANIFEST_NAME),
+            serde_json::to_vec(&manifest).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_sync_from_mirror() {
+        let api = MockApi::new();
+        let valid = crate::pdf::tests::sample_pdf(1);
+        let tampered = crate::pdf::tests::sample_pdf(2);
+        serve_mirror(
+            &api,
+            &[
+                ("LFPG", &valid, &valid),
+                ("LFPN", &valid, &tampered),
+                ("LFPO", b"<html>Error</html>", b"<html>Error</html>"),
+            ],
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+
+        let stats = downloader
+            .sync_from_mirror(&api.url("/mirror"), None)
+            .unwrap();
+        assert_eq!(stats.downloaded, 1);
+        assert_eq!(stats.failed, 2);
+        assert_eq!(downloader.storage.read("LFPG.pdf").unwrap(), Some(valid));
+        assert!(!downloader.storage.exists("LFPN.pdf").unwrap());
+        assert!(!downloader.storage.exists("LFPO.pdf").unwrap());
+        let errors: Vec<_> = stats.failures.iter().map(|f| f.error.as_str()).collect();
+        assert!(errors[0].starts_with("Hash mismatch"), "{:?}", errors);
+        assert!(errors[1].starts_with("Invalid PDF"), "{:?}", errors);
+        assert_eq!(downloader.pending_downloads().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_mirror_storage_failure() {
+        let api = MockApi::new();
+        let valid = crate::pdf::tests::sample_pdf(1);
+        serve_mirror(&api, &[("LFPG", &valid, &valid), ("LFPN", &valid, &valid)]);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+        // A directory in the way of the file of LFPN
+        let blocked = downloader.storage.local_path("LFPN.pdf").unwrap();
+        std::fs::create_dir_all(blocked.join("LFPN")).unwrap();
+
+        let stats = downloader
+            .sync_from_mirror(&api.url("/mirror"), None)
+            .unwrap();
+        assert_eq!(stats.downloaded, 1);
+        assert_eq!(stats.failed, 1);
+        assert!(stats.failures[0]
+            .error
+            .starts_with("Failed to store PDF for LFPN"));
+        // The chart stored before the failure is recorded, the failed one stays queued
+        assert_eq!(
+            downloader
+                .database
+                .get_cached_version("LFPG", "AD")
+                .unwrap()
+                .as_deref(),
+            Some("1")
+        );
+        let pending = downloader.pending_downloads().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].entry.oaci, "LFPN");
+    }
+
+    #[test]
+    fn test_mirror_circuit_breaker() {
+        let api = MockApi::new();
+        let valid = crate::pdf::tests::sample_pdf(1);
+        serve_mirror(
+            &api,
+            &[
+                ("LFPG", &valid, &valid),
+                ("LFPN", &valid, &valid),
+                ("LFPO", &valid, &valid),
+            ],
+        );
+        api.fail("/mirror/LF", StatusCode::FORBIDDEN);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_max_consecutive_failures(2);
+
+        let error = downloader
+            .sync_from_mirror(&api.url("/mirror"), None)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with(
+                "Stopped the sync after 2 downloads in a row failed with 403 Forbidden, 1 left"
+            ),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_region_file_name_template() {
+        let api = MockApi::new();
+        // LFPG is placed through the department overrides, NTAA is in no region
+        api.publish("LFPG", "1");
+        api.publish("NTAA", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_file_name_template(
+            FileNameTemplate::new("{region}/{oaci}/{oaci}_{type}_{version}.pdf").unwrap(),
+        );
+
+        assert_eq!(downloader.sync(None).unwrap().downloaded, 2);
+        assert!(downloader
+            .storage
+            .exists("Ile-de-France/LFPG/LFPG_AD_1.pdf")
+            .unwrap());
+        assert!(downloader
+            .storage
+            .exists("Unknown/NTAA/NTAA_AD_1.pdf")
+            .unwrap());
+    }
+
+    #[test]
+    fn test_clean_removes_orphans() {
+        let api = MockApi::new();
+        api.publish("LFPN", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+        downloader.sync(None).unwrap();
+        for name in GENERATED_FILES.iter().chain(&["stale.pdf", "old/LFPT.pdf"]) {
+            downloader.storage.write(name, b"contents").unwrap();
+        }
+
+        let orphans = vec!["old/LFPT.pdf".to_string(), "stale.pdf".to_string()];
+        assert_eq!(downloader.find_orphans().unwrap(), orphans);
+        let result = downloader.clean(true).unwrap();
+        assert_eq!(result.orphans, orphans);
+        assert!(result.removed.is_empty());
+        assert!(downloader.storage.exists("stale.pdf").unwrap());
+
+        let result = downloader.clean(false).unwrap();
+        assert_eq!(result.removed, orphans);
+        for name in &orphans {
+            assert!(!downloader.storage.exists(name).unwrap());
+        }
+        for name in GENERATED_FILES.iter().chain(&["AD-2.LFPN.pdf"]) {
+            assert!(downloader.storage.exists(name).unwrap(), "{}", name);
+        }
+        assert!(downloader.find_orphans().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_storage_report_by_region() {
+        let api = MockApi::new();
+        // Placed through the department overrides, without a position in the listing
+        for oaci in ["LFPG", "LFPO", "LFLL", "NTAA"] {
+            api.publish(oaci, "1");
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path());
+        downloader.sync(None).unwrap();
+
+        let report = downloader.storage_report().unwrap();
+        let regions: Vec<(&str, usize, usize)> = report
+            .by_region
+            .iter()
+            .map(|(region, usage)| (region.as_str(), usage.airfields, usage.entries))
+            .collect();
+        assert_eq!(
+            regions,
+            [
+                ("Auvergne-Rhône-Alpes", 1, 1),
+                ("Unknown", 1, 1),
+                ("Île-de-France", 2, 2),
+            ]
+        );
+        let total: u64 = report
+            .by_region
+            .values()
+            .map(|usage| usage.stored_size)
+            .sum();
+        assert_eq!(total, report.stored_size);
+    }
+
+    #[test]
+    fn test_force_downloads_current_charts() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT"] {
+            api.publish(oaci, "1");
+        }
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(api.downloader(dir.path()).sync(None).unwrap().downloaded, 2);
+
+        let stats = api.downloader(dir.path()).sync(None).unwrap();
+        assert_eq!((stats.downloaded, stats.up_to_date), (0, 2));
+        assert_eq!(api.file_requests().len(), 2);
+
+        // Hashes match, yet forced charts are fetched again, without validators
+        let stats = api
+            .downloader(dir.path())
+            .with_force_oacis(vec!["lfpn".to_string()])
+            .sync(None)
+            .unwrap();
+        assert_eq!((stats.downloaded, stats.up_to_date), (1, 1));
+        let outcome = |oaci: &str| {
+            stats
+                .results
+                .iter()
+                .find(|result| result.oaci == oaci)
+                .map(|result| result.outcome)
+        };
+        assert_eq!(outcome("LFPN"), Some(EntryOutcome::Downloaded));
+        assert_eq!(outcome("LFPT"), Some(EntryOutcome::Verified));
+        assert_eq!(api.file_requests().len(), 3);
+        assert!(api.file_requests()[2].contains("LFPN"));
+
+        let stats = api.downloader(dir.path()).with_force().sync(None).unwrap();
+        assert_eq!((stats.downloaded, stats.up_to_date), (2, 0));
+        assert_eq!(api.file_requests().len(), 5);
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT"] {
+            api.publish(oaci, "1");
+        }
+        api.fail("/file-path/LFPT/", StatusCode::INTERNAL_SERVER_ERROR);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api.downloader(dir.path()).with_quarantine_after(2);
+        let sync = |downloader: &VacDownloader| {
+            downloader.oacis_cache.lock().unwrap().take();
+            downloader.sync(None).unwrap()
+        };
+
+        assert_eq!(sync(&downloader).failed, 1);
+        assert_eq!(sync(&downloader).failed, 1);
+
+        // Quarantined after two failures in a row, without failing the sync
+        let requests = api.file_requests().len();
+        let stats = sync(&downloader);
+        assert_eq!((stats.quarantined, stats.failed), (1, 0));
+        assert_eq!(stats.results[1].outcome, EntryOutcome::Quarantined);
+        assert_eq!(api.file_requests().len(), requests);
+        assert_eq!(downloader.pending_downloads().unwrap().len(), 1);
+
+        // Until retried
+        let downloader = api
+            .downloader(dir.path())
+            .with_quarantine_after(2)
+            .with_retry_quarantined();
+        let stats = sync(&downloader);
+        assert_eq!((stats.quarantined, stats.failed), (0, 1));
+    }
+
+    #[test]
+    fn test_skip_not_found() {
+        let api = MockApi::new();
+        for oaci in ["LFPN", "LFPT"] {
+            api.publish(oaci, "1");
+        }
+        api.fail("/file-path/LFPT/", StatusCode::NOT_FOUND);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = api
+            .downloader(dir.path())
+            .with_skip_not_found(Duration::from_secs(86400));
+
+        // The 404 is reported as such
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.downloaded, stats.failed), (1, 1));
+        assert_eq!(stats.failures[0].kind, FailureKind::NotFound);
+        assert_eq!(
+            stats.failures_by_kind(),
+            BTreeMap::from([(FailureKind::NotFound, 1)])
+        );
+
+        // Then skipped while the chart stays queued
+        downloader.oacis_cache.lock().unwrap().take();
+        let requests = api.file_requests().len();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.skipped_not_found, stats.failed), (1, 0));
+        assert_eq!(stats.results[1].outcome, EntryOutcome::Skipped);
+        assert_eq!(api.file_requests().len(), requests);
+        assert_eq!(downloader.pending_downloads().unwrap().len(), 1);
+
+        // A new version is tried again
+        api.publish("LFPT", "2");
+        downloader.oacis_cache.lock().unwrap().take();
+        let stats = downloader.sync(None).unwrap();
+        assert_eq!((stats.skipped_not_found, stats.failed), (0, 1));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = Arc::new(
+            VacDownloader::new(dir.path().join("vac_cache.db"), dir.path().join("pdfs")).unwrap(),
+        );
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let downloader = Arc::clone(&downloader);
+                std::thread::spawn(move || {
+                    assert!(downloader.sync_history(10).unwrap().is_empty());
+                    assert!(downloader.storage_report().unwrap().airfields.is_empty());
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
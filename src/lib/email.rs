@@ -0,0 +1,278 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::downloader::SyncStats;
+use crate::report::{ReportFormat, SyncReport};
+use anyhow::{Context, Result};
+use lettre::message::{Mailbox, MessageBuilder, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::info;
+
+/// Timeout of the SMTP connection and commands
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Encryption of the connection to the SMTP server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (port 587)
+    #[default]
+    Starttls,
+    /// TLS from the start (port 465)
+    Tls,
+    /// Unencrypted connection, for relays on the local network (port 25)
+    None,
+}
+
+/// SMTP settings of the sync notification emails, as found in the configuration file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EmailConfig {
+    /// Host name of the SMTP server
+    pub server: String,
+    /// Port of the SMTP server (defaults to the one of the security mode)
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address (e.g. "VAC Downloader <vac@club.example>")
+    pub from: String,
+    /// Recipient addresses
+    pub to: Vec<String>,
+}
+
+/// Emails the report of a sync when new versions were downloaded or downloads
+/// failed, and the error of a sync that could not run
+pub struct EmailNotifier {
+    transport: SmtpTransport,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailNotifier {
+    /// Create a notifier from its settings, checking the addresses
+    pub fn new(config: &EmailConfig) -> Result<Self> {
+        let from = config
+            .from
+            .parse()
+            .context(format!("Invalid sender address: {}", config.from))?;
+        if config.to.is_empty() {
+            anyhow::bail!("No recipient address for the notification emails");
+        }
+        let to = config
+            .to
+            .iter()
+            .map(|address| {
+                address
+                    .parse()
+                    .context(format!("Invalid recipient address: {}", address))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut builder = match config.security {
+            SmtpSecurity::None => SmtpTransport::builder_dangerous(&config.server),
+            SmtpSecurity::Starttls => SmtpTransport::starttls_relay(&config.server)
+                .context(format!("Invalid SMTP server: {}", config.server))?,
+            SmtpSecurity::Tls => SmtpTransport::relay(&config.server)
+                .context(format!("Invalid SMTP server: {}", config.server))?,
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        match (&config.username, &config.password) {
+            (Some(username), Some(password)) => {
+                builder = builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("SMTP username and password must be set together"),
+        }
+
+        Ok(EmailNotifier {
+            transport: builder.timeout(Some(SMTP_TIMEOUT)).build(),
+            from,
+            to,
+        })
+    }
+
+    /// Email the report of a sync, unless it neither downloaded new versions
+    /// nor had failures
+    ///
+    /// # Returns
+    /// Whether an email was sent
+    pub fn notify(&self, stats: &SyncStats) -> Result<bool> {
+        if stats.updates.is_empty() && stats.failures.is_empty() {
+            return Ok(false);
+        }
+
+        let message = self.message(stats)?;
+        self.transport
+            .send(&message)
+            .context("Failed to send the notification email")?;
+        info!(
+            "📧 Emailed the sync report to {} recipient(s)",
+            self.to.len()
+        );
+        Ok(true)
+    }
+
+    /// Email the error of a sync that could not run, such as a tripped circuit
+    /// breaker, a listing that could not be fetched or rejected credentials
+    pub fn notify_error(&self, error: &anyhow::Error) -> Result<()> {
+        let message = self.error_message(error)?;
+        self.transport
+            .send(&message)
+            .context("Failed to send the notification email")?;
+        info!(
+            "📧 Emailed the sync error to {} recipient(s)",
+            self.to.len()
+        );
+        Ok(())
+    }
+
+    /// Build the notification email of a sync, with the report as plain text
+    /// (Markdown) and HTML
+    fn message(&self, stats: &SyncStats) -> Result<Message> {
+        let report = SyncReport::new(stats);
+        self.builder(subject(stats))
+            .multipart(MultiPart::alternative_plain_html(
+                report.render(ReportFormat::Markdown),
+                report.render(ReportFormat::Html),
+            ))
+            .context("Failed to build the notification email")
+    }
+
+    /// Build the notification email of a sync that could not run, as plain text
+    fn error_message(&self, error: &anyhow::Error) -> Result<Message> {
+        self.builder("VAC sync failed".to_string())
+            .singlepart(SinglePart::plain(format!(
+                "The sync could not run:\n\n{:#}\n",
+                error
+            )))
+            .context("Failed to build the notification email")
+    }
+
+    /// Message from the sender to the recipients
+    fn builder(&self, subject: String) -> MessageBuilder {
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        builder
+    }
+}
+
+/// Subject of the notification email of a sync
+fn subject(stats: &SyncStats) -> String {
+    match (stats.updates.len(), stats.failures.len()) {
+        (updates, 0) => format!("VAC sync: {} new version(s)", updates),
+        (0, failures) => format!("VAC sync: {} failure(s)", failures),
+        (updates, failures) => format!(
+            "VAC sync: {} new version(s), {} failure(s)",
+            updates, failures
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartFailure, ChartUpdate, FailureKind};
+
+    fn config() -> EmailConfig {
+        EmailConfig {
+            server: "localhost".to_string(),
+            port: Some(2525),
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "VAC Downloader <vac@club.example>".to_string(),
+            to: vec![
+                "chief@club.example".to_string(),
+                "Pilots <pilots@club.example>".to_string(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_message() {
+        let notifier = EmailNotifier::new(&config()).unwrap();
+        let stats = SyncStats {
+            updates: vec![ChartUpdate {
+                oaci: "LFPN".to_string(),
+                city: "TOUSSUS LE NOBLE".to_string(),
+                vac_type: "AD".to_string(),
+                version: "2025-01-23".to_string(),
+                previous_version: Some("2024-11-28".to_string()),
+                file_size: 1000,
+            }],
+            failures: vec![ChartFailure {
+                oaci: "LFPT".to_string(),
+                vac_type: "AD".to_string(),
+                kind: FailureKind::Server,
+                error: "HTTP 500".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let message = String::from_utf8(notifier.message(&stats).unwrap().formatted()).unwrap();
+        assert!(message.contains("Subject: VAC sync: 1 new version(s), 1 failure(s)"));
+        assert!(message.contains("pilots@club.example"));
+        assert!(message.contains("multipart/alternative"));
+        assert!(message.contains("text/html"));
+        assert!(message.contains("LFPN"));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let notifier = EmailNotifier::new(&config()).unwrap();
+        let error = anyhow::anyhow!("HTTP 401").context("Failed to fetch the listing");
+        let message =
+            String::from_utf8(notifier.error_message(&error).unwrap().formatted()).unwrap();
+        assert!(message.contains("Subject: VAC sync failed"));
+        assert!(message.contains("chief@club.example"));
+        assert!(message.contains("Failed to fetch the listing: HTTP 401"));
+    }
+
+    #[test]
+    fn test_nothing_to_notify() {
+        // Nothing listens on the port: notify must not connect
+        let notifier = EmailNotifier::new(&config()).unwrap();
+        assert!(!notifier.notify(&SyncStats::default()).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut invalid = config();
+        invalid.to.clear();
+        assert!(EmailNotifier::new(&invalid).is_err());
+
+        let mut invalid = config();
+        invalid.from = "not an address".to_string();
+        assert!(EmailNotifier::new(&invalid).is_err());
+
+        let mut invalid = config();
+        invalid.username = Some("pilot".to_string());
+        assert!(EmailNotifier::new(&invalid).is_err());
+    }
+}
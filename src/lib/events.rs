@@ -0,0 +1,256 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::downloader::{EntryResult, SyncStats};
+use crate::models::VacEntry;
+use crate::observer::SyncObserver;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+
+/// Event of a sync, as written to the event stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SyncEvent {
+    SyncStarted {
+        source: String,
+    },
+    ListingFetched {
+        entries: usize,
+    },
+    ChartVerified {
+        oaci: String,
+        vac_type: String,
+        version: String,
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        problem: Option<String>,
+    },
+    DownloadStarted {
+        oaci: String,
+        vac_type: String,
+        version: String,
+        /// Position in the download queue, counting from 1
+        index: usize,
+        total: usize,
+    },
+    DownloadFinished {
+        oaci: String,
+        vac_type: String,
+        version: String,
+    },
+    DownloadFailed {
+        oaci: String,
+        vac_type: String,
+        version: String,
+        error: String,
+    },
+    SyncFinished {
+        total_entries: usize,
+        downloaded: usize,
+        failed: usize,
+        up_to_date: usize,
+        verified: usize,
+        deferred: usize,
+        bytes_downloaded: u64,
+        duration_ms: u64,
+        /// Outcome of each chart checked
+        results: Vec<EntryResult>,
+    },
+    SyncFailed {
+        error: String,
+    },
+}
+
+/// Line of the event stream: an event and the time it happened
+#[derive(Serialize)]
+struct EventLine<'a> {
+    /// RFC 3339 UTC time
+    time: String,
+    #[serde(flatten)]
+    event: &'a SyncEvent,
+}
+
+/// Observer writing the events of syncs as newline-delimited JSON (NDJSON)
+///
+/// Each line is flushed as soon as it is written, so that other programs can
+/// follow the progress of a sync:
+///
+/// ```json
+/// {"time":"2025-01-23T06:30:00Z","event":"download_started","oaci":"LFPN","vac_type":"AD","version":"2025-01-23","index":1,"total":3}
+/// ```
+pub struct NdjsonEvents {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl NdjsonEvents {
+    /// Write the events to `out`
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        NdjsonEvents {
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Write one event; errors are ignored, the sync goes on without its observer
+    pub fn write(&self, event: &SyncEvent) {
+        let line = EventLine {
+            time: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            event,
+        };
+        let mut out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Ok(json) = serde_json::to_string(&line) {
+            let _ = writeln!(out, "{}", json);
+            let _ = out.flush();
+        }
+    }
+}
+
+impl SyncObserver for NdjsonEvents {
+    fn sync_started(&self, source: &str) {
+        self.write(&SyncEvent::SyncStarted {
+            source: source.to_string(),
+        });
+    }
+
+    fn listing_fetched(&self, entries: usize) {
+        self.write(&SyncEvent::ListingFetched { entries });
+    }
+
+    fn chart_verified(&self, entry: &VacEntry, problem: Option<&str>) {
+        self.write(&SyncEvent::ChartVerified {
+            oaci: entry.oaci.clone(),
+            vac_type: entry.vac_type.clone(),
+            version: entry.version.clone(),
+            ok: problem.is_none(),
+            problem: problem.map(str::to_string),
+        });
+    }
+
+    fn download_started(&self, entry: &VacEntry, index: usize, total: usize) {
+        self.write(&SyncEvent::DownloadStarted {
+            oaci: entry.oaci.clone(),
+            vac_type: entry.vac_type.clone(),
+            version: entry.version.clone(),
+            index: index + 1,
+            total,
+        });
+    }
+
+    fn download_finished(&self, entry: &VacEntry, error: Option<&anyhow::Error>) {
+        let (oaci, vac_type, version) = (
+            entry.oaci.clone(),
+            entry.vac_type.clone(),
+            entry.version.clone(),
+        );
+        self.write(&match error {
+            None => SyncEvent::DownloadFinished {
+                oaci,
+                vac_type,
+                version,
+            },
+            Some(error) => SyncEvent::DownloadFailed {
+                oaci,
+                vac_type,
+                version,
+                error: format!("{:#}", error),
+            },
+        });
+    }
+
+    fn sync_finished(&self, result: Result<&SyncStats, &anyhow::Error>) {
+        self.write(&match result {
+            Ok(stats) => SyncEvent::SyncFinished {
+                total_entries: stats.total_entries,
+                downloaded: stats.downloaded,
+                failed: stats.failed,
+                up_to_date: stats.up_to_date,
+                verified: stats.verified,
+                deferred: stats.deferred,
+                bytes_downloaded: stats.bytes_downloaded,
+                duration_ms: stats.duration.as_millis() as u64,
+                results: stats.results.clone(),
+            },
+            Err(error) => SyncEvent::SyncFailed {
+                error: format!("{:#}", error),
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Buffer shared with the test once moved into the observer
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_ndjson_events() {
+        let buffer = SharedBuffer::default();
+        let events = NdjsonEvents::new(Box::new(buffer.clone()));
+        let entry = VacEntry {
+            oaci: "LFPN".to_string(),
+            vac_type: "AD".to_string(),
+            version: "2025-01-23".to_string(),
+            file_name: "LFPN.pdf".to_string(),
+            file_size: 1000,
+            city: "TOUSSUS LE NOBLE".to_string(),
+            file_hash: None,
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        };
+
+        events.sync_started("api");
+        events.download_started(&entry, 0, 2);
+        events.download_finished(&entry, Some(&anyhow::anyhow!("HTTP 500")));
+        events.sync_finished(Ok(&SyncStats::default()));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["event"], "sync_started");
+        assert_eq!(lines[0]["source"], "api");
+        assert!(lines[0]["time"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(lines[1]["event"], "download_started");
+        assert_eq!(lines[1]["index"], 1);
+        assert_eq!(lines[1]["total"], 2);
+        assert_eq!(lines[2]["event"], "download_failed");
+        assert_eq!(lines[2]["error"], "HTTP 500");
+        assert_eq!(lines[3]["event"], "sync_finished");
+        assert_eq!(lines[3]["downloaded"], 0);
+    }
+}
@@ -0,0 +1,588 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::html_index::{encode_path, escape};
+use crate::matching::fold;
+use crate::storage::Storage;
+use crate::{OacisEntry, Runway, VacDatabase, VacEntry};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write as _};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Airport icon of the Google Earth placemarks
+const KML_ICON: &str = "http://maps.google.com/mapfiles/kml/shapes/airports.png";
+
+/// Header row of SeeYou waypoint files
+const CUP_HEADER: &str = "name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc";
+
+/// SeeYou waypoint styles
+const CUP_GRASS_AIRFIELD: u8 = 2;
+const CUP_SOLID_AIRFIELD: u8 = 5;
+
+/// File format of an airfield export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// GeoJSON FeatureCollection of airfield points
+    GeoJson,
+    /// KML placemarks for Google Earth
+    Kml,
+    /// KML zipped as a KMZ archive
+    Kmz,
+    /// SeeYou waypoints for glide computers and XCSoar
+    Cup,
+    /// CSV of the radio frequencies of each airfield
+    FrequenciesCsv,
+}
+
+impl ExportFormat {
+    /// Usual extension of files in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Kml => "kml",
+            ExportFormat::Kmz => "kmz",
+            ExportFormat::Cup => "cup",
+            ExportFormat::FrequenciesCsv => "csv",
+        }
+    }
+
+    /// Whether the format places airfields by their position
+    pub fn needs_position(&self) -> bool {
+        !matches!(self, ExportFormat::FrequenciesCsv)
+    }
+}
+
+/// Cached airfield with the stored details of the listing
+pub(crate) struct ExportedAirfield {
+    pub(crate) airfield: OacisEntry,
+    /// Cached charts, sorted by type
+    pub(crate) charts: Vec<VacEntry>,
+    /// Local path of the first chart, if stored on the local filesystem
+    pub(crate) path: Option<String>,
+}
+
+/// Cached airfields with their details, as exported to mapping and radio tools
+pub(crate) struct AirfieldExport {
+    pub(crate) airfields: Vec<ExportedAirfield>,
+    /// Cached airfields left out for lack of stored details
+    pub(crate) unknown: Vec<String>,
+}
+
+impl AirfieldExport {
+    /// Gather the cached airfields and the details stored by the last sync
+    pub(crate) fn from_database(database: &VacDatabase, storage: &dyn Storage) -> Result<Self> {
+        let mut charts: HashMap<String, Vec<VacEntry>> = HashMap::new();
+        for entry in database
+            .get_all_entries()
+            .context("Failed to read cache entries")?
+        {
+            charts.entry(entry.oaci.clone()).or_default().push(entry);
+        }
+
+        let mut airfields = Vec::new();
+        for airfield in database
+            .get_cached_airfields()
+            .context("Failed to read airfield details")?
+        {
+            let Some(mut entries) = charts.remove(&airfield.code) else {
+                continue;
+            };
+            entries.sort_by(|a, b| a.vac_type.cmp(&b.vac_type));
+            let path = storage
+                .local_path(&entries[0].file_name)
+                .and_then(|path| path.canonicalize().ok())
+                .map(|path| path.display().to_string());
+            airfields.push(ExportedAirfield {
+                airfield,
+                charts: entries,
+                path,
+            });
+        }
+
+        let mut unknown: Vec<String> = charts.into_keys().collect();
+        unknown.sort();
+        Ok(AirfieldExport { airfields, unknown })
+    }
+
+    /// Airfields whose details have no position
+    pub(crate) fn unlocated(&self) -> Vec<&str> {
+        self.airfields
+            .iter()
+            .filter(|exported| exported.airfield.coordinates().is_none())
+            .map(|exported| exported.airfield.code.as_str())
+            .collect()
+    }
+
+    /// Number of airfields exported in a format
+    pub(crate) fn len(&self, format: ExportFormat) -> usize {
+        if format.needs_position() {
+            self.airfields.len() - self.unlocated().len()
+        } else {
+            self.airfields.len()
+        }
+    }
+
+    /// Render the export in a file format
+    pub(crate) fn render(&self, format: ExportFormat) -> Result<Vec<u8>> {
+        match format {
+            ExportFormat::GeoJson => Ok(serde_json::to_vec_pretty(&self.geojson())?),
+            ExportFormat::Kml => Ok(self.kml().into_bytes()),
+            ExportFormat::Kmz => {
+                let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+                archive.start_file("doc.kml", SimpleFileOptions::default())?;
+                archive.write_all(self.kml().as_bytes())?;
+                Ok(archive.finish()?.into_inner())
+            }
+            ExportFormat::Cup => Ok(self.cup().into_bytes()),
+            ExportFormat::FrequenciesCsv => Ok(self.frequencies_csv().into_bytes()),
+        }
+    }
+
+    /// FeatureCollection with one point per airfield
+    fn geojson(&self) -> Value {
+        let features: Vec<Value> = self
+            .airfields
+            .iter()
+            .filter_map(|exported| {
+                let coordinates = exported.airfield.coordinates()?;
+                let chart = &exported.charts[0];
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [coordinates.longitude, coordinates.latitude],
+                    },
+                    "properties": {
+                        "oaci": exported.airfield.code,
+                        "city": exported.airfield.city,
+                        "elevation_ft": exported.airfield.elevation(),
+                        "vac_type": chart.vac_type,
+                        "version": chart.version,
+                        "path": exported.path,
+                    },
+                }))
+            })
+            .collect();
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+
+    /// KML document with one placemark per airfield
+    fn kml(&self) -> String {
+        let mut kml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n\
+             <name>VAC airfields</name>\n",
+        );
+        let _ = writeln!(
+            kml,
+            "<Style id=\"airfield\"><IconStyle><color>ff00aaff</color><Icon><href>{}</href></Icon>\
+             </IconStyle><LabelStyle><scale>0.8</scale></LabelStyle></Style>",
+            KML_ICON
+        );
+
+        for exported in &self.airfields {
+            let Some(coordinates) = exported.airfield.coordinates() else {
+                continue;
+            };
+            let _ = writeln!(
+                kml,
+                "<Placemark><name>{}</name><styleUrl>#airfield</styleUrl>\
+                 <description>{}</description>\
+                 <Point><coordinates>{},{}</coordinates></Point></Placemark>",
+                escape(&exported.airfield.code),
+                escape(&kml_description(exported)),
+                coordinates.longitude,
+                coordinates.latitude
+            );
+        }
+
+        kml.push_str("</Document>\n</kml>\n");
+        kml
+    }
+
+    /// SeeYou waypoint file with one airfield per row
+    ///
+    /// The runway columns describe the longest runway, and the frequency is
+    /// the first listed one, tower first.
+    fn cup(&self) -> String {
+        let mut cup = format!("{}\r\n", CUP_HEADER);
+        for exported in &self.airfields {
+            let airfield = &exported.airfield;
+            let Some(coordinates) = airfield.coordinates() else {
+                continue;
+            };
+            let runway = airfield
+                .runways
+                .iter()
+                .max_by_key(|runway| runway.length_meters().unwrap_or(0));
+            let style = if airfield.runways.iter().any(is_paved) {
+                CUP_SOLID_AIRFIELD
+            } else {
+                CUP_GRASS_AIRFIELD
+            };
+            let frequency = airfield
+                .frequencies
+                .iter()
+                .flat_map(|frequency| frequency.parsed())
+                .map(|(_, frequency)| frequency)
+                .next();
+            let chart = &exported.charts[0];
+
+            let _ = write!(
+                cup,
+                "{},{},FR,{},{},{},{},{},{},{},{}\r\n",
+                cup_quote(&airfield.city),
+                cup_quote(&airfield.code),
+                cup_coordinate(coordinates.latitude, 2, ['N', 'S']),
+                cup_coordinate(coordinates.longitude, 3, ['E', 'W']),
+                airfield
+                    .elevation()
+                    .map(|elevation| format!("{}ft", elevation.round()))
+                    .unwrap_or_default(),
+                style,
+                runway
+                    .and_then(|runway| runway.headings().ok())
+                    .and_then(|headings| headings.first().map(|h| h.degrees().to_string()))
+                    .unwrap_or_default(),
+                runway
+                    .and_then(|runway| runway.length_meters().ok())
+                    .map(|length| format!("{}m", length))
+                    .unwrap_or_default(),
+                frequency
+                    .map(|frequency| cup_quote(&frequency.to_string()))
+                    .unwrap_or_default(),
+                cup_quote(&format!("VAC {} {}", chart.vac_type, chart.version)),
+            );
+        }
+        cup
+    }
+
+    /// CSV with one row per frequency of each airfield
+    fn frequencies_csv(&self) -> String {
+        let mut csv = String::from("oaci,city,service,frequency,name\n");
+        for exported in &self.airfields {
+            let airfield = &exported.airfield;
+            for (service, frequency) in airfield.frequencies.iter().flat_map(|f| f.named()) {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{},{}",
+                    csv_field(&airfield.code),
+                    csv_field(&airfield.city),
+                    service,
+                    csv_field(frequency.trim()),
+                    csv_field(&format!("{} {}", airfield.code, service)),
+                );
+            }
+        }
+        csv
+    }
+}
+
+/// Quote a CSV field when it contains a separator, a quote or a line break
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Quote a text field of a SeeYou file
+fn cup_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Format a coordinate in degrees as SeeYou degrees and decimal minutes,
+/// e.g. "4845.000N" for a latitude or "00206.600E" for a longitude
+fn cup_coordinate(degrees: f64, width: usize, hemispheres: [char; 2]) -> String {
+    let hemisphere = if degrees < 0.0 {
+        hemispheres[1]
+    } else {
+        hemispheres[0]
+    };
+    let thousandths = (degrees.abs() * 60_000.0).round() as u64;
+    let (whole, minutes) = (thousandths / 60_000, thousandths % 60_000);
+    format!(
+        "{:0width$}{:02}.{:03}{}",
+        whole,
+        minutes / 1000,
+        minutes % 1000,
+        hemisphere,
+        width = width
+    )
+}
+
+/// Whether a runway has a solid surface, from the French surface type
+fn is_paved(runway: &Runway) -> bool {
+    let surface = fold(&runway.runway_type);
+    (surface.contains("REVETU") && !surface.contains("NON")) || surface.contains("BETON")
+}
+
+/// HTML description of a placemark: city, elevation, runways, frequencies and charts
+fn kml_description(exported: &ExportedAirfield) -> String {
+    let airfield = &exported.airfield;
+    let mut html = format!("<b>{}</b>", escape(&airfield.city));
+    if let Some(elevation) = airfield.elevation() {
+        let _ = write!(html, "<br>Elevation: {} ft", elevation);
+    }
+    for runway in &airfield.runways {
+        let _ = write!(
+            html,
+            "<br>Runway {}: {} x {} m, {}",
+            escape(&runway.degrees),
+            escape(&runway.length),
+            escape(&runway.width),
+            escape(&runway.runway_type)
+        );
+    }
+    for (name, value) in airfield.frequencies.iter().flat_map(|f| f.named()) {
+        let _ = write!(html, "<br>{}: {}", name, escape(value));
+    }
+    for chart in &exported.charts {
+        let _ = write!(
+            html,
+            "<br>Chart {} version {}",
+            escape(&chart.vac_type),
+            escape(&chart.version)
+        );
+    }
+    if let Some(path) = &exported.path {
+        let path = path.replace('\\', "/");
+        let separator = if path.starts_with('/') { "" } else { "/" };
+        let _ = write!(
+            html,
+            "<br><a href=\"file://{}{}\">Open the chart</a>",
+            separator,
+            encode_path(&path)
+        );
+    }
+    html
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::fs;
+
+    /// Listing entry of an airfield, as returned by the API
+    pub(crate) fn sample_airfield(
+        code: &str,
+        city: &str,
+        position: Option<(f64, f64)>,
+    ) -> OacisEntry {
+        let grounds = match position {
+            Some((latitude, longitude)) => json!([{
+                "type": "AD",
+                "elevation": "538",
+                "coordinates": {"latitude": latitude, "longitude": longitude},
+            }]),
+            None => json!([]),
+        };
+        serde_json::from_value(json!({
+            "code": code,
+            "city": city,
+            "grounds": grounds,
+            "maps": [],
+            "runways": [{"length": "1100", "width": "30", "type": "Revêtue", "degrees": "07/25"}],
+            "frequencies": [{"freqTWR": "119.300", "freqATIS": "128.650"}],
+            "information": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_kml() {
+        let export = AirfieldExport {
+            airfields: vec![ExportedAirfield {
+                airfield: sample_airfield("LFPN", "TOUSSUS & CO", Some((48.75, 2.11))),
+                charts: vec![VacEntry {
+                    oaci: "LFPN".to_string(),
+                    city: "TOUSSUS & CO".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1.0".to_string(),
+                    file_name: "LFPN_AD.pdf".to_string(),
+                    file_size: 4,
+                    file_hash: None,
+                    remote_file_name: None,
+                    page_count: None,
+                    available_locally: false,
+                }],
+                path: Some("/charts/LFPN AD.pdf".to_string()),
+            }],
+            unknown: Vec::new(),
+        };
+
+        let kml = String::from_utf8(export.render(ExportFormat::Kml).unwrap()).unwrap();
+        assert!(kml.contains("<name>LFPN</name>"));
+        assert!(kml.contains("<coordinates>2.11,48.75</coordinates>"));
+        assert!(kml.contains("&lt;b&gt;TOUSSUS &amp;amp; CO&lt;/b&gt;"));
+        assert!(kml.contains("Runway 07/25: 1100 x 30 m"));
+        assert!(kml.contains("TWR: 119.300"));
+        assert!(kml.contains("ATIS: 128.650"));
+        assert!(kml.contains("file:///charts/LFPN%20AD.pdf"));
+
+        let kmz = export.render(ExportFormat::Kmz).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(kmz)).unwrap();
+        let mut doc = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("doc.kml").unwrap(), &mut doc).unwrap();
+        assert_eq!(doc, kml);
+    }
+
+    #[test]
+    fn test_cup() {
+        let mut grass = sample_airfield("LFFE", "ENGHIEN \"MOISSELLES\"", Some((49.046, 2.353)));
+        grass.runways = vec![
+            serde_json::from_value(
+                json!({"length": "850", "width": "40", "type": "Non revêtue", "degrees": "12/30"}),
+            )
+            .unwrap(),
+            serde_json::from_value(
+                json!({"length": "650", "width": "40", "type": "Non revêtue", "degrees": "03/21"}),
+            )
+            .unwrap(),
+        ];
+        grass.frequencies = Vec::new();
+        let chart = |oaci: &str| VacEntry {
+            oaci: oaci.to_string(),
+            city: String::new(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: format!("{}_AD.pdf", oaci),
+            file_size: 4,
+            file_hash: None,
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        };
+        let export = AirfieldExport {
+            airfields: vec![
+                ExportedAirfield {
+                    airfield: sample_airfield("LFPN", "TOUSSUS LE NOBLE", Some((48.7519, 2.1061))),
+                    charts: vec![chart("LFPN")],
+                    path: None,
+                },
+                ExportedAirfield {
+                    airfield: grass,
+                    charts: vec![chart("LFFE")],
+                    path: None,
+                },
+            ],
+            unknown: Vec::new(),
+        };
+
+        let cup = String::from_utf8(export.render(ExportFormat::Cup).unwrap()).unwrap();
+        let lines: Vec<&str> = cup.split("\r\n").collect();
+        assert_eq!(lines[0], CUP_HEADER);
+        assert_eq!(
+            lines[1],
+            "\"TOUSSUS LE NOBLE\",\"LFPN\",FR,4845.114N,00206.366E,538ft,5,70,1100m,\"119.300\",\"VAC AD 1.0\""
+        );
+        assert_eq!(
+            lines[2],
+            "\"ENGHIEN \"\"MOISSELLES\"\"\",\"LFFE\",FR,4902.760N,00221.180E,538ft,2,120,850m,,\"VAC AD 1.0\""
+        );
+    }
+
+    #[test]
+    fn test_frequencies_csv() {
+        let export = AirfieldExport {
+            airfields: vec![ExportedAirfield {
+                airfield: sample_airfield("LFPZ", "SAINT CYR, L'ECOLE", None),
+                charts: Vec::new(),
+                path: None,
+            }],
+            unknown: Vec::new(),
+        };
+
+        let csv = String::from_utf8(export.render(ExportFormat::FrequenciesCsv).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "oaci,city,service,frequency,name\n\
+             LFPZ,\"SAINT CYR, L'ECOLE\",TWR,119.300,LFPZ TWR\n\
+             LFPZ,\"SAINT CYR, L'ECOLE\",ATIS,128.650,LFPZ ATIS\n"
+        );
+    }
+
+    #[test]
+    fn test_cup_coordinate() {
+        assert_eq!(cup_coordinate(48.75, 2, ['N', 'S']), "4845.000N");
+        assert_eq!(cup_coordinate(-0.5, 3, ['E', 'W']), "00030.000W");
+        assert_eq!(cup_coordinate(2.99999999, 3, ['E', 'W']), "00300.000E");
+    }
+
+    #[test]
+    fn test_geojson() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let database = VacDatabase::new(":memory:").unwrap();
+        database
+            .upsert_airfields(&[
+                sample_airfield("LFPN", "TOUSSUS LE NOBLE", Some((48.75, 2.11))),
+                sample_airfield("LFPZ", "SAINT CYR L'ECOLE", None),
+                sample_airfield("LFPT", "PONTOISE", Some((49.1, 2.04))),
+            ])
+            .unwrap();
+        for oaci in ["LFPN", "LFPZ", "LFOR"] {
+            database
+                .upsert_entry(&VacEntry {
+                    oaci: oaci.to_string(),
+                    city: "City".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1.0".to_string(),
+                    file_name: format!("{}_AD.pdf", oaci),
+                    file_size: 4,
+                    file_hash: None,
+                    remote_file_name: None,
+                    page_count: None,
+                    available_locally: false,
+                })
+                .unwrap();
+        }
+        fs::write(dir.path().join("LFPN_AD.pdf"), b"%PDF").unwrap();
+
+        let export = AirfieldExport::from_database(&database, &storage).unwrap();
+        assert_eq!(export.len(ExportFormat::GeoJson), 1);
+        assert_eq!(export.len(ExportFormat::FrequenciesCsv), 2);
+        assert_eq!(export.unknown, vec!["LFOR"]);
+        assert_eq!(export.unlocated(), vec!["LFPZ"]);
+
+        let geojson: Value =
+            serde_json::from_slice(&export.render(ExportFormat::GeoJson).unwrap()).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let feature = &geojson["features"][0];
+        assert_eq!(feature["geometry"]["coordinates"], json!([2.11, 48.75]));
+        assert_eq!(feature["properties"]["oaci"], "LFPN");
+        assert_eq!(feature["properties"]["elevation_ft"], 538.0);
+        assert_eq!(feature["properties"]["version"], "1.0");
+        assert!(feature["properties"]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("LFPN_AD.pdf"));
+    }
+}
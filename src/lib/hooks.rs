@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use std::process::Command;
+use tracing::debug;
+
+/// External commands run around syncs, e.g. to copy new charts to a tablet
+///
+/// Commands run through the shell (`sh -c`, or `cmd /C` on Windows) with
+/// environment variables describing the sync or the downloaded chart:
+///
+/// * `pre_sync`: nothing; the sync is aborted if the command fails
+/// * `post_download`: `VAC_OACI`, `VAC_TYPE`, `VAC_CITY`, `VAC_VERSION`,
+///   `VAC_PREVIOUS_VERSION` (empty for new charts), `VAC_FILE_NAME` and, for
+///   local storage, `VAC_PATH`
+/// * `post_sync`: `VAC_STATUS` (`success` or `failure`), `VAC_DOWNLOADED`,
+///   `VAC_FAILED`, `VAC_BYTES_DOWNLOADED` and, for failed syncs, `VAC_ERROR`
+#[derive(Debug, Clone, Default)]
+pub struct SyncHooks {
+    /// Command run before each sync
+    pub pre_sync: Option<String>,
+    /// Command run after each sync, successful or not
+    pub post_sync: Option<String>,
+    /// Command run after each downloaded chart
+    pub post_download: Option<String>,
+}
+
+impl SyncHooks {
+    /// Whether no hook is set
+    pub fn is_empty(&self) -> bool {
+        self.pre_sync.is_none() && self.post_sync.is_none() && self.post_download.is_none()
+    }
+}
+
+/// Command running `command` through the shell of the platform
+pub fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Run a hook with the given environment variables, failing if it does not succeed
+pub(crate) fn run(name: &str, command: &str, env: &[(&str, String)]) -> Result<()> {
+    debug!("Running {} hook: {}", name, command);
+    let status = shell(command)
+        .envs(env.iter().map(|(key, value)| (*key, value)))
+        .status()
+        .context(format!("Failed to run the {} hook", name))?;
+    if !status.success() {
+        anyhow::bail!("The {} hook failed ({})", name, status);
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let command = format!("echo \"$VAC_OACI $VAC_VERSION\" > '{}'", out.display());
+        run(
+            "post_download",
+            &command,
+            &[
+                ("VAC_OACI", "LFPN".to_string()),
+                ("VAC_VERSION", "2025-01-23".to_string()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(out).unwrap(), "LFPN 2025-01-23\n");
+
+        let error = run("pre_sync", "exit 3", &[]).unwrap_err();
+        assert!(error.to_string().contains("pre_sync hook failed"));
+    }
+}
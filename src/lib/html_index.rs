@@ -0,0 +1,240 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::matching::fold;
+use crate::storage::Storage;
+use crate::units::format_size;
+use crate::{VacDatabase, VacEntry};
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+/// Name of the HTML index written at the root of the download directory
+pub const HTML_INDEX_NAME: &str = "index.html";
+
+const STYLE: &str = "body{font-family:sans-serif;margin:1em auto;max-width:60em;padding:0 1em}\
+nav a{margin-right:.5em}input{font-size:1em;margin:1em 0;padding:.4em;width:100%;box-sizing:border-box}\
+table{border-collapse:collapse;width:100%}th,td{padding:.3em .6em;text-align:left}\
+tr:nth-child(even){background:#f2f2f2}h2{border-bottom:1px solid #ccc}";
+
+/// Hides the rows not matching the text typed in the filter box
+const SCRIPT: &str = "document.getElementById('filter').addEventListener('input',e=>{\
+const q=e.target.value.toLowerCase();\
+document.querySelectorAll('section').forEach(s=>{let n=0;\
+s.querySelectorAll('tbody tr').forEach(r=>{const m=r.textContent.toLowerCase().includes(q);\
+r.hidden=!m;if(m)n++;});s.hidden=n==0;});});";
+
+/// Browsable list of the downloaded charts, grouped by the initial of their city
+pub(crate) struct HtmlIndex {
+    /// Charts with the time of their last update, sorted by city
+    charts: Vec<(VacEntry, String)>,
+    generated_at: String,
+}
+
+impl HtmlIndex {
+    /// Build the index from the cached entries whose PDF is present
+    pub(crate) fn from_database(database: &VacDatabase, storage: &dyn Storage) -> Result<Self> {
+        let mut charts = Vec::new();
+        for (entry, last_updated) in database
+            .get_entries_with_last_updated()
+            .context("Failed to read cache entries")?
+        {
+            if storage.exists(&entry.file_name)? {
+                charts.push((entry, last_updated));
+            }
+        }
+        charts.sort_by_cached_key(|(entry, _)| {
+            (
+                fold(&entry.city),
+                entry.oaci.clone(),
+                entry.vac_type.clone(),
+            )
+        });
+
+        Ok(HtmlIndex {
+            charts,
+            generated_at: chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+        })
+    }
+
+    /// Number of charts in the index
+    pub(crate) fn len(&self) -> usize {
+        self.charts.len()
+    }
+
+    /// Render the index as a standalone page, usable offline
+    pub(crate) fn render(&self) -> String {
+        let mut sections: Vec<(char, Vec<&(VacEntry, String)>)> = Vec::new();
+        for chart in &self.charts {
+            let initial = section_initial(&chart.0.city);
+            match sections.last_mut() {
+                Some((last, charts)) if *last == initial => charts.push(chart),
+                _ => sections.push((initial, vec![chart])),
+            }
+        }
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        html.push_str("<title>VAC charts</title>\n");
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+        html.push_str("<h1>VAC charts</h1>\n");
+        let _ = writeln!(
+            html,
+            "<p>{} chart(s), generated on {}</p>",
+            self.charts.len(),
+            self.generated_at
+        );
+
+        html.push_str("<nav>");
+        for (initial, _) in &sections {
+            let _ = write!(html, "<a href=\"#{0}\">{0}</a>", initial);
+        }
+        html.push_str("</nav>\n");
+        html.push_str(
+            "<input id=\"filter\" type=\"search\" placeholder=\"Filter by city or OACI code\">\n",
+        );
+
+        for (initial, charts) in &sections {
+            let _ = writeln!(html, "<section id=\"{0}\">\n<h2>{0}</h2>", initial);
+            html.push_str("<table>\n<thead><tr><th>City</th><th>OACI</th><th>Type</th><th>Version</th><th>Size</th><th>Updated</th></tr></thead>\n<tbody>\n");
+            for (entry, last_updated) in charts {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&entry.city),
+                    escape(&encode_path(&entry.file_name)),
+                    escape(&entry.oaci),
+                    escape(&entry.vac_type),
+                    escape(&entry.version),
+                    format_size(entry.file_size.max(0) as u64),
+                    escape(last_updated.get(..10).unwrap_or(last_updated)),
+                );
+            }
+            html.push_str("</tbody>\n</table>\n</section>\n");
+        }
+
+        let _ = writeln!(html, "<script>{}</script>\n</body>\n</html>", SCRIPT);
+        html
+    }
+
+    /// Write the index at the root of the storage
+    pub(crate) fn write(&self, storage: &dyn Storage) -> Result<()> {
+        storage
+            .write(HTML_INDEX_NAME, self.render().as_bytes())
+            .context("Failed to write HTML index")
+    }
+}
+
+/// Section of a city: its initial without accent, or `#` for other characters
+fn section_initial(city: &str) -> char {
+    fold(city)
+        .chars()
+        .next()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .unwrap_or('#')
+}
+
+/// Escape text for HTML content and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode a relative file name for use in a link, keeping `/` separators
+pub(crate) fn encode_path(file_name: &str) -> String {
+    let mut encoded = String::with_capacity(file_name.len());
+    for byte in file_name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::fs;
+
+    fn entry(oaci: &str, city: &str, file_name: &str) -> VacEntry {
+        VacEntry {
+            oaci: oaci.to_string(),
+            city: city.to_string(),
+            vac_type: "AD".to_string(),
+            version: "2024-11".to_string(),
+            file_name: file_name.to_string(),
+            file_size: 2048,
+            file_hash: Some("abc123".to_string()),
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_index_groups_present_charts_by_city() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let db = VacDatabase::new(":memory:").unwrap();
+        db.upsert_entry(&entry("LFPN", "TOUSSUS LE NOBLE", "LFPN/LFPN AD.pdf"))
+            .unwrap();
+        db.upsert_entry(&entry("LFOR", "ÉTAMPES & CHARTRES", "LFOR_AD.pdf"))
+            .unwrap();
+        db.upsert_entry(&entry("LFPG", "PARIS", "LFPG_AD.pdf"))
+            .unwrap();
+        fs::create_dir_all(dir.path().join("LFPN")).unwrap();
+        fs::write(dir.path().join("LFPN/LFPN AD.pdf"), b"%PDF").unwrap();
+        fs::write(dir.path().join("LFOR_AD.pdf"), b"%PDF").unwrap();
+
+        let index = HtmlIndex::from_database(&db, &storage).unwrap();
+        assert_eq!(index.len(), 2);
+        index.write(&storage).unwrap();
+
+        let html = fs::read_to_string(dir.path().join(HTML_INDEX_NAME)).unwrap();
+        assert!(html.contains("<nav><a href=\"#E\">E</a><a href=\"#T\">T</a></nav>"));
+        assert!(html.contains("<td>ÉTAMPES &amp; CHARTRES</td>"));
+        assert!(html.contains("<a href=\"LFPN/LFPN%20AD.pdf\">LFPN</a>"));
+        assert!(!html.contains("LFPG"));
+        assert!(html.find("ÉTAMPES").unwrap() < html.find("TOUSSUS").unwrap());
+    }
+
+    #[test]
+    fn test_section_initial() {
+        assert_eq!(section_initial("Étampes"), 'E');
+        assert_eq!(section_initial("paris"), 'P');
+        assert_eq!(section_initial("1er RHC"), '#');
+        assert_eq!(section_initial(""), '#');
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::template::FileNameTemplate;
+use crate::VacEntry;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Result from importing a directory of PDFs
+#[derive(Debug, Default)]
+pub struct DirectoryImport {
+    /// Entries whose PDF was copied into storage and recorded in the cache
+    pub imported: Vec<VacEntry>,
+    /// PDFs that could not be matched against the remote listing
+    pub unmatched: Vec<PathBuf>,
+    /// PDFs that matched an entry which had already been matched by another file
+    pub duplicates: Vec<PathBuf>,
+}
+
+/// A local PDF matched to a remote entry
+#[derive(Debug)]
+pub(crate) struct MatchedFile {
+    pub path: PathBuf,
+    pub entry: VacEntry,
+    pub hash: String,
+}
+
+/// Outcome of matching the PDFs of a directory against the remote listing
+#[derive(Debug, Default)]
+pub(crate) struct DirectoryMatch {
+    pub matched: Vec<MatchedFile>,
+    pub unmatched: Vec<PathBuf>,
+    pub duplicates: Vec<PathBuf>,
+}
+
+/// Recursively collect the PDF files below a directory, sorted by path
+pub(crate) fn collect_pdfs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for item in fs::read_dir(&current).context(format!("Failed to list {:?}", current))? {
+            let path = item?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Match the PDFs found in a directory against the remote listing
+///
+/// A file matches an entry when its name is the file name used by the server
+/// or the name produced by the file name template. Otherwise, its SHA256 hash
+/// is compared with the hashes recorded in the cache, in which case the cached
+/// version is kept so that a stale file is still updated by the next sync.
+///
+/// # Arguments
+/// * `dir` - Directory to scan
+/// * `remote` - Entries from the remote listing
+/// * `cached` - Entries currently in the cache
+/// * `template` - Template used to lay out downloaded PDFs
+pub(crate) fn match_directory(
+    dir: &Path,
+    remote: &[VacEntry],
+    cached: &[VacEntry],
+    template: &FileNameTemplate,
+) -> Result<DirectoryMatch> {
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    for (index, entry) in remote.iter().enumerate() {
+        let server_name = entry.remote_file_name.as_ref().unwrap_or(&entry.file_name);
+        by_name.entry(base_name(server_name)).or_insert(index);
+        by_name
+            .entry(base_name(&template.render(entry)))
+            .or_insert(index);
+    }
+
+    let mut by_hash: HashMap<&str, (usize, &VacEntry)> = HashMap::new();
+    for cached_entry in cached {
+        let Some(hash) = cached_entry.file_hash.as_deref() else {
+            continue;
+        };
+        if let Some(index) = remote.iter().position(|entry| {
+            entry.oaci == cached_entry.oaci && entry.vac_type == cached_entry.vac_type
+        }) {
+            by_hash.insert(hash, (index, cached_entry));
+        }
+    }
+
+    let mut result = DirectoryMatch::default();
+    let mut by_file_name = Vec::new();
+    let mut by_file_hash = Vec::new();
+
+    for path in collect_pdfs(dir)? {
+        let data = fs::read(&path).context(format!("Failed to read {:?}", path))?;
+        let hash = format!("{:x}", Sha256::digest(&data));
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if let Some(&index) = by_name.get(&name) {
+            let mut entry = remote[index].clone();
+            entry.file_size = data.len() as i64;
+            by_file_name.push((index, MatchedFile { path, entry, hash }));
+        } else if let Some(&(index, cached_entry)) = by_hash.get(hash.as_str()) {
+            let mut entry = remote[index].clone();
+            entry.version = cached_entry.version.clone();
+            entry.file_size = data.len() as i64;
+            by_file_hash.push((index, MatchedFile { path, entry, hash }));
+        } else {
+            result.unmatched.push(path);
+        }
+    }
+
+    // Files named after an entry take precedence over copies found by hash
+    let mut seen = vec![false; remote.len()];
+    for (index, matched) in by_file_name.into_iter().chain(by_file_hash) {
+        if seen[index] {
+            result.duplicates.push(matched.path);
+        } else {
+            seen[index] = true;
+            result.matched.push(matched);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Lowercased last segment of a stored or server file name
+fn base_name(name: &str) -> String {
+    name.rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(name)
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote_entry(oaci: &str, file_name: &str) -> VacEntry {
+        VacEntry {
+            oaci: oaci.to_string(),
+            city: "City".to_string(),
+            vac_type: "AD".to_string(),
+            version: "2".to_string(),
+            file_name: file_name.to_string(),
+            remote_file_name: Some(file_name.to_string()),
+            file_size: 0,
+            file_hash: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_match_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("lfpg.PDF"), b"paris").unwrap();
+        fs::write(dir.path().join("renamed.pdf"), b"toussus").unwrap();
+        fs::write(dir.path().join("unknown.pdf"), b"unknown").unwrap();
+        fs::write(dir.path().join("LFPG_copy.pdf"), b"paris").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+        let remote = vec![
+            remote_entry("LFPG", "LFPG.pdf"),
+            remote_entry("LFPN", "LFPN.pdf"),
+        ];
+        let mut cached = remote_entry("LFPN", "LFPN.pdf");
+        cached.version = "1".to_string();
+        cached.file_hash = Some(format!("{:x}", Sha256::digest(b"toussus")));
+        let mut paris_cached = remote_entry("LFPG", "LFPG.pdf");
+        paris_cached.file_hash = Some(format!("{:x}", Sha256::digest(b"paris")));
+
+        let result = match_directory(
+            dir.path(),
+            &remote,
+            &[cached, paris_cached],
+            &FileNameTemplate::default(),
+        )
+        .unwrap();
+
+        assert_eq!(result.matched.len(), 2);
+        let paris = result
+            .matched
+            .iter()
+            .find(|m| m.entry.oaci == "LFPG")
+            .unwrap();
+        assert_eq!(paris.entry.version, "2");
+        assert_eq!(paris.entry.file_size, 5);
+        let toussus = result
+            .matched
+            .iter()
+            .find(|m| m.entry.oaci == "LFPN")
+            .unwrap();
+        // Matched by hash: the cached version is kept
+        assert_eq!(toussus.entry.version, "1");
+
+        assert_eq!(result.unmatched, vec![dir.path().join("unknown.pdf")]);
+        assert_eq!(result.duplicates, vec![dir.path().join("LFPG_copy.pdf")]);
+    }
+}
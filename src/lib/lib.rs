@@ -19,12 +19,83 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+#[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+compile_error!("either the `native-tls` or the `rustls-tls` feature must be enabled");
+
+pub mod airac;
+pub mod api;
 pub mod auth;
+pub mod bundle;
+pub mod calendar;
+pub mod cancel;
+pub mod checksums;
+mod circuit;
 pub mod database;
 pub mod downloader;
+pub mod email;
+pub mod events;
+pub mod export;
+pub mod hooks;
+pub mod html_index;
+pub mod import;
+mod lock;
+pub mod matching;
+pub mod metrics;
+pub mod mirror;
 pub mod models;
+pub mod observer;
+pub mod pack;
+pub mod pdf;
+pub mod rate_limit;
+pub mod regions;
+pub mod report;
+pub mod retry;
+pub mod route;
+pub mod storage;
+pub mod template;
+#[cfg(test)]
+mod testing;
+pub mod transport;
+pub mod trip;
+pub mod units;
 
-pub use auth::AuthGenerator;
+pub use airac::{parse_effective_date, AiracCycle};
+pub use api::{SiaApiClient, SIA_API_URL};
+pub use auth::{AuthError, AuthGenerator, AuthScheme};
+pub use bundle::{BundleImport, BundleManifest};
+pub use calendar::ChartCalendar;
+pub use cancel::CancellationToken;
+pub use checksums::{ChecksumManifest, SigningKey, CHECKSUMS_NAME, CHECKSUMS_SIGNATURE_NAME};
 pub use database::VacDatabase;
-pub use downloader::{DeleteResult, VacDownloader};
+pub use downloader::{
+    AirfieldUsage, Airport, AvailableUpdate, ChartFailure, ChartSummary, ChartUpdate, CheckResult,
+    CleanResult, CycleOutlook, DedupeResult, DeleteResult, EntryOutcome, EntryResult, FailureKind,
+    GcResult, OacisIter, RegionUsage, RollbackResult, StorageReport, UpdateReason, VacDownloader,
+    VacDownloaderBuilder, VerifyProblem, VerifyResult, ARCHIVE_DIR,
+};
+pub use email::{EmailConfig, EmailNotifier, SmtpSecurity};
+pub use events::{NdjsonEvents, SyncEvent};
+pub use export::{csv_field, ExportFormat};
+pub use hooks::SyncHooks;
+pub use html_index::HTML_INDEX_NAME;
+pub use import::DirectoryImport;
+pub use metrics::Metrics;
+pub use mirror::{MirrorEntry, MirrorManifest, MIRROR_MANIFEST_NAME};
 pub use models::*;
+pub use observer::SyncObserver;
+pub use pack::ContentPack;
+pub use rate_limit::RateLimiter;
+pub use regions::{Department, Territory};
+pub use report::{ReportFormat, SyncReport};
+pub use retry::RetryPolicy;
+pub use route::{parse_route, RoutePoint};
+pub use storage::{LocalStorage, S3Storage, Storage, StorageConfig, WebDavStorage};
+pub use template::{
+    sanitize_path, FileNameTemplate, DEFAULT_FILE_NAME_TEMPLATE, VERSIONED_FILE_NAME_TEMPLATE,
+};
+pub use transport::{
+    HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, TransportError, TransportErrorKind,
+};
+pub use trip::{TripLayout, TripPdf};
+pub use units::{format_size, parse_duration, parse_size};
+pub use vac_downloader_core::{changes, is_outdated, ChangeReason, ChartChange, LocalChart};
@@ -0,0 +1,95 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Advisory lock preventing two instances from changing the same cache at once
+///
+/// The lock is released when the value is dropped, or when the process exits.
+pub(crate) struct InstanceLock {
+    file: File,
+}
+
+impl InstanceLock {
+    /// Lock file used for a database
+    pub(crate) fn path_for(db_path: &Path) -> PathBuf {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(".lock");
+        PathBuf::from(name)
+    }
+
+    /// Acquire the lock
+    ///
+    /// # Arguments
+    /// * `path` - Path of the lock file, created if needed
+    /// * `wait` - Wait for another instance to release the lock instead of failing
+    pub(crate) fn acquire(path: &Path, wait: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .context(format!("Failed to open lock file {}", path.display()))?;
+
+        if file.try_lock_exclusive().is_err() {
+            if !wait {
+                anyhow::bail!(
+                    "Another instance is running (lock held on {})",
+                    path.display()
+                );
+            }
+            info!("⏳ Waiting for another instance to finish...");
+            file.lock_exclusive()
+                .context(format!("Failed to lock {}", path.display()))?;
+        }
+
+        Ok(InstanceLock { file })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = InstanceLock::path_for(&dir.path().join("vac_cache.db"));
+        assert!(path.ends_with("vac_cache.db.lock"));
+
+        let lock = InstanceLock::acquire(&path, false).unwrap();
+        let error = InstanceLock::acquire(&path, false).err().unwrap();
+        assert!(error.to_string().contains("Another instance is running"));
+
+        drop(lock);
+        assert!(InstanceLock::acquire(&path, false).is_ok());
+    }
+}
@@ -0,0 +1,315 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::VacEntry;
+use std::collections::HashSet;
+use tracing::warn;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Maximum number of airfields suggested for a code matching nothing
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Fold a name for comparisons: uppercase, without accents, with punctuation
+/// replaced by single spaces
+pub fn fold(text: &str) -> String {
+    let folded: String = text
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    folded
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Number of single-character insertions, deletions, substitutions and
+/// transpositions of adjacent characters turning `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j - 1] + cost)
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// Whether an OACI code matches a code or a glob pattern (`*` and `?`), ignoring case
+pub fn matches_code(pattern: &str, code: &str) -> bool {
+    fn matches(pattern: &[char], code: &[char]) -> bool {
+        match pattern.split_first() {
+            None => code.is_empty(),
+            Some(('*', rest)) => (0..=code.len()).any(|skip| matches(rest, &code[skip..])),
+            Some((p, rest)) => code
+                .split_first()
+                .is_some_and(|(c, code)| (*p == '?' || p == c) && matches(rest, code)),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.to_uppercase().chars().collect();
+    let code: Vec<char> = code.to_uppercase().chars().collect();
+    matches(&pattern, &code)
+}
+
+/// Airfields that may have been meant by a code matching none of the entries,
+/// closest first
+///
+/// Codes one edit away come first (e.g. a mistyped or swapped letter), then
+/// codes starting with the given text and airfields whose city contains it.
+pub fn suggest_codes<'a>(code: &str, entries: &'a [VacEntry]) -> Vec<&'a VacEntry> {
+    let code = code.trim().to_uppercase();
+    let text = fold(&code);
+    let mut seen = HashSet::new();
+    let mut candidates: Vec<(usize, &VacEntry)> = entries
+        .iter()
+        .filter(|entry| seen.insert(entry.oaci.to_uppercase()))
+        .filter_map(|entry| {
+            let oaci = entry.oaci.to_uppercase();
+            let distance = edit_distance(&code, &oaci);
+            let score = if distance <= 1 {
+                distance
+            } else if code.len() >= 2 && oaci.starts_with(&code) {
+                2
+            } else if text.len() >= 3 && fold(&entry.city).contains(&text) {
+                3
+            } else {
+                return None;
+            };
+            Some((score, entry))
+        })
+        .collect();
+    candidates.sort_by(|(a_score, a), (b_score, b)| a_score.cmp(b_score).then(a.oaci.cmp(&b.oaci)));
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Whether every word of a folded query is close to a word of a folded name
+///
+/// Query words match name words starting with them, or, for words of 5 letters
+/// or more, differing by one edit.
+fn fuzzy_words_match(query: &str, name: &str) -> bool {
+    let name_words: Vec<&str> = name.split(' ').collect();
+    query.split(' ').all(|query_word| {
+        let tolerance = usize::from(query_word.chars().count() >= 5);
+        name_words.iter().any(|name_word| {
+            name_word.starts_with(query_word) || edit_distance(query_word, name_word) <= tolerance
+        })
+    })
+}
+
+/// Entries whose city or OACI code matches a query, best matches first
+///
+/// The comparison ignores case, accents and punctuation, and tolerates typos
+/// in the words of the query: "chartre" finds "CHARTRES" and "saint etiene"
+/// finds "SAINT-ÉTIENNE".
+pub fn search<'a>(query: &str, entries: &'a [VacEntry]) -> Vec<&'a VacEntry> {
+    let mut results: Vec<(usize, &VacEntry)> = entries
+        .iter()
+        .filter_map(|entry| Some((search_rank(query, &entry.oaci, &entry.city)?, entry)))
+        .collect();
+    results.sort_by(|(a_score, a), (b_score, b)| {
+        a_score
+            .cmp(b_score)
+            .then(a.oaci.cmp(&b.oaci))
+            .then(a.vac_type.cmp(&b.vac_type))
+    });
+    results.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Rank of an airfield for a search query, lower is better, or None when it
+/// does not match
+///
+/// The exact OACI code ranks first, then cities and OACI codes starting with
+/// the query, cities containing it and cities matching it with typos.
+pub fn search_rank(query: &str, oaci: &str, city: &str) -> Option<usize> {
+    let query = fold(query);
+    if query.is_empty() {
+        return None;
+    }
+    let city = fold(city);
+    if oaci.eq_ignore_ascii_case(&query) {
+        Some(0)
+    } else if city.starts_with(&query)
+        || (query.len() >= 2 && oaci.to_uppercase().starts_with(&query))
+    {
+        Some(1)
+    } else if city.contains(&query) {
+        Some(2)
+    } else if fuzzy_words_match(&query, &city) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Suggestions for a code matching nothing, e.g. "LFPG (PARIS CHARLES DE GAULLE), LFPO (PARIS ORLY)"
+pub(crate) fn format_suggestions(code: &str, entries: &[VacEntry]) -> Option<String> {
+    let suggestions = suggest_codes(code, entries);
+    if suggestions.is_empty() {
+        return None;
+    }
+    Some(
+        suggestions
+            .iter()
+            .map(|entry| format!("{} ({})", entry.oaci, entry.city))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+/// Warn about the codes of a filter that match none of the entries, suggesting close ones
+pub(crate) fn warn_unmatched_codes(codes: &[String], entries: &[VacEntry]) {
+    for code in codes {
+        if entries.iter().any(|entry| matches_code(code, &entry.oaci)) {
+            continue;
+        }
+        match format_suggestions(code, entries) {
+            Some(suggestions) => warn!(
+                "⚠️  No airfield matches {}. Did you mean {}?",
+                code, suggestions
+            ),
+            None => warn!("⚠️  No airfield matches {}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(oaci: &str, city: &str) -> VacEntry {
+        VacEntry {
+            oaci: oaci.to_string(),
+            city: city.to_string(),
+            vac_type: "AD".to_string(),
+            version: "1.0".to_string(),
+            file_name: format!("{}_AD.pdf", oaci),
+            remote_file_name: None,
+            file_size: 1024,
+            file_hash: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("Saint-Étienne  Bouthéon"), "SAINT ETIENNE BOUTHEON");
+        assert_eq!(fold("chartres"), "CHARTRES");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("LFPG", "LFPG"), 0);
+        assert_eq!(edit_distance("LFGP", "LFPG"), 1);
+        assert_eq!(edit_distance("LFOR", "LFPG"), 2);
+        assert_eq!(edit_distance("LFP", "LFPG"), 1);
+        assert_eq!(edit_distance("LFPX", "LFPG"), 1);
+        assert_eq!(edit_distance("", "LFPG"), 4);
+    }
+
+    #[test]
+    fn test_matches_code() {
+        assert!(matches_code("LFPG", "LFPG"));
+        assert!(matches_code("lfpg", "LFPG"));
+        assert!(!matches_code("LFPG", "LFPO"));
+        assert!(!matches_code("LFP", "LFPG"));
+        assert!(matches_code("LFP*", "LFPG"));
+        assert!(matches_code("*", "LFPG"));
+        assert!(matches_code("LF?G", "LFPG"));
+        assert!(!matches_code("LF?G", "LFPO"));
+        assert!(matches_code("L*G", "LFPG"));
+        assert!(!matches_code("LFPG?", "LFPG"));
+    }
+
+    #[test]
+    fn test_search() {
+        let entries = vec![
+            entry("LFPG", "PARIS CHARLES DE GAULLE"),
+            entry("LFOR", "CHARTRES METROPOLE"),
+            entry("LFMH", "SAINT-ÉTIENNE BOUTHÉON"),
+            entry("LFPN", "TOUSSUS LE NOBLE"),
+        ];
+        let codes = |query| -> Vec<&str> {
+            search(query, &entries)
+                .iter()
+                .map(|entry| entry.oaci.as_str())
+                .collect()
+        };
+
+        assert_eq!(codes("chartres"), vec!["LFOR"]);
+        assert_eq!(codes("Chartre"), vec!["LFOR"]);
+        assert_eq!(codes("saint etienne"), vec!["LFMH"]);
+        assert_eq!(codes("etiene"), vec!["LFMH"]);
+        assert_eq!(codes("noble"), vec!["LFPN"]);
+        assert_eq!(codes("lfpg"), vec!["LFPG"]);
+        assert_eq!(codes("lfp"), vec!["LFPG", "LFPN"]);
+        // City prefixes rank before other matches
+        assert_eq!(codes("ch"), vec!["LFOR", "LFPG"]);
+        assert!(codes("brest").is_empty());
+        assert!(codes("  ").is_empty());
+    }
+
+    #[test]
+    fn test_suggest_codes() {
+        let entries = vec![
+            entry("LFPG", "PARIS CHARLES DE GAULLE"),
+            entry("LFPO", "PARIS ORLY"),
+            entry("LFOR", "CHARTRES METROPOLE"),
+            entry("LFRB", "BREST BRETAGNE"),
+        ];
+        let codes = |code| -> Vec<&str> {
+            suggest_codes(code, &entries)
+                .iter()
+                .map(|entry| entry.oaci.as_str())
+                .collect()
+        };
+
+        // One edit away, then the other codes of the prefix
+        assert_eq!(codes("LFPX"), vec!["LFPG", "LFPO"]);
+        assert_eq!(codes("LFGP"), vec!["LFPG"]);
+        assert_eq!(codes("lfp"), vec!["LFPG", "LFPO"]);
+        // City names, ignoring case and accents
+        assert_eq!(codes("chartres"), vec!["LFOR"]);
+        assert_eq!(codes("Brést"), vec!["LFRB"]);
+        assert!(codes("XXXX").is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::{SyncRun, SyncTotals};
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Prefix of the metric names
+const PREFIX: &str = "vac_downloader";
+
+/// State of the cache and of the sync history, exported as Prometheus metrics
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// Charts in the cache
+    pub cached_charts: u64,
+    /// Total size of the cached charts
+    pub cache_size: u64,
+    /// Downloads waiting in the download queue
+    pub pending_downloads: u64,
+    pub totals: SyncTotals,
+    pub last_run: Option<SyncRun>,
+}
+
+impl Metrics {
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            let _ = writeln!(text, "# HELP {}_{} {}", PREFIX, name, help);
+            let _ = writeln!(text, "# TYPE {}_{} {}", PREFIX, name, kind);
+            let _ = writeln!(text, "{}_{} {}", PREFIX, name, value);
+        };
+
+        metric(
+            "cached_charts",
+            "gauge",
+            "Charts in the cache.",
+            self.cached_charts as f64,
+        );
+        metric(
+            "cache_size_bytes",
+            "gauge",
+            "Total size of the cached charts.",
+            self.cache_size as f64,
+        );
+        metric(
+            "pending_downloads",
+            "gauge",
+            "Downloads waiting in the download queue.",
+            self.pending_downloads as f64,
+        );
+
+        let totals = &self.totals;
+        metric(
+            "sync_runs_total",
+            "counter",
+            "Sync runs.",
+            totals.runs as f64,
+        );
+        metric(
+            "sync_errors_total",
+            "counter",
+            "Sync runs aborted by an error.",
+            totals.failed_runs as f64,
+        );
+        metric(
+            "downloads_total",
+            "counter",
+            "Charts downloaded.",
+            totals.downloaded as f64,
+        );
+        metric(
+            "download_failures_total",
+            "counter",
+            "Failed downloads.",
+            totals.failed as f64,
+        );
+        metric(
+            "downloaded_bytes_total",
+            "counter",
+            "Size of the downloaded charts.",
+            totals.bytes_downloaded as f64,
+        );
+
+        if let Some(run) = &self.last_run {
+            metric(
+                "last_sync_timestamp_seconds",
+                "gauge",
+                "Time the last sync finished.",
+                finished_at(run),
+            );
+            metric(
+                "last_sync_duration_seconds",
+                "gauge",
+                "Duration of the last sync.",
+                run.duration_ms as f64 / 1000.0,
+            );
+            metric(
+                "last_sync_entries",
+                "gauge",
+                "Charts checked by the last sync.",
+                run.total_entries as f64,
+            );
+            metric(
+                "last_sync_downloads",
+                "gauge",
+                "Charts downloaded by the last sync.",
+                run.downloaded as f64,
+            );
+            metric(
+                "last_sync_failures",
+                "gauge",
+                "Failed downloads of the last sync.",
+                run.failed as f64,
+            );
+            metric(
+                "last_sync_success",
+                "gauge",
+                "Whether the last sync completed without being aborted by an error.",
+                if run.error.is_none() { 1.0 } else { 0.0 },
+            );
+        }
+        if let Some(run) = &totals.last_success {
+            metric(
+                "last_success_timestamp_seconds",
+                "gauge",
+                "Time the last sync not aborted by an error finished.",
+                finished_at(run),
+            );
+        }
+        text
+    }
+
+    /// Write the metrics to a file, e.g. for the textfile collector of node_exporter
+    ///
+    /// The file is replaced atomically, so the collector never reads it half written.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        std::fs::write(&temp, self.render())
+            .with_context(|| format!("Failed to write {}", Path::new(&temp).display()))?;
+        std::fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Unix time at which a run finished
+fn finished_at(run: &SyncRun) -> f64 {
+    NaiveDateTime::parse_from_str(&run.started_at, "%Y-%m-%d %H:%M:%S")
+        .map(|started| started.and_utc().timestamp() as f64 + run.duration_ms as f64 / 1000.0)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(error: Option<&str>) -> SyncRun {
+        SyncRun {
+            started_at: "2025-01-01 00:00:00".to_string(),
+            duration_ms: 2500,
+            source: "api".to_string(),
+            total_entries: 312,
+            downloaded: 2,
+            failed: 1,
+            bytes_downloaded: 2048,
+            download_ms: 1000,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics {
+            cached_charts: 312,
+            cache_size: 1_000_000,
+            pending_downloads: 1,
+            totals: SyncTotals {
+                runs: 3,
+                failed_runs: 1,
+                downloaded: 4,
+                failed: 1,
+                bytes_downloaded: 4096,
+                last_success: Some(run(None)),
+            },
+            last_run: Some(run(Some("API unreachable"))),
+        };
+        let text = metrics.render();
+        assert!(text.contains(
+            "# TYPE vac_downloader_downloads_total counter\nvac_downloader_downloads_total 4\n"
+        ));
+        assert!(text.contains("\nvac_downloader_cache_size_bytes 1000000\n"));
+        assert!(text.contains("\nvac_downloader_last_sync_success 0\n"));
+        assert!(text.contains("\nvac_downloader_last_success_timestamp_seconds 1735689602.5\n"));
+    }
+
+    #[test]
+    fn test_render_without_history() {
+        let text = Metrics::default().render();
+        assert!(text.contains("\nvac_downloader_sync_runs_total 0\n"));
+        assert!(!text.contains("last_sync"));
+    }
+}
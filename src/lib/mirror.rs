@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::storage::Storage;
+use crate::VacDatabase;
+use anyhow::{Context, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
+pub use vac_downloader_core::{MirrorEntry, MirrorManifest, MIRROR_MANIFEST_NAME};
+
+/// Build a manifest from the cached entries whose PDF is present and hashed
+pub(crate) fn manifest_from_database(
+    database: &VacDatabase,
+    storage: &dyn Storage,
+) -> Result<MirrorManifest> {
+    let mut entries = Vec::new();
+    for entry in database
+        .get_all_entries()
+        .context("Failed to read cache entries")?
+    {
+        let Some(file_hash) = entry.file_hash else {
+            continue;
+        };
+        if !storage.exists(&entry.file_name)? {
+            continue;
+        }
+        entries.push(MirrorEntry {
+            oaci: entry.oaci,
+            city: entry.city,
+            vac_type: entry.vac_type,
+            version: entry.version,
+            file_name: entry.file_name,
+            file_hash,
+            file_size: entry.file_size,
+        });
+    }
+
+    let generated_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(MirrorManifest::new(generated_at, entries))
+}
+
+/// Write a manifest at the root of the storage
+pub(crate) fn write_manifest(manifest: &MirrorManifest, storage: &dyn Storage) -> Result<()> {
+    let json = serde_json::to_vec_pretty(manifest)?;
+    storage
+        .write(MIRROR_MANIFEST_NAME, &json)
+        .context("Failed to write mirror manifest")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use crate::VacEntry;
+    use std::fs;
+
+    #[test]
+    fn test_manifest_only_lists_present_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let db = VacDatabase::new(":memory:").unwrap();
+
+        for oaci in ["LFPG", "LFPO"] {
+            db.upsert_entry(&VacEntry {
+                oaci: oaci.to_string(),
+                city: "Paris".to_string(),
+                vac_type: "AD".to_string(),
+                version: "1.0".to_string(),
+                file_name: format!("{}_AD.pdf", oaci),
+                file_size: 4,
+                file_hash: Some("abc123".to_string()),
+                remote_file_name: None,
+                page_count: None,
+                available_locally: false,
+            })
+            .unwrap();
+        }
+        fs::write(dir.path().join("LFPG_AD.pdf"), b"%PDF").unwrap();
+
+        let manifest = manifest_from_database(&db, &storage).unwrap();
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].oaci, "LFPG");
+
+        write_manifest(&manifest, &storage).unwrap();
+        let contents = fs::read_to_string(dir.path().join(MIRROR_MANIFEST_NAME)).unwrap();
+        let parsed: MirrorManifest = serde_json::from_str(&contents).unwrap();
+        assert_eq!(parsed.entries[0].file_hash, "abc123");
+    }
+}
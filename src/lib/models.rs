@@ -19,214 +19,378 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use serde::de::{self, Visitor};
-use serde::{Deserialize, Deserializer};
+use crate::template::sanitize_path;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+pub use vac_downloader_core::models::{
+    Coordinates, Frequency, Ground, Information, MagneticHeading, Map, OacisEntry, ParseError,
+    RadioFrequency, Runway, VacEntry,
+};
 
-/// Custom deserializer for elevation that handles both String and f64
-fn deserialize_elevation<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    struct ElevationVisitor;
-
-    impl<'de> Visitor<'de> for ElevationVisitor {
-        type Value = Option<f64>;
+/// Response from the OACIS API (Hydra pagination format)
+#[derive(Debug, Deserialize)]
+pub struct OacisResponse {
+    #[serde(rename = "hydra:member")]
+    pub members: Vec<OacisEntry>,
+    #[serde(rename = "hydra:totalItems")]
+    pub total_items: i32,
+}
 
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a string or number representing elevation")
-        }
+/// Rectangle of latitudes and longitudes, e.g. the area of a trip drawn on a map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    /// South-west corner
+    pub min: Coordinates,
+    /// North-east corner
+    pub max: Coordinates,
+}
 
-        fn visit_none<E>(self) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            Ok(None)
+impl BoundingBox {
+    /// Rectangle between two opposite corners, in any order
+    pub fn new(a: Coordinates, b: Coordinates) -> Self {
+        BoundingBox {
+            min: Coordinates {
+                latitude: a.latitude.min(b.latitude),
+                longitude: a.longitude.min(b.longitude),
+            },
+            max: Coordinates {
+                latitude: a.latitude.max(b.latitude),
+                longitude: a.longitude.max(b.longitude),
+            },
         }
+    }
 
-        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-        where
-            D: Deserializer<'de>,
-        {
-            deserializer.deserialize_any(ElevationValueVisitor)
-        }
+    /// Whether a position is inside the rectangle or on its edges
+    pub fn contains(&self, position: &Coordinates) -> bool {
+        (self.min.latitude..=self.max.latitude).contains(&position.latitude)
+            && (self.min.longitude..=self.max.longitude).contains(&position.longitude)
     }
+}
 
-    struct ElevationValueVisitor;
+impl FromStr for BoundingBox {
+    type Err = anyhow::Error;
 
-    impl<'de> Visitor<'de> for ElevationValueVisitor {
-        type Value = Option<f64>;
+    /// Parse two opposite corners in decimal degrees, "lat1,lon1,lat2,lon2"
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "Invalid bounding box {:?}, expected lat1,lon1,lat2,lon2",
+                value
+            )
+        };
+        let numbers = value
+            .split(',')
+            .map(|number| number.trim().parse::<f64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        let [lat1, lon1, lat2, lon2] = numbers[..] else {
+            return Err(invalid());
+        };
+        Ok(Self::new(
+            Coordinates::new(lat1, lon1)?,
+            Coordinates::new(lat2, lon2)?,
+        ))
+    }
+}
 
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a string or number")
-        }
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.min.latitude, self.min.longitude, self.max.latitude, self.max.longitude
+        )
+    }
+}
 
-        fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            Ok(Some(value))
-        }
+/// Download waiting in the persistent queue
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingDownload {
+    /// Entry to download; `file_hash` holds the expected hash when known
+    pub entry: VacEntry,
+    /// "api" or the URL of the mirror to download from
+    pub source: String,
+    /// Downloads with a higher priority are processed first
+    pub priority: i64,
+    /// Number of failed attempts for this version
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
 
-        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            Ok(Some(value as f64))
-        }
+/// Validators of an HTTP response, sent back in conditional requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpCacheEntry {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Response body, kept when a `304 Not Modified` must be answered from the cache
+    pub body: Option<Vec<u8>>,
+}
 
-        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            Ok(Some(value as f64))
-        }
+/// Response from the supplements API (Hydra pagination format)
+#[derive(Debug, Deserialize)]
+pub struct SupplementsResponse {
+    #[serde(rename = "hydra:member")]
+    pub members: Vec<SupplementEntry>,
+    #[serde(rename = "hydra:totalItems")]
+    pub total_items: i32,
+}
 
-        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            value.parse::<f64>().map(Some).map_err(|_| {
-                de::Error::custom(format!("failed to parse elevation string: {}", value))
-            })
-        }
+/// AIP supplement (SUP AIP) as listed by the API
+#[derive(Debug, Deserialize, Clone)]
+pub struct SupplementEntry {
+    pub id: i64,
+    /// Supplement number, e.g. "SUP 123/24"
+    pub number: String,
+    pub title: String,
+    /// Start of validity (ISO 8601)
+    #[serde(rename = "validFrom")]
+    pub valid_from: Option<String>,
+    /// End of validity (ISO 8601), if the supplement has one
+    #[serde(rename = "validUntil")]
+    pub valid_until: Option<String>,
+    /// OACI codes of the airfields the supplement affects
+    #[serde(default)]
+    pub aerodromes: Vec<String>,
+    /// Fields the API sent that are not modeled
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// AIP supplement stored in the cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Supplement {
+    pub number: String,
+    /// Identifier of the supplement on the server
+    pub remote_id: i64,
+    pub title: String,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+    pub aerodromes: Vec<String>,
+    pub file_name: String,
+    pub file_hash: Option<String>,
+}
 
-        fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            self.visit_str(&value)
+impl Supplement {
+    /// Build the cache entry of a listed supplement, stored under `SUP/`
+    pub fn from_supplement_entry(entry: &SupplementEntry) -> Self {
+        Supplement {
+            number: entry.number.clone(),
+            remote_id: entry.id,
+            title: entry.title.clone(),
+            valid_from: entry.valid_from.clone(),
+            valid_until: entry.valid_until.clone(),
+            aerodromes: entry.aerodromes.clone(),
+            file_name: sanitize_path(&format!(
+                "SUP/{}.pdf",
+                entry.number.replace('/', "-").replace(' ', "_")
+            )),
+            file_hash: None,
         }
     }
+}
 
-    deserializer.deserialize_option(ElevationVisitor)
+/// Page of a cached chart matching a full-text search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextMatch {
+    pub oaci: String,
+    pub vac_type: String,
+    /// Page number, starting at 1
+    pub page: u32,
+    /// Text around the match, with the matched words in brackets
+    pub snippet: String,
 }
 
-/// Response from the OACIS API (Hydra pagination format)
-#[derive(Debug, Deserialize)]
-pub struct OacisResponse {
-    #[serde(rename = "hydra:member")]
-    pub members: Vec<OacisEntry>,
-    #[serde(rename = "hydra:totalItems")]
-    pub total_items: i32,
+/// Record of a sync run, kept in the database history
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncRun {
+    /// Start time (UTC, "YYYY-MM-DD HH:MM:SS")
+    pub started_at: String,
+    pub duration_ms: u64,
+    /// "api" or the URL of the mirror the sync ran against
+    pub source: String,
+    pub total_entries: usize,
+    pub downloaded: usize,
+    pub failed: usize,
+    pub bytes_downloaded: u64,
+    /// Time spent downloading the PDFs
+    pub download_ms: u64,
+    /// Error that aborted the run, if any
+    pub error: Option<String>,
 }
 
-/// Individual OACIS entry (VAC/Heliport)
-#[derive(Debug, Deserialize, Clone)]
-pub struct OacisEntry {
-    pub code: String,
-    pub city: String,
-    pub grounds: Vec<Ground>,
-    pub maps: Vec<Map>,
-    pub runways: Vec<Runway>,
-    pub frequencies: Vec<Frequency>,
-    pub information: Vec<Information>,
+impl SyncRun {
+    /// Average download throughput in bytes per second, None when nothing was downloaded
+    pub fn throughput(&self) -> Option<u64> {
+        throughput(
+            self.bytes_downloaded,
+            std::time::Duration::from_millis(self.download_ms),
+        )
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Ground {
-    #[serde(rename = "type")]
-    pub ground_type: String,
-    #[serde(deserialize_with = "deserialize_elevation")]
-    pub elevation: Option<f64>,
-    pub coordinates: Option<Coordinates>,
+/// Bytes per second of a transfer, None when nothing was transferred
+pub(crate) fn throughput(bytes: u64, duration: std::time::Duration) -> Option<u64> {
+    let secs = duration.as_secs_f64();
+    (bytes > 0 && secs > 0.0).then(|| (bytes as f64 / secs) as u64)
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Coordinates {
-    pub latitude: f64,
-    pub longitude: f64,
+/// Kind of change recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// A chart was downloaded, restored or imported
+    Download,
+    /// A stored file was moved to another name
+    Move,
+    /// A chart or supplement was removed
+    Delete,
+    /// A chart was removed to respect the maximum cache size
+    Evict,
+    /// A file no cache entry referred to was removed
+    Clean,
+    /// A previous version of a chart was restored
+    Rollback,
+    /// A stored file was replaced with a link to an identical one
+    Link,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Map {
-    #[serde(rename = "fileName")]
-    pub file_name: String,
-    #[serde(rename = "type")]
-    pub map_type: String,
-    pub version: String,
-    #[serde(rename = "fileSize")]
-    pub file_size: i64,
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Download => "download",
+            AuditAction::Move => "move",
+            AuditAction::Delete => "delete",
+            AuditAction::Evict => "evict",
+            AuditAction::Clean => "clean",
+            AuditAction::Rollback => "rollback",
+            AuditAction::Link => "link",
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Runway {
-    pub length: String,
-    pub width: String,
-    #[serde(rename = "type")]
-    pub runway_type: String,
-    pub degrees: String,
+impl FromStr for AuditAction {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        [
+            AuditAction::Download,
+            AuditAction::Move,
+            AuditAction::Delete,
+            AuditAction::Evict,
+            AuditAction::Clean,
+            AuditAction::Rollback,
+            AuditAction::Link,
+        ]
+        .into_iter()
+        .find(|action| action.as_str() == value)
+        .ok_or_else(|| anyhow::anyhow!("Unknown audit action: {:?}", value))
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Frequency {
-    #[serde(rename = "freqAPP")]
-    pub freq_app: Option<String>,
-    #[serde(rename = "freqTWR")]
-    pub freq_twr: Option<String>,
-    #[serde(rename = "freqVDF")]
-    pub freq_vdf: Option<String>,
-    #[serde(rename = "freqATIS")]
-    pub freq_atis: Option<String>,
-    #[serde(rename = "freqFIS")]
-    pub freq_fis: Option<String>,
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Information {
-    pub address: Option<String>,
-    #[serde(rename = "phoneNumber")]
-    pub phone_number: Option<String>,
-    #[serde(rename = "faxNumber")]
-    pub fax_number: Option<String>,
-    pub hotel: Option<String>,
-    pub restaurant: Option<String>,
-    pub fuel: Option<String>,
-    pub repair: Option<String>,
-    pub night: Option<bool>,
-    #[serde(rename = "codeActivity")]
-    pub code_activity: Option<String>,
-    #[serde(rename = "descriptionActivity")]
-    pub description_activity: Option<String>,
-    pub language: Option<String>,
-    pub manager: Option<String>,
-    pub bank: Option<String>,
-}
-
-/// Processed VAC entry for database storage
-#[derive(Debug, Clone)]
-pub struct VacEntry {
+/// Change to the cache or the stored files, kept in the database audit log
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// Time of the change (UTC, "YYYY-MM-DD HH:MM:SS")
+    pub at: String,
+    pub action: AuditAction,
+    pub oaci: Option<String>,
+    pub vac_type: Option<String>,
+    /// Stored file affected by the change
+    pub file_name: Option<String>,
+    /// Why the change was made, e.g. "version 1 updated to 2"
+    pub reason: String,
+}
+
+impl AuditEntry {
+    /// Change made now to the chart of an entry and its file
+    pub fn chart(action: AuditAction, entry: &VacEntry, reason: impl Into<String>) -> Self {
+        AuditEntry {
+            oaci: Some(entry.oaci.clone()),
+            vac_type: Some(entry.vac_type.clone()),
+            file_name: Some(entry.file_name.clone()),
+            ..Self::file(action, &entry.file_name, reason)
+        }
+    }
+
+    /// Change made now to a stored file that is not a cached chart
+    pub fn file(action: AuditAction, file_name: &str, reason: impl Into<String>) -> Self {
+        AuditEntry {
+            at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            action,
+            oaci: None,
+            vac_type: None,
+            file_name: Some(file_name.to_string()),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Version of a chart downloaded in the past, whose file may still be stored
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartVersion {
     pub oaci: String,
-    pub city: String,
     pub vac_type: String,
     pub version: String,
     pub file_name: String,
     pub file_size: i64,
     pub file_hash: Option<String>,
-    pub available_locally: bool,
-}
-
-impl VacEntry {
-    /// Extract AD (airport) entries from OACIS data
-    pub fn from_oacis_entry(entry: &OacisEntry) -> Vec<Self> {
-        let mut results = Vec::new();
-
-        for map in &entry.maps {
-            // Filter only "AD" type (airports)
-            if map.map_type == "AD" {
-                results.push(VacEntry {
-                    oaci: entry.code.clone(),
-                    city: entry.city.clone(),
-                    vac_type: map.map_type.clone(),
-                    version: map.version.clone(),
-                    file_name: map.file_name.clone(),
-                    file_size: map.file_size,
-                    file_hash: None,          // Hash computed after download
-                    available_locally: false, // Not yet known to be local
-                });
-            }
+    pub page_count: Option<u32>,
+    /// Time of the download (UTC, "YYYY-MM-DD HH:MM:SS")
+    pub downloaded_at: String,
+    /// Whether the file is stored compressed with zstd, under [`ChartVersion::stored_name`]
+    pub compressed: bool,
+}
+
+impl ChartVersion {
+    /// Extension added to the names of compressed files
+    pub const COMPRESSED_EXTENSION: &'static str = ".zst";
+
+    /// Name of the stored file, with the extension of compressed files if needed
+    pub fn stored_name(&self) -> String {
+        if self.compressed {
+            format!("{}{}", self.file_name, Self::COMPRESSED_EXTENSION)
+        } else {
+            self.file_name.clone()
         }
+    }
+}
+
+/// Totals over the sync history
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncTotals {
+    pub runs: u64,
+    /// Runs aborted by an error
+    pub failed_runs: u64,
+    pub downloaded: u64,
+    pub failed: u64,
+    pub bytes_downloaded: u64,
+    /// Last run that was not aborted by an error
+    pub last_success: Option<SyncRun>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        results
+    #[test]
+    fn test_bounding_box() {
+        // Corners in any order
+        let bbox: BoundingBox = "49.2, 2.4, 48.6, 1.8".parse().unwrap();
+        assert_eq!(bbox, "48.6,1.8,49.2,2.4".parse().unwrap());
+        assert_eq!(bbox.to_string(), "48.6,1.8,49.2,2.4");
+        assert!(bbox.contains(&Coordinates::new(48.7519, 2.1061).unwrap()));
+        assert!(bbox.contains(&Coordinates::new(48.6, 1.8).unwrap()));
+        assert!(!bbox.contains(&Coordinates::new(49.0097, 2.5479).unwrap()));
+        assert!("48.6,1.8,49.2".parse::<BoundingBox>().is_err());
+        assert!("48.6,1.8,49.2,east".parse::<BoundingBox>().is_err());
+        assert!("48.6,1.8,95,2.4".parse::<BoundingBox>().is_err());
     }
 }
@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::downloader::SyncStats;
+use crate::models::VacEntry;
+
+/// Receives the progress of syncs, e.g. to drive a progress bar
+///
+/// Methods are called from the thread running the sync and do nothing by
+/// default.
+///
+/// ```
+/// use vac_downloader::{SyncObserver, VacEntry};
+///
+/// struct Printer;
+///
+/// impl SyncObserver for Printer {
+///     fn download_started(&self, entry: &VacEntry, index: usize, total: usize) {
+///         println!("[{}/{}] {}", index + 1, total, entry.oaci);
+///     }
+/// }
+/// ```
+pub trait SyncObserver: Send + Sync {
+    /// A sync starts against `source`, "api" or the URL of a mirror
+    fn sync_started(&self, _source: &str) {}
+
+    /// The listing of the API or the manifest of the mirror was fetched, with `entries` charts
+    fn listing_fetched(&self, _entries: usize) {}
+
+    /// The stored file of an up-to-date chart was checked against its hash,
+    /// with the problem found if any (it is then downloaded again)
+    fn chart_verified(&self, _entry: &VacEntry, _problem: Option<&str>) {}
+
+    /// A download starts, `index` counting from 0 among the `total` queued downloads
+    fn download_started(&self, _entry: &VacEntry, _index: usize, _total: usize) {}
+
+    /// Part of a download was received: `received` bytes so far, out of
+    /// `total` when the server or the listing gave the size of the file
+    fn download_progress(&self, _entry: &VacEntry, _received: u64, _total: Option<u64>) {}
+
+    /// A download finished, with the error that made it fail if any
+    fn download_finished(&self, _entry: &VacEntry, _error: Option<&anyhow::Error>) {}
+
+    /// A sync finished, with its counters or the error that aborted it
+    fn sync_finished(&self, _result: Result<&SyncStats, &anyhow::Error>) {}
+}
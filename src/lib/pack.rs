@@ -0,0 +1,186 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::matching;
+use crate::storage::Storage;
+use crate::VacDatabase;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use tracing::warn;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Folder of Bring Your Own Plates documents in a content pack
+const BYOP_DIR: &str = "byop";
+
+/// Manifest of an EFB content pack, as read by ForeFlight
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentPack {
+    pub name: String,
+    /// Short name shown on the pack, also the name of its root folder
+    pub abbreviation: String,
+    /// Increases with each export (YYYY.MMDD), so the app replaces older packs
+    pub version: f64,
+    pub organization_name: String,
+    /// Plates of the pack, relative to its root folder
+    #[serde(skip)]
+    pub plates: Vec<String>,
+}
+
+impl ContentPack {
+    /// Manifest of a pack generated today
+    pub(crate) fn new(name: &str, abbreviation: &str) -> Self {
+        let today = Utc::now().date_naive();
+        ContentPack {
+            name: name.to_string(),
+            abbreviation: abbreviation.to_string(),
+            version: today.year() as f64 + (today.month() * 100 + today.day()) as f64 / 10_000.0,
+            organization_name: "SIA".to_string(),
+            plates: Vec::new(),
+        }
+    }
+}
+
+/// Name of the plate of a chart in the Bring Your Own Plates folder
+///
+/// The app files plates under the airport given before the dash.
+fn plate_name(oaci: &str, vac_type: &str) -> String {
+    format!("{}/{}-VAC {}.pdf", BYOP_DIR, oaci, vac_type)
+}
+
+/// Write cached charts into a zipped content pack
+///
+/// The pack has a single root folder, named after the abbreviation, holding
+/// `manifest.json` and the charts under `byop/`.
+pub(crate) fn export_pack(
+    database: &VacDatabase,
+    storage: &dyn Storage,
+    mut pack: ContentPack,
+    output: &Path,
+    oaci_filter: Option<&[String]>,
+) -> Result<ContentPack> {
+    let mut entries = database
+        .get_all_entries()
+        .context("Failed to read cache entries")?;
+
+    if let Some(codes) = oaci_filter {
+        matching::warn_unmatched_codes(codes, &entries);
+        entries.retain(|entry| codes.iter().any(|c| c.eq_ignore_ascii_case(&entry.oaci)));
+    }
+
+    let file =
+        File::create(output).context(format!("Failed to create content pack at {:?}", output))?;
+    let mut archive = ZipWriter::new(file);
+    // PDFs are compressed already
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for entry in entries {
+        let Some(data) = storage.read(&entry.file_name)? else {
+            warn!("⚠️  File missing for {} - skipping", entry.oaci);
+            continue;
+        };
+        let plate = plate_name(&entry.oaci, &entry.vac_type);
+        let context = || format!("Failed to add {} to content pack", entry.file_name);
+        archive
+            .start_file(format!("{}/{}", pack.abbreviation, plate), options)
+            .with_context(context)?;
+        archive.write_all(&data).with_context(context)?;
+        pack.plates.push(plate);
+    }
+
+    archive
+        .start_file(format!("{}/manifest.json", pack.abbreviation), options)
+        .context("Failed to write content pack manifest")?;
+    archive.write_all(&serde_json::to_vec_pretty(&pack)?)?;
+    archive
+        .finish()
+        .context("Failed to finalize content pack")?;
+
+    Ok(pack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use crate::VacEntry;
+    use std::io::Read;
+
+    #[test]
+    fn test_export_pack() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path().join("pdfs")).unwrap();
+        let database = VacDatabase::new(":memory:").unwrap();
+        for oaci in ["LFPN", "LFPT", "LFPZ"] {
+            let file_name = format!("{}/{}_AD.pdf", oaci, oaci);
+            if oaci != "LFPZ" {
+                storage.write(&file_name, oaci.as_bytes()).unwrap();
+            }
+            database
+                .upsert_entry(&VacEntry {
+                    oaci: oaci.to_string(),
+                    city: "City".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1.0".to_string(),
+                    file_name,
+                    file_size: 4,
+                    file_hash: None,
+                    remote_file_name: None,
+                    page_count: None,
+                    available_locally: false,
+                })
+                .unwrap();
+        }
+
+        let output = dir.path().join("pack.zip");
+        let pack = export_pack(
+            &database,
+            &storage,
+            ContentPack::new("VAC France", "VAC"),
+            &output,
+            Some(&["lfpn".to_string(), "LFPZ".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(pack.plates, vec!["byop/LFPN-VAC AD.pdf"]);
+        assert!(pack.version > 2000.0);
+
+        let mut archive = zip::ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let mut plate = String::new();
+        archive
+            .by_name("VAC/byop/LFPN-VAC AD.pdf")
+            .unwrap()
+            .read_to_string(&mut plate)
+            .unwrap();
+        assert_eq!(plate, "LFPN");
+
+        let manifest: serde_json::Value =
+            serde_json::from_reader(archive.by_name("VAC/manifest.json").unwrap()).unwrap();
+        assert_eq!(manifest["name"], "VAC France");
+        assert_eq!(manifest["abbreviation"], "VAC");
+        assert_eq!(manifest["organizationName"], "SIA");
+        assert!(manifest.get("plates").is_none());
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use lopdf::Document;
+
+/// Check that `pdf` is a readable PDF document and count its pages
+///
+/// Fails when the file is not a PDF, cannot be parsed or has no pages, so
+/// that a corrupt file served by the SIA is not mistaken for a valid chart.
+pub fn page_count(pdf: &[u8]) -> Result<u32> {
+    if !pdf.starts_with(b"%PDF-") {
+        anyhow::bail!("Not a PDF file");
+    }
+    let document = Document::load_mem(pdf).context("Failed to parse PDF")?;
+    let pages = document.get_pages().len() as u32;
+    if pages == 0 {
+        anyhow::bail!("PDF has no pages");
+    }
+    Ok(pages)
+}
+
+/// Bytes at the end of a PDF in which its `%%EOF` marker must appear
+const TRAILER_WINDOW: usize = 1024;
+
+/// Check of the `%PDF-` header and the `%%EOF` trailer of a PDF received in
+/// chunks, keeping only its first and last bytes in memory
+///
+/// This catches error pages and truncated transfers without parsing the
+/// document; [`page_count`] checks it fully.
+#[derive(Debug, Default)]
+pub struct StreamCheck {
+    head: Vec<u8>,
+    tail: Vec<u8>,
+}
+
+impl StreamCheck {
+    /// Account for the next chunk of the file
+    pub fn update(&mut self, chunk: &[u8]) {
+        let missing = 5usize.saturating_sub(self.head.len()).min(chunk.len());
+        self.head.extend_from_slice(&chunk[..missing]);
+        self.tail.extend_from_slice(chunk);
+        let excess = self.tail.len().saturating_sub(TRAILER_WINDOW);
+        self.tail.drain(..excess);
+    }
+
+    /// Check the header and trailer once the whole file was received
+    pub fn finish(&self) -> Result<()> {
+        if !self.head.starts_with(b"%PDF-") {
+            anyhow::bail!("Not a PDF file");
+        }
+        if !self.tail.windows(5).any(|window| window == b"%%EOF") {
+            anyhow::bail!("Truncated PDF, no %%EOF marker at its end");
+        }
+        Ok(())
+    }
+}
+
+/// Extract the text of each page of a PDF
+///
+/// Pages whose text cannot be decoded, e.g. with unsupported font encodings,
+/// are returned empty.
+pub fn extract_text(pdf: &[u8]) -> Result<Vec<String>> {
+    let document = Document::load_mem(pdf).context("Failed to parse PDF")?;
+    Ok(document
+        .get_pages()
+        .keys()
+        .map(|&page| document.extract_text(&[page]).unwrap_or_default())
+        .collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object, Stream};
+
+    /// A PDF with the given number of pages, inheriting its media box from the page tree
+    pub(crate) fn sample_pdf(pages: usize) -> Vec<u8> {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let kids: Vec<Object> = (0..pages)
+            .map(|_| {
+                let content_id = document.add_object(Stream::new(dictionary! {}, Vec::new()));
+                document
+                    .add_object(dictionary! {
+                        "Type" => "Page",
+                        "Parent" => pages_id,
+                        "Contents" => content_id,
+                    })
+                    .into()
+            })
+            .collect();
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => pages as i64,
+                "MediaBox" => vec![0.into(), 0.into(), 420.into(), 595.into()],
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+        let mut pdf = Vec::new();
+        document.save_to(&mut pdf).unwrap();
+        pdf
+    }
+
+    #[test]
+    fn test_stream_check() {
+        let pdf = sample_pdf(1);
+        let mut check = StreamCheck::default();
+        for chunk in pdf.chunks(3) {
+            check.update(chunk);
+        }
+        assert!(check.finish().is_ok());
+
+        let mut truncated = StreamCheck::default();
+        truncated.update(&pdf[..pdf.len() - 10]);
+        assert!(truncated.finish().is_err());
+
+        let mut html = StreamCheck::default();
+        html.update(b"<html>Error</html>");
+        assert!(html.finish().is_err());
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let mut document = Document::load_mem(&sample_pdf(2)).unwrap();
+        let page = document.get_pages()[&2];
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let content_id = document.add_object(Stream::new(
+            dictionary! {},
+            b"BT /F1 12 Tf 10 10 Td (Activite ULM intense) Tj ET".to_vec(),
+        ));
+        let page = document
+            .get_object_mut(page)
+            .unwrap()
+            .as_dict_mut()
+            .unwrap();
+        page.set("Contents", content_id);
+        page.set(
+            "Resources",
+            dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        );
+        let mut pdf = Vec::new();
+        document.save_to(&mut pdf).unwrap();
+
+        let pages = extract_text(&pdf).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].trim().is_empty());
+        assert!(pages[1].contains("Activite ULM intense"));
+    }
+
+    #[test]
+    fn test_page_count() {
+        assert_eq!(page_count(&sample_pdf(1)).unwrap(), 1);
+        assert_eq!(page_count(&sample_pdf(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_page_count_rejects_invalid_files() {
+        assert!(page_count(b"<html>Service unavailable</html>").is_err());
+        assert!(page_count(b"%PDF-1.5\n").is_err());
+        assert!(page_count(&sample_pdf(0)).is_err());
+
+        let pdf = sample_pdf(2);
+        assert!(page_count(&pdf[..pdf.len() / 2]).is_err());
+    }
+}
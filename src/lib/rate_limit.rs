@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Share of the interval between requests added at random to each delay
+const JITTER: f64 = 0.2;
+
+/// Client-side limit on the rate of requests sent to a server
+///
+/// Requests are spaced by at least `1 / requests_per_second`, plus a random
+/// jitter of up to 20% of that interval so that scheduled runs do not hit the
+/// server in lockstep. The limiter can be shared between threads: each call
+/// to [`RateLimiter::acquire`] reserves the next free slot.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// Earliest time the next request may be sent
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Allow up to `requests_per_second` requests per second on average
+    pub fn new(requests_per_second: f64) -> anyhow::Result<Self> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            anyhow::bail!(
+                "Invalid rate limit: {} requests per second",
+                requests_per_second
+            );
+        }
+        Ok(RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            next: Mutex::new(None),
+        })
+    }
+
+    /// Minimum interval between two requests
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Wait until a request may be sent
+    pub fn acquire(&self) {
+        let delay = self.reserve(Instant::now(), random_fraction());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Reserve the next slot at or after `now`, returning how long to wait for it
+    fn reserve(&self, now: Instant, jitter: f64) -> Duration {
+        let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval.mul_f64(1.0 + JITTER * jitter));
+        slot - now
+    }
+}
+
+/// Random number in [0, 1), good enough for jitter
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_spaces_requests() {
+        let limiter = RateLimiter::new(2.0).unwrap();
+        assert_eq!(limiter.interval(), Duration::from_millis(500));
+
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now, 0.0), Duration::ZERO);
+        assert_eq!(limiter.reserve(now, 1.0), Duration::from_millis(500));
+        // The jitter of the previous request delays the next one by up to 20%
+        assert_eq!(limiter.reserve(now, 0.0), Duration::from_millis(1100));
+
+        // Slots are not accumulated while idle
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(later, 0.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        assert!(RateLimiter::new(0.0).is_err());
+        assert!(RateLimiter::new(-1.0).is_err());
+        assert!(RateLimiter::new(f64::NAN).is_err());
+        assert!((0.0..1.0).contains(&random_fraction()));
+    }
+}
@@ -0,0 +1,450 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! French territories, regions and departments of the airfields
+//!
+//! Airfields are placed in the department whose center is the closest to their
+//! position, distances being scaled by the size of the departments. This needs no
+//! boundary data but is approximate: an airfield close to a border may be placed
+//! in the neighboring department. Known misplaced airfields are listed in
+//! [`DEPARTMENT_OVERRIDES`].
+
+use crate::matching::fold;
+use crate::{Coordinates, OacisEntry};
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum distance between an airfield and the center of its department,
+/// relative to the radius of a disc of the department's area; farther
+/// airfields, e.g. in overseas collectivities, are in no department
+const MAX_RELATIVE_DISTANCE: f64 = 3.0;
+
+const ARA: &str = "Auvergne-Rhône-Alpes";
+const BFC: &str = "Bourgogne-Franche-Comté";
+const BRE: &str = "Bretagne";
+const CVL: &str = "Centre-Val de Loire";
+const COR: &str = "Corse";
+const GE: &str = "Grand Est";
+const HDF: &str = "Hauts-de-France";
+const IDF: &str = "Île-de-France";
+const NOR: &str = "Normandie";
+const NA: &str = "Nouvelle-Aquitaine";
+const OCC: &str = "Occitanie";
+const PDL: &str = "Pays de la Loire";
+const PACA: &str = "Provence-Alpes-Côte d'Azur";
+const GP: &str = "Guadeloupe";
+const MQ: &str = "Martinique";
+const GF: &str = "Guyane";
+const RE: &str = "La Réunion";
+const YT: &str = "Mayotte";
+
+/// Part of the SIA catalogue, told apart by the prefix of the OACI codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Territory {
+    /// Mainland France and Corsica
+    Metropole,
+    /// Guadeloupe, Martinique, Saint-Martin and Saint-Barthélemy
+    Antilles,
+    Guyane,
+    /// La Réunion and Mayotte
+    OceanIndien,
+    Polynesie,
+    NouvelleCaledonie,
+    WallisEtFutuna,
+    SaintPierreEtMiquelon,
+}
+
+impl Territory {
+    pub const ALL: [Territory; 8] = [
+        Territory::Metropole,
+        Territory::Antilles,
+        Territory::Guyane,
+        Territory::OceanIndien,
+        Territory::Polynesie,
+        Territory::NouvelleCaledonie,
+        Territory::WallisEtFutuna,
+        Territory::SaintPierreEtMiquelon,
+    ];
+
+    /// Name of the territory on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Territory::Metropole => "metropole",
+            Territory::Antilles => "antilles",
+            Territory::Guyane => "guyane",
+            Territory::OceanIndien => "ocean-indien",
+            Territory::Polynesie => "polynesie",
+            Territory::NouvelleCaledonie => "nouvelle-caledonie",
+            Territory::WallisEtFutuna => "wallis-et-futuna",
+            Territory::SaintPierreEtMiquelon => "saint-pierre-et-miquelon",
+        }
+    }
+
+    /// Territory of an airfield, from the prefix of its OACI code
+    pub fn of(oaci: &str) -> Option<Territory> {
+        let oaci = oaci.to_ascii_uppercase();
+        // Saint-Pierre-et-Miquelon shares the LF prefix of mainland France
+        if oaci.starts_with("LFV") {
+            return Some(Territory::SaintPierreEtMiquelon);
+        }
+        match oaci.get(..2)? {
+            "LF" => Some(Territory::Metropole),
+            "TF" => Some(Territory::Antilles),
+            "SO" => Some(Territory::Guyane),
+            "FM" => Some(Territory::OceanIndien),
+            "NT" => Some(Territory::Polynesie),
+            "NW" => Some(Territory::NouvelleCaledonie),
+            "NL" => Some(Territory::WallisEtFutuna),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Territory {
+    type Err = anyhow::Error;
+
+    /// Parse the name of a territory, ignoring case, accents and punctuation
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        Territory::ALL
+            .into_iter()
+            .find(|territory| fold(territory.name()) == fold(value))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Territory::ALL.iter().map(|t| t.name()).collect();
+                anyhow::anyhow!(
+                    "Unknown territory {:?}, expected one of: {}, all",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Territory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Region reported for airfields outside of the French regions
+pub const UNKNOWN_REGION: &str = "Unknown";
+
+/// Regions, metropolitan then overseas
+pub const REGIONS: &[&str] = &[
+    ARA, BFC, BRE, CVL, COR, GE, HDF, IDF, NOR, NA, OCC, PDL, PACA, GP, MQ, GF, RE, YT,
+];
+
+/// French department
+#[derive(Debug, Clone, PartialEq)]
+pub struct Department {
+    /// Number of the department, e.g. "44" or "2A"
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Name of the region of the department
+    pub region: &'static str,
+    /// Approximate geographic center
+    center: Coordinates,
+    /// Area in square kilometers
+    area: f64,
+}
+
+impl Department {
+    /// Distance of a position to the center, relative to the size of the department
+    fn relative_distance(&self, position: &Coordinates) -> f64 {
+        let radius = (self.area / std::f64::consts::PI).sqrt() * 1000.0;
+        position.distance(&self.center) / radius
+    }
+}
+
+const fn department(
+    code: &'static str,
+    name: &'static str,
+    region: &'static str,
+    latitude: f64,
+    longitude: f64,
+    area: f64,
+) -> Department {
+    Department {
+        code,
+        name,
+        region,
+        center: Coordinates {
+            latitude,
+            longitude,
+        },
+        area,
+    }
+}
+
+/// Departments, by number
+pub const DEPARTMENTS: &[Department] = &[
+    department("01", "Ain", ARA, 46.10, 5.35, 5762.0),
+    department("02", "Aisne", HDF, 49.56, 3.56, 7369.0),
+    department("03", "Allier", ARA, 46.39, 3.19, 7340.0),
+    department("04", "Alpes-de-Haute-Provence", PACA, 44.11, 6.24, 6925.0),
+    department("05", "Hautes-Alpes", PACA, 44.66, 6.26, 5549.0),
+    department("06", "Alpes-Maritimes", PACA, 43.94, 7.12, 4299.0),
+    department("07", "Ardèche", ARA, 44.75, 4.42, 5529.0),
+    department("08", "Ardennes", GE, 49.62, 4.64, 5229.0),
+    department("09", "Ariège", OCC, 42.92, 1.50, 4890.0),
+    department("10", "Aube", GE, 48.30, 4.16, 6004.0),
+    department("11", "Aude", OCC, 43.10, 2.41, 6139.0),
+    department("12", "Aveyron", OCC, 44.28, 2.68, 8735.0),
+    department("13", "Bouches-du-Rhône", PACA, 43.54, 5.09, 5087.0),
+    department("14", "Calvados", NOR, 49.10, -0.36, 5548.0),
+    department("15", "Cantal", ARA, 45.05, 2.67, 5726.0),
+    department("16", "Charente", NA, 45.72, 0.20, 5956.0),
+    department("17", "Charente-Maritime", NA, 45.78, -0.67, 6864.0),
+    department("18", "Cher", CVL, 47.06, 2.49, 7235.0),
+    department("19", "Corrèze", NA, 45.36, 1.88, 5857.0),
+    department("2A", "Corse-du-Sud", COR, 41.86, 8.99, 4014.0),
+    department("2B", "Haute-Corse", COR, 42.39, 9.21, 4666.0),
+    department("21", "Côte-d'Or", BFC, 47.42, 4.77, 8763.0),
+    department("22", "Côtes-d'Armor", BRE, 48.44, -2.86, 6878.0),
+    department("23", "Creuse", NA, 46.09, 2.02, 5565.0),
+    department("24", "Dordogne", NA, 45.10, 0.74, 9060.0),
+    department("25", "Doubs", BFC, 47.16, 6.36, 5234.0),
+    department("26", "Drôme", ARA, 44.68, 5.17, 6530.0),
+    department("27", "Eure", NOR, 49.11, 0.99, 6040.0),
+    department("28", "Eure-et-Loir", CVL, 48.39, 1.37, 5880.0),
+    department("29", "Finistère", BRE, 48.26, -4.06, 6733.0),
+    department("30", "Gard", OCC, 43.99, 4.18, 5853.0),
+    department("31", "Haute-Garonne", OCC, 43.36, 1.17, 6309.0),
+    department("32", "Gers", OCC, 43.69, 0.45, 6257.0),
+    department("33", "Gironde", NA, 44.83, -0.58, 9976.0),
+    department("34", "Hérault", OCC, 43.58, 3.37, 6101.0),
+    department("35", "Ille-et-Vilaine", BRE, 48.15, -1.64, 6775.0),
+    department("36", "Indre", CVL, 46.78, 1.58, 6791.0),
+    department("37", "Indre-et-Loire", CVL, 47.26, 0.69, 6127.0),
+    department("38", "Isère", ARA, 45.26, 5.58, 7431.0),
+    department("39", "Jura", BFC, 46.73, 5.70, 4999.0),
+    department("40", "Landes", NA, 43.97, -0.78, 9243.0),
+    department("41", "Loir-et-Cher", CVL, 47.62, 1.43, 6343.0),
+    department("42", "Loire", ARA, 45.73, 4.17, 4781.0),
+    department("43", "Haute-Loire", ARA, 45.13, 3.81, 4977.0),
+    department("44", "Loire-Atlantique", PDL, 47.36, -1.68, 6815.0),
+    department("45", "Loiret", CVL, 47.91, 2.34, 6775.0),
+    department("46", "Lot", OCC, 44.62, 1.60, 5217.0),
+    department("47", "Lot-et-Garonne", NA, 44.37, 0.46, 5361.0),
+    department("48", "Lozère", OCC, 44.52, 3.50, 5167.0),
+    department("49", "Maine-et-Loire", PDL, 47.39, -0.56, 7166.0),
+    department("50", "Manche", NOR, 49.08, -1.33, 5938.0),
+    department("51", "Marne", GE, 48.95, 4.24, 8162.0),
+    department("52", "Haute-Marne", GE, 48.11, 5.23, 6211.0),
+    department("53", "Mayenne", PDL, 48.15, -0.65, 5175.0),
+    department("54", "Meurthe-et-Moselle", GE, 48.79, 6.16, 5246.0),
+    department("55", "Meuse", GE, 48.99, 5.38, 6211.0),
+    department("56", "Morbihan", BRE, 47.85, -2.81, 6823.0),
+    department("57", "Moselle", GE, 49.04, 6.66, 6216.0),
+    department("58", "Nièvre", BFC, 47.12, 3.50, 6817.0),
+    department("59", "Nord", HDF, 50.45, 3.22, 5743.0),
+    department("60", "Oise", HDF, 49.41, 2.43, 5860.0),
+    department("61", "Orne", NOR, 48.62, 0.13, 6103.0),
+    department("62", "Pas-de-Calais", HDF, 50.49, 2.29, 6671.0),
+    department("63", "Puy-de-Dôme", ARA, 45.73, 3.13, 7970.0),
+    department("64", "Pyrénées-Atlantiques", NA, 43.26, -0.76, 7645.0),
+    department("65", "Hautes-Pyrénées", OCC, 43.05, 0.16, 4464.0),
+    department("66", "Pyrénées-Orientales", OCC, 42.60, 2.52, 4116.0),
+    department("67", "Bas-Rhin", GE, 48.67, 7.55, 4755.0),
+    department("68", "Haut-Rhin", GE, 47.86, 7.27, 3525.0),
+    department("69", "Rhône", ARA, 45.87, 4.64, 3249.0),
+    department("70", "Haute-Saône", BFC, 47.64, 6.09, 5360.0),
+    department("71", "Saône-et-Loire", BFC, 46.64, 4.54, 8575.0),
+    department("72", "Sarthe", PDL, 47.99, 0.22, 6206.0),
+    department("73", "Savoie", ARA, 45.48, 6.44, 6028.0),
+    department("74", "Haute-Savoie", ARA, 46.03, 6.43, 4388.0),
+    department("75", "Paris", IDF, 48.86, 2.34, 105.0),
+    department("76", "Seine-Maritime", NOR, 49.66, 1.03, 6278.0),
+    department("77", "Seine-et-Marne", IDF, 48.63, 2.93, 5915.0),
+    department("78", "Yvelines", IDF, 48.82, 1.84, 2284.0),
+    department("79", "Deux-Sèvres", NA, 46.56, -0.32, 5999.0),
+    department("80", "Somme", HDF, 49.96, 2.28, 6170.0),
+    department("81", "Tarn", OCC, 43.79, 2.17, 5758.0),
+    department("82", "Tarn-et-Garonne", OCC, 44.08, 1.28, 3718.0),
+    department("83", "Var", PACA, 43.46, 6.22, 5973.0),
+    department("84", "Vaucluse", PACA, 44.01, 5.18, 3567.0),
+    department("85", "Vendée", PDL, 46.67, -1.30, 6720.0),
+    department("86", "Vienne", NA, 46.56, 0.46, 6990.0),
+    department("87", "Haute-Vienne", NA, 45.89, 1.23, 5520.0),
+    department("88", "Vosges", GE, 48.20, 6.38, 5874.0),
+    department("89", "Yonne", BFC, 47.84, 3.56, 7427.0),
+    department("90", "Territoire de Belfort", BFC, 47.63, 6.93, 609.0),
+    department("91", "Essonne", IDF, 48.52, 2.24, 1804.0),
+    department("92", "Hauts-de-Seine", IDF, 48.85, 2.25, 176.0),
+    department("93", "Seine-Saint-Denis", IDF, 48.92, 2.48, 236.0),
+    department("94", "Val-de-Marne", IDF, 48.78, 2.47, 245.0),
+    department("95", "Val-d'Oise", IDF, 49.08, 2.13, 1246.0),
+    department("971", "Guadeloupe", GP, 16.20, -61.55, 1628.0),
+    department("972", "Martinique", MQ, 14.64, -61.02, 1128.0),
+    department("973", "Guyane", GF, 4.30, -53.20, 83534.0),
+    department("974", "La Réunion", RE, -21.13, 55.53, 2512.0),
+    department("976", "Mayotte", YT, -12.82, 45.15, 374.0),
+];
+
+/// Departments of the airfields misplaced by their position, by OACI code
+pub const DEPARTMENT_OVERRIDES: &[(&str, &str)] = &[
+    ("LFAG", "80"),
+    ("LFBG", "16"),
+    ("LFBH", "17"),
+    ("LFFC", "95"),
+    ("LFIT", "31"),
+    ("LFJL", "57"),
+    ("LFLB", "73"),
+    ("LFLL", "69"),
+    ("LFNG", "34"),
+    ("LFOH", "76"),
+    ("LFPA", "95"),
+    ("LFPG", "95"),
+    ("LFPI", "75"),
+    ("LFPO", "94"),
+];
+
+/// Department of an airfield, None outside of France or without a known position
+pub fn airfield_department(airfield: &OacisEntry) -> Option<&'static Department> {
+    DEPARTMENT_OVERRIDES
+        .iter()
+        .find(|(oaci, _)| airfield.code.eq_ignore_ascii_case(oaci))
+        .and_then(|(_, code)| find_department(code))
+        .or_else(|| department_at(airfield.coordinates()?))
+}
+
+/// Region of an airfield, None outside of the French regions or without a known position
+pub fn airfield_region(airfield: &OacisEntry) -> Option<&'static str> {
+    airfield_department(airfield).map(|department| department.region)
+}
+
+/// Department of a position, None outside of France
+pub fn department_at(position: &Coordinates) -> Option<&'static Department> {
+    DEPARTMENTS
+        .iter()
+        .map(|department| (department, department.relative_distance(position)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|(_, distance)| *distance <= MAX_RELATIVE_DISTANCE)
+        .map(|(department, _)| department)
+}
+
+/// Find a department by number or name, ignoring case, accents and punctuation
+pub fn find_department(query: &str) -> Option<&'static Department> {
+    let query = fold(query);
+    DEPARTMENTS
+        .iter()
+        .find(|department| department.code == query || fold(department.name) == query)
+}
+
+/// Find a region by name, ignoring case, accents and punctuation
+pub fn find_region(query: &str) -> Option<&'static str> {
+    let query = fold(query);
+    REGIONS.iter().copied().find(|region| fold(region) == query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn department_code(latitude: f64, longitude: f64) -> Option<&'static str> {
+        department_at(&Coordinates {
+            latitude,
+            longitude,
+        })
+        .map(|department| department.code)
+    }
+
+    #[test]
+    fn test_department_at() {
+        // Toussus-le-Noble, next to the small Hauts-de-Seine
+        assert_eq!(department_code(48.752, 2.106), Some("78"));
+        // Brest, Nantes, Ajaccio and Saint-Denis de la Réunion
+        assert_eq!(department_code(48.448, -4.419), Some("29"));
+        assert_eq!(department_code(47.153, -1.611), Some("44"));
+        assert_eq!(department_code(41.924, 8.803), Some("2A"));
+        assert_eq!(department_code(-20.887, 55.510), Some("974"));
+        // Saint-Pierre-et-Miquelon and Saint-Barthélemy are not departments
+        assert_eq!(department_code(46.763, -56.174), None);
+        assert_eq!(department_code(17.900, -62.844), None);
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find_region("bretagne"), Some("Bretagne"));
+        assert_eq!(find_region("ile-de-france"), Some("Île-de-France"));
+        assert_eq!(
+            find_region("provence alpes cote d'azur"),
+            Some("Provence-Alpes-Côte d'Azur")
+        );
+        assert_eq!(find_region("Loire"), None);
+        assert_eq!(find_department("44").unwrap().name, "Loire-Atlantique");
+        assert_eq!(find_department("2a").unwrap().region, "Corse");
+        assert_eq!(find_department("cotes d'armor").unwrap().code, "22");
+        assert!(find_department("20").is_none());
+    }
+
+    #[test]
+    fn test_territory() {
+        assert_eq!(Territory::of("LFPN"), Some(Territory::Metropole));
+        assert_eq!(Territory::of("lfkj"), Some(Territory::Metropole));
+        assert_eq!(
+            Territory::of("LFVP"),
+            Some(Territory::SaintPierreEtMiquelon)
+        );
+        assert_eq!(Territory::of("TFFR"), Some(Territory::Antilles));
+        assert_eq!(Territory::of("NTAA"), Some(Territory::Polynesie));
+        assert_eq!(Territory::of("EGLL"), None);
+        assert_eq!(
+            "Métropole".parse::<Territory>().unwrap(),
+            Territory::Metropole
+        );
+        assert_eq!(
+            "ocean indien".parse::<Territory>().unwrap(),
+            Territory::OceanIndien
+        );
+        assert!("reunion".parse::<Territory>().is_err());
+        for territory in Territory::ALL {
+            assert_eq!(territory.name().parse::<Territory>().unwrap(), territory);
+        }
+    }
+
+    #[test]
+    fn test_airfield_department() {
+        let airfield: OacisEntry = serde_json::from_value(serde_json::json!({
+            "code": "LFPG",
+            "city": "PARIS CHARLES DE GAULLE",
+            "grounds": [{
+                "type": "AD",
+                "elevation": "392",
+                "coordinates": {"latitude": 49.0097, "longitude": 2.5479}
+            }]
+        }))
+        .unwrap();
+        // Placed in the Oise by its position
+        assert_eq!(department_code(49.0097, 2.5479), Some("60"));
+        assert_eq!(airfield_department(&airfield).unwrap().code, "95");
+    }
+
+    #[test]
+    fn test_every_department_has_a_region() {
+        for department in DEPARTMENTS {
+            assert!(REGIONS.contains(&department.region), "{}", department.code);
+            assert_eq!(find_department(department.code), Some(department));
+        }
+        for (oaci, code) in DEPARTMENT_OVERRIDES {
+            assert!(find_department(code).is_some(), "{}", oaci);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::downloader::SyncStats;
+use crate::html_index::escape;
+use crate::units::format_size;
+use std::fmt::Write;
+use std::path::Path;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:1em auto;max-width:60em;padding:0 1em}\
+table{border-collapse:collapse;margin-bottom:1em}th,td{padding:.3em .6em;text-align:left;vertical-align:top}\
+tr:nth-child(even){background:#f2f2f2}h2{border-bottom:1px solid #ccc}";
+
+/// File format of a sync report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Standalone HTML page
+    Html,
+    /// Markdown document
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Format matching the extension of a report file: Markdown for `.md` and
+    /// `.markdown`, HTML otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("md" | "markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Html,
+        }
+    }
+}
+
+/// Human-readable report of a sync: totals, new versions and failures
+pub struct SyncReport<'a> {
+    stats: &'a SyncStats,
+    generated_at: String,
+}
+
+impl<'a> SyncReport<'a> {
+    /// Report on the outcome of a sync
+    pub fn new(stats: &'a SyncStats) -> Self {
+        SyncReport {
+            stats,
+            generated_at: chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+        }
+    }
+
+    /// Render the report in the given format
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => self.render_html(),
+            ReportFormat::Markdown => self.render_markdown(),
+        }
+    }
+
+    /// Counters of the sync, with their label
+    fn totals(&self) -> Vec<(&'static str, String)> {
+        let stats = self.stats;
+        let mut totals = vec![
+            ("Charts checked", stats.total_entries.to_string()),
+            ("Up to date", stats.up_to_date.to_string()),
+            ("Verified", stats.verified.to_string()),
+            ("Downloaded", stats.downloaded.to_string()),
+            (
+                "Redownloaded (corrupted/missing)",
+                stats.redownloaded_corrupted.to_string(),
+            ),
+            ("Failed", stats.failed.to_string()),
+        ];
+        if stats.deferred > 0 {
+            totals.push(("Deferred (time budget)", stats.deferred.to_string()));
+        }
+        if stats.evicted > 0 {
+            totals.push(("Evicted", stats.evicted.to_string()));
+        }
+        if stats.supplements_downloaded > 0 || stats.supplements_removed > 0 {
+            totals.push((
+                "Supplements downloaded",
+                stats.supplements_downloaded.to_string(),
+            ));
+            totals.push(("Supplements removed", stats.supplements_removed.to_string()));
+        }
+        totals.push(("Downloaded size", format_size(stats.bytes_downloaded)));
+        if let Some(rate) = stats.throughput() {
+            totals.push(("Throughput", format!("{}/s", format_size(rate))));
+        }
+        totals.push(("Duration", format!("{:.1} s", stats.duration.as_secs_f64())));
+        totals
+    }
+
+    /// Rows of the new versions table
+    fn update_rows(&self) -> Vec<[String; 6]> {
+        self.stats
+            .updates
+            .iter()
+            .map(|update| {
+                let previous = match &update.previous_version {
+                    None => "new".to_string(),
+                    Some(previous) if *previous == update.version => "redownloaded".to_string(),
+                    Some(previous) => previous.clone(),
+                };
+                [
+                    update.oaci.clone(),
+                    update.city.clone(),
+                    update.vac_type.clone(),
+                    previous,
+                    update.version.clone(),
+                    format_size(update.file_size),
+                ]
+            })
+            .collect()
+    }
+
+    fn render_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>VAC sync report</title>\n");
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+        html.push_str("<h1>VAC sync report</h1>\n");
+        let _ = writeln!(html, "<p>Generated on {}</p>", self.generated_at);
+
+        html.push_str("<h2>Totals</h2>\n<table>\n");
+        for (label, value) in self.totals() {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>New versions</h2>\n");
+        let updates = self.update_rows();
+        if updates.is_empty() {
+            html.push_str("<p>No chart was downloaded.</p>\n");
+        } else {
+            html.push_str("<table>\n<thead><tr><th>OACI</th><th>City</th><th>Type</th><th>Previous version</th><th>Version</th><th>Size</th></tr></thead>\n<tbody>\n");
+            for row in updates {
+                html.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(html, "<td>{}</td>", escape(&cell));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html.push_str("<h2>Failures</h2>\n");
+        if self.stats.failures.is_empty() {
+            html.push_str("<p>No download failed.</p>\n");
+        } else {
+            html.push_str("<table>\n<thead><tr><th>OACI</th><th>Type</th><th>Kind</th><th>Error</th></tr></thead>\n<tbody>\n");
+            for failure in &self.stats.failures {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&failure.oaci),
+                    escape(&failure.vac_type),
+                    failure.kind,
+                    escape(&failure.error)
+                );
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut markdown = String::new();
+        markdown.push_str("# VAC sync report\n\n");
+        let _ = writeln!(markdown, "Generated on {}\n", self.generated_at);
+
+        markdown.push_str("## Totals\n\n| | |\n|---|---:|\n");
+        for (label, value) in self.totals() {
+            let _ = writeln!(markdown, "| {} | {} |", label, value);
+        }
+
+        markdown.push_str("\n## New versions\n\n");
+        let updates = self.update_rows();
+        if updates.is_empty() {
+            markdown.push_str("No chart was downloaded.\n");
+        } else {
+            markdown.push_str("| OACI | City | Type | Previous version | Version | Size |\n");
+            markdown.push_str("|---|---|---|---|---|---:|\n");
+            for row in updates {
+                let cells: Vec<String> = row.iter().map(|cell| table_cell(cell)).collect();
+                let _ = writeln!(markdown, "| {} |", cells.join(" | "));
+            }
+        }
+
+        markdown.push_str("\n## Failures\n\n");
+        if self.stats.failures.is_empty() {
+            markdown.push_str("No download failed.\n");
+        } else {
+            markdown.push_str("| OACI | Type | Kind | Error |\n|---|---|---|---|\n");
+            for failure in &self.stats.failures {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} | {} |",
+                    table_cell(&failure.oaci),
+                    table_cell(&failure.vac_type),
+                    failure.kind,
+                    table_cell(&failure.error)
+                );
+            }
+        }
+        markdown
+    }
+}
+
+/// Escape text for a Markdown table cell
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloader::{ChartFailure, ChartUpdate, FailureKind};
+    use std::time::Duration;
+
+    fn stats() -> SyncStats {
+        SyncStats {
+            total_entries: 3,
+            downloaded: 2,
+            failed: 1,
+            bytes_downloaded: 2048,
+            updates: vec![
+                ChartUpdate {
+                    oaci: "LFPG".to_string(),
+                    city: "Paris <CDG>".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "2".to_string(),
+                    previous_version: Some("1".to_string()),
+                    file_size: 1024,
+                },
+                ChartUpdate {
+                    oaci: "LFPO".to_string(),
+                    city: "Paris".to_string(),
+                    vac_type: "AD".to_string(),
+                    version: "1".to_string(),
+                    previous_version: None,
+                    file_size: 1024,
+                },
+            ],
+            failures: vec![ChartFailure {
+                oaci: "LFPN".to_string(),
+                vac_type: "AD".to_string(),
+                kind: FailureKind::NotFound,
+                error: "PDF download failed | 404".to_string(),
+            }],
+            duration: Duration::from_millis(1500),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.MD")),
+            ReportFormat::Markdown
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.html")),
+            ReportFormat::Html
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report")),
+            ReportFormat::Html
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let stats = stats();
+        let markdown = SyncReport::new(&stats).render(ReportFormat::Markdown);
+        assert!(markdown.contains("| Downloaded | 2 |"));
+        assert!(markdown.contains("| Duration | 1.5 s |"));
+        assert!(markdown.contains("| LFPG | Paris <CDG> | AD | 1 | 2 | 1.0 KB |"));
+        assert!(markdown.contains("| LFPO | Paris | AD | new | 1 |"));
+        assert!(markdown.contains("| LFPN | AD | not found | PDF download failed \\| 404 |"));
+    }
+
+    #[test]
+    fn test_render_html() {
+        let stats = stats();
+        let html = SyncReport::new(&stats).render(ReportFormat::Html);
+        assert!(html.contains("<td>Paris &lt;CDG&gt;</td>"));
+        assert!(html.contains("<td>PDF download failed | 404</td>"));
+
+        let html = SyncReport::new(&SyncStats::default()).render(ReportFormat::Html);
+        assert!(html.contains("No chart was downloaded."));
+        assert!(html.contains("No download failed."));
+    }
+}
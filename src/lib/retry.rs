@@ -0,0 +1,186 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::transport::{HttpResponse, TransportError};
+use reqwest::StatusCode;
+use std::time::Duration;
+use tracing::warn;
+
+/// How HTTP requests that failed with a transient error are retried
+///
+/// Connection errors, timeouts, `429 Too Many Requests` and server errors are
+/// retried with an exponential backoff. Other errors are returned right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each following retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// No retries, matching the behavior of previous releases
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry failed requests
+    pub fn none() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Retry up to `max_retries` times, starting with `initial_backoff` between attempts
+    pub fn exponential(max_retries: u32, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            initial_backoff,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Delay before the given retry, starting at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Send a request, retrying it according to the policy
+    ///
+    /// `send` is called once per attempt. The last response or error is
+    /// returned once the retries are exhausted.
+    pub(crate) fn send<F>(&self, mut send: F) -> Result<HttpResponse, TransportError>
+    where
+        F: FnMut() -> Result<HttpResponse, TransportError>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = send();
+            if retry >= self.max_retries || !is_transient(&result) {
+                return result;
+            }
+
+            retry += 1;
+            let delay = self.backoff(retry);
+            match &result {
+                Ok(response) => warn!(
+                    "⚠️  {} returned {}, retrying in {:?} ({}/{})",
+                    response.url(),
+                    response.status(),
+                    delay,
+                    retry,
+                    self.max_retries
+                ),
+                Err(e) => warn!(
+                    "⚠️  Request failed: {}, retrying in {:?} ({}/{})",
+                    e, delay, retry, self.max_retries
+                ),
+            }
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Whether a request outcome is worth retrying
+fn is_transient(result: &Result<HttpResponse, TransportError>) -> bool {
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        }
+        Err(e) => e.is_connect() || e.is_timeout(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpTransport, ReqwestTransport};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(3),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(3));
+        assert_eq!(policy.backoff(40), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_send_retries_server_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+        });
+
+        let transport = ReqwestTransport::new(reqwest::blocking::Client::new());
+        let policy = RetryPolicy::exponential(2, Duration::from_millis(10));
+        let mut attempts = 0;
+        let response = policy
+            .send(|| {
+                attempts += 1;
+                transport.get(HttpRequest::get(&url))
+            })
+            .unwrap();
+        server.join().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(attempts, 2);
+
+        // Connection errors are retried until the policy gives up
+        let mut attempts = 0;
+        let result = policy.send(|| {
+            attempts += 1;
+            transport.get(HttpRequest::get("http://127.0.0.1:0/"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+}
@@ -0,0 +1,329 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::{Coordinates, OacisEntry};
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+
+/// Maximum distance between a waypoint and an airfield for the waypoint to
+/// be resolved to the airfield by its position, in meters
+const MAX_AIRFIELD_DISTANCE: f64 = 3_000.0;
+
+/// Waypoint of a flight plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutePoint {
+    /// Identifier or name of the waypoint, e.g. "LFPN"
+    pub identifier: Option<String>,
+    /// Position in decimal degrees, if the flight plan gives one
+    pub position: Option<(f64, f64)>,
+}
+
+/// Read the waypoints of a flight plan, in order
+///
+/// Supported formats are GPX routes or waypoints, Garmin `.fpl` flight plans
+/// and SkyDemon `.flightplan` files, recognized by their root element.
+pub fn parse_route(contents: &str) -> Result<Vec<RoutePoint>> {
+    let document = Document::parse(contents).context("Failed to parse flight plan")?;
+    let root = document.root_element();
+    let points = match root.tag_name().name() {
+        "gpx" => parse_gpx(root),
+        "flight-plan" => parse_fpl(root),
+        "DivelementsFlightPlanner" => parse_skydemon(root)?,
+        other => anyhow::bail!("Unsupported flight plan format: <{}>", other),
+    };
+    if points.is_empty() {
+        anyhow::bail!("The flight plan has no waypoints");
+    }
+    Ok(points)
+}
+
+/// Text of the first child element with a name
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+/// Route points of a GPX file, or its waypoints when it has no route
+fn parse_gpx(root: Node) -> Vec<RoutePoint> {
+    let point = |node: Node| RoutePoint {
+        identifier: child_text(node, "name").map(str::to_string),
+        position: node
+            .attribute("lat")
+            .zip(node.attribute("lon"))
+            .and_then(|(lat, lon)| Some((lat.parse().ok()?, lon.parse().ok()?))),
+    };
+    let route: Vec<RoutePoint> = root
+        .descendants()
+        .filter(|node| node.has_tag_name("rtept"))
+        .map(point)
+        .collect();
+    if !route.is_empty() {
+        return route;
+    }
+    root.children()
+        .filter(|node| node.has_tag_name("wpt"))
+        .map(point)
+        .collect()
+}
+
+/// Route points of a Garmin flight plan, positioned with its waypoint table
+fn parse_fpl(root: Node) -> Vec<RoutePoint> {
+    let waypoints: Vec<Node> = root
+        .descendants()
+        .filter(|node| node.has_tag_name("waypoint"))
+        .collect();
+    root.descendants()
+        .filter(|node| node.has_tag_name("route-point"))
+        .map(|node| {
+            let identifier = child_text(node, "waypoint-identifier");
+            let position = waypoints
+                .iter()
+                .find(|waypoint| child_text(**waypoint, "identifier") == identifier)
+                .and_then(|waypoint| {
+                    Some((
+                        child_text(*waypoint, "lat")?.parse().ok()?,
+                        child_text(*waypoint, "lon")?.parse().ok()?,
+                    ))
+                });
+            RoutePoint {
+                identifier: identifier.map(str::to_string),
+                position,
+            }
+        })
+        .collect()
+}
+
+/// Start and legs of the primary route of a SkyDemon flight plan
+///
+/// SkyDemon only records positions, e.g. `N484507.00 E0020622.00`.
+fn parse_skydemon(root: Node) -> Result<Vec<RoutePoint>> {
+    let Some(route) = root
+        .children()
+        .find(|node| node.has_tag_name("PrimaryRoute"))
+    else {
+        return Ok(Vec::new());
+    };
+    let start = route.attribute("Start");
+    let legs = route
+        .children()
+        .filter(|node| node.is_element())
+        .filter_map(|node| node.attribute("To"));
+    start
+        .into_iter()
+        .chain(legs)
+        .map(|position| {
+            let position = parse_skydemon_position(position)
+                .with_context(|| format!("Invalid position in flight plan: {}", position))?;
+            Ok(RoutePoint {
+                identifier: None,
+                position: Some(position),
+            })
+        })
+        .collect()
+}
+
+/// Parse a SkyDemon position: hemisphere, then degrees, minutes and seconds
+fn parse_skydemon_position(position: &str) -> Option<(f64, f64)> {
+    let (latitude, longitude) = position.trim().split_once(' ')?;
+    Some((
+        parse_dms(latitude, 2, ['N', 'S'])?,
+        parse_dms(longitude.trim(), 3, ['E', 'W'])?,
+    ))
+}
+
+/// Parse e.g. `N484507.00` with 2 digits of degrees into decimal degrees
+fn parse_dms(value: &str, degree_digits: usize, hemispheres: [char; 2]) -> Option<f64> {
+    let mut chars = value.chars();
+    let hemisphere = chars.next()?;
+    let digits = chars.as_str();
+    if digits.len() < degree_digits + 4 || !digits.is_char_boundary(degree_digits + 4) {
+        return None;
+    }
+    let degrees: f64 = digits[..degree_digits].parse().ok()?;
+    let minutes: f64 = digits[degree_digits..degree_digits + 2].parse().ok()?;
+    let seconds: f64 = digits[degree_digits + 2..].parse().ok()?;
+    let decimal = degrees + minutes / 60.0 + seconds / 3600.0;
+    match hemisphere {
+        c if c == hemispheres[0] => Some(decimal),
+        c if c == hemispheres[1] => Some(-decimal),
+        _ => None,
+    }
+}
+
+/// Resolve waypoints to the OACI codes of airfields, in route order
+///
+/// Waypoints are matched by identifier first, then to the closest airfield
+/// within 3 km of their position. Waypoints matching no airfield, such as
+/// turning points, are skipped, and airfields visited twice appear once.
+pub(crate) fn resolve_airfields(points: &[RoutePoint], airfields: &[OacisEntry]) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    for point in points {
+        let by_identifier = point.identifier.as_deref().and_then(|identifier| {
+            let identifier = identifier.split_whitespace().next()?;
+            airfields
+                .iter()
+                .find(|airfield| airfield.code.eq_ignore_ascii_case(identifier))
+        });
+        let by_position = || {
+            let (latitude, longitude) = point.position?;
+            let position = Coordinates {
+                latitude,
+                longitude,
+            };
+            airfields
+                .iter()
+                .filter_map(|airfield| {
+                    let coordinates = airfield.coordinates()?;
+                    let d = position.distance(coordinates);
+                    (d <= MAX_AIRFIELD_DISTANCE).then_some((airfield, d))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(airfield, _)| airfield)
+        };
+        if let Some(airfield) = by_identifier.or_else(by_position) {
+            if !codes.contains(&airfield.code) {
+                codes.push(airfield.code.clone());
+            }
+        }
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::tests::sample_airfield;
+
+    #[test]
+    fn test_parse_gpx() {
+        let gpx = r#"<?xml version="1.0"?>
+            <gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+              <wpt lat="1" lon="1"><name>IGNORED</name></wpt>
+              <rte>
+                <rtept lat="48.7519" lon="2.1061"><name>LFPN</name></rtept>
+                <rtept lat="48.9" lon="1.5"><name>TURN</name></rtept>
+              </rte>
+            </gpx>"#;
+        assert_eq!(
+            parse_route(gpx).unwrap(),
+            vec![
+                RoutePoint {
+                    identifier: Some("LFPN".to_string()),
+                    position: Some((48.7519, 2.1061)),
+                },
+                RoutePoint {
+                    identifier: Some("TURN".to_string()),
+                    position: Some((48.9, 1.5)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_fpl() {
+        let fpl = r#"<?xml version="1.0" encoding="utf-8"?>
+            <flight-plan xmlns="http://www8.garmin.com/xmlschemas/FlightPlan/v1">
+              <waypoint-table>
+                <waypoint><identifier>LFPN</identifier><type>AIRPORT</type>
+                  <lat>48.751922</lat><lon>2.106194</lon></waypoint>
+                <waypoint><identifier>RBT</identifier><type>VOR</type>
+                  <lat>48.65</lat><lon>1.99</lon></waypoint>
+              </waypoint-table>
+              <route>
+                <route-name>LFPN LFOR</route-name>
+                <route-point><waypoint-identifier>LFPN</waypoint-identifier></route-point>
+                <route-point><waypoint-identifier>RBT</waypoint-identifier></route-point>
+                <route-point><waypoint-identifier>LFOR</waypoint-identifier></route-point>
+              </route>
+            </flight-plan>"#;
+        let points = parse_route(fpl).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].identifier.as_deref(), Some("LFPN"));
+        assert_eq!(points[0].position, Some((48.751922, 2.106194)));
+        assert_eq!(points[2].identifier.as_deref(), Some("LFOR"));
+        assert_eq!(points[2].position, None);
+    }
+
+    #[test]
+    fn test_parse_skydemon() {
+        let plan = r#"<?xml version="1.0" encoding="utf-8"?>
+            <DivelementsFlightPlanner>
+              <PrimaryRoute Start="N484507.00 E0020622.00" Level="2000">
+                <RhumbLineRoute To="N482739.00 E0012450.00" Level="2500" />
+              </PrimaryRoute>
+            </DivelementsFlightPlanner>"#;
+        let points = parse_route(plan).unwrap();
+        assert_eq!(points.len(), 2);
+        let (latitude, longitude) = points[0].position.unwrap();
+        assert!((latitude - 48.751944).abs() < 1e-5);
+        assert!((longitude - 2.106111).abs() < 1e-5);
+        let (latitude, longitude) = points[1].position.unwrap();
+        assert!((latitude - 48.460833).abs() < 1e-5);
+        assert!((longitude - 1.413889).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_parse_route_errors() {
+        assert!(parse_route("not xml").is_err());
+        assert!(parse_route("<kml/>").is_err());
+        assert!(parse_route("<gpx/>").is_err());
+    }
+
+    #[test]
+    fn test_resolve_airfields() {
+        let airfields = vec![
+            sample_airfield("LFPN", "TOUSSUS LE NOBLE", Some((48.7519, 2.1061))),
+            sample_airfield("LFOR", "CHARTRES", Some((48.4608, 1.5239))),
+            sample_airfield("LFPZ", "SAINT CYR L'ECOLE", None),
+        ];
+        let points = vec![
+            RoutePoint {
+                identifier: Some("lfpn Toussus".to_string()),
+                position: None,
+            },
+            // Turning point far from any airfield
+            RoutePoint {
+                identifier: Some("RBT".to_string()),
+                position: Some((48.65, 1.99)),
+            },
+            // 1 km from Chartres
+            RoutePoint {
+                identifier: None,
+                position: Some((48.4698, 1.5239)),
+            },
+            RoutePoint {
+                identifier: Some("LFPZ".to_string()),
+                position: None,
+            },
+            RoutePoint {
+                identifier: Some("LFPN".to_string()),
+                position: None,
+            },
+        ];
+        assert_eq!(
+            resolve_airfields(&points, &airfields),
+            vec!["LFPN", "LFOR", "LFPZ"]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::regions::UNKNOWN_REGION;
+use crate::VacEntry;
+use anyhow::Result;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Template used to lay out downloaded PDFs, matching the server file names
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "{file_name}";
+
+/// Template including the chart version, so older versions are never overwritten
+pub const VERSIONED_FILE_NAME_TEMPLATE: &str = "{oaci}_{type}_v{version}.pdf";
+
+/// Placeholders available in file name templates
+const PLACEHOLDERS: &[&str] = &["oaci", "type", "version", "city", "region", "file_name"];
+
+/// Device names reserved by Windows, regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Longest file name (in bytes) accepted by common filesystems
+const MAX_SEGMENT_LEN: usize = 255;
+
+/// Make a single path segment valid on NTFS, FAT32 and exFAT
+///
+/// Accented characters are decomposed and reduced to their ASCII base,
+/// remaining non-ASCII and forbidden characters are replaced with `_`,
+/// trailing dots and spaces are removed and reserved device names are
+/// prefixed with `_`.
+fn sanitize_segment(segment: &str) -> String {
+    let mut sanitized: String = segment
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_ascii_control() || !c.is_ascii() => '_',
+            c => c,
+        })
+        .collect();
+
+    sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        sanitized.insert(0, '_');
+    }
+
+    if sanitized.len() > MAX_SEGMENT_LEN {
+        // Shorten the stem, keeping the extension
+        let extension = sanitized
+            .rfind('.')
+            .map(|i| sanitized[i..].to_string())
+            .filter(|ext| ext.len() < 16)
+            .unwrap_or_default();
+        sanitized.truncate(MAX_SEGMENT_LEN - extension.len());
+        sanitized.push_str(&extension);
+    }
+
+    sanitized
+}
+
+/// Make a relative path (using `/` as separator) portable across filesystems
+pub fn sanitize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(sanitize_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Derive an alternative file name to resolve a collision, e.g. `LFPG_AD_2.pdf`
+pub fn with_collision_suffix(path: &str, index: u32) -> String {
+    let name_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match path[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = name_start + dot;
+            format!("{}_{}{}", &path[..dot], index, &path[dot..])
+        }
+        _ => format!("{}_{}", path, index),
+    }
+}
+
+/// Path template applied to downloaded PDFs
+///
+/// Templates are relative paths where `/` separates directories, e.g.
+/// `{oaci}/{oaci}_{type}_{version}.pdf`. Available placeholders are
+/// `{oaci}`, `{type}`, `{version}`, `{city}`, `{region}` (see
+/// [`crate::regions`]) and `{file_name}` (the name provided by the server).
+/// Rendered paths are sanitized with [`sanitize_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileNameTemplate {
+    template: String,
+}
+
+impl Default for FileNameTemplate {
+    fn default() -> Self {
+        FileNameTemplate {
+            template: DEFAULT_FILE_NAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl FileNameTemplate {
+    /// Parse a template, rejecting unknown placeholders and unsafe paths
+    pub fn new(template: &str) -> Result<Self> {
+        if template.trim().is_empty() {
+            anyhow::bail!("File name template cannot be empty");
+        }
+        if template.starts_with('/') || template.split('/').any(|part| part == "..") {
+            anyhow::bail!("File name template must be a relative path: {}", template);
+        }
+
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| {
+                anyhow::anyhow!("Unclosed placeholder in file name template: {}", template)
+            })?;
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                anyhow::bail!(
+                    "Unknown placeholder {{{}}} in file name template (available: {})",
+                    name,
+                    PLACEHOLDERS
+                        .iter()
+                        .map(|p| format!("{{{}}}", p))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            rest = &rest[start + end + 1..];
+        }
+
+        Ok(FileNameTemplate {
+            template: template.to_string(),
+        })
+    }
+
+    /// Template naming files after the chart version, e.g. `LFPG_AD_v2024-11.pdf`
+    pub fn versioned() -> Self {
+        FileNameTemplate {
+            template: VERSIONED_FILE_NAME_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Template string as configured
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Check whether paths depend on the region of the airfield
+    pub fn uses_region(&self) -> bool {
+        self.template.contains("{region}")
+    }
+
+    /// Resolve the relative path of an entry's PDF, for an airfield of unknown region
+    pub fn render(&self, entry: &VacEntry) -> String {
+        self.render_in_region(entry, None)
+    }
+
+    /// Resolve the relative path of an entry's PDF, for an airfield in `region`
+    pub fn render_in_region(&self, entry: &VacEntry, region: Option<&str>) -> String {
+        let mut rendered = self.template.clone();
+        for placeholder in PLACEHOLDERS {
+            let value = match *placeholder {
+                "oaci" => entry.oaci.as_str(),
+                "type" => &entry.vac_type,
+                "version" => &entry.version,
+                "city" => &entry.city,
+                "region" => region.unwrap_or(UNKNOWN_REGION),
+                _ => &entry.file_name,
+            };
+            // Values must not introduce directories of their own
+            let value = value.replace(['/', '\\'], "-");
+            rendered = rendered.replace(&format!("{{{}}}", placeholder), &value);
+        }
+        sanitize_path(&rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> VacEntry {
+        VacEntry {
+            oaci: "LFPG".to_string(),
+            city: "Paris".to_string(),
+            vac_type: "AD".to_string(),
+            version: "2024/11".to_string(),
+            file_name: "AD-2.LFPG.pdf".to_string(),
+            file_size: 1024,
+            file_hash: None,
+            remote_file_name: None,
+            page_count: None,
+            available_locally: false,
+        }
+    }
+
+    #[test]
+    fn test_default_template_keeps_server_name() {
+        assert_eq!(
+            FileNameTemplate::default().render(&entry()),
+            "AD-2.LFPG.pdf"
+        );
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = FileNameTemplate::new("{oaci}/{oaci}_{type}_{version}.pdf").unwrap();
+        assert_eq!(template.render(&entry()), "LFPG/LFPG_AD_2024-11.pdf");
+    }
+
+    #[test]
+    fn test_render_region() {
+        let template =
+            FileNameTemplate::new("{region}/{oaci}/{oaci}_{type}_{version}.pdf").unwrap();
+        assert!(template.uses_region());
+        assert_eq!(
+            template.render_in_region(&entry(), Some("Île-de-France")),
+            "Ile-de-France/LFPG/LFPG_AD_2024-11.pdf"
+        );
+        assert_eq!(
+            template.render(&entry()),
+            "Unknown/LFPG/LFPG_AD_2024-11.pdf"
+        );
+        assert!(!FileNameTemplate::default().uses_region());
+    }
+
+    #[test]
+    fn test_versioned_template() {
+        let mut entry = entry();
+        entry.version = "2024-11".to_string();
+        assert_eq!(
+            FileNameTemplate::versioned().render(&entry),
+            "LFPG_AD_v2024-11.pdf"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_path() {
+        assert_eq!(
+            sanitize_path("Orléans Saint-Denis.pdf"),
+            "Orleans Saint-Denis.pdf"
+        );
+        assert_eq!(sanitize_path("LFPG: AD?.pdf"), "LFPG_ AD_.pdf");
+        assert_eq!(sanitize_path("Île-de-France/LFPG. "), "Ile-de-France/LFPG");
+        assert_eq!(sanitize_path("CON.pdf"), "_CON.pdf");
+        assert_eq!(sanitize_path("com1"), "_com1");
+        assert_eq!(sanitize_path("Aéroport/Zürich.pdf"), "Aeroport/Zurich.pdf");
+        assert_eq!(sanitize_path("中文.pdf"), "__.pdf");
+
+        let long_name = format!("{}.pdf", "a".repeat(300));
+        let sanitized = sanitize_path(&long_name);
+        assert_eq!(sanitized.len(), MAX_SEGMENT_LEN);
+        assert!(sanitized.ends_with(".pdf"));
+    }
+
+    #[test]
+    fn test_render_sanitizes_values() {
+        let mut entry = entry();
+        entry.city = "Saint-Étienne".to_string();
+        let template = FileNameTemplate::new("{city}/{oaci}.pdf").unwrap();
+        assert_eq!(template.render(&entry), "Saint-Etienne/LFPG.pdf");
+    }
+
+    #[test]
+    fn test_collision_suffix() {
+        assert_eq!(with_collision_suffix("LFPG_AD.pdf", 2), "LFPG_AD_2.pdf");
+        assert_eq!(with_collision_suffix("a.b/LFPG", 3), "a.b/LFPG_3");
+        assert_eq!(with_collision_suffix("dir/.hidden", 2), "dir/.hidden_2");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(FileNameTemplate::new("").is_err());
+        assert!(FileNameTemplate::new("{unknown}.pdf").is_err());
+        assert!(FileNameTemplate::new("{oaci.pdf").is_err());
+        assert!(FileNameTemplate::new("/abs/{oaci}.pdf").is_err());
+        assert!(FileNameTemplate::new("../{oaci}.pdf").is_err());
+    }
+}
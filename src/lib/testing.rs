@@ -0,0 +1,227 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! In-process SIA API answering from canned Hydra pages and PDFs, so the
+//! sync logic can be tested without a network
+
+use crate::pdf::tests::sample_pdf;
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport, TransportError};
+use crate::{VacDownloader, VacDownloaderBuilder};
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const BASE_URL: &str = "http://sia.test";
+
+/// AD chart served by the mock API
+struct MockChart {
+    oaci: String,
+    version: String,
+    pdf: Vec<u8>,
+}
+
+#[derive(Default)]
+struct MockState {
+    charts: Vec<MockChart>,
+    /// Airfields per listing page
+    page_size: Option<usize>,
+    /// Status answered to the requests whose path contains a pattern
+    failures: Vec<(String, StatusCode)>,
+    /// Path and query of every request, in order
+    requests: Vec<String>,
+    /// Static files served at their path, e.g. a mirror
+    files: Vec<(String, Vec<u8>)>,
+}
+
+/// Mock of the SIA API, used as the transport of a downloader
+///
+/// The listing honors the `page`, `itemsPerPage` and `code[]` parameters,
+/// PDFs carry an ETag and are answered with `304 Not Modified` when the
+/// request has a matching `If-None-Match`.
+#[derive(Default)]
+pub(crate) struct MockApi {
+    state: Mutex<MockState>,
+}
+
+impl MockApi {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(MockApi::default())
+    }
+
+    /// Publish the AD chart of an airfield, or a new version of it
+    pub(crate) fn publish(&self, oaci: &str, version: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.charts.retain(|chart| chart.oaci != oaci);
+        // A distinct page count per version gives distinct files
+        let pages = version.bytes().map(usize::from).sum::<usize>() % 7 + 1;
+        state.charts.push(MockChart {
+            oaci: oaci.to_string(),
+            version: version.to_string(),
+            pdf: sample_pdf(pages),
+        });
+        state.charts.sort_by(|a, b| a.oaci.cmp(&b.oaci));
+    }
+
+    /// Serve the listing with this many airfields per page
+    pub(crate) fn set_page_size(&self, page_size: usize) {
+        self.state.lock().unwrap().page_size = Some(page_size);
+    }
+
+    /// Serve `body` at `path`, e.g. a file of a mirror
+    pub(crate) fn serve(&self, path: &str, body: Vec<u8>) {
+        let mut state = self.state.lock().unwrap();
+        state.files.retain(|(file, _)| file != path);
+        state.files.push((path.to_string(), body));
+    }
+
+    /// Answer `status` to every request whose path and query contain `pattern`
+    pub(crate) fn fail(&self, pattern: &str, status: StatusCode) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .push((pattern.to_string(), status));
+    }
+
+    /// Path and query of the requests received so far
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Requests received for PDFs so far
+    pub(crate) fn file_requests(&self) -> Vec<String> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.starts_with("/api/v1/custom/file-path/"))
+            .collect()
+    }
+
+    /// URL of `path` on this API
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{}", BASE_URL, path)
+    }
+
+    /// Builder of a downloader storing its cache and PDFs in `dir` and querying this API
+    pub(crate) fn builder(self: &Arc<Self>, dir: &Path) -> VacDownloaderBuilder {
+        VacDownloader::builder(dir.join("vac_cache.db"), dir.join("pdfs"))
+            .base_url(BASE_URL)
+            .transport(self.clone())
+    }
+
+    /// Downloader storing its cache and PDFs in `dir` and querying this API
+    pub(crate) fn downloader(self: &Arc<Self>, dir: &Path) -> VacDownloader {
+        self.builder(dir).build().unwrap()
+    }
+
+    fn listing(state: &MockState, query: &str) -> serde_json::Value {
+        let mut page: usize = 1;
+        let mut items_per_page = state.page_size.unwrap_or(usize::MAX);
+        let mut codes = Vec::new();
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "page" => page = value.parse().unwrap(),
+                "itemsPerPage" => items_per_page = value.parse().unwrap(),
+                "code[]" => codes.push(value.to_string()),
+                _ => {}
+            }
+        }
+        let charts: Vec<&MockChart> = state
+            .charts
+            .iter()
+            .filter(|chart| codes.is_empty() || codes.contains(&chart.oaci))
+            .collect();
+        let members: Vec<serde_json::Value> = charts
+            .iter()
+            .skip((page - 1).saturating_mul(items_per_page))
+            .take(items_per_page)
+            .map(|chart| {
+                json!({
+                    "code": chart.oaci,
+                    "city": format!("CITY {}", chart.oaci),
+                    "grounds": [],
+                    "maps": [{
+                        "fileName": format!("AD-2.{}.pdf", chart.oaci),
+                        "type": "AD",
+                        "version": chart.version,
+                        "fileSize": chart.pdf.len(),
+                    }],
+                    "runways": [],
+                    "frequencies": [],
+                    "information": [],
+                })
+            })
+            .collect();
+        json!({"hydra:member": members, "hydra:totalItems": charts.len()})
+    }
+}
+
+impl HttpTransport for MockApi {
+    fn get(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let mut state = self.state.lock().unwrap();
+        let target = request.url.trim_start_matches(BASE_URL).to_string();
+        state.requests.push(target.clone());
+        let respond = |status: StatusCode, headers: HeaderMap, body: Vec<u8>| {
+            Ok(HttpResponse::from_bytes(
+                status,
+                &request.url,
+                headers,
+                body,
+            ))
+        };
+
+        if let Some((_, status)) = state
+            .failures
+            .iter()
+            .find(|(pattern, _)| target.contains(pattern.as_str()))
+        {
+            return respond(*status, HeaderMap::new(), Vec::new());
+        }
+
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        if let Some((_, body)) = state.files.iter().find(|(file, _)| file == path) {
+            return respond(StatusCode::OK, HeaderMap::new(), body.clone());
+        }
+        if path == "/api/v1/oacis" {
+            let body = serde_json::to_vec(&Self::listing(&state, query)).unwrap();
+            return respond(StatusCode::OK, HeaderMap::new(), body);
+        }
+        if let Some(file) = path.strip_prefix("/api/v1/custom/file-path/") {
+            let oaci = file.split('/').next().unwrap_or_default();
+            if let Some(chart) = state.charts.iter().find(|chart| chart.oaci == oaci) {
+                let etag = format!("\"{}-{}\"", chart.oaci, chart.version);
+                let mut headers = HeaderMap::new();
+                headers.insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+                if request
+                    .headers
+                    .get(IF_NONE_MATCH)
+                    .and_then(|v| v.to_str().ok())
+                    == Some(etag.as_str())
+                {
+                    return respond(StatusCode::NOT_MODIFIED, headers, Vec::new());
+                }
+                return respond(StatusCode::OK, headers, chart.pdf.clone());
+            }
+        }
+        respond(StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new())
+    }
+}
@@ -0,0 +1,340 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::cancel::CancellationToken;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName};
+use reqwest::StatusCode;
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use std::time::Instant;
+
+/// GET request sent through an [`HttpTransport`]
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+impl HttpRequest {
+    /// Request for the given URL, without headers
+    pub fn get(url: &str) -> Self {
+        HttpRequest {
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Add a header, skipping values that are not valid in a header
+    pub fn header<K: IntoHeaderName>(mut self, name: K, value: &str) -> Self {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+/// Response to an [`HttpRequest`], whose body is read on demand
+pub struct HttpResponse {
+    status: StatusCode,
+    url: String,
+    headers: HeaderMap,
+    body: Box<dyn Read + Send>,
+}
+
+impl HttpResponse {
+    pub fn new(
+        status: StatusCode,
+        url: &str,
+        headers: HeaderMap,
+        body: Box<dyn Read + Send>,
+    ) -> Self {
+        HttpResponse {
+            status,
+            url: url.to_string(),
+            headers,
+            body,
+        }
+    }
+
+    /// Response with a body held in memory, e.g. a canned one in tests
+    pub fn from_bytes(status: StatusCode, url: &str, headers: HeaderMap, body: Vec<u8>) -> Self {
+        Self::new(status, url, headers, Box::new(std::io::Cursor::new(body)))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// URL of the response, after redirections
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Read the whole body
+    pub fn bytes(mut self) -> std::io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.body.read_to_end(&mut body)?;
+        Ok(body)
+    }
+
+    /// Read the whole body, failing with a `TimedOut` error past the deadline
+    /// and with an error once `cancel` is cancelled
+    ///
+    /// The deadline and the token are checked between reads, each of them
+    /// being bounded by the timeout of the client.
+    pub fn bytes_until(
+        self,
+        deadline: Option<Instant>,
+        cancel: Option<&CancellationToken>,
+    ) -> std::io::Result<Vec<u8>> {
+        if deadline.is_none() && cancel.is_none() {
+            return self.bytes();
+        }
+        let mut body = Vec::new();
+        self.read_chunks(deadline, cancel, |chunk| {
+            body.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(body)
+    }
+
+    /// Read the body in chunks of at most 16 KiB passed to `on_chunk` as they
+    /// arrive, e.g. to write them to a file and report the progress, with the
+    /// deadline and cancellation of [`HttpResponse::bytes_until`]
+    pub fn read_chunks(
+        mut self,
+        deadline: Option<Instant>,
+        cancel: Option<&CancellationToken>,
+        mut on_chunk: impl FnMut(&[u8]) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        let mut buf = [0; 16 * 1024];
+        loop {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Download did not complete in time",
+                ));
+            }
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                return Err(CancellationToken::error());
+            }
+            match self.body.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(read) => on_chunk(&buf[..read])?,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Read the whole body as text, replacing invalid UTF-8
+    pub fn text(self) -> std::io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.bytes()?).into_owned())
+    }
+}
+
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Why a request got no response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportErrorKind {
+    /// The connection could not be established, e.g. a DNS or network error
+    Connect,
+    Timeout,
+    Other,
+}
+
+/// Error of a request that got no response
+#[derive(Debug)]
+pub struct TransportError {
+    kind: TransportErrorKind,
+    error: Box<dyn Error + Send + Sync>,
+}
+
+impl TransportError {
+    pub fn new<E: Into<Box<dyn Error + Send + Sync>>>(kind: TransportErrorKind, error: E) -> Self {
+        TransportError {
+            kind,
+            error: error.into(),
+        }
+    }
+
+    pub fn kind(&self) -> TransportErrorKind {
+        self.kind
+    }
+
+    pub fn is_connect(&self) -> bool {
+        self.kind == TransportErrorKind::Connect
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind == TransportErrorKind::Timeout
+    }
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for TransportError {
+    // The wrapped error is displayed as this one, so the chain goes on with its causes
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Sends the HTTP requests of the API client
+///
+/// The default transport is a reqwest client; tests and embedders can
+/// provide their own, e.g. answering from canned responses.
+pub trait HttpTransport: Send + Sync {
+    fn get(&self, request: HttpRequest) -> Result<HttpResponse, TransportError>;
+}
+
+/// Transport sending requests with a reqwest blocking client
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        ReqwestTransport { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn get(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let response = self
+            .client
+            .get(&request.url)
+            .headers(request.headers)
+            .send()
+            .map_err(|e| {
+                let kind = if e.is_connect() {
+                    TransportErrorKind::Connect
+                } else if e.is_timeout() {
+                    TransportErrorKind::Timeout
+                } else {
+                    TransportErrorKind::Other
+                };
+                TransportError::new(kind, e)
+            })?;
+        let status = response.status();
+        let url = response.url().to_string();
+        let headers = response.headers().clone();
+        Ok(HttpResponse::new(status, &url, headers, Box::new(response)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Body sending one byte at a time, slowly
+    struct SlowBody(usize);
+
+    impl Read for SlowBody {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0 == 0 || buf.is_empty() {
+                return Ok(0);
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            self.0 -= 1;
+            buf[0] = b'x';
+            Ok(1)
+        }
+    }
+
+    fn slow_response(size: usize) -> HttpResponse {
+        HttpResponse::new(
+            StatusCode::OK,
+            "http://test/",
+            HeaderMap::new(),
+            Box::new(SlowBody(size)),
+        )
+    }
+
+    #[test]
+    fn test_bytes_until() {
+        assert_eq!(slow_response(3).bytes_until(None, None).unwrap(), b"xxx");
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            slow_response(3).bytes_until(Some(deadline), None).unwrap(),
+            b"xxx"
+        );
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let error = slow_response(100)
+            .bytes_until(Some(deadline), None)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+
+        let cancel = CancellationToken::new();
+        assert_eq!(
+            slow_response(3).bytes_until(None, Some(&cancel)).unwrap(),
+            b"xxx"
+        );
+        cancel.cancel();
+        let error = slow_response(3)
+            .bytes_until(None, Some(&cancel))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Operation cancelled");
+    }
+
+    #[test]
+    fn test_read_chunks() {
+        let mut chunks = Vec::new();
+        slow_response(3)
+            .read_chunks(None, None, |chunk| {
+                chunks.push(chunk.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(chunks, [b"x", b"x", b"x"]);
+
+        let error = slow_response(3)
+            .read_chunks(None, None, |_| Err(std::io::Error::other("Disk full")))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Disk full");
+    }
+}
@@ -0,0 +1,503 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::{Context, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Bookmark, Dictionary, Document, Object, ObjectId, Stream};
+
+/// Page attributes a page can inherit from its ancestors in the page tree
+const INHERITABLE_ATTRIBUTES: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// A4 portrait, in points
+const COVER_SIZE: (i64, i64) = (595, 842);
+
+/// Entries listed on each table of contents page
+const ENTRIES_PER_COVER_PAGE: usize = 40;
+
+/// A4 landscape sheet side of a booklet, holding two A5 pages
+const SHEET_SIZE: (f32, f32) = (842.0, 595.0);
+
+/// Page layout of a trip PDF
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TripLayout {
+    /// One page per chart page, with bookmarks
+    #[default]
+    Pages,
+    /// Pages scaled two by two onto A4 landscape sheets, in the order needed
+    /// to print them duplex (flipping on the short edge) and fold the stack
+    /// into an A5 booklet
+    Booklet,
+}
+
+/// Chart to merge into a trip PDF
+pub(crate) struct TripChart {
+    /// Title of its bookmark and table of contents entry
+    pub(crate) title: String,
+    pub(crate) pdf: Vec<u8>,
+}
+
+/// Summary of a trip PDF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripPdf {
+    /// Titles of the merged charts, in order
+    pub charts: Vec<String>,
+    /// Number of pages of the PDF, including the table of contents, or
+    /// number of sheet sides of a booklet
+    pub pages: usize,
+}
+
+/// Merge charts into a single PDF, after a table of contents and with one bookmark per chart
+///
+/// Booklets have no bookmarks, as their pages are not in reading order.
+/// Returns the PDF and its summary.
+pub(crate) fn merge(
+    charts: Vec<TripChart>,
+    heading: &str,
+    layout: TripLayout,
+) -> Result<(Vec<u8>, TripPdf)> {
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+
+    // Import the pages of every chart, renumbered after the objects already imported
+    let mut chart_pages: Vec<(String, Vec<ObjectId>)> = Vec::new();
+    for chart in charts {
+        let mut source = Document::load_mem(&chart.pdf)
+            .with_context(|| format!("Failed to parse the PDF of {}", chart.title))?;
+        source.renumber_objects_with(document.max_id + 1);
+
+        let page_ids: Vec<ObjectId> = source.get_pages().into_values().collect();
+        if page_ids.is_empty() {
+            anyhow::bail!("The PDF of {} has no page", chart.title);
+        }
+        for &page_id in &page_ids {
+            let mut page = source.get_dictionary(page_id)?.clone();
+            inherit_attributes(&source, &mut page);
+            page.set("Parent", pages_id);
+            source.objects.insert(page_id, Object::Dictionary(page));
+        }
+
+        // The page tree and catalog of the chart are replaced by the merged ones
+        for (id, object) in source.objects {
+            if !matches!(object.type_name(), Ok(b"Catalog") | Ok(b"Pages")) {
+                document.objects.insert(id, object);
+            }
+        }
+        document.max_id = document.max_id.max(source.max_id);
+        chart_pages.push((chart.title, page_ids));
+    }
+
+    // Table of contents, with links to the first page of each chart
+    let cover_count = chart_pages.len().div_ceil(ENTRIES_PER_COVER_PAGE).max(1);
+    let font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let mut first_page = cover_count + 1;
+    let mut entries = Vec::new();
+    for (title, page_ids) in &chart_pages {
+        entries.push((title.as_str(), first_page, page_ids[0]));
+        first_page += page_ids.len();
+    }
+    let mut cover_ids = Vec::new();
+    for (index, chunk) in entries.chunks(ENTRIES_PER_COVER_PAGE).enumerate() {
+        let heading = (index == 0).then_some(heading);
+        cover_ids.push(add_cover_page(
+            &mut document,
+            pages_id,
+            font_id,
+            heading,
+            chunk,
+        )?);
+    }
+    if cover_ids.is_empty() {
+        cover_ids.push(add_cover_page(
+            &mut document,
+            pages_id,
+            font_id,
+            Some(heading),
+            &[],
+        )?);
+    }
+
+    let mut page_ids: Vec<ObjectId> = cover_ids
+        .iter()
+        .chain(chart_pages.iter().flat_map(|(_, page_ids)| page_ids))
+        .copied()
+        .collect();
+    let mut catalog = dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    };
+    match layout {
+        TripLayout::Pages => {
+            document.add_bookmark(
+                Bookmark::new("Contents".to_string(), [0.0, 0.0, 0.0], 0, cover_ids[0]),
+                None,
+            );
+            for (title, page_ids) in &chart_pages {
+                document.add_bookmark(
+                    Bookmark::new(title.clone(), [0.0, 0.0, 0.0], 0, page_ids[0]),
+                    None,
+                );
+            }
+            if let Some(outline_id) = document.build_outline() {
+                catalog.set("Outlines", outline_id);
+                catalog.set("PageMode", "UseOutlines");
+            }
+        }
+        TripLayout::Booklet => page_ids = impose_booklet(&mut document, pages_id, &page_ids)?,
+    }
+
+    let page_count = page_ids.len();
+    document.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids.into_iter().map(Object::Reference).collect::<Vec<_>>(),
+            "Count" => page_count as i64,
+        }),
+    );
+    let catalog_id = document.add_object(catalog);
+    document.trailer.set("Root", catalog_id);
+    // Drop the pages replaced by booklet sheets
+    document.prune_objects();
+
+    let mut pdf = Vec::new();
+    document
+        .save_to(&mut pdf)
+        .context("Failed to write the merged PDF")?;
+    let summary = TripPdf {
+        charts: chart_pages.into_iter().map(|(title, _)| title).collect(),
+        pages: page_count,
+    };
+    Ok((pdf, summary))
+}
+
+/// Copy the attributes a page inherits from its ancestors, before moving it to another page tree
+fn inherit_attributes(source: &Document, page: &mut Dictionary) {
+    let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+    while let Some(parent_id) = parent {
+        let Ok(node) = source.get_dictionary(parent_id) else {
+            break;
+        };
+        for key in INHERITABLE_ATTRIBUTES {
+            if !page.has(key) {
+                if let Ok(value) = node.get(key) {
+                    page.set(key, value.clone());
+                }
+            }
+        }
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+}
+
+/// Add a table of contents page listing `(title, page number, first page)` entries
+fn add_cover_page(
+    document: &mut Document,
+    pages_id: ObjectId,
+    font_id: ObjectId,
+    heading: Option<&str>,
+    entries: &[(&str, usize, ObjectId)],
+) -> Result<ObjectId> {
+    let (width, height) = COVER_SIZE;
+    let mut operations = Vec::new();
+    let mut text = |size: i64, x: i64, y: i64, line: &str| {
+        operations.extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), size.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new("Tj", vec![Object::string_literal(win_ansi(line))]),
+            Operation::new("ET", vec![]),
+        ]);
+    };
+
+    let mut y = height - 72;
+    if let Some(heading) = heading {
+        text(20, 56, y, heading);
+        y -= 40;
+    }
+    let mut annotations = Vec::new();
+    for (title, page, target) in entries {
+        text(11, 56, y, title);
+        text(11, width - 96, y, &format!("p. {}", page));
+        annotations.push(Object::Dictionary(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![50.into(), (y - 4).into(), (width - 50).into(), (y + 12).into()],
+            "Border" => vec![0.into(), 0.into(), 0.into()],
+            "Dest" => vec![Object::Reference(*target), "Fit".into()],
+        }));
+        y -= 17;
+    }
+
+    let content = Content { operations }
+        .encode()
+        .context("Failed to encode the table of contents")?;
+    let content_id = document.add_object(Stream::new(dictionary! {}, content));
+    Ok(document.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+        "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        "Contents" => content_id,
+        "Annots" => annotations,
+    }))
+}
+
+/// Page indexes on the left and right halves of each sheet side of a booklet
+///
+/// Sheets are printed duplex, front then back, and folded in the middle once
+/// stacked. Pages are padded with blanks (None) to a multiple of four.
+fn booklet_order(pages: usize) -> Vec<[Option<usize>; 2]> {
+    let padded = pages.div_ceil(4) * 4;
+    let page = |index: usize| (index < pages).then_some(index);
+    (0..padded / 4)
+        .flat_map(|sheet| {
+            [
+                [page(padded - 1 - 2 * sheet), page(2 * sheet)],
+                [page(2 * sheet + 1), page(padded - 2 - 2 * sheet)],
+            ]
+        })
+        .collect()
+}
+
+/// Replace pages by the sheet sides of a booklet, returning the sheet sides
+///
+/// Each page becomes a form XObject, scaled to fit half of a sheet side.
+fn impose_booklet(
+    document: &mut Document,
+    pages_id: ObjectId,
+    page_ids: &[ObjectId],
+) -> Result<Vec<ObjectId>> {
+    let mut forms = Vec::new();
+    for &page_id in page_ids {
+        let page = document.get_dictionary(page_id)?.clone();
+        let media_box = media_box(document, &page);
+        let rotate = page
+            .get(b"Rotate")
+            .and_then(Object::as_i64)
+            .unwrap_or(0)
+            .rem_euclid(360);
+        let mut form = dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => media_box.iter().map(|&v| Object::Real(v)).collect::<Vec<_>>(),
+        };
+        if let Ok(resources) = page.get(b"Resources") {
+            form.set("Resources", resources.clone());
+        }
+        let mut stream = Stream::new(form, document.get_page_content(page_id));
+        stream
+            .compress()
+            .context("Failed to compress a booklet page")?;
+        forms.push((document.add_object(stream), media_box, rotate));
+    }
+
+    let (sheet_width, sheet_height) = SHEET_SIZE;
+    let slot_width = sheet_width / 2.0;
+    let mut sides = Vec::new();
+    for slots in booklet_order(page_ids.len()) {
+        let mut xobjects = Dictionary::new();
+        let mut operations = Vec::new();
+        for (slot, index) in slots.iter().enumerate() {
+            let Some(&(form_id, [x0, y0, x1, y1], rotate)) = index.map(|index| &forms[index])
+            else {
+                continue;
+            };
+            let (width, height) = (x1 - x0, y1 - y0);
+            // Turn the page as a viewer would display it
+            let (shown_width, shown_height, rotation) = match rotate {
+                90 => (height, width, [0.0, -1.0, 1.0, 0.0, 0.0, width]),
+                180 => (width, height, [-1.0, 0.0, 0.0, -1.0, width, height]),
+                270 => (height, width, [0.0, 1.0, -1.0, 0.0, height, 0.0]),
+                _ => (width, height, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+            };
+            let scale = (slot_width / shown_width).min(sheet_height / shown_height);
+            let x = slot as f32 * slot_width + (slot_width - shown_width * scale) / 2.0;
+            let y = (sheet_height - shown_height * scale) / 2.0;
+
+            let name = format!("P{}", slot);
+            xobjects.set(name.as_str(), form_id);
+            let matrix = |values: [f32; 6]| values.iter().map(|&v| Object::Real(v)).collect();
+            operations.extend([
+                Operation::new("q", vec![]),
+                Operation::new("cm", matrix([scale, 0.0, 0.0, scale, x, y])),
+                Operation::new("cm", matrix(rotation)),
+                Operation::new("cm", matrix([1.0, 0.0, 0.0, 1.0, -x0, -y0])),
+                Operation::new("Do", vec![Object::Name(name.into_bytes())]),
+                Operation::new("Q", vec![]),
+            ]);
+        }
+
+        let content = Content { operations }
+            .encode()
+            .context("Failed to encode a booklet sheet")?;
+        let content_id = document.add_object(Stream::new(dictionary! {}, content));
+        sides.push(document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), Object::Real(sheet_width), Object::Real(sheet_height)],
+            "Resources" => dictionary! { "XObject" => xobjects },
+            "Contents" => content_id,
+        }));
+    }
+    Ok(sides)
+}
+
+/// Media box of a page, A4 portrait when it is missing or invalid
+fn media_box(document: &Document, page: &Dictionary) -> [f32; 4] {
+    let values = page
+        .get(b"MediaBox")
+        .and_then(|object| match object {
+            Object::Reference(id) => document.get_object(*id),
+            _ => Ok(object),
+        })
+        .and_then(Object::as_array)
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|value| value.as_float().ok())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    match values[..] {
+        [x0, y0, x1, y1] if x1 > x0 && y1 > y0 => [x0, y0, x1, y1],
+        _ => [0.0, 0.0, COVER_SIZE.0 as f32, COVER_SIZE.1 as f32],
+    }
+}
+
+/// Encode text for the standard Helvetica font, replacing unsupported characters by `?`
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '’' => 0x92,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::tests::sample_pdf;
+
+    #[test]
+    fn test_merge() {
+        let charts = vec![
+            TripChart {
+                title: "LFPN - TOUSSUS LE NOBLE (AD)".to_string(),
+                pdf: sample_pdf(2),
+            },
+            TripChart {
+                title: "LFOR - CHARTRES MÉTROPOLE (AD)".to_string(),
+                pdf: sample_pdf(1),
+            },
+        ];
+        let (pdf, summary) = merge(charts, "Trip", TripLayout::Pages).unwrap();
+        assert_eq!(summary.pages, 4);
+        assert_eq!(summary.charts.len(), 2);
+
+        let merged = Document::load_mem(&pdf).unwrap();
+        let pages = merged.get_pages();
+        assert_eq!(pages.len(), 4);
+        // Inherited attributes survive the move to the merged page tree
+        let last = merged.get_dictionary(pages[&4]).unwrap();
+        assert!(last.has(b"MediaBox"));
+
+        let toc = merged.get_toc().unwrap();
+        let titles: Vec<(&str, usize)> = toc
+            .toc
+            .iter()
+            .map(|entry| (entry.title.as_str(), entry.page))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("Contents", 1),
+                ("LFPN - TOUSSUS LE NOBLE (AD)", 2),
+                ("LFOR - CHARTRES MÉTROPOLE (AD)", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_rejects_invalid_pdf() {
+        let charts = vec![TripChart {
+            title: "LFPN".to_string(),
+            pdf: b"not a pdf".to_vec(),
+        }];
+        assert!(merge(charts, "Trip", TripLayout::Pages).is_err());
+    }
+
+    #[test]
+    fn test_merge_booklet() {
+        let charts = vec![TripChart {
+            title: "LFPN - TOUSSUS LE NOBLE (AD)".to_string(),
+            pdf: sample_pdf(4),
+        }];
+        let (pdf, summary) = merge(charts, "Trip", TripLayout::Booklet).unwrap();
+        // 5 pages padded to 8 fit on 2 sheets, printed on both sides
+        assert_eq!(summary.pages, 4);
+
+        let booklet = Document::load_mem(&pdf).unwrap();
+        let pages = booklet.get_pages();
+        assert_eq!(pages.len(), 4);
+        let first = booklet.get_dictionary(pages[&1]).unwrap();
+        let media_box: Vec<f32> = first
+            .get(b"MediaBox")
+            .and_then(Object::as_array)
+            .unwrap()
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect();
+        assert_eq!(media_box, vec![0.0, 0.0, 842.0, 595.0]);
+        assert!(booklet.get_toc().is_err());
+    }
+
+    #[test]
+    fn test_booklet_order() {
+        assert_eq!(
+            booklet_order(4),
+            vec![[Some(3), Some(0)], [Some(1), Some(2)]]
+        );
+        assert_eq!(
+            booklet_order(6),
+            vec![
+                [None, Some(0)],
+                [Some(1), None],
+                [Some(5), Some(2)],
+                [Some(3), Some(4)],
+            ]
+        );
+        assert!(booklet_order(0).is_empty());
+    }
+
+    #[test]
+    fn test_win_ansi() {
+        assert_eq!(win_ansi("Étampes"), b"\xc9tampes");
+        assert_eq!(win_ansi("A→B"), b"A?B");
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright (c) 2025 Jeremie Corbier
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the “Software”), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use anyhow::Result;
+use std::time::Duration;
+
+const UNITS: &[(&str, u64)] = &[
+    ("TB", 1 << 40),
+    ("GB", 1 << 30),
+    ("MB", 1 << 20),
+    ("KB", 1 << 10),
+];
+
+/// Format a byte count for display (e.g. "1.5 MB")
+pub fn format_size(bytes: u64) -> String {
+    for &(unit, factor) in UNITS {
+        if bytes >= factor {
+            return format!("{:.1} {}", bytes as f64 / factor as f64, unit);
+        }
+    }
+    format!("{} B", bytes)
+}
+
+/// Parse a human readable size such as "500MB", "2 GB" or "1048576"
+///
+/// Units are binary multiples (1 KB = 1024 bytes) and case insensitive.
+pub fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {:?}", value))?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let factor: u64 = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => anyhow::bail!("Invalid size unit in {:?}", value),
+    };
+
+    Ok((number * factor as f64) as u64)
+}
+
+/// Parse a human readable duration such as "10m", "1h30m", "45s", "7d" or "90"
+///
+/// A number without unit is a number of seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let factor = match c.to_ascii_lowercase() {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => anyhow::bail!("Invalid duration unit in {:?}", value),
+        };
+        let amount: u64 = number
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid duration: {:?}", value))?;
+        total += amount * factor;
+        number.clear();
+    }
+    if !number.is_empty() {
+        anyhow::bail!("Missing unit at the end of duration {:?}", value);
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576").unwrap(), 1 << 20);
+        assert_eq!(parse_size("500MB").unwrap(), 500 << 20);
+        assert_eq!(parse_size("2 GiB").unwrap(), 2 << 30);
+        assert_eq!(parse_size("1.5k").unwrap(), 1536);
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("10 XB").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604800));
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 << 30), "3.0 GB");
+    }
+}